@@ -0,0 +1,122 @@
+// accumulation.rs
+
+use nalgebra_glm::Vec3;
+
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::framebuffer::Framebuffer;
+use crate::scene::Scene;
+
+// Pasadas después de las cuales la imagen se da por terminada y se deja de renderizar
+pub const MAX_PASSES: u32 = 256;
+
+// Lo que cambia la imagen de un frame a otro sin editar el mundo: la cámara, las luces
+// (posición, color, intensidad animada y radio), la luz ambiente y el tono del cielo del
+// ciclo del día, el tiempo de las emisiones y texturas animadas y el giro del cielo y de las nubes
+#[derive(Clone, PartialEq)]
+struct ViewState {
+    camera: Camera,
+    lights: Vec<(Vec3, [f32; 3], f32, f32)>,
+    daylight: ([f32; 3], [f32; 3]),
+    material_time: Option<f32>,
+    sky: (f32, Option<Vec3>),
+}
+
+impl ViewState {
+    fn of(scene: &Scene) -> Self {
+        let (ambient, tint) = (scene.ambient, scene.skybox.tint);
+        ViewState {
+            camera: scene.camera.clone(),
+            lights: scene
+                .active_lights()
+                .map(|light| {
+                    let color = [light.color.r, light.color.g, light.color.b];
+                    let intensity = light.intensity * light.intensity_scale;
+                    (light.position, color, intensity, light.radius)
+                })
+                .collect(),
+            daylight: ([ambient.r, ambient.g, ambient.b], [tint.r, tint.g, tint.b]),
+            // Solo cuenta si algún material tiene la emisión o la textura animada
+            material_time: scene.light_time.filter(|_| {
+                scene.world.materials().any(|(_, _, material)| {
+                    material.emission_animation.is_some() || material.texture_animation.is_some()
+                })
+            }),
+            sky: (
+                scene.skybox.yaw + scene.skybox.drift,
+                scene.clouds.as_ref().map(|clouds| clouds.drift),
+            ),
+        }
+    }
+}
+
+// Promedio de los frames renderizados desde que la vista quedó quieta: cada pasada mueve
+// las muestras dentro del pixel y el ruido del sombreado, así que la imagen se suaviza
+// (bordes, sombras suaves, desenfoque) mientras se la mira
+pub struct Accumulator {
+    sum: Vec<Color>,
+    passes: u32,
+    state: Option<ViewState>,
+}
+
+impl Accumulator {
+    pub fn new() -> Self {
+        Accumulator {
+            sum: Vec::new(),
+            passes: 0,
+            state: None,
+        }
+    }
+
+    // Número de la pasada que toca renderizar; vuelve a 0 si la escena cambió desde el
+    // frame anterior o si `changed` avisa de algo que no se compara (bloques editados,
+    // objetos animados, otra calidad)
+    pub fn begin(&mut self, scene: &Scene, changed: bool) -> u32 {
+        let state = ViewState::of(scene);
+        if changed || self.state.as_ref() != Some(&state) {
+            self.passes = 0;
+            self.state = Some(state);
+        }
+        self.passes
+    }
+
+    // Si ya se juntaron todas las pasadas y no hace falta renderizar de nuevo
+    pub fn converged(&self) -> bool {
+        self.passes >= MAX_PASSES
+    }
+
+    pub fn passes(&self) -> u32 {
+        self.passes
+    }
+
+    // Suma el frame recién renderizado y deja en el framebuffer el promedio de todos
+    pub fn add(&mut self, framebuffer: &mut Framebuffer) {
+        if self.passes == 0 || self.sum.len() != framebuffer.buffer.len() {
+            self.sum.clear();
+            self.sum.resize(framebuffer.buffer.len(), Color::black());
+            self.passes = 0;
+        }
+        for (sum, pixel) in self.sum.iter_mut().zip(&framebuffer.buffer) {
+            *sum = *sum + *pixel;
+        }
+        self.passes += 1;
+        self.restore(framebuffer);
+    }
+
+    // Copia el promedio al framebuffer, por ejemplo después de dibujar contornos encima
+    pub fn restore(&self, framebuffer: &mut Framebuffer) {
+        if self.passes == 0 || self.sum.len() != framebuffer.buffer.len() {
+            return;
+        }
+        let weight = 1.0 / self.passes as f32;
+        for (pixel, sum) in framebuffer.buffer.iter_mut().zip(&self.sum) {
+            *pixel = *sum * weight;
+        }
+    }
+}
+
+impl Default for Accumulator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
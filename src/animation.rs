@@ -0,0 +1,136 @@
+// animation.rs
+
+use nalgebra_glm::Vec3;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Interpolation {
+    // Mantiene el valor de la clave anterior hasta la siguiente
+    Step,
+    Linear,
+    // Curva suave que pasa por todas las claves
+    CatmullRom,
+}
+
+impl Interpolation {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Interpolation::Step => "step",
+            Interpolation::Linear => "linear",
+            Interpolation::CatmullRom => "catmull_rom",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "step" => Some(Interpolation::Step),
+            "linear" => Some(Interpolation::Linear),
+            "catmull_rom" => Some(Interpolation::CatmullRom),
+            _ => None,
+        }
+    }
+}
+
+pub trait Animatable: Copy {
+    fn lerp(a: Self, b: Self, t: f32) -> Self;
+    fn catmull_rom(p0: Self, p1: Self, p2: Self, p3: Self, t: f32) -> Self;
+}
+
+impl Animatable for f32 {
+    fn lerp(a: f32, b: f32, t: f32) -> f32 {
+        a + (b - a) * t
+    }
+
+    fn catmull_rom(p0: f32, p1: f32, p2: f32, p3: f32, t: f32) -> f32 {
+        let t2 = t * t;
+        let t3 = t2 * t;
+        0.5 * (2.0 * p1
+            + (p2 - p0) * t
+            + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t2
+            + (3.0 * p1 - p0 - 3.0 * p2 + p3) * t3)
+    }
+}
+
+impl Animatable for Vec3 {
+    fn lerp(a: Vec3, b: Vec3, t: f32) -> Vec3 {
+        a + (b - a) * t
+    }
+
+    fn catmull_rom(p0: Vec3, p1: Vec3, p2: Vec3, p3: Vec3, t: f32) -> Vec3 {
+        Vec3::new(
+            f32::catmull_rom(p0.x, p1.x, p2.x, p3.x, t),
+            f32::catmull_rom(p0.y, p1.y, p2.y, p3.y, t),
+            f32::catmull_rom(p0.z, p1.z, p2.z, p3.z, t),
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Keyframe<T> {
+    pub time: f32,
+    pub value: T,
+}
+
+#[derive(Debug, Clone)]
+pub struct Track<T> {
+    pub interpolation: Interpolation,
+    // Repite la pista al llegar a la última clave
+    pub looping: bool,
+    keys: Vec<Keyframe<T>>,
+}
+
+impl<T: Animatable> Track<T> {
+    pub fn new(interpolation: Interpolation, looping: bool) -> Self {
+        Track {
+            interpolation,
+            looping,
+            keys: Vec::new(),
+        }
+    }
+
+    // Inserta la clave manteniendo el orden por tiempo
+    pub fn add_key(&mut self, time: f32, value: T) {
+        let index = self.keys.partition_point(|key| key.time <= time);
+        self.keys.insert(index, Keyframe { time, value });
+    }
+
+    pub fn keys(&self) -> &[Keyframe<T>] {
+        &self.keys
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.keys.last().map_or(0.0, |key| key.time)
+    }
+
+    pub fn sample(&self, time: f32) -> Option<T> {
+        let first = self.keys.first()?;
+        let last = self.keys.last()?;
+
+        let time = if self.looping && last.time > first.time {
+            first.time + (time - first.time).rem_euclid(last.time - first.time)
+        } else {
+            time
+        };
+
+        if time <= first.time {
+            return Some(first.value);
+        }
+        if time >= last.time {
+            return Some(last.value);
+        }
+
+        let next = self.keys.partition_point(|key| key.time <= time);
+        let current = next - 1;
+        let (a, b) = (&self.keys[current], &self.keys[next]);
+        let t = (time - a.time) / (b.time - a.time);
+
+        Some(match self.interpolation {
+            Interpolation::Step => a.value,
+            Interpolation::Linear => T::lerp(a.value, b.value, t),
+            Interpolation::CatmullRom => {
+                let before = self.keys[current.saturating_sub(1)].value;
+                let after = self.keys[(next + 1).min(self.keys.len() - 1)].value;
+                T::catmull_rom(before, a.value, b.value, after, t)
+            }
+        })
+    }
+}
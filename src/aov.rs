@@ -0,0 +1,94 @@
+// aov.rs
+
+use nalgebra_glm::Vec3;
+
+use crate::color::Color;
+use crate::framebuffer::Framebuffer;
+
+// Datos auxiliares de un pixel (AOV), promediados entre sus muestras, para filtrar el ruido
+// o componer la imagen en otro programa. Las muestras que ven el cielo no cuentan.
+#[derive(Debug, Clone, Copy)]
+pub struct AovPixel {
+    // Distancia de lo que se ve al plano de la cámara; infinita donde solo hay cielo
+    pub depth: f32,
+    // Normal en coordenadas del mundo, con el normal map aplicado
+    pub normal: Vec3,
+    // Color de la superficie con su textura, sin luz
+    pub albedo: Color,
+    // Brillo propio y luz que llega directo de las luces a lo primero que se ve
+    pub direct: Color,
+    // El resto de la luz de los bloques: reflejos, refracciones y lo que dispersa la niebla
+    pub indirect: Color,
+}
+
+impl AovPixel {
+    pub fn empty() -> Self {
+        AovPixel {
+            depth: f32::INFINITY,
+            normal: Vec3::zeros(),
+            albedo: Color::black(),
+            direct: Color::black(),
+            indirect: Color::black(),
+        }
+    }
+}
+
+// Las pasadas auxiliares de una imagen, un pixel por pixel del framebuffer
+pub struct Aovs {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<AovPixel>,
+}
+
+impl Aovs {
+    pub fn new(width: usize, height: usize) -> Self {
+        Aovs {
+            width,
+            height,
+            pixels: vec![AovPixel::empty(); width * height],
+        }
+    }
+
+    // Cada pasada como imagen, con el nombre que lleva su archivo. La profundidad va en
+    // grises, de blanco en lo más cercano a negro en lo más lejano y el cielo; las normales
+    // van de -1..1 a 0..1 por canal, como en la vista de depuración.
+    pub fn images(&self) -> Vec<(&'static str, Framebuffer)> {
+        let far = self
+            .pixels
+            .iter()
+            .map(|pixel| pixel.depth)
+            .filter(|depth| depth.is_finite())
+            .fold(0.0_f32, f32::max);
+        let depth = |pixel: &AovPixel| {
+            let value = if pixel.depth.is_finite() && far > 0.0 {
+                1.0 - pixel.depth / far
+            } else {
+                0.0
+            };
+            Color::new(value, value, value)
+        };
+        let normal = |pixel: &AovPixel| {
+            if pixel.normal == Vec3::zeros() {
+                return Color::black();
+            }
+            let normal = pixel.normal * 0.5 + Vec3::new(0.5, 0.5, 0.5);
+            Color::new(normal.x, normal.y, normal.z)
+        };
+
+        vec![
+            ("depth", self.image(depth)),
+            ("normal", self.image(normal)),
+            ("albedo", self.image(|pixel| pixel.albedo)),
+            ("direct", self.image(|pixel| pixel.direct)),
+            ("indirect", self.image(|pixel| pixel.indirect)),
+        ]
+    }
+
+    fn image(&self, color: impl Fn(&AovPixel) -> Color) -> Framebuffer {
+        let mut framebuffer = Framebuffer::new(self.width, self.height);
+        for (pixel, aov) in framebuffer.buffer.iter_mut().zip(&self.pixels) {
+            *pixel = color(aov);
+        }
+        framebuffer
+    }
+}
@@ -0,0 +1,341 @@
+// assets.rs
+
+use image::{Rgba, RgbaImage};
+use std::collections::HashMap;
+use std::fs;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use crate::procedural::Pattern;
+
+// Imagen compartida: los materiales y las caras del cielo que usan el mismo archivo
+// apuntan a la misma copia en memoria
+pub type ImageTexture = Arc<MipChain>;
+
+// Lo que pinta la superficie de un material
+#[derive(Debug, Clone)]
+pub enum Texture {
+    Image(ImageTexture),
+    // Se calcula al sombrear, sin archivo de imagen
+    Pattern(Pattern),
+}
+
+// Una imagen y sus versiones reducidas a la mitad hasta llegar a 1x1 (mipmaps). De lejos
+// se lee una versión reducida, que ya promedia los texels que caen en un mismo pixel y
+// evita el parpadeo de las texturas. Se usa como la imagen original a través de `Deref`.
+#[derive(Debug)]
+pub struct MipChain {
+    levels: Vec<RgbaImage>,
+    has_alpha: bool,
+}
+
+impl MipChain {
+    pub fn new(image: RgbaImage) -> Self {
+        let has_alpha = image.pixels().any(|pixel| pixel.0[3] < u8::MAX);
+        let mut levels = vec![image];
+        loop {
+            let previous = &levels[levels.len() - 1];
+            let (width, height) = previous.dimensions();
+            if width <= 1 && height <= 1 {
+                break;
+            }
+            let next = downsample(previous);
+            levels.push(next);
+        }
+        MipChain { levels, has_alpha }
+    }
+
+    // Si algún texel de la imagen no es opaco
+    pub fn has_alpha(&self) -> bool {
+        self.has_alpha
+    }
+
+    pub fn level_count(&self) -> usize {
+        self.levels.len()
+    }
+
+    // Nivel `level`, o el más pequeño si no hay tantos
+    pub fn level(&self, level: usize) -> &RgbaImage {
+        &self.levels[level.min(self.levels.len() - 1)]
+    }
+}
+
+impl Deref for MipChain {
+    type Target = RgbaImage;
+
+    fn deref(&self) -> &RgbaImage {
+        &self.levels[0]
+    }
+}
+
+// Promedia cada bloque de 2x2 texels; con lados impares se repite el último texel
+fn downsample(image: &RgbaImage) -> RgbaImage {
+    let (width, height) = image.dimensions();
+    RgbaImage::from_fn((width / 2).max(1), (height / 2).max(1), |x, y| {
+        let mut sum = [0u32; 4];
+        for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+            let pixel = image.get_pixel((2 * x + dx).min(width - 1), (2 * y + dy).min(height - 1));
+            for (total, channel) in sum.iter_mut().zip(pixel.0) {
+                *total += channel as u32;
+            }
+        }
+        Rgba(sum.map(|total| ((total + 2) / 4) as u8))
+    })
+}
+
+// Cuadros de una textura animada, como el agua de los resource packs de Minecraft, con los
+// segundos que dura cada uno. Con `interpolate` cada cuadro se funde de a poco con el
+// siguiente en lugar de cambiar de golpe.
+#[derive(Debug, Clone)]
+pub struct TextureAnimation {
+    pub frames: Vec<(ImageTexture, f32)>,
+    pub interpolate: bool,
+}
+
+impl TextureAnimation {
+    // El cuadro a los `time` segundos; la animación se repite
+    pub fn frame(&self, time: f32) -> ImageTexture {
+        let total: f32 = self.frames.iter().map(|(_, duration)| duration).sum();
+        let mut time = if total > 0.0 {
+            time.rem_euclid(total)
+        } else {
+            0.0
+        };
+        for (index, (frame, duration)) in self.frames.iter().enumerate() {
+            if time >= *duration {
+                time -= duration;
+                continue;
+            }
+            if !self.interpolate {
+                return Arc::clone(frame);
+            }
+            let (next, _) = &self.frames[(index + 1) % self.frames.len()];
+            return Arc::new(MipChain::new(blend(frame, next, time / duration)));
+        }
+        Arc::clone(&self.frames[self.frames.len() - 1].0)
+    }
+}
+
+// Mezcla de dos imágenes del mismo tamaño: con 0 es `from` y con 1 es `to`
+fn blend(from: &RgbaImage, to: &RgbaImage, amount: f32) -> RgbaImage {
+    let mut image = from.clone();
+    for (pixel, target) in image.pixels_mut().zip(to.pixels()) {
+        for (channel, target) in pixel.0.iter_mut().zip(target.0) {
+            let value = *channel as f32 + (target as f32 - *channel as f32) * amount;
+            *channel = value.round() as u8;
+        }
+    }
+    image
+}
+
+// Cómo se lee una textura entre texel y texel (y entre niveles de mipmap)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextureFilter {
+    // El texel más cercano: bordes nítidos, estilo pixel art
+    #[default]
+    Nearest,
+    // Mezcla de los cuatro texels vecinos, suaviza la textura vista de cerca
+    Bilinear,
+}
+
+impl TextureFilter {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TextureFilter::Nearest => "nearest",
+            TextureFilter::Bilinear => "bilinear",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "nearest" => Some(TextureFilter::Nearest),
+            "bilinear" => Some(TextureFilter::Bilinear),
+            _ => None,
+        }
+    }
+}
+
+// Lee la textura en (u, v), ambos entre 0 y 1 con v = 0 en la fila de arriba. Devuelve
+// RGBA entre 0 y 1. Fuera del rango se repite el borde.
+pub fn sample_texture(texture: &RgbaImage, u: f32, v: f32, filter: TextureFilter) -> [f32; 4] {
+    let (width, height) = texture.dimensions();
+    let texel = |x: u32, y: u32| {
+        let pixel = texture.get_pixel(x.min(width - 1), y.min(height - 1));
+        pixel.0.map(|channel| channel as f32 / 255.0)
+    };
+
+    match filter {
+        TextureFilter::Nearest => {
+            let x = (u.clamp(0.0, 1.0 - f32::EPSILON) * width as f32) as u32;
+            let y = (v.clamp(0.0, 1.0 - f32::EPSILON) * height as f32) as u32;
+            texel(x, y)
+        }
+        TextureFilter::Bilinear => {
+            // Los centros de los texels están en medio de cada celda
+            let x = (u * width as f32 - 0.5).clamp(0.0, (width - 1) as f32);
+            let y = (v * height as f32 - 0.5).clamp(0.0, (height - 1) as f32);
+            let (x0, y0) = (x.floor() as u32, y.floor() as u32);
+            let (fx, fy) = (x.fract(), y.fract());
+
+            let top_left = texel(x0, y0);
+            let top_right = texel(x0 + 1, y0);
+            let bottom_left = texel(x0, y0 + 1);
+            let bottom_right = texel(x0 + 1, y0 + 1);
+            std::array::from_fn(|i| {
+                let top = top_left[i] + (top_right[i] - top_left[i]) * fx;
+                let bottom = bottom_left[i] + (bottom_right[i] - bottom_left[i]) * fx;
+                top + (bottom - top) * fy
+            })
+        }
+    }
+}
+
+// Como `sample_texture`, eligiendo el nivel de mipmap según `footprint`: el ancho que cubre
+// el pixel sobre la textura, en las mismas unidades que u y v. Con `Bilinear` se mezclan
+// los dos niveles más cercanos (trilineal); con 0 se lee la imagen original.
+pub fn sample_mipmapped(
+    texture: &MipChain,
+    u: f32,
+    v: f32,
+    filter: TextureFilter,
+    footprint: f32,
+) -> [f32; 4] {
+    let size = texture.width().max(texture.height()) as f32;
+    let lod = (footprint * size).max(1.0).log2();
+
+    match filter {
+        TextureFilter::Nearest => sample_texture(texture.level(lod.round() as usize), u, v, filter),
+        TextureFilter::Bilinear => {
+            let lower = lod.floor() as usize;
+            let blend = lod.fract();
+            let near = sample_texture(texture.level(lower), u, v, filter);
+            if blend <= 0.0 || lower + 1 >= texture.level_count() {
+                return near;
+            }
+            let far = sample_texture(texture.level(lower + 1), u, v, filter);
+            std::array::from_fn(|i| near[i] + (far[i] - near[i]) * blend)
+        }
+    }
+}
+
+// Texturas incluidas en el binario para poder ejecutar sin la carpeta textures
+const BUILTIN_TEXTURES: &[(&str, &[u8])] = &[
+    (
+        "old-cobblestone-texture.png",
+        include_bytes!("textures/old-cobblestone-texture.png"),
+    ),
+    ("grass.png", include_bytes!("textures/grass.png")),
+    ("wood.png", include_bytes!("textures/wood.png")),
+    ("glowstone.png", include_bytes!("textures/glowstone.png")),
+    ("water.png", include_bytes!("textures/water.png")),
+    ("water1.png", include_bytes!("textures/water1.png")),
+    ("water2.png", include_bytes!("textures/water2.png")),
+    ("sky.jpg", include_bytes!("textures/sky.jpg")),
+];
+
+const PLACEHOLDER_SIZE: u32 = 16;
+
+// Texturas ya cargadas, por ruta. Cada archivo se decodifica la primera vez que se pide y
+// las siguientes veces se devuelve la misma imagen.
+pub struct TextureCache {
+    textures: Mutex<HashMap<PathBuf, ImageTexture>>,
+}
+
+impl TextureCache {
+    pub fn new() -> Self {
+        TextureCache {
+            textures: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn get(&self, path: &str) -> ImageTexture {
+        // Rutas distintas al mismo archivo (./a.png y a.png) comparten la entrada
+        let key = fs::canonicalize(path).unwrap_or_else(|_| PathBuf::from(path));
+        let mut textures = self.textures.lock().unwrap_or_else(|err| err.into_inner());
+        Arc::clone(
+            textures
+                .entry(key)
+                .or_insert_with(|| Arc::new(MipChain::new(decode_texture(path)))),
+        )
+    }
+
+    // Vuelve a decodificar el archivo aunque ya esté cargado; los que pidan la ruta
+    // después reciben la imagen nueva y los materiales que tenían la vieja la conservan
+    pub fn reload(&self, path: &str) -> ImageTexture {
+        let key = fs::canonicalize(path).unwrap_or_else(|_| PathBuf::from(path));
+        let texture = Arc::new(MipChain::new(decode_texture(path)));
+        let mut textures = self.textures.lock().unwrap_or_else(|err| err.into_inner());
+        textures.insert(key, Arc::clone(&texture));
+        texture
+    }
+
+    // Cantidad de imágenes distintas cargadas
+    pub fn len(&self) -> usize {
+        self.textures.lock().map_or(0, |textures| textures.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for TextureCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Caché compartida por todas las escenas del programa
+pub fn texture_cache() -> &'static TextureCache {
+    static CACHE: OnceLock<TextureCache> = OnceLock::new();
+    CACHE.get_or_init(TextureCache::new)
+}
+
+pub fn load_texture(path: &str) -> ImageTexture {
+    texture_cache().get(path)
+}
+
+fn decode_texture(path: &str) -> RgbaImage {
+    match image::open(path) {
+        Ok(image) => image.to_rgba8(),
+        Err(err) => {
+            eprintln!(
+                "No se pudo cargar '{}' ({}), usando textura integrada",
+                path, err
+            );
+            builtin_texture(path)
+        }
+    }
+}
+
+pub fn builtin_texture(path: &str) -> RgbaImage {
+    let file_name = Path::new(path)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or(path);
+
+    BUILTIN_TEXTURES
+        .iter()
+        .find(|(name, _)| *name == file_name)
+        .and_then(|(_, bytes)| image::load_from_memory(bytes).ok())
+        .map(|image| image.to_rgba8())
+        .unwrap_or_else(|| {
+            eprintln!(
+                "'{}' no tiene textura integrada, usando patrón de reemplazo",
+                file_name
+            );
+            placeholder_texture()
+        })
+}
+
+// Tablero magenta/negro para recursos que no existen ni en disco ni en el binario
+pub fn placeholder_texture() -> RgbaImage {
+    RgbaImage::from_fn(PLACEHOLDER_SIZE, PLACEHOLDER_SIZE, |x, y| {
+        if (x / 4 + y / 4) % 2 == 0 {
+            Rgba([255, 0, 255, 255])
+        } else {
+            Rgba([0, 0, 0, 255])
+        }
+    })
+}
@@ -0,0 +1,108 @@
+// beauty.rs
+
+use minifb::{Key, Window};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::color::Color;
+use crate::cube::Cube;
+use crate::framebuffer::Framebuffer;
+use crate::post::apply_post;
+use crate::render::{render_rows, RenderSettings};
+use crate::scene::Scene;
+use crate::screenshot::{save_screenshot, SCREENSHOT_DIR};
+
+// Captura de alta calidad: el doble de resolución y 4x4 muestras por pixel
+const BEAUTY_SCALE: usize = 2;
+const BEAUTY_SAMPLES: u32 = 4;
+// Tiempo de render entre actualizaciones de la ventana, para que siga respondiendo
+const SLICE_BUDGET: Duration = Duration::from_millis(100);
+const PROGRESS_BAR_HEIGHT: usize = 6;
+
+// Renderiza la vista actual por franjas de filas mostrando el avance sobre la imagen
+// interactiva, y guarda la captura al terminar. Mientras tanto la cámara y la edición
+// quedan congeladas; Retroceso cancela.
+pub fn beauty_shot(window: &mut Window, scene: &Scene, cubes: &[Cube], preview: &Framebuffer) {
+    let mut capture = Framebuffer::new(preview.width * BEAUTY_SCALE, preview.height * BEAUTY_SCALE);
+    let mut display = Framebuffer::new(preview.width, preview.height);
+    display.buffer.copy_from_slice(&preview.buffer);
+    // Lo que se muestra en la ventana: el avance más la barra de progreso
+    let mut frame = Framebuffer::new(preview.width, preview.height);
+    capture.dither = scene.dither;
+    frame.dither = scene.dither;
+
+    let start = Instant::now();
+    let mut next_row = 0;
+    // Filas por franja; se ajusta según lo que tardó la anterior
+    let mut band = 1;
+
+    while next_row < capture.height {
+        if !window.is_open() || window.is_key_down(Key::Backspace) {
+            println!("Captura de alta calidad cancelada");
+            return;
+        }
+
+        let slice_start = Instant::now();
+        let end_row = (next_row + band).min(capture.height);
+        render_rows(
+            &mut capture,
+            scene,
+            cubes,
+            &RenderSettings::with_samples(BEAUTY_SAMPLES),
+            next_row..end_row,
+        );
+        copy_rows(&capture, &mut display, next_row, end_row);
+        next_row = end_row;
+
+        let elapsed = slice_start.elapsed().as_secs_f32().max(1e-3);
+        let rows_per_budget = band as f32 * SLICE_BUDGET.as_secs_f32() / elapsed;
+        band = (rows_per_budget as usize).clamp(1, capture.height);
+
+        let progress = next_row as f32 / capture.height as f32;
+        frame.buffer.copy_from_slice(&display.buffer);
+        draw_progress_bar(&mut frame, progress);
+        window.set_title(&format!(
+            "Minecraft - Captura de alta calidad: {:.0}% (Retroceso cancela)",
+            progress * 100.0
+        ));
+        window
+            .update_with_buffer(frame.as_u32_buffer(), preview.width, preview.height)
+            .unwrap();
+    }
+
+    apply_post(&scene.post, &mut capture);
+    match save_screenshot(&capture, Path::new(SCREENSHOT_DIR)) {
+        Ok(path) => println!(
+            "Captura de alta calidad guardada en {} ({:.1} s)",
+            path.display(),
+            start.elapsed().as_secs_f32()
+        ),
+        Err(err) => eprintln!("No se pudo guardar la captura: {}", err),
+    }
+}
+
+// Copia a la vista previa las filas ya renderizadas de la captura, reduciendo su tamaño
+fn copy_rows(capture: &Framebuffer, display: &mut Framebuffer, first_row: usize, end_row: usize) {
+    let first = first_row / BEAUTY_SCALE;
+    let end = end_row / BEAUTY_SCALE;
+    for y in first..end.min(display.height) {
+        for x in 0..display.width {
+            display.buffer[y * display.width + x] =
+                capture.buffer[y * BEAUTY_SCALE * capture.width + x * BEAUTY_SCALE];
+        }
+    }
+}
+
+fn draw_progress_bar(frame: &mut Framebuffer, progress: f32) {
+    let filled = (progress * frame.width as f32) as usize;
+    let top = frame.height.saturating_sub(PROGRESS_BAR_HEIGHT);
+    for y in top..frame.height {
+        for x in 0..frame.width {
+            frame.buffer[y * frame.width + x] = if x < filled {
+                Color::new(1.0, 1.0, 1.0)
+            } else {
+                Color::new(0.15, 0.15, 0.15)
+            };
+        }
+    }
+}
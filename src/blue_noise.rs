@@ -0,0 +1,136 @@
+// blue_noise.rs
+
+use std::sync::OnceLock;
+
+// Lado de la máscara; se repite en mosaico sobre la imagen
+pub const BLUE_NOISE_SIZE: usize = 64;
+const PIXELS: usize = BLUE_NOISE_SIZE * BLUE_NOISE_SIZE;
+const SIGMA: f32 = 1.5;
+// Conjugado de la razón áurea, para desplazar la máscara entre dimensiones y muestras
+const GOLDEN_RATIO: f32 = 0.618_034;
+
+// Máscara de ruido azul: cada pixel tiene un umbral entre 0 y 1 y pixeles cercanos
+// tienen valores muy distintos, así el error de muestreo queda como grano fino
+pub struct BlueNoise {
+    values: Vec<f32>,
+}
+
+impl BlueNoise {
+    // Valor en [0, 1) para el pixel y la dimensión dados (0 = antialiasing x, 1 = y, ...)
+    pub fn sample(&self, x: usize, y: usize, dimension: u32) -> f32 {
+        let index = (y % BLUE_NOISE_SIZE) * BLUE_NOISE_SIZE + x % BLUE_NOISE_SIZE;
+        (self.values[index] + dimension as f32 * GOLDEN_RATIO).fract()
+    }
+}
+
+// La máscara se genera una sola vez y se comparte entre todos los hilos de render
+pub fn blue_noise() -> &'static BlueNoise {
+    static MASK: OnceLock<BlueNoise> = OnceLock::new();
+    MASK.get_or_init(generate)
+}
+
+// Algoritmo void-and-cluster de Ulichney sobre una cuadrícula toroidal
+fn generate() -> BlueNoise {
+    let kernel = gaussian_kernel();
+
+    // Patrón inicial: un 10% de pixeles encendidos al azar (semilla fija)
+    let mut pattern = vec![false; PIXELS];
+    let mut state: u32 = 0x2545_F491;
+    let mut ones = 0;
+    while ones < PIXELS / 10 {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        let index = state as usize % PIXELS;
+        if !pattern[index] {
+            pattern[index] = true;
+            ones += 1;
+        }
+    }
+
+    let mut energy = vec![0.0; PIXELS];
+    for (index, _) in pattern.iter().enumerate().filter(|(_, &on)| on) {
+        splat(&mut energy, &kernel, index, 1.0);
+    }
+
+    // Mueve los puntos del cúmulo más denso al hueco más grande hasta que el patrón se estabiliza
+    for _ in 0..PIXELS {
+        let cluster = tightest_cluster(&pattern, &energy);
+        pattern[cluster] = false;
+        splat(&mut energy, &kernel, cluster, -1.0);
+
+        let void = largest_void(&pattern, &energy);
+        pattern[void] = true;
+        splat(&mut energy, &kernel, void, 1.0);
+
+        if void == cluster {
+            break;
+        }
+    }
+
+    let mut ranks = vec![0usize; PIXELS];
+
+    // Fase 1: quitar puntos del patrón inicial asignando rangos de mayor a menor
+    let mut working = pattern.clone();
+    let mut working_energy = energy.clone();
+    for rank in (0..ones).rev() {
+        let cluster = tightest_cluster(&working, &working_energy);
+        working[cluster] = false;
+        splat(&mut working_energy, &kernel, cluster, -1.0);
+        ranks[cluster] = rank;
+    }
+
+    // Fases 2 y 3: llenar los huecos restantes hasta cubrir toda la máscara
+    for rank in ones..PIXELS {
+        let void = largest_void(&pattern, &energy);
+        pattern[void] = true;
+        splat(&mut energy, &kernel, void, 1.0);
+        ranks[void] = rank;
+    }
+
+    BlueNoise {
+        values: ranks
+            .iter()
+            .map(|&rank| (rank as f32 + 0.5) / PIXELS as f32)
+            .collect(),
+    }
+}
+
+fn gaussian_kernel() -> Vec<f32> {
+    let mut kernel = vec![0.0; PIXELS];
+    for dy in 0..BLUE_NOISE_SIZE {
+        for dx in 0..BLUE_NOISE_SIZE {
+            // Distancia más corta dando la vuelta por los bordes
+            let wx = dx.min(BLUE_NOISE_SIZE - dx) as f32;
+            let wy = dy.min(BLUE_NOISE_SIZE - dy) as f32;
+            kernel[dy * BLUE_NOISE_SIZE + dx] =
+                (-(wx * wx + wy * wy) / (2.0 * SIGMA * SIGMA)).exp();
+        }
+    }
+    kernel
+}
+
+fn splat(energy: &mut [f32], kernel: &[f32], index: usize, sign: f32) {
+    let (cx, cy) = (index % BLUE_NOISE_SIZE, index / BLUE_NOISE_SIZE);
+    for y in 0..BLUE_NOISE_SIZE {
+        let dy = (y + BLUE_NOISE_SIZE - cy) % BLUE_NOISE_SIZE;
+        for x in 0..BLUE_NOISE_SIZE {
+            let dx = (x + BLUE_NOISE_SIZE - cx) % BLUE_NOISE_SIZE;
+            energy[y * BLUE_NOISE_SIZE + x] += sign * kernel[dy * BLUE_NOISE_SIZE + dx];
+        }
+    }
+}
+
+fn tightest_cluster(pattern: &[bool], energy: &[f32]) -> usize {
+    (0..PIXELS)
+        .filter(|&index| pattern[index])
+        .max_by(|&a, &b| energy[a].total_cmp(&energy[b]))
+        .unwrap_or(0)
+}
+
+fn largest_void(pattern: &[bool], energy: &[f32]) -> usize {
+    (0..PIXELS)
+        .filter(|&index| !pattern[index])
+        .min_by(|&a, &b| energy[a].total_cmp(&energy[b]))
+        .unwrap_or(0)
+}
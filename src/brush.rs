@@ -0,0 +1,109 @@
+// brush.rs
+
+use minifb::{MouseButton, MouseMode, Window};
+
+use crate::color::Color;
+use crate::cube::Cube;
+use crate::framebuffer::{window_to_framebuffer, Framebuffer};
+use crate::keymap::{Action, KeyMap};
+use crate::render::{render_masked, RenderSettings, MAX_DEPTH};
+use crate::scene::Scene;
+
+// Radio del pincel en pixeles del framebuffer
+const BRUSH_RADIUS: i32 = 12;
+// Calidad de la región marcada: 3x3 muestras por pixel y el doble de rebotes
+const BRUSH_SAMPLES: u32 = 3;
+const BRUSH_DEPTH: u32 = MAX_DEPTH * 2;
+
+// Pincel para marcar zonas de la pantalla que se vuelven a renderizar en alta calidad
+// encima de cada frame interactivo: clic central pinta, C borra la región
+pub struct RegionBrush {
+    mask: Vec<bool>,
+    width: usize,
+    height: usize,
+    active: bool,
+}
+
+impl RegionBrush {
+    pub fn new(width: usize, height: usize) -> Self {
+        RegionBrush {
+            mask: vec![false; width * height],
+            width,
+            height,
+            active: false,
+        }
+    }
+
+    pub fn update(&mut self, window: &Window, keys: &KeyMap) {
+        if keys.pressed(window, Action::ClearBrush) {
+            self.mask.fill(false);
+            self.active = false;
+        }
+
+        if !window.get_mouse_down(MouseButton::Middle) {
+            return;
+        }
+        // La ventana estira el framebuffer, así que se escala la posición del mouse
+        let Some((x, y)) = window.get_mouse_pos(MouseMode::Discard).and_then(|mouse| {
+            window_to_framebuffer(mouse, window.get_size(), (self.width, self.height))
+        }) else {
+            return;
+        };
+        let (center_x, center_y) = (x as i32, y as i32);
+
+        for dy in -BRUSH_RADIUS..=BRUSH_RADIUS {
+            for dx in -BRUSH_RADIUS..=BRUSH_RADIUS {
+                let (x, y) = (center_x + dx, center_y + dy);
+                let inside = dx * dx + dy * dy <= BRUSH_RADIUS * BRUSH_RADIUS;
+                if inside
+                    && x >= 0
+                    && y >= 0
+                    && (x as usize) < self.width
+                    && (y as usize) < self.height
+                {
+                    self.mask[y as usize * self.width + x as usize] = true;
+                    self.active = true;
+                }
+            }
+        }
+    }
+
+    // Renderiza la región marcada con más calidad sobre el frame ya renderizado
+    pub fn apply(&self, framebuffer: &mut Framebuffer, scene: &Scene, cubes: &[Cube]) {
+        if self.active {
+            let settings = RenderSettings {
+                samples: BRUSH_SAMPLES,
+                max_depth: BRUSH_DEPTH,
+                ..RenderSettings::high_quality()
+            };
+            render_masked(framebuffer, scene, cubes, &settings, &self.mask);
+        }
+    }
+
+    // Marca el borde de la región con pixeles tenues para saber qué parte está refinada
+    pub fn draw_outline(&self, framebuffer: &mut Framebuffer) {
+        if !self.active {
+            return;
+        }
+
+        let marked = |x: usize, y: usize| self.mask[y * self.width + x];
+        framebuffer.set_current_color(Color::new(1.0, 0.85, 0.2));
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let border = marked(x, y)
+                    && (x == 0
+                        || y == 0
+                        || x + 1 == self.width
+                        || y + 1 == self.height
+                        || !marked(x - 1, y)
+                        || !marked(x + 1, y)
+                        || !marked(x, y - 1)
+                        || !marked(x, y + 1));
+                // Línea punteada para no tapar lo que se quiere revisar
+                if border && (x + y) % 3 == 0 {
+                    framebuffer.point(x, y);
+                }
+            }
+        }
+    }
+}
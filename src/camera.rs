@@ -1,37 +1,238 @@
-use nalgebra_glm::Vec3;
+use nalgebra_glm::{self as glm, Mat4, Vec3};
 use std::f32::consts::PI;
 
+// Límites del zoom interactivo, en radianes
+pub const MIN_FOV: f32 = PI / 18.0;
+pub const MAX_FOV: f32 = PI * 2.0 / 3.0;
+const MIN_ORTHO_HEIGHT: f32 = 1.0;
+const MAX_ORTHO_HEIGHT: f32 = 500.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Projection {
+    Perspective,
+    // Rayos paralelos: los objetos no se achican con la distancia (vista isométrica)
+    Orthographic,
+}
+
+// Después de cambiar a mano `position`, `target`, `up_direction` o `roll` hay que llamar a
+// `update_view`; los métodos que mueven la cámara ya lo hacen.
+#[derive(Clone, PartialEq)]
 pub struct Camera {
     pub position: Vec3,
     pub target: Vec3,
     pub up_direction: Vec3,
+    // Giro alrededor de la dirección de vista en radianes; positivo inclina la cámara hacia
+    // la izquierda (el horizonte se ve girado en sentido horario)
+    pub roll: f32,
+    // Campo de visión vertical en radianes
+    pub fov: f32,
+    // Distancia al plano cercano: lo que esté más cerca de la cámara no se ve
+    pub near: f32,
+    // Distancia al plano lejano: lo que esté más lejos no se ve; None no recorta
+    pub far: Option<f32>,
+    // Ancho de la franja junto a cada plano donde los bloques se desvanecen en lugar de
+    // cortarse de golpe; 0 corta en seco
+    pub clip_fade: f32,
+    // Proporción ancho / alto de la imagen final; None usa la del framebuffer.
+    // Sirve cuando la ventana estira el framebuffer a otra proporción.
+    pub aspect_ratio: Option<f32>,
+    // Radio de la lente; 0 es una cámara estenopeica con todo enfocado
+    pub aperture: f32,
+    // Distancia (a lo largo de la vista) del plano enfocado; None enfoca el objetivo
+    pub focus_distance: Option<f32>,
+    pub projection: Projection,
+    // Altura en bloques de lo que se ve con la proyección ortográfica
+    pub ortho_height: f32,
+    // Matriz de vista (mundo a cámara) calculada por `update_view`
+    view: Mat4,
 }
 
 impl Camera {
     pub fn new(position: Vec3, target: Vec3, up_direction: Vec3) -> Self {
-        Self {
+        let mut camera = Self {
             position,
             target,
             up_direction,
-        }
+            roll: 0.0,
+            fov: PI / 3.0,
+            near: 0.0,
+            far: None,
+            clip_fade: 0.0,
+            aspect_ratio: None,
+            aperture: 0.0,
+            focus_distance: None,
+            projection: Projection::Perspective,
+            ortho_height: 10.0,
+            view: Mat4::identity(),
+        };
+        camera.update_view();
+        camera
     }
 
-    pub fn transform_vector(&self, input_vector: &Vec3) -> Vec3 {
+    // Recalcula la matriz de vista a partir de la posición, el objetivo, `up_direction` y `roll`
+    pub fn update_view(&mut self) {
         let forward = (self.target - self.position).normalize();
-        let right = forward.cross(&self.up_direction).normalize();
-        let up = right.cross(&forward).normalize();
+        let up = glm::rotate_vec3(&self.up_direction, -self.roll, &forward);
+        self.view = glm::look_at(&self.position, &self.target, &up);
+    }
+
+    pub fn view_matrix(&self) -> &Mat4 {
+        &self.view
+    }
+
+    // Ejes de la cámara en coordenadas del mundo (derecha, arriba, adelante): las filas de
+    // la rotación de la matriz de vista, que mira hacia -z
+    fn basis(&self) -> (Vec3, Vec3, Vec3) {
+        let right = glm::row(&self.view, 0).xyz();
+        let up = glm::row(&self.view, 1).xyz();
+        let forward = -glm::row(&self.view, 2).xyz();
+        (right, up, forward)
+    }
+
+    // Ángulo horizontal de la cámara alrededor del objetivo
+    pub fn yaw(&self) -> f32 {
+        let offset = self.position - self.target;
+        offset.z.atan2(offset.x)
+    }
+
+    // Ángulo vertical de la cámara alrededor del objetivo; positivo mira desde abajo
+    pub fn pitch(&self) -> f32 {
+        let offset = self.position - self.target;
+        let xz_distance = (offset.x * offset.x + offset.z * offset.z).sqrt();
+        (-offset.y).atan2(xz_distance)
+    }
+
+    fn aspect(&self, width: f32, height: f32) -> f32 {
+        self.aspect_ratio.unwrap_or(width / height)
+    }
+
+    pub fn transform_vector(&self, input_vector: &Vec3) -> Vec3 {
+        let (right, up, forward) = self.basis();
         let transformed = input_vector.x * right + input_vector.y * up - input_vector.z * forward;
         transformed.normalize()
     }
 
+    // Dirección del rayo que pasa por el pixel (x, y) de una imagen de width x height
+    pub fn ray_direction(&self, x: f32, y: f32, width: f32, height: f32) -> Vec3 {
+        let perspective_scale = (self.fov * 0.5).tan();
+        let screen_x = ((2.0 * x) / width - 1.0) * self.aspect(width, height) * perspective_scale;
+        let screen_y = (-(2.0 * y) / height + 1.0) * perspective_scale;
+        self.transform_vector(&Vec3::new(screen_x, screen_y, -1.0))
+    }
+
+    // Rayo primario (origen, dirección) que pasa por el pixel (x, y) según la proyección
+    pub fn primary_ray(&self, x: f32, y: f32, width: f32, height: f32) -> (Vec3, Vec3) {
+        match self.projection {
+            Projection::Perspective => {
+                let direction = self.ray_direction(x, y, width, height);
+                (self.ray_origin(&direction), direction)
+            }
+            Projection::Orthographic => {
+                let (right, up, forward) = self.basis();
+                let half_height = self.ortho_height * 0.5;
+                let screen_x = ((2.0 * x) / width - 1.0) * self.aspect(width, height) * half_height;
+                let screen_y = (-(2.0 * y) / height + 1.0) * half_height;
+                let origin = self.position + right * screen_x + up * screen_y + forward * self.near;
+                (origin, forward)
+            }
+        }
+    }
+
+    // Ancho que cubre un pixel a una distancia d de la cámara: `width + spread * d`
+    pub fn pixel_footprint(&self, height: f32) -> (f32, f32) {
+        match self.projection {
+            Projection::Perspective => (0.0, 2.0 * (self.fov * 0.5).tan() / height),
+            Projection::Orthographic => (self.ortho_height / height, 0.0),
+        }
+    }
+
+    // Origen del rayo primario con esa dirección, sobre el plano cercano
+    pub fn ray_origin(&self, direction: &Vec3) -> Vec3 {
+        if self.near <= 0.0 {
+            return self.position;
+        }
+        let (_, _, forward) = self.basis();
+        self.position + direction * (self.near / direction.dot(&forward))
+    }
+
+    // Cuánto se ve un punto según los planos de recorte: 1 dentro, 0 fuera y un valor
+    // intermedio en la franja de `clip_fade`. La profundidad se mide a lo largo de la vista.
+    pub fn clip_visibility(&self, point: &Vec3) -> f32 {
+        if self.far.is_none() && self.clip_fade <= 0.0 {
+            return 1.0;
+        }
+        let (_, _, forward) = self.basis();
+        let depth = (point - self.position).dot(&forward);
+        let far = self.far.unwrap_or(f32::INFINITY);
+        if depth > far {
+            return 0.0;
+        }
+        if self.clip_fade <= 0.0 {
+            return 1.0;
+        }
+        let from_near = (depth - self.near) / self.clip_fade;
+        let from_far = (far - depth) / self.clip_fade;
+        from_near.min(from_far).clamp(0.0, 1.0)
+    }
+
+    // Distancia de un punto al plano de la cámara, a lo largo de la vista
+    pub fn depth(&self, point: &Vec3) -> f32 {
+        let (_, _, forward) = self.basis();
+        (point - self.position).dot(&forward)
+    }
+
+    pub fn focus(&self) -> f32 {
+        self.focus_distance
+            .unwrap_or_else(|| (self.target - self.position).magnitude())
+    }
+
+    // Desvía un rayo primario como si pasara por una lente delgada. (u, v) en [0, 1) eligen
+    // el punto de la apertura; los puntos en el plano de enfoque quedan nítidos.
+    pub fn lens_ray(&self, origin: &Vec3, direction: &Vec3, u: f32, v: f32) -> (Vec3, Vec3) {
+        if self.aperture <= 0.0 {
+            return (*origin, *direction);
+        }
+
+        let (right, up, forward) = self.basis();
+        // El origen ya está sobre el plano cercano
+        let focus_depth = (self.focus() - self.near).max(0.0);
+        let focus_point = origin + direction * (focus_depth / direction.dot(&forward));
+
+        // Punto uniforme en el disco de la lente
+        let radius = self.aperture * u.sqrt();
+        let angle = 2.0 * PI * v;
+        let lens_point = origin + (right * angle.cos() + up * angle.sin()) * radius;
+        (lens_point, (focus_point - lens_point).normalize())
+    }
+
+    // Inverso de primary_ray: pixel donde se ve un punto, o None si está detrás de la cámara
+    pub fn project(&self, point: &Vec3, width: f32, height: f32) -> Option<(f32, f32)> {
+        let (right, up, forward) = self.basis();
+
+        let offset = point - self.position;
+        let depth = offset.dot(&forward);
+        if depth <= self.near.max(1e-3) {
+            return None;
+        }
+
+        // Mitad de la altura visible a la distancia del punto
+        let half_height = match self.projection {
+            Projection::Perspective => depth * (self.fov * 0.5).tan(),
+            Projection::Orthographic => self.ortho_height * 0.5,
+        };
+        let aspect = self.aspect(width, height);
+        let screen_x = offset.dot(&right) / (aspect * half_height);
+        let screen_y = offset.dot(&up) / half_height;
+        Some((
+            (screen_x + 1.0) * width * 0.5,
+            (1.0 - screen_y) * height * 0.5,
+        ))
+    }
+
     pub fn rotate_around_target(&mut self, delta_yaw: f32, delta_pitch: f32) {
-        let offset = self.position - self.target;
-        let radius = offset.magnitude();
-        let current_yaw = offset.z.atan2(offset.x);
-        let xz_distance = (offset.x * offset.x + offset.z * offset.z).sqrt();
-        let current_pitch = (-offset.y).atan2(xz_distance);
-        let adjusted_yaw = (current_yaw + delta_yaw) % (2.0 * PI);
-        let adjusted_pitch = (current_pitch + delta_pitch).clamp(-PI / 2.0 + 0.1, PI / 2.0 - 0.1);
+        let radius = (self.position - self.target).magnitude();
+        let adjusted_yaw = (self.yaw() + delta_yaw) % (2.0 * PI);
+        let adjusted_pitch = (self.pitch() + delta_pitch).clamp(-PI / 2.0 + 0.1, PI / 2.0 - 0.1);
         let new_position = self.target + Vec3::new(
             radius * adjusted_yaw.cos() * adjusted_pitch.cos(),
             -radius * adjusted_pitch.sin(),
@@ -39,15 +240,65 @@ impl Camera {
         );
 
         self.position = new_position;
+        self.update_view();
+    }
+
+    // Inclina la cámara alrededor de la dirección de vista
+    pub fn roll_by(&mut self, delta_roll: f32) {
+        self.roll = (self.roll + delta_roll) % (2.0 * PI);
+        self.update_view();
     }
 
     pub fn move_towards_target(&mut self, distance: f32) {
         let forward = (self.target - self.position).normalize();
         self.position += forward * distance;
+        self.update_view();
+    }
+
+    // Cambia el campo de visión sin mover la cámara; un delta negativo acerca la imagen.
+    // En ortográfica se escala la altura visible en la misma proporción.
+    pub fn zoom(&mut self, delta_fov: f32) {
+        match self.projection {
+            Projection::Perspective => {
+                self.fov = (self.fov + delta_fov).clamp(MIN_FOV, MAX_FOV);
+            }
+            Projection::Orthographic => {
+                let factor = (self.fov + delta_fov) / self.fov;
+                self.ortho_height =
+                    (self.ortho_height * factor).clamp(MIN_ORTHO_HEIGHT, MAX_ORTHO_HEIGHT);
+            }
+        }
+    }
+
+    // Vista isométrica clásica: ortográfica mirando el objetivo en diagonal desde arriba,
+    // con el mismo ángulo respecto a los tres ejes y a la misma distancia que antes
+    pub fn set_isometric(&mut self) {
+        let distance = (self.position - self.target).magnitude();
+        self.position = self.target + Vec3::new(1.0, 1.0, 1.0).normalize() * distance;
+        self.up_direction = Vec3::new(0.0, 1.0, 0.0);
+        self.roll = 0.0;
+        self.update_view();
+        if self.projection == Projection::Perspective {
+            self.toggle_projection();
+        }
+    }
+
+    // Al pasar a ortográfica se conserva el tamaño con que se ve el objetivo
+    pub fn toggle_projection(&mut self) {
+        self.projection = match self.projection {
+            Projection::Perspective => {
+                let distance = (self.position - self.target).magnitude();
+                self.ortho_height = (2.0 * distance * (self.fov * 0.5).tan())
+                    .clamp(MIN_ORTHO_HEIGHT, MAX_ORTHO_HEIGHT);
+                Projection::Orthographic
+            }
+            Projection::Orthographic => Projection::Perspective,
+        };
     }
 
     pub fn move_away_from_target(&mut self, distance: f32) {
         let forward = (self.target - self.position).normalize();
         self.position -= forward * distance;
+        self.update_view();
     }
 }
@@ -0,0 +1,107 @@
+// camera_motion.rs
+
+use std::f32::consts::PI;
+
+use crate::camera::Camera;
+
+// Velocidad de giro alrededor del objetivo con las teclas, en radianes por segundo
+const ORBIT_SPEED: f32 = PI * 0.75;
+// Radianes de campo de visión por segundo con Z / X
+const ZOOM_SPEED: f32 = PI / 4.0;
+// Radianes de inclinación por segundo con Q / E
+const ROLL_SPEED: f32 = PI / 4.0;
+// Qué tan rápido se alcanza la velocidad pedida por las teclas (por segundo); más alto
+// responde antes, más bajo deja más inercia al soltar
+const RESPONSIVENESS: f32 = 8.0;
+// Frenado del acercamiento con la rueda (por segundo)
+const DOLLY_DAMPING: f32 = 6.0;
+// Bloques que recorre en total cada paso de la rueda hasta detenerse
+const DOLLY_STEP: f32 = 0.2;
+// Por debajo de esta velocidad el movimiento se da por terminado
+const REST_SPEED: f32 = 1e-3;
+
+// Suaviza el movimiento de la cámara: las teclas fijan una velocidad objetivo a la que se
+// llega (y de la que se sale) de forma exponencial, y la rueda da impulsos que se frenan
+// solos. Todo avanza con `delta_time`, así se mueve igual a cualquier cantidad de fps.
+#[derive(Debug, Clone, Default)]
+pub struct CameraMotion {
+    yaw_velocity: f32,
+    pitch_velocity: f32,
+    zoom_velocity: f32,
+    roll_velocity: f32,
+    dolly_velocity: f32,
+}
+
+impl CameraMotion {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Cada paso de la rueda hacia adelante acerca la cámara al objetivo y hacia atrás la aleja
+    pub fn push_dolly(&mut self, scroll: f32) {
+        self.dolly_velocity += scroll * DOLLY_STEP * DOLLY_DAMPING;
+    }
+
+    // Detiene todo movimiento pendiente, por ejemplo al saltar a la vista isométrica
+    pub fn stop(&mut self) {
+        *self = Self::default();
+    }
+
+    // Si la cámara todavía se está moviendo por las teclas, la rueda o la inercia
+    pub fn is_moving(&self) -> bool {
+        self.yaw_velocity != 0.0
+            || self.pitch_velocity != 0.0
+            || self.zoom_velocity != 0.0
+            || self.roll_velocity != 0.0
+            || self.dolly_velocity != 0.0
+    }
+
+    // `yaw`, `pitch`, `zoom` y `roll` van de -1 a 1 según las teclas presionadas en este frame
+    pub fn update(
+        &mut self,
+        camera: &mut Camera,
+        yaw: f32,
+        pitch: f32,
+        zoom: f32,
+        roll: f32,
+        delta_time: f32,
+    ) {
+        let blend = 1.0 - (-RESPONSIVENESS * delta_time).exp();
+        self.yaw_velocity = approach(self.yaw_velocity, yaw * ORBIT_SPEED, blend);
+        self.pitch_velocity = approach(self.pitch_velocity, pitch * ORBIT_SPEED, blend);
+        self.zoom_velocity = approach(self.zoom_velocity, zoom * ZOOM_SPEED, blend);
+        self.roll_velocity = approach(self.roll_velocity, roll * ROLL_SPEED, blend);
+
+        if self.yaw_velocity != 0.0 || self.pitch_velocity != 0.0 {
+            camera.rotate_around_target(
+                self.yaw_velocity * delta_time,
+                self.pitch_velocity * delta_time,
+            );
+        }
+        if self.zoom_velocity != 0.0 {
+            camera.zoom(self.zoom_velocity * delta_time);
+        }
+        if self.roll_velocity != 0.0 {
+            camera.roll_by(self.roll_velocity * delta_time);
+        }
+
+        // Integral exacta del frenado para que la distancia total no dependa de los fps
+        if self.dolly_velocity != 0.0 {
+            let decay = (-DOLLY_DAMPING * delta_time).exp();
+            camera.move_towards_target(self.dolly_velocity * (1.0 - decay) / DOLLY_DAMPING);
+            self.dolly_velocity *= decay;
+            if self.dolly_velocity.abs() < REST_SPEED {
+                self.dolly_velocity = 0.0;
+            }
+        }
+    }
+}
+
+fn approach(velocity: f32, target: f32, blend: f32) -> f32 {
+    let next = velocity + (target - velocity) * blend;
+    if target == 0.0 && next.abs() < REST_SPEED {
+        0.0
+    } else {
+        next
+    }
+}
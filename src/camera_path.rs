@@ -0,0 +1,43 @@
+// camera_path.rs
+
+use nalgebra_glm::Vec3;
+
+use crate::animation::{Interpolation, Track};
+use crate::camera::Camera;
+
+// Recorrido de cámara por claves: en cada clave se fija la posición y el punto al que mira.
+// Se carga con `scene_file::load_camera_path`.
+#[derive(Debug, Clone)]
+pub struct CameraPath {
+    pub position: Track<Vec3>,
+    pub target: Track<Vec3>,
+}
+
+impl CameraPath {
+    pub fn new(interpolation: Interpolation, looping: bool) -> Self {
+        CameraPath {
+            position: Track::new(interpolation, looping),
+            target: Track::new(interpolation, looping),
+        }
+    }
+
+    pub fn add_key(&mut self, time: f32, position: Vec3, target: Vec3) {
+        self.position.add_key(time, position);
+        self.target.add_key(time, target);
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.position.duration()
+    }
+
+    // Coloca la cámara donde indica el recorrido a los `time` segundos
+    pub fn apply(&self, camera: &mut Camera, time: f32) {
+        if let (Some(position), Some(target)) =
+            (self.position.sample(time), self.target.sample(time))
+        {
+            camera.position = position;
+            camera.target = target;
+            camera.update_view();
+        }
+    }
+}
@@ -0,0 +1,82 @@
+// chunk.rs
+
+pub const CHUNK_SIZE: usize = 16;
+pub const CHUNK_VOLUME: usize = CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE;
+
+// Índice dentro de la paleta de materiales del mundo; 0 siempre es aire
+pub type BlockId = u16;
+pub const AIR: BlockId = 0;
+
+#[derive(Debug, Clone)]
+pub struct Chunk {
+    blocks: Vec<BlockId>,
+    solid_count: usize,
+}
+
+impl Chunk {
+    pub fn new() -> Self {
+        Chunk {
+            blocks: vec![AIR; CHUNK_VOLUME],
+            solid_count: 0,
+        }
+    }
+
+    fn index(x: usize, y: usize, z: usize) -> usize {
+        (y * CHUNK_SIZE + z) * CHUNK_SIZE + x
+    }
+
+    pub fn get(&self, x: usize, y: usize, z: usize) -> BlockId {
+        self.blocks[Self::index(x, y, z)]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, z: usize, block: BlockId) {
+        let slot = &mut self.blocks[Self::index(x, y, z)];
+        if *slot == AIR && block != AIR {
+            self.solid_count += 1;
+        } else if *slot != AIR && block == AIR {
+            self.solid_count -= 1;
+        }
+        *slot = block;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.solid_count == 0
+    }
+
+    // Todos los bloques en orden y, z, x, el mismo en que se guardan
+    pub fn as_slice(&self) -> &[BlockId] {
+        &self.blocks
+    }
+
+    // Chunk a partir de los bloques en el orden de `as_slice`; None si no son exactamente
+    // los de un chunk
+    pub fn from_slice(blocks: &[BlockId]) -> Option<Self> {
+        if blocks.len() != CHUNK_VOLUME {
+            return None;
+        }
+        Some(Chunk {
+            blocks: blocks.to_vec(),
+            solid_count: blocks.iter().filter(|&&block| block != AIR).count(),
+        })
+    }
+
+    // Recorre los bloques sólidos como (x, y, z, id) en coordenadas locales
+    pub fn blocks(&self) -> impl Iterator<Item = (usize, usize, usize, BlockId)> + '_ {
+        self.blocks
+            .iter()
+            .enumerate()
+            .filter(|(_, &block)| block != AIR)
+            .map(|(index, &block)| {
+                let x = index % CHUNK_SIZE;
+                let z = (index / CHUNK_SIZE) % CHUNK_SIZE;
+                let y = index / (CHUNK_SIZE * CHUNK_SIZE);
+                (x, y, z, block)
+            })
+    }
+}
+
+impl Default for Chunk {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,1003 @@
+// cli.rs
+
+use clap::error::ErrorKind;
+use clap::{ArgAction, CommandFactory, Parser, Subcommand};
+use std::ffi::OsString;
+use std::path::PathBuf;
+
+use proyecto2::export::{SequenceFormat, SequenceMode, SequenceSettings};
+use proyecto2::gallery::{Showcase, SHOWCASES};
+use proyecto2::interactive::{DisplayMode, Resolution};
+use proyecto2::parity::DEFAULT_TOLERANCE;
+use proyecto2::render::MAX_DEPTH;
+use proyecto2::stereo::{StereoLayout, StereoSettings};
+use proyecto2::sweep::SweepAxis;
+use proyecto2::terrain::TerrainSettings;
+
+// Sin --scene ni opciones de terreno se continúa desde este mundo si existe
+pub const DEFAULT_WORLD: &str = "scenes/mundo.ron";
+// Tamaño de cada imagen de la hoja de contactos
+const SWEEP_CELL_WIDTH: usize = 160;
+const SWEEP_CELL_HEIGHT: usize = 120;
+// Tamaño de las imágenes que compara parity; la referencia prueba cada cubo por rayo
+const PARITY_WIDTH: usize = 160;
+const PARITY_HEIGHT: usize = 120;
+
+pub enum Command {
+    Interactive,
+    Export(SequenceSettings),
+    Render(RenderSettings),
+    Parity(ParitySettings),
+    // Modelo OBJ de salida
+    Obj(PathBuf),
+}
+
+pub struct RenderSettings {
+    // Asignaciones `ruta=valor` en el orden en que se dieron
+    pub overrides: Vec<String>,
+    // Con parámetros a variar se genera una hoja de contactos en lugar de una imagen
+    pub sweep: Vec<SweepAxis>,
+    pub output: PathBuf,
+    pub width: usize,
+    pub height: usize,
+    pub samples: u32,
+    pub max_depth: u32,
+    pub time_of_day: Option<f32>,
+    // Filtrar el ruido antes de guardar
+    pub denoise: bool,
+    // Guardar las pasadas auxiliares junto a la imagen
+    pub aovs: bool,
+}
+
+pub struct ParitySettings {
+    pub width: usize,
+    pub height: usize,
+    pub samples: u32,
+    pub tolerance: f32,
+    pub time_of_day: Option<f32>,
+}
+
+pub struct Options {
+    pub scene: Option<PathBuf>,
+    pub gallery: Option<&'static Showcase>,
+    pub terrain: Option<TerrainSettings>,
+    pub world: PathBuf,
+    pub firefly_clamp: Option<f32>,
+    pub roulette: Option<f32>,
+    pub camera_path: Option<PathBuf>,
+    pub demo: bool,
+    // Límite de frames por segundo de la ventana; 0 no limita
+    pub max_fps: Option<f32>,
+    pub resolution: Option<Resolution>,
+    pub display: Option<DisplayMode>,
+    pub screen_size: Option<(usize, usize)>,
+    // Archivo de teclas pedido con --keys; sin él se usa teclas.cfg si existe
+    pub keys: Option<PathBuf>,
+    pub transparent: bool,
+    pub dither: bool,
+    pub dynamic_sky: bool,
+    pub moon: bool,
+    pub near: Option<f32>,
+    pub far: Option<f32>,
+    pub command: Command,
+}
+
+// La ayuda sigue este formato en lugar del de clap, para que los títulos queden en
+// castellano como el resto del programa
+const HELP_TEMPLATE: &str = "{about-with-newline}\nUso: {usage}\n\n{all-args}";
+
+const TERRAIN: &str = "Generar un terreno con ruido en lugar del diorama";
+const SEQUENCE: &str = "Exportar una secuencia de frames";
+const IMAGE: &str = "Renderizar una sola imagen (subcomando render y --headless)";
+const PARITY: &str =
+    "Comparar los caminos rápidos del render con uno de referencia (subcomando parity)";
+
+// Las opciones valen antes y después del subcomando; las reglas de cuál se combina con
+// cuál según el subcomando están en `parse_args`
+#[derive(Parser)]
+#[command(
+    name = "Proyecto2",
+    about = "Sin opciones abre la ventana interactiva.",
+    override_usage = "Proyecto2 [opciones]
+       Proyecto2 render <escena> [--set ruta=valor]... [opciones]
+       Proyecto2 parity [opciones]
+       Proyecto2 obj [opciones]
+       Proyecto2 --headless [opciones]",
+    help_template = HELP_TEMPLATE,
+    next_help_heading = "Opciones",
+    subcommand_help_heading = "Subcomandos",
+    subcommand_value_name = "subcomando",
+    disable_help_flag = true,
+    disable_help_subcommand = true,
+    args_override_self = true
+)]
+struct Cli {
+    #[command(subcommand)]
+    subcommand: Option<Sub>,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "archivo",
+        help = "Cargar la escena desde un archivo .scene"
+    )]
+    scene: Option<PathBuf>,
+    #[arg(
+        long,
+        global = true,
+        value_name = "archivo",
+        default_value = DEFAULT_WORLD,
+        hide_default_value = true,
+        help = "Mundo editado que se carga al iniciar y se guarda al salir (por defecto: \
+                scenes/mundo.ron)"
+    )]
+    world: PathBuf,
+    #[arg(
+        long,
+        global = true,
+        value_name = "archivo",
+        help = "Mover la cámara por un recorrido de claves (P lo pausa en la ventana; al \
+                exportar reemplaza la órbita)"
+    )]
+    camera_path: Option<PathBuf>,
+    #[arg(
+        long,
+        global = true,
+        value_name = "nombre",
+        num_args = 0..=1,
+        value_parser = showcase,
+        conflicts_with_all = ["scene", "terrain", "terrain_size", "sea_level"],
+        help = "Empezar por una escena de la galería (refraccion, sombras, noche, pbr, \
+                patrones, niebla; por defecto la primera) con su menú abierto; no carga ni \
+                guarda el mundo. En la ventana G abre el menú"
+    )]
+    gallery: Option<Option<&'static Showcase>>,
+    #[arg(
+        long,
+        global = true,
+        help = "Sesión reproducible: no carga ni guarda el mundo, empieza a la misma hora y \
+                avanza un paso fijo por frame en lugar del reloj"
+    )]
+    demo: bool,
+    #[arg(
+        long,
+        global = true,
+        value_name = "n",
+        value_parser = non_negative,
+        help = "Frames por segundo máximos de la ventana; 0 no limita (por defecto: 60)"
+    )]
+    max_fps: Option<f32>,
+    #[arg(
+        long,
+        global = true,
+        value_name = "n",
+        value_parser = at_least_one,
+        conflicts_with = "fixed_size",
+        help = "Un pixel del render por cada n x n de la ventana; el render sigue el tamaño \
+                de la ventana (por defecto: 1)"
+    )]
+    render_scale: Option<usize>,
+    #[arg(
+        long,
+        global = true,
+        value_name = "ancho>x<alto",
+        value_parser = size,
+        help = "Renderizar siempre a ese tamaño y estirarlo a la ventana sin deformarlo"
+    )]
+    fixed_size: Option<(usize, usize)>,
+    #[arg(
+        long,
+        global = true,
+        conflicts_with = "fullscreen",
+        help = "Abrir la ventana sin bordes ni barra de título"
+    )]
+    borderless: bool,
+    #[arg(
+        long,
+        global = true,
+        help = "Abrir la ventana en pantalla completa (F11 cambia entre ventana, sin bordes \
+                y pantalla completa)"
+    )]
+    fullscreen: bool,
+    #[arg(
+        long,
+        global = true,
+        value_name = "ancho>x<alto",
+        value_parser = size,
+        help = "Tamaño de la pantalla completa (por defecto: 1920x1080)"
+    )]
+    screen_size: Option<(usize, usize)>,
+    #[arg(
+        long,
+        global = true,
+        value_name = "archivo",
+        help = "Teclas de la ventana, una acción por línea (por defecto: teclas.cfg si existe)"
+    )]
+    keys: Option<PathBuf>,
+    #[arg(
+        long,
+        global = true,
+        value_name = "bloques",
+        value_parser = non_negative,
+        help = "Ocultar lo que esté más cerca de la cámara (plano cercano)"
+    )]
+    near: Option<f32>,
+    #[arg(
+        long,
+        global = true,
+        value_name = "bloques",
+        value_parser = positive,
+        help = "Ocultar lo que esté más lejos, para aislar una construcción"
+    )]
+    far: Option<f32>,
+    #[arg(
+        long,
+        global = true,
+        help = "Fondo transparente: donde se vería el cielo la imagen queda con alfa 0 (PNG \
+                RGBA con alfa premultiplicado)"
+    )]
+    transparent: bool,
+    #[arg(
+        long,
+        global = true,
+        help = "Al pasar la imagen a 8 bits repartir el error con ruido azul, para que los \
+                degradados del cielo no se vean en escalones"
+    )]
+    dither: bool,
+    #[arg(
+        long,
+        global = true,
+        help = "Reemplazar el cielo por uno calculado que sigue al sol, con amanecer, \
+                atardecer y estrellas de noche"
+    )]
+    dynamic_sky: bool,
+    #[arg(
+        long,
+        global = true,
+        help = "Agregar una luna del lado opuesto al sol que ilumina las noches y se ve en el \
+                cielo"
+    )]
+    moon: bool,
+    #[arg(
+        long,
+        global = true,
+        value_name = "n",
+        value_parser = non_negative,
+        help = "Luminancia máxima de cada muestra de luz indirecta; 0 no limita (por \
+                defecto: 1, o el valor de la escena)"
+    )]
+    firefly_clamp: Option<f32>,
+    #[arg(
+        long,
+        global = true,
+        value_name = "n",
+        value_parser = fraction,
+        help = "Cortar al azar los reflejos y refracciones que traen menos de esa fracción \
+                del color del pixel, de 0 a 1 (por defecto: 0, o el valor de la escena)"
+    )]
+    roulette: Option<f32>,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "semilla",
+        conflicts_with = "scene",
+        help_heading = TERRAIN,
+        help = "Semilla del generador"
+    )]
+    terrain: Option<u64>,
+    #[arg(
+        long,
+        global = true,
+        value_name = "x>x<z",
+        value_parser = terrain_size,
+        conflicts_with = "scene",
+        help_heading = TERRAIN,
+        help = "Tamaño del terreno en bloques (por defecto: 32x32)"
+    )]
+    terrain_size: Option<(i32, i32)>,
+    #[arg(
+        long,
+        global = true,
+        value_name = "y",
+        allow_negative_numbers = true,
+        conflicts_with = "scene",
+        help_heading = TERRAIN,
+        help = "Altura hasta donde se llena de agua (por defecto: 4)"
+    )]
+    sea_level: Option<i32>,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "frames",
+        help_heading = SEQUENCE,
+        help = "Renderiza <frames> imágenes sin abrir la ventana"
+    )]
+    export: Option<u32>,
+    #[arg(
+        long,
+        global = true,
+        value_name = "orbit|day",
+        value_parser = sequence_mode,
+        help_heading = SEQUENCE,
+        help = "Girar la cámara (orbit) o avanzar el ciclo de día (day)"
+    )]
+    mode: Option<SequenceMode>,
+    #[arg(
+        long,
+        global = true,
+        help_heading = SEQUENCE,
+        help = "Guardar un GIF animado en lugar de PNGs numerados"
+    )]
+    gif: bool,
+    #[arg(
+        long,
+        global = true,
+        value_name = "ruta",
+        help_heading = SEQUENCE,
+        help = "Carpeta de salida (por defecto: frames); con render, la imagen (por defecto: \
+                render.png, o .exr en punto flotante) y con obj, el modelo (por defecto: \
+                escena.obj)"
+    )]
+    output: Option<PathBuf>,
+    #[arg(
+        long,
+        global = true,
+        value_name = "ancho>x<alto",
+        value_parser = size,
+        help_heading = SEQUENCE,
+        help = "Resolución de cada frame o imagen (por defecto: 600x400)"
+    )]
+    size: Option<(usize, usize)>,
+    #[arg(
+        long,
+        global = true,
+        value_name = "n",
+        value_parser = at_least_one,
+        help_heading = SEQUENCE,
+        help = "Cambiar solo el ancho de --size"
+    )]
+    width: Option<usize>,
+    #[arg(
+        long,
+        global = true,
+        value_name = "n",
+        value_parser = at_least_one,
+        help_heading = SEQUENCE,
+        help = "Cambiar solo el alto de --size"
+    )]
+    height: Option<usize>,
+    #[arg(
+        long,
+        global = true,
+        value_name = "n",
+        help_heading = SEQUENCE,
+        help = "Muestras por eje de cada pixel (por defecto: 1)"
+    )]
+    samples: Option<u32>,
+    #[arg(
+        long,
+        global = true,
+        value_name = "n",
+        help_heading = SEQUENCE,
+        help = "Rebotes de reflexión y refracción de cada rayo (por defecto: 3)"
+    )]
+    max_depth: Option<u32>,
+    #[arg(
+        long,
+        global = true,
+        value_name = "n",
+        help_heading = SEQUENCE,
+        help = "Velocidad del GIF (por defecto: 30)"
+    )]
+    fps: Option<u32>,
+    #[arg(
+        long,
+        global = true,
+        value_name = "0..1",
+        help_heading = SEQUENCE,
+        help = "Hora del día inicial (por defecto: 0.3); con render, sin --time la escena no \
+                aplica el ciclo de día"
+    )]
+    time: Option<f32>,
+    #[arg(
+        long,
+        global = true,
+        value_name = "sbs|tb",
+        value_parser = stereo_layout,
+        help_heading = SEQUENCE,
+        help = "Dos vistas por frame para video VR, lado a lado (sbs) o arriba y abajo (tb); \
+                --size indica el tamaño de cada vista"
+    )]
+    stereo: Option<StereoLayout>,
+    #[arg(
+        long,
+        global = true,
+        value_name = "bloques",
+        value_parser = non_negative,
+        help_heading = SEQUENCE,
+        help = "Distancia entre los ojos (por defecto: 0.064)"
+    )]
+    ipd: Option<f32>,
+    #[arg(
+        long,
+        global = true,
+        value_name = "bloques",
+        value_parser = positive,
+        help_heading = SEQUENCE,
+        help = "Distancia donde las vistas coinciden (por defecto: la de enfoque)"
+    )]
+    convergence: Option<f32>,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "ruta>=<valor",
+        help_heading = IMAGE,
+        help = "Cambiar un valor de la escena antes de renderizar; se puede repetir (ej. \
+                lights[0].intensity=2 o materials.agua.ior=1.4)"
+    )]
+    set: Vec<String>,
+    #[arg(
+        long,
+        global = true,
+        help_heading = IMAGE,
+        help = "Lo mismo que render, pero la escena sale de --scene, --gallery, las opciones \
+                de terreno o el mundo guardado, como en la ventana"
+    )]
+    headless: bool,
+    #[arg(
+        long,
+        global = true,
+        value_name = "ruta>=<i>:<f>:<n",
+        value_parser = SweepAxis::parse,
+        help_heading = IMAGE,
+        help = "Hoja de contactos: una imagen pequeña por cada uno de <n> valores entre <i> y \
+                <f> (o por cada valor de una lista a|b|c), con su rótulo; un segundo --vary \
+                agrega filas. La ruta 'time' recorre la hora del día. --size es el tamaño de \
+                cada celda (por defecto: 160x120)"
+    )]
+    vary: Vec<SweepAxis>,
+    #[arg(
+        long,
+        global = true,
+        help_heading = IMAGE,
+        help = "Filtrar el ruido de la imagen guiándose por las normales y la profundidad de \
+                lo que se ve; no se usa con --vary"
+    )]
+    denoise: bool,
+    #[arg(
+        long,
+        global = true,
+        help_heading = IMAGE,
+        help = "Guardar junto a la imagen sus pasadas auxiliares: profundidad, normales, \
+                albedo y luz directa e indirecta (<nombre>_depth.png, etc., o canales del \
+                mismo archivo si es .exr); no se usa con --vary"
+    )]
+    aov: bool,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "t",
+        value_parser = non_negative,
+        help_heading = PARITY,
+        help = "Diferencia máxima por canal (por defecto: 1/255); si algún camino la supera \
+                el programa termina con error. --size es de 160x120 por defecto"
+    )]
+    tolerance: Option<f32>,
+
+    #[arg(short, long, global = true, action = ArgAction::Help, help = "Mostrar esta ayuda")]
+    help: Option<bool>,
+}
+
+#[derive(Subcommand)]
+enum Sub {
+    #[command(
+        about = "Renderizar una sola imagen de una escena",
+        override_usage = "Proyecto2 render <escena> [--set ruta=valor]... [opciones]",
+        help_template = HELP_TEMPLATE,
+        disable_help_flag = true
+    )]
+    Render {
+        #[arg(
+            value_name = "escena",
+            help_heading = "Argumentos",
+            help = "Archivo .scene"
+        )]
+        path: PathBuf,
+    },
+    #[command(
+        about = "Comparar los caminos rápidos del render con uno de referencia",
+        override_usage = "Proyecto2 parity [opciones]",
+        help_template = HELP_TEMPLATE,
+        disable_help_flag = true
+    )]
+    Parity,
+    #[command(
+        about = "Exportar la escena como modelo 3D para Blender; los materiales van en un \
+                 .mtl con el mismo nombre y las texturas sin archivo en PNG al lado",
+        override_usage = "Proyecto2 obj [opciones]",
+        help_template = HELP_TEMPLATE,
+        disable_help_flag = true
+    )]
+    Obj,
+}
+
+// Lee los argumentos, con el nombre del programa primero. `--help` y los errores vuelven
+// como `clap::Error`, que se muestra y termina el programa con `exit`.
+pub fn parse_args<I, T>(args: I) -> Result<Options, clap::Error>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let cli = Cli::try_parse_from(args)?;
+    let conflict = |message: &str| Err(Cli::command().error(ErrorKind::ArgumentConflict, message));
+
+    let (mut render, parity, obj) = match &cli.subcommand {
+        Some(Sub::Render { .. }) => (true, false, false),
+        Some(Sub::Parity) => (false, true, false),
+        Some(Sub::Obj) => (false, false, true),
+        None => (false, false, false),
+    };
+    // --scene después de render reemplaza a la escena del subcomando
+    let scene = match cli.subcommand {
+        Some(Sub::Render { path }) => cli.scene.or(Some(path)),
+        _ => cli.scene,
+    };
+    let export = cli.export.is_some();
+
+    if cli.headless {
+        if render || parity || obj {
+            return conflict("--headless ya renderiza una imagen; no lleva subcomando");
+        }
+        render = true;
+    }
+
+    if (!cli.set.is_empty() || !cli.vary.is_empty()) && !render {
+        return conflict("--set y --vary solo se usan con el subcomando render");
+    }
+    if cli.vary.len() > 2 {
+        return conflict("La hoja de contactos admite dos --vary como máximo");
+    }
+    if (cli.denoise || cli.aov) && !render {
+        return conflict("--denoise y --aov solo se usan con render y --headless");
+    }
+    if (cli.denoise || cli.aov) && !cli.vary.is_empty() {
+        return conflict("--denoise y --aov no se combinan con --vary");
+    }
+    if render && export {
+        return conflict("render genera una sola imagen; no se combina con --export");
+    }
+    let window_only = render || parity || export;
+    if cli.max_fps.is_some() && window_only {
+        return conflict("--max-fps solo se usa con la ventana interactiva");
+    }
+    if (cli.render_scale.is_some() || cli.fixed_size.is_some()) && window_only {
+        return conflict(
+            "--render-scale y --fixed-size solo se usan con la ventana interactiva; \
+             para las imágenes usa --size",
+        );
+    }
+    if (cli.borderless || cli.fullscreen || cli.screen_size.is_some()) && window_only {
+        return conflict(
+            "--borderless, --fullscreen y --screen-size solo se usan con la ventana interactiva",
+        );
+    }
+    if cli.keys.is_some() && window_only {
+        return conflict("--keys solo se usa con la ventana interactiva");
+    }
+    if cli.max_depth.is_some() && !(render || export) {
+        return conflict("--max-depth solo se usa con render, --headless y --export");
+    }
+    if cli.tolerance.is_some() && !parity {
+        return conflict("--tolerance solo se usa con el subcomando parity");
+    }
+    if parity && export {
+        return conflict("parity no exporta imágenes; no se combina con --export");
+    }
+    if obj && export {
+        return conflict("obj exporta un modelo; no se combina con --export");
+    }
+
+    // Cualquier opción de terreno lo genera, con los valores por defecto para el resto
+    let terrain = (cli.terrain.is_some() || cli.terrain_size.is_some() || cli.sea_level.is_some())
+        .then(|| {
+            let mut terrain = TerrainSettings::default();
+            if let Some(seed) = cli.terrain {
+                terrain.seed = seed;
+            }
+            if let Some((width, depth)) = cli.terrain_size {
+                (terrain.width, terrain.depth) = (width, depth);
+            }
+            if let Some(sea_level) = cli.sea_level {
+                terrain.sea_level = sea_level;
+            }
+            terrain
+        });
+
+    let mut settings = SequenceSettings::default();
+    if let Some(frames) = cli.export {
+        settings.frames = frames;
+    }
+    if let Some(mode) = cli.mode {
+        settings.mode = mode;
+    }
+    if cli.gif {
+        settings.format = SequenceFormat::Gif;
+    }
+    if let Some(samples) = cli.samples {
+        settings.samples = samples;
+    }
+    if let Some(fps) = cli.fps {
+        settings.fps = fps;
+    }
+    // --ipd y --convergence sin --stereo usan el formato lado a lado
+    if cli.stereo.is_some() || cli.ipd.is_some() || cli.convergence.is_some() {
+        let stereo = settings.stereo.insert(StereoSettings::new(
+            cli.stereo.unwrap_or(StereoLayout::SideBySide),
+        ));
+        if let Some(separation) = cli.ipd {
+            stereo.eye_separation = separation;
+        }
+        if cli.convergence.is_some() {
+            stereo.convergence = cli.convergence;
+        }
+    }
+
+    // --width y --height cambian un solo lado del tamaño que toque
+    let resolve_size = |default: (usize, usize)| {
+        let (default_width, default_height) = cli.size.unwrap_or(default);
+        (
+            cli.width.unwrap_or(default_width),
+            cli.height.unwrap_or(default_height),
+        )
+    };
+
+    let command = if render {
+        let (width, height) = if cli.vary.is_empty() {
+            resolve_size((settings.width, settings.height))
+        } else {
+            resolve_size((SWEEP_CELL_WIDTH, SWEEP_CELL_HEIGHT))
+        };
+        Command::Render(RenderSettings {
+            overrides: cli.set,
+            sweep: cli.vary,
+            output: cli.output.unwrap_or_else(|| PathBuf::from("render.png")),
+            width,
+            height,
+            samples: settings.samples,
+            max_depth: cli.max_depth.unwrap_or(MAX_DEPTH),
+            time_of_day: cli.time,
+            denoise: cli.denoise,
+            aovs: cli.aov,
+        })
+    } else if parity {
+        let (width, height) = resolve_size((PARITY_WIDTH, PARITY_HEIGHT));
+        Command::Parity(ParitySettings {
+            width,
+            height,
+            samples: settings.samples,
+            tolerance: cli.tolerance.unwrap_or(DEFAULT_TOLERANCE),
+            time_of_day: cli.time,
+        })
+    } else if obj {
+        Command::Obj(cli.output.unwrap_or_else(|| PathBuf::from("escena.obj")))
+    } else if export {
+        (settings.width, settings.height) = resolve_size((settings.width, settings.height));
+        if let Some(max_depth) = cli.max_depth {
+            settings.max_depth = max_depth;
+        }
+        if let Some(output) = cli.output {
+            settings.output_dir = output;
+        }
+        if let Some(time_of_day) = cli.time {
+            settings.time_of_day = time_of_day;
+        }
+        Command::Export(settings)
+    } else {
+        Command::Interactive
+    };
+
+    let resolution = match (cli.render_scale, cli.fixed_size) {
+        (Some(scale), _) => Some(Resolution::Window(scale)),
+        (None, Some((width, height))) => Some(Resolution::Fixed(width, height)),
+        (None, None) => None,
+    };
+    let display = if cli.fullscreen {
+        Some(DisplayMode::Fullscreen)
+    } else if cli.borderless {
+        Some(DisplayMode::Borderless)
+    } else {
+        None
+    };
+    Ok(Options {
+        scene,
+        gallery: cli
+            .gallery
+            .map(|showcase| showcase.unwrap_or(&SHOWCASES[0])),
+        terrain,
+        world: cli.world,
+        firefly_clamp: cli.firefly_clamp,
+        roulette: cli.roulette,
+        camera_path: cli.camera_path,
+        demo: cli.demo,
+        max_fps: cli.max_fps,
+        resolution,
+        display,
+        screen_size: cli.screen_size,
+        keys: cli.keys,
+        transparent: cli.transparent,
+        dither: cli.dither,
+        dynamic_sky: cli.dynamic_sky,
+        moon: cli.moon,
+        near: cli.near,
+        far: cli.far,
+        command,
+    })
+}
+
+fn showcase(name: &str) -> Result<&'static Showcase, String> {
+    Showcase::find(name).ok_or_else(|| {
+        let names: Vec<&str> = SHOWCASES.iter().map(|showcase| showcase.name).collect();
+        format!(
+            "no hay una escena así en la galería (usa {})",
+            names.join(", ")
+        )
+    })
+}
+
+fn sequence_mode(mode: &str) -> Result<SequenceMode, String> {
+    match mode {
+        "orbit" => Ok(SequenceMode::Orbit),
+        "day" => Ok(SequenceMode::DayCycle),
+        _ => Err("modo desconocido (usa orbit o day)".to_string()),
+    }
+}
+
+fn stereo_layout(layout: &str) -> Result<StereoLayout, String> {
+    match layout {
+        "sbs" => Ok(StereoLayout::SideBySide),
+        "tb" => Ok(StereoLayout::TopBottom),
+        _ => Err("formato estéreo desconocido (usa sbs o tb)".to_string()),
+    }
+}
+
+// Tamaño escrito como ancho x alto, ej. 800x600
+fn size(text: &str) -> Result<(usize, usize), String> {
+    let (width, height) = text
+        .split_once('x')
+        .ok_or_else(|| "usa ancho x alto, ej. 800x600".to_string())?;
+    Ok((at_least_one(width)?, at_least_one(height)?))
+}
+
+fn terrain_size(text: &str) -> Result<(i32, i32), String> {
+    let (width, depth) = text
+        .split_once('x')
+        .ok_or_else(|| "usa x por z, ej. 64x64".to_string())?;
+    let width: i32 = number(width)?;
+    let depth: i32 = number(depth)?;
+    if width <= 0 || depth <= 0 {
+        return Err("el terreno tiene que medir al menos 1x1".to_string());
+    }
+    Ok((width, depth))
+}
+
+fn at_least_one(text: &str) -> Result<usize, String> {
+    match number(text)? {
+        0 => Err("tiene que ser al menos 1".to_string()),
+        value => Ok(value),
+    }
+}
+
+fn non_negative(text: &str) -> Result<f32, String> {
+    let value: f32 = number(text)?;
+    if value < 0.0 || value.is_nan() {
+        return Err("no puede ser negativo".to_string());
+    }
+    Ok(value)
+}
+
+fn positive(text: &str) -> Result<f32, String> {
+    let value: f32 = number(text)?;
+    if value <= 0.0 || value.is_nan() {
+        return Err("tiene que ser mayor que 0".to_string());
+    }
+    Ok(value)
+}
+
+fn fraction(text: &str) -> Result<f32, String> {
+    let value: f32 = number(text)?;
+    if !(0.0..=1.0).contains(&value) {
+        return Err("tiene que estar entre 0 y 1".to_string());
+    }
+    Ok(value)
+}
+
+fn number<T: std::str::FromStr>(text: &str) -> Result<T, String> {
+    text.parse()
+        .map_err(|_| "no es un número válido".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, clap::Error> {
+        parse_args(std::iter::once("Proyecto2").chain(args.iter().copied()))
+    }
+
+    fn rejects(args: &[&str]) -> bool {
+        parse(args).is_err()
+    }
+
+    #[test]
+    fn the_definition_is_consistent() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn opens_the_window_without_options() {
+        let options = parse(&[]).unwrap();
+        assert!(matches!(options.command, Command::Interactive));
+        assert_eq!(options.world, PathBuf::from(DEFAULT_WORLD));
+        assert!(options.scene.is_none() && options.terrain.is_none());
+    }
+
+    #[test]
+    fn render_keeps_its_scene_and_overrides_in_order() {
+        let options = parse(&[
+            "render", "a.scene", "--set", "x=1", "--set", "y=2", "--size", "100x50", "--width",
+            "80",
+        ])
+        .unwrap();
+        assert_eq!(options.scene, Some(PathBuf::from("a.scene")));
+        let Command::Render(settings) = options.command else {
+            panic!("se esperaba render");
+        };
+        assert_eq!(settings.overrides, ["x=1", "y=2"]);
+        assert_eq!((settings.width, settings.height), (80, 50));
+        assert_eq!(settings.output, PathBuf::from("render.png"));
+        assert_eq!(settings.max_depth, MAX_DEPTH);
+        assert!(rejects(&["render"]));
+    }
+
+    #[test]
+    fn headless_renders_without_a_subcommand() {
+        let options = parse(&["--headless", "--max-depth", "5", "--gallery"]).unwrap();
+        assert_eq!(
+            options.gallery.map(|showcase| showcase.name),
+            Some(SHOWCASES[0].name)
+        );
+        let Command::Render(settings) = options.command else {
+            panic!("se esperaba render");
+        };
+        assert_eq!(settings.max_depth, 5);
+        assert!(rejects(&["--headless", "render", "a.scene"]));
+        assert!(rejects(&["--headless", "parity"]));
+    }
+
+    #[test]
+    fn export_takes_the_sequence_options() {
+        let options = parse(&[
+            "--export",
+            "10",
+            "--gif",
+            "--max-depth",
+            "2",
+            "--output",
+            "out",
+            "--height",
+            "90",
+            "--ipd",
+            "0.1",
+        ])
+        .unwrap();
+        let Command::Export(settings) = options.command else {
+            panic!("se esperaba exportar");
+        };
+        assert_eq!(settings.frames, 10);
+        assert_eq!(settings.format, SequenceFormat::Gif);
+        assert_eq!(settings.max_depth, 2);
+        assert_eq!(settings.output_dir, PathBuf::from("out"));
+        assert_eq!((settings.width, settings.height), (600, 90));
+        let stereo = settings.stereo.unwrap();
+        assert_eq!(stereo.layout, StereoLayout::SideBySide);
+        assert_eq!(stereo.eye_separation, 0.1);
+    }
+
+    #[test]
+    fn subcommands_keep_their_own_options() {
+        assert!(rejects(&["render", "a.scene", "--export", "3"]));
+        assert!(rejects(&["parity", "--export", "3"]));
+        assert!(rejects(&["obj", "--export", "3"]));
+        assert!(rejects(&["--set", "x=1"]));
+        assert!(rejects(&["--denoise"]));
+        assert!(rejects(&[
+            "render",
+            "a.scene",
+            "--vary",
+            "time=0:1:3",
+            "--aov"
+        ]));
+        assert!(rejects(&["--max-depth", "2"]));
+        assert!(rejects(&["--tolerance", "0.1"]));
+        for option in ["--max-fps", "--render-scale", "--keys"] {
+            assert!(rejects(&["parity", option, "2"]), "{}", option);
+        }
+        assert!(rejects(&["--export", "3", "--fullscreen"]));
+
+        let Command::Parity(settings) = parse(&["parity", "--tolerance", "0.1"]).unwrap().command
+        else {
+            panic!("se esperaba parity");
+        };
+        assert_eq!(
+            (settings.width, settings.height),
+            (PARITY_WIDTH, PARITY_HEIGHT)
+        );
+        assert_eq!(settings.tolerance, 0.1);
+        let Command::Obj(output) = parse(&["obj"]).unwrap().command else {
+            panic!("se esperaba obj");
+        };
+        assert_eq!(output, PathBuf::from("escena.obj"));
+    }
+
+    #[test]
+    fn the_scene_comes_from_one_place() {
+        assert!(rejects(&["--scene", "a.scene", "--terrain", "3"]));
+        assert!(rejects(&["--gallery", "--scene", "a.scene"]));
+        assert!(rejects(&["--gallery", "noche", "--sea-level", "2"]));
+        assert!(rejects(&["--gallery", "nada"]));
+
+        let terrain = parse(&["--terrain-size", "8x4", "--sea-level", "-2"])
+            .unwrap()
+            .terrain
+            .unwrap();
+        assert_eq!((terrain.width, terrain.depth), (8, 4));
+        assert_eq!(terrain.sea_level, -2);
+        assert_eq!(terrain.seed, TerrainSettings::default().seed);
+    }
+
+    #[test]
+    fn window_options_exclude_each_other() {
+        assert!(rejects(&["--render-scale", "2", "--fixed-size", "10x10"]));
+        assert!(rejects(&["--borderless", "--fullscreen"]));
+        let options = parse(&["--fixed-size", "64x48", "--borderless"]).unwrap();
+        assert_eq!(options.resolution, Some(Resolution::Fixed(64, 48)));
+        assert_eq!(options.display, Some(DisplayMode::Borderless));
+    }
+
+    #[test]
+    fn rejects_values_out_of_range() {
+        for args in [
+            &["--headless", "--size", "0x10"][..],
+            &["--headless", "--width", "0"],
+            &["--render-scale", "0"],
+            &["--screen-size", "10x"],
+            &["--roulette", "2"],
+            &["--far", "0"],
+            &["--near", "-1"],
+            &["--terrain-size", "0x4"],
+            &[
+                "--vary",
+                "time=0:1:3",
+                "--vary",
+                "x=1|2",
+                "--vary",
+                "y=1|2",
+                "--headless",
+            ],
+        ] {
+            assert!(rejects(args), "{:?}", args);
+        }
+    }
+
+    #[test]
+    fn help_is_not_an_error_to_report() {
+        for flag in ["--help", "-h"] {
+            let err = parse(&[flag]).err().unwrap();
+            assert_eq!(err.kind(), ErrorKind::DisplayHelp);
+        }
+    }
+}
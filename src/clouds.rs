@@ -0,0 +1,160 @@
+// clouds.rs
+
+use nalgebra_glm::Vec3;
+
+use crate::color::Color;
+use crate::light::Light;
+use crate::procedural::fractal_noise;
+use crate::skybox::Skybox;
+
+// Muestras a lo largo del rayo dentro de la capa y hacia el sol desde cada una
+const CLOUD_STEPS: u32 = 12;
+const LIGHT_STEPS: u32 = 3;
+// Tramo máximo de la capa que se recorre, en grosores: los rayos casi horizontales la
+// cruzan muy de costado y más allá de esto la nube ya es opaca
+const MAX_MARCH_THICKNESS: f32 = 6.0;
+// Cuánta luz apaga una nube de densidad 1 por bloque recorrido
+const EXTINCTION: f32 = 0.6;
+// Ancho de la transición entre cielo despejado y nube; más chico da bordes más duros
+const EDGE_SOFTNESS: f32 = 0.15;
+// El ruido casi nunca se aleja de 0.5; se estira para que `coverage` abarque de 0 a 1
+const NOISE_CONTRAST: f32 = 1.8;
+// Las nubes se desvanecen en el cielo a esta distancia, en alturas de la capa sobre el
+// observador, y pierden los detalles finos antes, para que el horizonte no se llene de
+// manchas más chicas que un pixel
+const FADE_HEIGHTS: f32 = 12.0;
+// Fracción del color del cenit que ilumina las nubes desde todas las direcciones
+const AMBIENT: f32 = 0.5;
+
+// Capa de nubes entre dos alturas sobre el mundo: la densidad sale de ruido 3D, la ilumina
+// la primera luz de la escena (el sol) y el viento la desplaza con el tiempo. Solo se ve
+// en los rayos que llegan al cielo; las nubes no dan sombra sobre los bloques.
+#[derive(Debug, Clone)]
+pub struct CloudLayer {
+    // Altura de la base de las nubes y grosor de la capa, en bloques
+    pub height: f32,
+    pub thickness: f32,
+    // Fracción del cielo cubierta, de 0 (despejado) a 1 (cerrado)
+    pub coverage: f32,
+    // Tamaño aproximado de una nube, en bloques
+    pub scale: f32,
+    // Velocidad del viento en bloques por segundo; solo cuentan x y z
+    pub wind: Vec3,
+    pub color: Color,
+    // Desplazamiento acumulado por el viento; lo actualiza `CloudLayer::animate`
+    pub drift: Vec3,
+}
+
+impl Default for CloudLayer {
+    fn default() -> Self {
+        CloudLayer {
+            height: 24.0,
+            thickness: 4.0,
+            coverage: 0.5,
+            scale: 16.0,
+            wind: Vec3::new(1.0, 0.0, 0.4),
+            color: Color::new(1.0, 1.0, 1.0),
+            drift: Vec3::zeros(),
+        }
+    }
+}
+
+impl CloudLayer {
+    // Mueve las nubes a donde las llevó el viento a los `time` segundos
+    pub fn animate(&mut self, time: f32) {
+        self.drift = Vec3::new(self.wind.x, 0.0, self.wind.z) * time;
+    }
+
+    // Color del cielo visto desde `origin` con las nubes que cruza el rayo por delante
+    pub fn composite(
+        &self,
+        origin: &Vec3,
+        direction: &Vec3,
+        sky: &Skybox,
+        sun: Option<&Light>,
+    ) -> Color {
+        let background = sky.get_color_from_direction(direction);
+        let Some((start, end)) = self.span(origin, direction) else {
+            return background;
+        };
+        let altitude = (self.height - origin.y).abs().max(self.thickness);
+        let distance = start / (altitude * FADE_HEIGHTS);
+        let fade = (-distance).exp();
+        if fade < 0.01 {
+            return background;
+        }
+
+        let sun_light = sun.map(|sun| {
+            let intensity = sun.intensity * sun.intensity_scale;
+            (sun.position.normalize(), sun.color * intensity)
+        });
+        let ambient = sky.get_color_from_direction(&Vec3::new(0.0, 1.0, 0.0)) * AMBIENT;
+
+        let step = (end - start) / CLOUD_STEPS as f32;
+        let mut transmittance = 1.0;
+        let mut light = Color::black();
+        for i in 0..CLOUD_STEPS {
+            let point = origin + direction * (start + (i as f32 + 0.5) * step);
+            let density = self.density(&point, distance);
+            if density <= 0.0 {
+                continue;
+            }
+            let absorbed = 1.0 - (-density * EXTINCTION * step).exp();
+
+            // Cuánto de la luz del sol llega a este punto atravesando la nube
+            let mut lit = ambient;
+            if let Some((sun_direction, sun_color)) = sun_light {
+                let light_step = self.thickness / (2.0 * LIGHT_STEPS as f32);
+                let depth: f32 = (1..=LIGHT_STEPS)
+                    .map(|j| {
+                        self.density(&(point + sun_direction * (j as f32 * light_step)), distance)
+                    })
+                    .sum::<f32>()
+                    * light_step;
+                lit = lit + sun_color * (-depth * EXTINCTION).exp();
+            }
+
+            light = light + self.color * lit * (absorbed * transmittance);
+            transmittance *= 1.0 - absorbed;
+            if transmittance < 0.01 {
+                break;
+            }
+        }
+
+        background * (1.0 - (1.0 - transmittance) * fade) + light * fade
+    }
+
+    // Densidad entre 0 y 1 en un punto; 0 fuera de la capa. Los detalles más chicos que
+    // `width` (en tamaños de nube) se promedian.
+    fn density(&self, point: &Vec3, width: f32) -> f32 {
+        let h = (point.y - self.height) / self.thickness;
+        if !(0.0..=1.0).contains(&h) {
+            return 0.0;
+        }
+        // Base plana y cima redondeada: la nube se adelgaza hacia arriba
+        let profile = (h * 8.0).min(1.0) * ((1.0 - h) * 2.0).min(1.0);
+        let noise = fractal_noise(&((point + self.drift) / self.scale), width);
+        let value = ((noise - 0.5) * NOISE_CONTRAST + 0.5) * profile;
+        let threshold = 1.0 - self.coverage;
+        ((value - threshold) / EDGE_SOFTNESS).clamp(0.0, 1.0)
+    }
+
+    // Distancias a lo largo del rayo donde entra y sale de la capa, si la cruza
+    fn span(&self, origin: &Vec3, direction: &Vec3) -> Option<(f32, f32)> {
+        let top = self.height + self.thickness;
+        let (start, end) = if direction.y.abs() < 1e-6 {
+            if origin.y < self.height || origin.y > top {
+                return None;
+            }
+            (0.0, f32::INFINITY)
+        } else {
+            let a = (self.height - origin.y) / direction.y;
+            let b = (top - origin.y) / direction.y;
+            (a.min(b).max(0.0), a.max(b))
+        };
+        if end <= start {
+            return None;
+        }
+        Some((start, end.min(start + self.thickness * MAX_MARCH_THICKNESS)))
+    }
+}
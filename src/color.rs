@@ -32,6 +32,11 @@ impl Color {
         (r << 16) | (g << 8) | b
     }
 
+    // Luminancia relativa (pesos de Rec. 709)
+    pub fn luminance(&self) -> f32 {
+        0.2126 * self.r + 0.7152 * self.g + 0.0722 * self.b
+    }
+
     pub fn clamp(self) -> Self {
         Color {
             r: self.r.clamp(0.0, 1.0),
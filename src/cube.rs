@@ -1,181 +1,285 @@
-use crate::color::Color;
 use crate::material::Material;
 use crate::ray_intersect::{Intersect, RayIntersect};
 use nalgebra_glm::Vec3;
+use std::sync::Arc;
 
+// El material se comparte con la paleta del mundo: copiar un cubo no copia sus texturas
+#[derive(Clone)]
 pub struct Cube {
     pub min_corner: Vec3,
     pub max_corner: Vec3,
-    pub material: Material,
+    pub material: Arc<Material>,
 }
 
 impl RayIntersect for Cube {
-    fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Intersect {
-        // Calcular tmin y tmax para cada eje (x, y, z)
-        let mut tmin = (self.min_corner.x - ray_origin.x) / ray_direction.x;
-        let mut tmax = (self.max_corner.x - ray_origin.x) / ray_direction.x;
-        if tmin > tmax {
-            std::mem::swap(&mut tmin, &mut tmax);
-        }
-
-        let mut tymin = (self.min_corner.y - ray_origin.y) / ray_direction.y;
-        let mut tymax = (self.max_corner.y - ray_origin.y) / ray_direction.y;
-        if tymin > tymax {
-            std::mem::swap(&mut tymin, &mut tymax);
-        }
-
-        if (tmin > tymax) || (tymin > tmax) {
-            return Intersect::empty();
-        }
+    fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Intersect<'_> {
+        intersect_box(
+            &self.min_corner,
+            &self.max_corner,
+            &self.material,
+            ray_origin,
+            ray_direction,
+        )
+    }
+}
 
-        if tymin > tmin {
-            tmin = tymin;
-        }
-        if tymax < tmax {
-            tmax = tymax;
-        }
+// Intersección con una caja alineada a los ejes; la comparten los cubos libres y los bloques del mundo
+pub fn intersect_box<'a>(
+    min_corner: &Vec3,
+    max_corner: &Vec3,
+    material: &'a Material,
+    ray_origin: &Vec3,
+    ray_direction: &Vec3,
+) -> Intersect<'a> {
+    // Calcular tmin y tmax para cada eje (x, y, z)
+    let (mut tmin, mut tmax) = slab(min_corner.x, max_corner.x, ray_origin.x, ray_direction.x);
+    // Ejes de las caras por las que entra y sale el rayo
+    let mut axis = 0;
+    let mut exit_axis = 0;
 
-        let mut tzmin = (self.min_corner.z - ray_origin.z) / ray_direction.z;
-        let mut tzmax = (self.max_corner.z - ray_origin.z) / ray_direction.z;
-        if tzmin > tzmax {
-            std::mem::swap(&mut tzmin, &mut tzmax);
-        }
+    let (tymin, tymax) = slab(min_corner.y, max_corner.y, ray_origin.y, ray_direction.y);
 
-        if (tmin > tzmax) || (tzmin > tmax) {
-            return Intersect::empty();
-        }
+    if (tmin > tymax) || (tymin > tmax) {
+        return Intersect::empty();
+    }
 
-        if tzmin > tmin {
-            tmin = tzmin;
-        }
-        if tzmax < tmax {
-            tmax = tzmax;
-        }
+    if tymin > tmin {
+        tmin = tymin;
+        axis = 1;
+    }
+    if tymax < tmax {
+        tmax = tymax;
+        exit_axis = 1;
+    }
 
-        // Si el valor de tmin es negativo, no hay intersección delante del rayo
-        if tmin < 0.0 {
-            return Intersect::empty();
-        }
+    let (tzmin, tzmax) = slab(min_corner.z, max_corner.z, ray_origin.z, ray_direction.z);
 
-        // Calcular el punto de intersección
-        let intersection_point = ray_origin + ray_direction * tmin;
+    if (tmin > tzmax) || (tzmin > tmax) {
+        return Intersect::empty();
+    }
 
-        // Calcular la normal de la intersección
-        let mut normal = self.calculate_normal(&intersection_point);
-        let (u, v) = self.get_texture_coordinates(&intersection_point);
-        let distance = tmin;
+    if tzmin > tmin {
+        tmin = tzmin;
+        axis = 2;
+    }
+    if tzmax < tmax {
+        tmax = tzmax;
+        exit_axis = 2;
+    }
 
-        // Obtener el color de la textura si está disponible
-        let texture_color = if let Some(texture) = &self.material.texture {
-            // Clampear u y v para evitar desbordes de la textura
-            let u_clamped = u.clamp(0.0, 1.0 - f32::EPSILON);
-            let v_clamped = v.clamp(0.0, 1.0 - f32::EPSILON);
+    // Si el valor de tmin es negativo, no hay intersección delante del rayo
+    if tmin < 0.0 {
+        return Intersect::empty();
+    }
 
-            let tex_x = (u_clamped * texture.width() as f32) as u32;
-            let tex_y = (v_clamped * texture.height() as f32) as u32;
+    box_faces(
+        min_corner,
+        max_corner,
+        material,
+        ray_origin,
+        ray_direction,
+        [(tmin, axis), (tmax, exit_axis)],
+    )
+}
 
-            let pixel = texture.get_pixel(tex_x, tex_y);
-            Color::new(pixel[0] as f32 / 255.0, pixel[1] as f32 / 255.0, pixel[2] as f32 / 255.0)
+// Intersección con una caja por una cara ya conocida, la distancia y el eje por los que entra
+// el rayo, como la de un bloque del mundo a partir de su quad. La entrada no se vuelve a
+// calcular: en una arista el redondeo de las slabs puede dejar al rayo afuera de la caja.
+pub fn intersect_box_at_face<'a>(
+    min_corner: &Vec3,
+    max_corner: &Vec3,
+    material: &'a Material,
+    ray_origin: &Vec3,
+    ray_direction: &Vec3,
+    entry: (f32, usize),
+) -> Intersect<'a> {
+    // La salida es el más cercano de los planos de atrás de cada eje
+    let mut exit = (f32::INFINITY, entry.1);
+    for axis in 0..3 {
+        let direction = ray_direction[axis];
+        if direction == 0.0 {
+            continue;
+        }
+        let far = if direction > 0.0 {
+            max_corner[axis]
         } else {
-            self.material.diffuse
+            min_corner[axis]
         };
+        let distance = (far - ray_origin[axis]) / direction;
+        if distance < exit.0 {
+            exit = (distance, axis);
+        }
+    }
+    box_faces(
+        min_corner,
+        max_corner,
+        material,
+        ray_origin,
+        ray_direction,
+        [entry, exit],
+    )
+}
 
-        // Ajustar la normal con el normal map si está disponible
-        if let Some(normal_map) = &self.material.normal_map {
-            let u_clamped = u.clamp(0.0, 1.0 - f32::EPSILON);
-            let v_clamped = v.clamp(0.0, 1.0 - f32::EPSILON);
-
-            let tex_x = (u_clamped * normal_map.width() as f32) as u32;
-            let tex_y = (v_clamped * normal_map.height() as f32) as u32;
-
-            let pixel = normal_map.get_pixel(tex_x, tex_y);
-
-            let normal_tangent = Vec3::new(
-                (pixel[0] as f32 / 255.0) * 2.0 - 1.0,
-                (pixel[1] as f32 / 255.0) * 2.0 - 1.0,
-                (pixel[2] as f32 / 255.0) * 2.0 - 1.0,
-            )
-            .normalize();
-
-            // Asumir que la normal calculada ya actúa como la base para ajustar
-            let tangent = normal.cross(&Vec3::new(0.0, 1.0, 0.0)).normalize();
-            let bitangent = normal.cross(&tangent);
+// Choque con la cara de entrada o, si su texel es transparente, con el lado interior de la de
+// salida, como en las hojas; cada una como (distancia, eje)
+fn box_faces<'a>(
+    min_corner: &Vec3,
+    max_corner: &Vec3,
+    material: &'a Material,
+    ray_origin: &Vec3,
+    ray_direction: &Vec3,
+    [entry, exit]: [(f32, usize); 2],
+) -> Intersect<'a> {
+    for ((distance, axis), exiting) in [(entry, false), (exit, true)] {
+        let intersection_point = ray_origin + ray_direction * distance;
 
-            normal = (tangent * normal_tangent.x
-                + bitangent * normal_tangent.y
-                + normal * normal_tangent.z)
-                .normalize();
+        // La normal apunta contra el rayo; la textura se lee con la normal hacia afuera de
+        // la cara para que los huecos coincidan vistos desde los dos lados
+        let mut normal = Vec3::zeros();
+        normal[axis] = if ray_direction[axis] > 0.0 { -1.0 } else { 1.0 };
+        let outward = if exiting { -normal } else { normal };
+        let uv = face_uv(min_corner, max_corner, &intersection_point, &outward);
+        if material.is_cut_out(uv) {
+            continue;
         }
 
-        Intersect::new(
+        // Tamaño de la cara: el lado más largo de los dos ejes que no son la normal
+        let extent = max_corner - min_corner;
+        let face_size = (0..3)
+            .filter(|&axis| normal[axis] == 0.0)
+            .map(|axis| extent[axis])
+            .fold(0.0, f32::max);
+
+        return Intersect::new(
             intersection_point,
             normal,
             distance,
-            Material::new(
-                texture_color,
-                self.material.specular,
-                self.material.albedo,
-                self.material.refractive_index,
-                self.material.texture.clone(),
-                self.material.normal_map.clone(),
-                self.material.emission,
-            ),
-        )
+            material,
+            uv,
+            face_size,
+            (*min_corner, *max_corner),
+        );
     }
+
+    Intersect::empty()
 }
 
-impl Cube {
-    fn get_texture_coordinates(&self, point: &Vec3) -> (f32, f32) {
-        let epsilon = 1e-4;
-
-        if (point.x - self.min_corner.x).abs() < epsilon {
-            // Cara izquierda
-            let u = (point.z - self.min_corner.z) / (self.max_corner.z - self.min_corner.z);
-            let v = (point.y - self.min_corner.y) / (self.max_corner.y - self.min_corner.y);
-            (u, v)
-        } else if (point.x - self.max_corner.x).abs() < epsilon {
-            // Cara derecha
-            let u = (point.z - self.min_corner.z) / (self.max_corner.z - self.min_corner.z);
-            let v = (self.max_corner.y - point.y) / (self.max_corner.y - self.min_corner.y);
-            (u, v)
-        } else if (point.y - self.min_corner.y).abs() < epsilon {
-            // Cara inferior
-            let u = (point.x - self.min_corner.x) / (self.max_corner.x - self.min_corner.x);
-            let v = (point.z - self.min_corner.z) / (self.max_corner.z - self.min_corner.z);
-            (u, v)
-        } else if (point.y - self.max_corner.y).abs() < epsilon {
-            // Cara superior
-            let u = (point.x - self.min_corner.x) / (self.max_corner.x - self.min_corner.x);
-            let v = (self.max_corner.z - point.z) / (self.max_corner.z - self.min_corner.z);
-            (u, v)
-        } else if (point.z - self.min_corner.z).abs() < epsilon {
-            // Cara trasera
-            let u = (point.x - self.min_corner.x) / (self.max_corner.x - self.min_corner.x);
-            let v = (point.y - self.min_corner.y) / (self.max_corner.y - self.min_corner.y);
-            (u, v)
-        } else {
-            // Cara frontal
-            let u = (point.x - self.min_corner.x) / (self.max_corner.x - self.min_corner.x);
-            let v = (self.max_corner.y - point.y) / (self.max_corner.y - self.min_corner.y);
-            (u, v)
+// Cubos o rayos que se prueban juntos, uno por carril, en `entry_distances` y en los
+// paquetes de rayos
+pub const LANES: usize = 4;
+
+// Un valor por carril de cada eje, agrupados por eje (una estructura de arreglos)
+pub type Lanes = [[f32; LANES]; 3];
+
+// Distancia a la que el rayo entra en cada uno de los primeros `LANES` cubos de `cubes`, o
+// infinito si no lo toca o le queda detrás, para descartar de a varios los cubos que no
+// hace falta intersecar con texturas y normales
+pub fn entry_distances(cubes: &[Cube], ray_origin: &Vec3, ray_direction: &Vec3) -> [f32; LANES] {
+    let mut min = [[0.0; LANES]; 3];
+    let mut max = [[0.0; LANES]; 3];
+    for (lane, cube) in cubes.iter().take(LANES).enumerate() {
+        for axis in 0..3 {
+            min[axis][lane] = cube.min_corner[axis];
+            max[axis][lane] = cube.max_corner[axis];
+        }
+    }
+    let mut distances = lane_entry_distances(
+        &min,
+        &max,
+        &broadcast(ray_origin),
+        &broadcast(ray_direction),
+    );
+    // Los carriles que sobran no tienen cubo
+    for distance in distances.iter_mut().skip(cubes.len()) {
+        *distance = f32::INFINITY;
+    }
+    distances
+}
+
+// `vector` repetido en todos los carriles
+pub fn broadcast(vector: &Vec3) -> Lanes {
+    [[vector.x; LANES], [vector.y; LANES], [vector.z; LANES]]
+}
+
+// La prueba de franjas de `intersect_box` en cada carril, entre la caja de `min` a `max` y el
+// rayo que sale de `origin` hacia `direction`: la distancia a la que entra, o infinito si no
+// la toca o le queda detrás. Cada paso es la misma cuenta sobre todos los carriles, así el
+// compilador la hace con instrucciones SIMD.
+pub fn lane_entry_distances(
+    min: &Lanes,
+    max: &Lanes,
+    origin: &Lanes,
+    direction: &Lanes,
+) -> [f32; LANES] {
+    let mut near = [f32::NEG_INFINITY; LANES];
+    let mut far = [f32::INFINITY; LANES];
+    for axis in 0..3 {
+        for lane in 0..LANES {
+            let a = (min[axis][lane] - origin[axis][lane]) / direction[axis][lane];
+            let b = (max[axis][lane] - origin[axis][lane]) / direction[axis][lane];
+            // 0/0: el rayo va paralelo al eje justo sobre un plano, así que cruza la franja
+            // entera, igual que en `slab`; con el origen fuera los dos lados dan el mismo
+            // infinito y la franja queda vacía sin caso aparte
+            let (enter, exit) = if a.is_nan() || b.is_nan() {
+                (f32::NEG_INFINITY, f32::INFINITY)
+            } else {
+                (a.min(b), a.max(b))
+            };
+            near[lane] = near[lane].max(enter);
+            far[lane] = far[lane].min(exit);
         }
     }
 
-    fn calculate_normal(&self, point: &Vec3) -> Vec3 {
-        let epsilon = 1e-4;
+    let mut distances = [f32::INFINITY; LANES];
+    for lane in 0..LANES {
+        if near[lane] <= far[lane] && near[lane] >= 0.0 {
+            distances[lane] = near[lane];
+        }
+    }
+    distances
+}
 
-        if (point.x - self.min_corner.x).abs() < epsilon {
-            Vec3::new(-1.0, 0.0, 0.0)
-        } else if (point.x - self.max_corner.x).abs() < epsilon {
-            Vec3::new(1.0, 0.0, 0.0)
-        } else if (point.y - self.min_corner.y).abs() < epsilon {
-            Vec3::new(0.0, -1.0, 0.0)
-        } else if (point.y - self.max_corner.y).abs() < epsilon {
-            Vec3::new(0.0, 1.0, 0.0)
-        } else if (point.z - self.min_corner.z).abs() < epsilon {
-            Vec3::new(0.0, 0.0, -1.0)
+// Distancias (ordenadas) a las que el rayo cruza los dos planos de un eje. Paralelo al eje,
+// un rayo justo sobre uno de los planos daría 0/0: dentro de la franja la cruza entera y
+// fuera no la toca.
+fn slab(min: f32, max: f32, origin: f32, direction: f32) -> (f32, f32) {
+    if direction == 0.0 {
+        return if (min..=max).contains(&origin) {
+            (f32::NEG_INFINITY, f32::INFINITY)
         } else {
-            Vec3::new(0.0, 0.0, 1.0)
-        }
+            (f32::INFINITY, f32::NEG_INFINITY)
+        };
+    }
+    let near = (min - origin) / direction;
+    let far = (max - origin) / direction;
+    (near.min(far), near.max(far))
+}
+
+// Coordenadas de textura de 0 a 1 en la cara, con v = 0 en la fila de arriba de la imagen.
+// Mirando la cara desde afuera la textura nunca queda espejada: en los lados la fila de arriba
+// va hacia +y, en la cara superior hacia -z y en la inferior hacia +z.
+fn face_uv(min_corner: &Vec3, max_corner: &Vec3, point: &Vec3, normal: &Vec3) -> (f32, f32) {
+    let extent = max_corner - min_corner;
+    let local = |axis: usize| ((point[axis] - min_corner[axis]) / extent[axis]).clamp(0.0, 1.0);
+    let (x, y, z) = (local(0), local(1), local(2));
+
+    if normal.x > 0.0 {
+        // Cara derecha
+        (1.0 - z, 1.0 - y)
+    } else if normal.x < 0.0 {
+        // Cara izquierda
+        (z, 1.0 - y)
+    } else if normal.y > 0.0 {
+        // Cara superior
+        (x, z)
+    } else if normal.y < 0.0 {
+        // Cara inferior
+        (x, 1.0 - z)
+    } else if normal.z > 0.0 {
+        // Cara frontal
+        (x, 1.0 - y)
+    } else {
+        // Cara trasera
+        (1.0 - x, 1.0 - y)
     }
 }
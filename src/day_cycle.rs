@@ -0,0 +1,169 @@
+// day_cycle.rs
+
+use nalgebra_glm::Vec3;
+use std::f32::consts::PI;
+
+use crate::color::Color;
+
+// Duración de un día completo de la ventana, en segundos
+pub const DEFAULT_DAY_LENGTH: f32 = 60.0;
+// Velocidades del día que se pueden pedir con las teclas, como múltiplo de la normal
+const MIN_SPEED: f32 = 1.0 / 16.0;
+const MAX_SPEED: f32 = 64.0;
+// Momentos a los que se salta con la tecla: amanecer, mediodía, atardecer y medianoche
+const MOMENTS: [f32; 4] = [0.0, 0.25, 0.5, 0.75];
+
+// Luz ambiente y tono del cielo a lo largo del día: (avance, ambiente, tono). El sol está
+// bajo el horizonte entre 0.5 y 1.0, así que la noche azul oscura va en esa mitad; entre
+// los puntos se interpola y después de 1.0 se vuelve al primero.
+const SKY_KEYS: [(f32, [f32; 3], [f32; 3]); 6] = [
+    (0.0, [0.09, 0.07, 0.07], [1.0, 0.7, 0.55]),
+    (0.1, [0.1, 0.11, 0.13], [1.0, 1.0, 1.0]),
+    (0.4, [0.1, 0.11, 0.13], [1.0, 1.0, 1.0]),
+    (0.5, [0.09, 0.07, 0.07], [1.0, 0.65, 0.5]),
+    (0.6, [0.02, 0.03, 0.07], [0.08, 0.1, 0.25]),
+    (0.9, [0.02, 0.03, 0.07], [0.08, 0.1, 0.25]),
+];
+// Intensidad de la luna alta en el cielo y su color cerca del horizonte y arriba
+const MOON_INTENSITY: f32 = 0.35;
+const MOON_LOW: [f32; 3] = [0.55, 0.55, 0.75];
+const MOON_HIGH: [f32; 3] = [0.65, 0.72, 0.95];
+
+// Todo lo que cambia con la hora del día, calculado de una vez para que el sol, la luz
+// ambiente y el cielo no se contradigan
+#[derive(Debug, Clone, Copy)]
+pub struct DayLighting {
+    // Dirección hacia el sol: sale por +x en el amanecer y se pone por -x
+    pub sun_direction: Vec3,
+    pub sun_color: Color,
+    pub sun_intensity: f32,
+    // Luz del cielo que llega a todas las superficies, sin sombras
+    pub ambient: Color,
+    // Multiplica la imagen del cielo; el cielo dinámico calcula su propia noche
+    pub sky_tint: Color,
+    // La luna está del lado opuesto al sol; su luz es fría y se apaga bajo el horizonte
+    pub moon_color: Color,
+    pub moon_intensity: f32,
+}
+
+// Iluminación en un punto del día, de 0.0 (amanecer) a 1.0
+pub fn day_lighting(progress: f32) -> DayLighting {
+    let progress = progress.rem_euclid(1.0);
+    let sun_angle = progress * 2.0 * PI;
+    let sun_direction = Vec3::new(sun_angle.cos(), sun_angle.sin(), 0.0);
+
+    let (sun_intensity, sun_color) = if progress < 0.25 {
+        let factor = progress / 0.25;
+        (
+            0.5 + 0.5 * factor,
+            Color::from_u8(255, 183, 76) * factor + Color::from_u8(50, 50, 100) * (1.0 - factor),
+        )
+    } else if progress < 0.5 {
+        (1.0, Color::from_u8(255, 255, 255))
+    } else if progress < 0.75 {
+        let factor = (progress - 0.5) / 0.25;
+        (
+            1.0 - 0.5 * factor,
+            Color::from_u8(255, 183, 76) * (1.0 - factor) + Color::from_u8(50, 50, 100) * factor,
+        )
+    } else {
+        (0.5, Color::from_u8(50, 50, 100))
+    };
+
+    // La luna aparece apenas asoma sobre el horizonte y llega a su intensidad al subir
+    let moon_elevation = -sun_direction.y;
+    let moon_rise = ((moon_elevation + 0.05) / 0.3).clamp(0.0, 1.0);
+
+    let next = SKY_KEYS
+        .iter()
+        .position(|&(at, _, _)| at > progress)
+        .unwrap_or(SKY_KEYS.len());
+    let (start, ambient_start, tint_start) = SKY_KEYS[next - 1];
+    let (end, ambient_end, tint_end) = match SKY_KEYS.get(next) {
+        Some(&key) => key,
+        None => (1.0 + SKY_KEYS[0].0, SKY_KEYS[0].1, SKY_KEYS[0].2),
+    };
+    let factor = (progress - start) / (end - start);
+    let mix = |from: [f32; 3], to: [f32; 3], factor: f32| {
+        Color::new(
+            from[0] + (to[0] - from[0]) * factor,
+            from[1] + (to[1] - from[1]) * factor,
+            from[2] + (to[2] - from[2]) * factor,
+        )
+    };
+
+    DayLighting {
+        sun_direction,
+        sun_color,
+        sun_intensity,
+        ambient: mix(ambient_start, ambient_end, factor),
+        sky_tint: mix(tint_start, tint_end, factor),
+        moon_color: mix(MOON_LOW, MOON_HIGH, moon_elevation.clamp(0.0, 1.0)),
+        moon_intensity: MOON_INTENSITY * moon_rise * moon_rise * (3.0 - 2.0 * moon_rise),
+    }
+}
+
+// Hora del día de la ventana interactiva. El avance va de 0.0 (amanecer) a 1.0 y se
+// guarda como fracción, así cambiar la duración del día no cambia la hora.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DayNightCycle {
+    progress: f32,
+    // Segundos que dura un día a velocidad normal
+    pub length: f32,
+    // Multiplicador del paso del tiempo
+    pub speed: f32,
+    pub paused: bool,
+}
+
+impl DayNightCycle {
+    pub fn new(progress: f32) -> Self {
+        DayNightCycle {
+            progress: progress.rem_euclid(1.0),
+            length: DEFAULT_DAY_LENGTH,
+            speed: 1.0,
+            paused: false,
+        }
+    }
+
+    // Avanza `delta_time` segundos a la velocidad elegida, salvo que esté detenido
+    pub fn advance(&mut self, delta_time: f32) {
+        if !self.paused {
+            self.progress = (self.progress + delta_time * self.speed / self.length).rem_euclid(1.0);
+        }
+    }
+
+    pub fn progress(&self) -> f32 {
+        self.progress
+    }
+
+    pub fn set_progress(&mut self, progress: f32) {
+        self.progress = progress.rem_euclid(1.0);
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    pub fn faster(&mut self) {
+        self.speed = (self.speed * 2.0).min(MAX_SPEED);
+    }
+
+    pub fn slower(&mut self) {
+        self.speed = (self.speed / 2.0).max(MIN_SPEED);
+    }
+
+    // Salta al siguiente de los cuatro momentos del día
+    pub fn skip_to_next_moment(&mut self) {
+        self.progress = MOMENTS
+            .iter()
+            .copied()
+            .find(|&moment| moment > self.progress + 1e-4)
+            .unwrap_or(MOMENTS[0]);
+    }
+}
+
+impl Default for DayNightCycle {
+    fn default() -> Self {
+        Self::new(0.0)
+    }
+}
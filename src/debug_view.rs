@@ -0,0 +1,66 @@
+// debug_view.rs
+
+use crate::color::Color;
+
+// Rayos por pixel que se ven en rojo en el mapa de rebotes; más también se ven rojos
+pub const MAX_HEAT_RAYS: u32 = 16;
+
+// Qué muestra el render: la imagen final o un dato del primer impacto de cada rayo de
+// cámara, para encontrar errores de intersección y de sombreado
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebugView {
+    Shaded,
+    // Normal de la superficie (después del normal map), de -1..1 a 0..1 por canal
+    Normals,
+    // Distancia a la cámara: blanco cerca, negro al doble de la distancia al objetivo
+    Depth,
+    // Coordenadas de textura de la cara en rojo (u) y verde (v)
+    Uv,
+    // Fracción de las luces que llegan al punto: blanco iluminado, negro en sombra
+    Shadow,
+    // Rayos trazados para el pixel (reflejos, refracciones, muestras rugosas), de azul a rojo
+    Bounces,
+}
+
+impl DebugView {
+    pub const ALL: [DebugView; 6] = [
+        DebugView::Shaded,
+        DebugView::Normals,
+        DebugView::Depth,
+        DebugView::Uv,
+        DebugView::Shadow,
+        DebugView::Bounces,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            DebugView::Shaded => "sombreado",
+            DebugView::Normals => "normales",
+            DebugView::Depth => "profundidad",
+            DebugView::Uv => "uv",
+            DebugView::Shadow => "sombras",
+            DebugView::Bounces => "rebotes",
+        }
+    }
+
+    // La siguiente en el orden de `ALL`, volviendo a la primera después de la última
+    pub fn next(&self) -> Self {
+        let index = DebugView::ALL
+            .iter()
+            .position(|view| view == self)
+            .unwrap_or(0);
+        DebugView::ALL[(index + 1) % DebugView::ALL.len()]
+    }
+}
+
+// Escala de calor de 0 (azul) a 1 (rojo) pasando por verde y amarillo
+pub fn heat(t: f32) -> Color {
+    let t = t.clamp(0.0, 1.0) * 3.0;
+    if t < 1.0 {
+        Color::new(0.0, t, 1.0 - t)
+    } else if t < 2.0 {
+        Color::new(t - 1.0, 1.0, 0.0)
+    } else {
+        Color::new(1.0, 3.0 - t, 0.0)
+    }
+}
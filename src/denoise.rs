@@ -0,0 +1,196 @@
+// denoise.rs
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::cube::Cube;
+use crate::framebuffer::Framebuffer;
+use crate::render::{primary_surfaces, Surface};
+use crate::scene::Scene;
+
+// Pasadas del filtro; cada una duplica la separación entre las muestras (1, 2, 4 y 8
+// pixeles), así un núcleo de 5x5 cubre 31x31 pixeles sin leerlos todos
+const ITERATIONS: u32 = 4;
+// Pesos del núcleo B-spline en cada eje
+const KERNEL: [f32; 5] = [1.0 / 16.0, 1.0 / 4.0, 3.0 / 8.0, 1.0 / 4.0, 1.0 / 16.0];
+// Potencia del coseno entre normales: más alta separa mejor las caras de un bloque
+const NORMAL_POWER: f32 = 64.0;
+// Diferencia de profundidad que se mezcla, como fracción de la profundidad del pixel por
+// cada pixel de separación
+const DEPTH_TOLERANCE: f32 = 0.01;
+// Diferencia relativa de brillo que se mezcla en la primera pasada; cada pasada la reduce a
+// la mitad para no borrar los detalles que las primeras dejaron
+const COLOR_SIGMA: f32 = 0.5;
+// Albedo mínimo por el que se divide, para no amplificar el ruido de lo casi negro
+const MIN_ALBEDO: f32 = 0.01;
+
+// Filtro À-Trous guiado por la geometría (Dammertz y otros, 2010): cada pixel se promedia
+// con los vecinos que están sobre la misma superficie (normal y profundidad parecidas) y
+// tienen un brillo parecido. Se filtra la luz que llega sin la textura, que se vuelve a
+// aplicar al final, así las texturas pixeladas de los bloques no se borronean. Donde se ve
+// el cielo la imagen no cambia.
+pub fn denoise_with(framebuffer: &mut Framebuffer, surfaces: &[Option<Surface>], camera: &Camera) {
+    let (width, height) = (framebuffer.width, framebuffer.height);
+    if surfaces.len() != width * height {
+        return;
+    }
+    let depths: Vec<f32> = surfaces
+        .iter()
+        .map(|surface| surface.map_or(f32::INFINITY, |surface| camera.depth(&surface.point)))
+        .collect();
+    let mut light: Vec<Color> = framebuffer
+        .buffer
+        .iter()
+        .zip(surfaces)
+        .map(|(&color, surface)| match surface {
+            Some(surface) => demodulate(color, surface.albedo),
+            None => color,
+        })
+        .collect();
+
+    for iteration in 0..ITERATIONS {
+        let step = 1isize << iteration;
+        let sigma = COLOR_SIGMA * 0.5_f32.powi(iteration as i32);
+        let filter_pixel = |index: usize| {
+            let Some(center) = &surfaces[index] else {
+                return light[index];
+            };
+            let (x, y) = ((index % width) as isize, (index / width) as isize);
+            let center_luminance = light[index].luminance().max(0.05);
+            let depth_range = DEPTH_TOLERANCE * depths[index].abs().max(1e-3) * step as f32;
+
+            let mut sum = Color::black();
+            let mut total = 0.0;
+            for (ky, wy) in KERNEL.iter().enumerate() {
+                let sy = y + (ky as isize - 2) * step;
+                if sy < 0 || sy >= height as isize {
+                    continue;
+                }
+                for (kx, wx) in KERNEL.iter().enumerate() {
+                    let sx = x + (kx as isize - 2) * step;
+                    if sx < 0 || sx >= width as isize {
+                        continue;
+                    }
+                    let sample = sy as usize * width + sx as usize;
+                    let Some(surface) = &surfaces[sample] else {
+                        continue;
+                    };
+                    let normal = center
+                        .normal
+                        .dot(&surface.normal)
+                        .max(0.0)
+                        .powf(NORMAL_POWER);
+                    let depth = (-(depths[sample] - depths[index]).abs() / depth_range).exp();
+                    let brightness = (-(light[sample].luminance() - center_luminance).abs()
+                        / (sigma * center_luminance))
+                        .exp();
+                    let weight = wx * wy * normal * depth * brightness;
+                    sum = sum + light[sample] * weight;
+                    total += weight;
+                }
+            }
+            // El centro siempre suma, así que el total nunca es cero
+            sum * (1.0 / total)
+        };
+
+        #[cfg(feature = "parallel")]
+        let filtered: Vec<Color> = (0..width * height)
+            .into_par_iter()
+            .map(filter_pixel)
+            .collect();
+        #[cfg(not(feature = "parallel"))]
+        let filtered: Vec<Color> = (0..width * height).map(filter_pixel).collect();
+        light = filtered;
+    }
+
+    for ((pixel, light), surface) in framebuffer.buffer.iter_mut().zip(&light).zip(surfaces) {
+        if let Some(surface) = surface {
+            *pixel = *light * clamped_albedo(surface.albedo);
+        }
+    }
+}
+
+// Traza las superficies de la vista de `scene` y filtra la imagen con ellas
+pub fn denoise(framebuffer: &mut Framebuffer, scene: &Scene, objects: &[Cube]) {
+    let surfaces = primary_surfaces(scene, objects, (framebuffer.width, framebuffer.height));
+    denoise_with(framebuffer, &surfaces, &scene.camera);
+}
+
+fn clamped_albedo(albedo: Color) -> Color {
+    Color::new(
+        albedo.r.max(MIN_ALBEDO),
+        albedo.g.max(MIN_ALBEDO),
+        albedo.b.max(MIN_ALBEDO),
+    )
+}
+
+// La luz que llegó al pixel: su color dividido por el de la superficie
+fn demodulate(color: Color, albedo: Color) -> Color {
+    let albedo = clamped_albedo(albedo);
+    Color::new(color.r / albedo.r, color.g / albedo.g, color.b / albedo.b)
+}
+
+// Filtro de la ventana interactiva. Guarda las superficies de la vista para no volver a
+// trazarlas mientras no cambie la cámara ni la geometría, y la última imagen filtrada para
+// mostrarla otra vez en los frames que no agregan una pasada al promedio.
+pub struct Denoiser {
+    // U lo enciende y lo apaga
+    pub enabled: bool,
+    camera: Option<Camera>,
+    surfaces: Vec<Option<Surface>>,
+    output: Vec<Color>,
+}
+
+impl Denoiser {
+    pub fn new() -> Self {
+        Denoiser {
+            enabled: false,
+            camera: None,
+            surfaces: Vec::new(),
+            output: Vec::new(),
+        }
+    }
+
+    // Muestra filtrado el promedio que tiene `framebuffer`. Si no es `fresh` (no se le
+    // agregó un frame desde la última vez) se copia el resultado guardado; `geometry_changed`
+    // avisa que hay que volver a trazar las superficies aunque la cámara sea la misma
+    // (bloques editados, objetos animados).
+    pub fn present(
+        &mut self,
+        framebuffer: &mut Framebuffer,
+        scene: &Scene,
+        objects: &[Cube],
+        fresh: bool,
+        geometry_changed: bool,
+    ) {
+        if geometry_changed {
+            self.surfaces.clear();
+        }
+        if !fresh && self.output.len() == framebuffer.buffer.len() {
+            framebuffer.buffer.clone_from(&self.output);
+            return;
+        }
+        let size = (framebuffer.width, framebuffer.height);
+        if self.camera.as_ref() != Some(&scene.camera) || self.surfaces.len() != size.0 * size.1 {
+            self.surfaces = primary_surfaces(scene, objects, size);
+            self.camera = Some(scene.camera.clone());
+        }
+        denoise_with(framebuffer, &self.surfaces, &scene.camera);
+        self.output.clone_from(&framebuffer.buffer);
+    }
+
+    // Olvida las superficies y la imagen filtrada: lo próximo que se muestre se filtra de
+    // cero
+    pub fn clear(&mut self) {
+        self.surfaces.clear();
+        self.output.clear();
+    }
+}
+
+impl Default for Denoiser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
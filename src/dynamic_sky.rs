@@ -0,0 +1,101 @@
+// dynamic_sky.rs
+
+use nalgebra_glm::Vec3;
+
+use crate::color::Color;
+use crate::procedural::lattice_value;
+
+// Radio angular del disco del sol en radianes; el real es unas 7 veces menor pero no se vería
+const SUN_RADIUS: f32 = 0.035;
+// Exponente del halo alrededor del sol: más alto lo concentra cerca del disco
+const SUN_GLOW_EXPONENT: i32 = 64;
+const SUN_GLOW: f32 = 0.4;
+// Celdas de estrellas por unidad de dirección (más celdas, estrellas más chicas) y fracción
+// de las celdas que tienen una
+const STAR_CELLS: f32 = 400.0;
+const STAR_FRACTION: f32 = 0.006;
+// Elevación del sol (seno) a la que empieza a clarear y a la que ya es de día, y hasta
+// dónde llegan los tonos cálidos; el crepúsculo sigue un rato con el sol bajo el horizonte
+const NIGHT_ELEVATION: f32 = -0.3;
+const DAY_ELEVATION: f32 = 0.25;
+const TWILIGHT_ELEVATION: f32 = 0.3;
+
+const DAY_ZENITH: Color = Color::new(0.18, 0.42, 0.85);
+const DAY_HORIZON: Color = Color::new(0.7, 0.82, 0.95);
+const NIGHT_ZENITH: Color = Color::new(0.005, 0.008, 0.03);
+const NIGHT_HORIZON: Color = Color::new(0.03, 0.04, 0.09);
+const TWILIGHT: Color = Color::new(0.9, 0.4, 0.15);
+// Se suma al color de la luz del sol para que el disco se vea casi blanco
+const SUN_CORE: Color = Color::new(0.6, 0.55, 0.45);
+
+// Cielo calculado a partir de la posición del sol en lugar de una imagen: degradado del
+// horizonte al cenit, el disco del sol con su halo, tonos cálidos al amanecer y al atardecer
+// y estrellas de noche. `Scene::apply_day_cycle` lo mueve junto con el sol.
+#[derive(Debug, Clone, Copy)]
+pub struct DynamicSky {
+    // Hacia el sol, normalizada
+    pub sun_direction: Vec3,
+    pub sun_color: Color,
+}
+
+impl DynamicSky {
+    pub fn new(sun_direction: Vec3) -> Self {
+        DynamicSky {
+            sun_direction: sun_direction.normalize(),
+            sun_color: Color::new(1.0, 1.0, 1.0),
+        }
+    }
+
+    pub fn color(&self, direction: &Vec3) -> Color {
+        let dir = direction.normalize();
+        let sun = self.sun_direction;
+        // 1 de día y 0 de noche, con la transición mientras el sol cruza el horizonte
+        let day = smoothstep(NIGHT_ELEVATION, DAY_ELEVATION, sun.y);
+        let twilight = (1.0 - sun.y.abs() / TWILIGHT_ELEVATION).max(0.0);
+
+        // El resplandor del amanecer se concentra del lado del sol y cerca del horizonte
+        let toward_sun = (dir.dot(&sun) * 0.5 + 0.5).powi(3);
+        let horizon = mix(NIGHT_HORIZON, DAY_HORIZON, day) + TWILIGHT * (twilight * toward_sun);
+        let zenith = mix(NIGHT_ZENITH, DAY_ZENITH, day);
+        let mut color = mix(horizon, zenith, dir.y.max(0.0).sqrt());
+        if dir.y < 0.0 {
+            // Debajo del horizonte el cielo se apaga hacia un suelo lejano sin detalle
+            color = mix(color, horizon * 0.35, (-dir.y * 5.0).min(1.0));
+        }
+
+        let cosine = dir.dot(&sun);
+        if cosine > SUN_RADIUS.cos() && dir.y > 0.0 {
+            return (self.sun_color + SUN_CORE).clamp();
+        }
+        let glow = cosine.max(0.0).powi(SUN_GLOW_EXPONENT) * SUN_GLOW * day.max(twilight);
+        color = color + self.sun_color * glow;
+
+        let night = 1.0 - day;
+        if night > 0.0 && dir.y > 0.0 {
+            color = color + Color::new(1.0, 1.0, 1.0) * (self.star(&dir) * night);
+        }
+        color
+    }
+
+    // Brillo de la estrella en esa dirección (casi siempre 0). Las estrellas giran alrededor
+    // del eje z con el sol y se apagan cerca del horizonte.
+    fn star(&self, dir: &Vec3) -> f32 {
+        let (sin, cos) = self.sun_direction.y.atan2(self.sun_direction.x).sin_cos();
+        let turned = Vec3::new(dir.x * cos + dir.y * sin, dir.y * cos - dir.x * sin, dir.z);
+        let cell = (turned * STAR_CELLS).map(|value| value.floor() as i32);
+        if lattice_value(cell.x, cell.y, cell.z) > STAR_FRACTION {
+            return 0.0;
+        }
+        let brightness = 0.3 + 0.7 * lattice_value(cell.z, cell.x, cell.y);
+        brightness * (dir.y * 4.0).min(1.0)
+    }
+}
+
+fn mix(a: Color, b: Color, t: f32) -> Color {
+    a * (1.0 - t) + b * t
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
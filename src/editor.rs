@@ -0,0 +1,255 @@
+// editor.rs
+
+use minifb::{Key, MouseButton, MouseMode, Window};
+use nalgebra_glm::Vec3;
+
+use crate::camera::Projection;
+use crate::chunk::{BlockId, AIR};
+use crate::color::Color;
+use crate::cube::Cube;
+use crate::framebuffer::{window_to_framebuffer, Framebuffer};
+use crate::keymap::{Action, KeyMap};
+use crate::material_library::MaterialLibrary;
+use crate::ray_intersect::RayIntersect;
+use crate::scene::Scene;
+use crate::world::{BlockHit, BlockPos, World};
+
+const MATERIAL_KEYS: [Key; 9] = [
+    Key::Key1,
+    Key::Key2,
+    Key::Key3,
+    Key::Key4,
+    Key::Key5,
+    Key::Key6,
+    Key::Key7,
+    Key::Key8,
+    Key::Key9,
+];
+// Largo de cada brazo de la mira, en pixeles
+const CROSSHAIR_SIZE: i32 = 6;
+// Cuánto se aclara la cara apuntada
+const FACE_TINT: f32 = 0.2;
+
+// Edición de bloques con el mouse: clic izquierdo quita, clic derecho coloca el material elegido
+pub struct BlockEditor {
+    pub selected: BlockId,
+    left_was_down: bool,
+    right_was_down: bool,
+    // Posición del mouse en pixeles del framebuffer; None fuera de la imagen
+    pointer: Option<(f32, f32)>,
+    hovered: Option<BlockHit>,
+}
+
+impl BlockEditor {
+    pub fn new() -> Self {
+        BlockEditor {
+            selected: 1,
+            left_was_down: false,
+            right_was_down: false,
+            pointer: None,
+            hovered: None,
+        }
+    }
+
+    // Actualiza el bloque apuntado y aplica los clics; devuelve el bloque que se quitó o
+    // se colocó, si el mundo cambió
+    pub fn update(
+        &mut self,
+        window: &Window,
+        keys: &KeyMap,
+        scene: &mut Scene,
+        cubes: &[Cube],
+        framebuffer: &Framebuffer,
+    ) -> Option<BlockPos> {
+        // Las teclas 1 a 9 eligen el material: primero los de la paleta en su orden y
+        // después los de la biblioteca que el mundo todavía no tiene, que se agregan al elegirlos
+        for (index, key) in MATERIAL_KEYS.iter().enumerate() {
+            if window.is_key_down(*key) {
+                if let Some(block) = material_choice(&mut scene.world, index) {
+                    self.selected = block;
+                }
+            }
+        }
+
+        self.pointer = window.get_mouse_pos(MouseMode::Discard).and_then(|mouse| {
+            // La ventana estira el framebuffer, así que se escala la posición del mouse
+            let size = (framebuffer.width, framebuffer.height);
+            window_to_framebuffer(mouse, window.get_size(), size)
+        });
+        self.hovered = self
+            .pointer
+            .and_then(|(x, y)| pick(scene, cubes, x, y, framebuffer));
+
+        let left_down = window.get_mouse_down(MouseButton::Left);
+        let right_down = window.get_mouse_down(MouseButton::Right);
+        let left_clicked = left_down && !self.left_was_down;
+        let right_clicked = right_down && !self.right_was_down;
+        self.left_was_down = left_down;
+        self.right_was_down = right_down;
+
+        let hit = self.hovered?;
+
+        // F enfoca la lente de la cámara en el bloque apuntado
+        if keys.pressed(window, Action::Focus) {
+            let camera = &mut scene.camera;
+            let center = Vec3::new(
+                hit.pos.0 as f32 + 0.5,
+                hit.pos.1 as f32 + 0.5,
+                hit.pos.2 as f32 + 0.5,
+            );
+            let forward = (camera.target - camera.position).normalize();
+            camera.focus_distance = Some((center - camera.position).dot(&forward).max(0.1));
+        }
+
+        if left_clicked {
+            scene.world.set_block(hit.pos, AIR);
+            self.hovered = None;
+            return Some(hit.pos);
+        }
+
+        if right_clicked {
+            let target = (
+                hit.pos.0 + hit.face.0,
+                hit.pos.1 + hit.face.1,
+                hit.pos.2 + hit.face.2,
+            );
+            if scene.world.get_block(target) == AIR {
+                scene.world.set_block(target, self.selected);
+                return Some(target);
+            }
+        }
+
+        None
+    }
+
+    // Dibuja sobre la imagen ya renderizada el contorno del bloque apuntado, con las aristas
+    // que se ven desde la cámara, y aclara la cara en la que se colocaría un bloque
+    pub fn draw_highlight(&self, framebuffer: &mut Framebuffer, scene: &Scene) {
+        let Some(hit) = self.hovered else {
+            return;
+        };
+        let camera = &scene.camera;
+        let origin = Vec3::new(hit.pos.0 as f32, hit.pos.1 as f32, hit.pos.2 as f32);
+
+        // Esquina `index` del bloque: cada bit suma 1 en un eje (x, y, z)
+        let corner = |index: usize| {
+            origin
+                + Vec3::new(
+                    (index & 1) as f32,
+                    ((index >> 1) & 1) as f32,
+                    ((index >> 2) & 1) as f32,
+                )
+        };
+        // Si alguna esquina queda detrás de la cámara o muy lejos de la imagen no se dibuja
+        let limit = 4.0 * framebuffer.width.max(framebuffer.height) as f32;
+        let mut points = [(0.0, 0.0); 8];
+        for (index, point) in points.iter_mut().enumerate() {
+            let size = (framebuffer.width as f32, framebuffer.height as f32);
+            match camera.project(&corner(index), size.0, size.1) {
+                Some((x, y)) if x.abs() < limit && y.abs() < limit => *point = (x, y),
+                _ => return,
+            }
+        }
+
+        // Si la cámara ve la cara del eje `axis` del lado `side` (0 el de coordenada menor)
+        let forward = (camera.target - camera.position).normalize();
+        let facing = |axis: usize, side: usize| {
+            let mut normal = Vec3::zeros();
+            normal[axis] = if side == 1 { 1.0 } else { -1.0 };
+            let view = match camera.projection {
+                Projection::Orthographic => forward,
+                Projection::Perspective => {
+                    origin + Vec3::new(0.5, 0.5, 0.5) + normal * 0.5 - camera.position
+                }
+            };
+            view.dot(&normal) < 0.0
+        };
+
+        let face = [hit.face.0, hit.face.1, hit.face.2];
+        let axis = face.iter().position(|&offset| offset != 0).unwrap_or(0);
+        let side = (face[axis] > 0) as usize;
+        let (a, b) = ((axis + 1) % 3, (axis + 2) % 3);
+        let quad = [(0, 0), (1, 0), (1, 1), (0, 1)]
+            .map(|(u, v): (usize, usize)| points[(side << axis) | (u << a) | (v << b)]);
+        framebuffer.set_current_color(Color::new(1.0, 1.0, 1.0));
+        framebuffer.fill_quad(quad, FACE_TINT);
+
+        // Una arista se dibuja si se ve alguna de sus dos caras; las de atrás quedan ocultas
+        for start in 0..8 {
+            for edge_axis in (0..3).filter(|&edge_axis| start & (1 << edge_axis) == 0) {
+                let seen = (0..3)
+                    .filter(|&other| other != edge_axis)
+                    .any(|other| facing(other, (start >> other) & 1));
+                if seen {
+                    let (x0, y0) = points[start];
+                    let (x1, y1) = points[start | (1 << edge_axis)];
+                    framebuffer.line(
+                        x0.round() as i32,
+                        y0.round() as i32,
+                        x1.round() as i32,
+                        y1.round() as i32,
+                    );
+                }
+            }
+        }
+    }
+
+    // Mira en el punto del mouse, que marca el pixel con el que se apunta aunque la ventana
+    // estire el render
+    pub fn draw_crosshair(&self, framebuffer: &mut Framebuffer) {
+        let Some((x, y)) = self.pointer else {
+            return;
+        };
+        let (x, y) = (x as i32, y as i32);
+        // Sombra detrás de las líneas para que se vean sobre el cielo y la nieve
+        framebuffer.set_current_color(Color::black());
+        let span = 2 * CROSSHAIR_SIZE + 3;
+        framebuffer.fill_rect(x - CROSSHAIR_SIZE - 1, y - 1, span, 3, 0.5);
+        framebuffer.fill_rect(x - 1, y - CROSSHAIR_SIZE - 1, 3, CROSSHAIR_SIZE, 0.5);
+        framebuffer.fill_rect(x - 1, y + 2, 3, CROSSHAIR_SIZE, 0.5);
+
+        framebuffer.set_current_color(Color::new(1.0, 1.0, 1.0));
+        framebuffer.line(x - CROSSHAIR_SIZE, y, x + CROSSHAIR_SIZE, y);
+        framebuffer.line(x, y - CROSSHAIR_SIZE, x, y + CROSSHAIR_SIZE);
+    }
+}
+
+// Material que elige la tecla número `index` (desde 0)
+fn material_choice(world: &mut World, index: usize) -> Option<BlockId> {
+    let palette: Vec<BlockId> = world.materials().map(|(block, _, _)| block).collect();
+    if let Some(&block) = palette.get(index) {
+        return Some(block);
+    }
+    let library = MaterialLibrary::builtin();
+    let name = library
+        .names()
+        .filter(|name| world.find_material(name).is_none())
+        .nth(index - palette.len())?;
+    library.block(world, name)
+}
+
+impl Default for BlockEditor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn pick(
+    scene: &Scene,
+    cubes: &[Cube],
+    x: f32,
+    y: f32,
+    framebuffer: &Framebuffer,
+) -> Option<BlockHit> {
+    let camera = &scene.camera;
+    let (origin, direction) =
+        camera.primary_ray(x, y, framebuffer.width as f32, framebuffer.height as f32);
+    let hit = scene.world.pick(&origin, &direction)?;
+
+    // Un cubo libre delante del bloque lo tapa
+    let covered = cubes.iter().any(|cube| {
+        let intersect = cube.ray_intersect(&origin, &direction);
+        intersect.is_intersecting && intersect.distance < hit.distance
+    });
+    (!covered).then_some(hit)
+}
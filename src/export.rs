@@ -0,0 +1,433 @@
+// export.rs
+
+use exr::prelude::{
+    AnyChannel, AnyChannels, Encoding, FlatSamples, Image, Layer, LayerAttributes, WritableImage,
+};
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::imageops::thumbnail;
+use image::{Delay, Frame, ImageResult};
+use serde::Serialize;
+use std::f32::consts::PI;
+use std::fs::{self, File};
+use std::io::{self, BufWriter};
+use std::path::{Path, PathBuf};
+
+use crate::aov::{AovPixel, Aovs};
+use crate::camera::Projection;
+use crate::camera_path::CameraPath;
+use crate::color::Color;
+use crate::framebuffer::Framebuffer;
+use crate::post::apply_post;
+use crate::render::{render, render_aovs, RenderSettings, MAX_DEPTH};
+use crate::scene::Scene;
+use crate::stereo::{render_stereo, StereoSettings};
+
+// Lado más largo de la miniatura que acompaña a cada render
+pub const THUMBNAIL_SIZE: usize = 160;
+// Miniatura y datos de una secuencia, dentro de su carpeta de salida
+pub const SEQUENCE_THUMBNAIL: &str = "miniatura.png";
+pub const SEQUENCE_METADATA: &str = "secuencia.json";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SequenceMode {
+    // La cámara da una vuelta completa alrededor del objetivo
+    Orbit,
+    // La cámara queda fija y se recorre un día completo
+    DayCycle,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SequenceFormat {
+    Png,
+    Gif,
+}
+
+#[derive(Debug, Clone)]
+pub struct SequenceSettings {
+    pub frames: u32,
+    pub mode: SequenceMode,
+    pub format: SequenceFormat,
+    pub width: usize,
+    pub height: usize,
+    pub samples: u32,
+    // Rebotes de reflexión y refracción de cada rayo
+    pub max_depth: u32,
+    pub fps: u32,
+    pub time_of_day: f32,
+    pub output_dir: PathBuf,
+    // Si hay recorrido, la cámara lo sigue al ritmo de `fps` en lugar de orbitar
+    pub camera_path: Option<CameraPath>,
+    // Con vista estéreo cada frame lleva las dos vistas de `width` x `height` empaquetadas
+    pub stereo: Option<StereoSettings>,
+}
+
+impl Default for SequenceSettings {
+    fn default() -> Self {
+        SequenceSettings {
+            frames: 120,
+            mode: SequenceMode::Orbit,
+            format: SequenceFormat::Png,
+            width: 600,
+            height: 400,
+            samples: 1,
+            max_depth: MAX_DEPTH,
+            fps: 30,
+            time_of_day: 0.3,
+            output_dir: PathBuf::from("frames"),
+            camera_path: None,
+            stereo: None,
+        }
+    }
+}
+
+// Lo necesario para repetir un render tal cual; se guarda en JSON junto a la imagen
+#[derive(Debug, Clone, Serialize)]
+pub struct RenderMetadata {
+    // Argumentos del programa sin el nombre del ejecutable: volver a correrlos desde la
+    // misma carpeta da la misma imagen
+    pub command: Vec<String>,
+    pub scene: Option<PathBuf>,
+    // Semilla del terreno generado. Las muestras de cada pixel salen de una máscara de
+    // ruido azul fija, así que el render en sí no tiene semilla
+    pub seed: Option<u64>,
+    pub overrides: Vec<String>,
+    // Parámetros de la hoja de contactos como `ruta=a|b|c`
+    pub sweep: Vec<String>,
+    pub time_of_day: Option<f32>,
+    pub width: usize,
+    pub height: usize,
+    pub samples: u32,
+    pub max_depth: u32,
+    // Solo en las secuencias
+    pub frames: Option<u32>,
+    pub firefly_clamp: f32,
+    pub roulette: f32,
+    pub transparent_background: bool,
+    pub dither: bool,
+    // Si la imagen pasó por el filtro de ruido
+    pub denoised: bool,
+    // Si junto a la imagen se guardaron las pasadas auxiliares
+    pub aovs: bool,
+    // La cámara al empezar el render
+    pub camera: CameraMetadata,
+    pub render_seconds: f32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CameraMetadata {
+    pub position: [f32; 3],
+    pub target: [f32; 3],
+    pub projection: String,
+    pub fov_degrees: f32,
+    pub ortho_height: f32,
+    pub roll_degrees: f32,
+    pub near: f32,
+    pub far: Option<f32>,
+    pub aperture: f32,
+    pub focus_distance: Option<f32>,
+}
+
+impl RenderMetadata {
+    // Toma la cámara y los ajustes de la escena; el resto lo completa quien renderiza
+    pub fn new(scene: &Scene, width: usize, height: usize, samples: u32) -> Self {
+        let camera = &scene.camera;
+        let projection = match camera.projection {
+            Projection::Perspective => "perspective",
+            Projection::Orthographic => "orthographic",
+        };
+        RenderMetadata {
+            command: Vec::new(),
+            scene: None,
+            seed: None,
+            overrides: Vec::new(),
+            sweep: Vec::new(),
+            time_of_day: None,
+            width,
+            height,
+            samples,
+            max_depth: MAX_DEPTH,
+            frames: None,
+            firefly_clamp: scene.firefly_clamp,
+            roulette: scene.roulette,
+            transparent_background: scene.transparent_background,
+            dither: scene.dither,
+            denoised: false,
+            aovs: false,
+            camera: CameraMetadata {
+                position: camera.position.into(),
+                target: camera.target.into(),
+                projection: projection.to_string(),
+                fov_degrees: camera.fov.to_degrees(),
+                ortho_height: camera.ortho_height,
+                roll_degrees: camera.roll.to_degrees(),
+                near: camera.near,
+                far: camera.far,
+                aperture: camera.aperture,
+                focus_distance: camera.focus_distance,
+            },
+            render_seconds: 0.0,
+        }
+    }
+}
+
+// Una sola imagen de la escena tal como está, sin animar ni mover la cámara
+pub fn render_image(
+    scene: &Scene,
+    width: usize,
+    height: usize,
+    samples: u32,
+    max_depth: u32,
+) -> Framebuffer {
+    let mut framebuffer = Framebuffer::new(width, height);
+    framebuffer.dither = scene.dither;
+    render(
+        &mut framebuffer,
+        scene,
+        &scene.cubes_at(0.0),
+        &RenderSettings {
+            max_depth,
+            ..RenderSettings::with_samples(samples)
+        },
+    );
+    framebuffer
+}
+
+// Como `render_image`, y además devuelve las pasadas auxiliares de cada pixel
+pub fn render_image_aovs(
+    scene: &Scene,
+    width: usize,
+    height: usize,
+    samples: u32,
+    max_depth: u32,
+) -> (Framebuffer, Aovs) {
+    let mut framebuffer = Framebuffer::new(width, height);
+    framebuffer.dither = scene.dither;
+    let aovs = render_aovs(
+        &mut framebuffer,
+        scene,
+        &scene.cubes_at(0.0),
+        &RenderSettings {
+            max_depth,
+            ..RenderSettings::with_samples(samples)
+        },
+    );
+    (framebuffer, aovs)
+}
+
+// Guarda cada pasada auxiliar como `<nombre>_<pasada>.png` al lado de la imagen `output`
+fn save_aovs(aovs: &Aovs, output: &Path) -> ImageResult<()> {
+    let stem = output.file_stem().unwrap_or_default().to_string_lossy();
+    for (name, image) in aovs.images() {
+        save_image(
+            &image,
+            &output.with_file_name(format!("{}_{}.png", stem, name)),
+        )?;
+    }
+    Ok(())
+}
+
+// Guarda la imagen (el formato sale de la extensión de `output`) con una miniatura
+// `<nombre>_mini.png` y los datos del render en `<nombre>.json` al lado. Las pasadas
+// auxiliares van en archivos aparte, o como canales de la misma imagen si es un EXR.
+pub fn save_render(
+    framebuffer: &Framebuffer,
+    aovs: Option<&Aovs>,
+    output: &Path,
+    metadata: &RenderMetadata,
+) -> ImageResult<()> {
+    match aovs {
+        Some(aovs) if is_exr_path(output) => save_exr(framebuffer, Some(aovs), output)?,
+        Some(aovs) => {
+            save_image(framebuffer, output)?;
+            save_aovs(aovs, output)?;
+        }
+        None => save_image(framebuffer, output)?,
+    }
+    let stem = output.file_stem().unwrap_or_default().to_string_lossy();
+    save_thumbnail(
+        framebuffer,
+        &output.with_file_name(format!("{}_mini.png", stem)),
+    )?;
+    save_metadata(metadata, &output.with_extension("json"))
+}
+
+// Guarda el framebuffer creando la carpeta si hace falta
+pub fn save_image(framebuffer: &Framebuffer, output: &Path) -> ImageResult<()> {
+    if is_exr_path(output) {
+        return save_exr(framebuffer, None, output);
+    }
+    create_parent(output)?;
+    framebuffer.to_image().save(output)
+}
+
+// Si `output` es un archivo OpenEXR
+fn is_exr_path(output: &Path) -> bool {
+    output
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("exr"))
+}
+
+// Guarda el framebuffer en OpenEXR, con los colores en punto flotante de 32 bits tal como
+// salen del render (sin pasarlos a 8 bits y con lo que supera 1, como un cielo HDR): canales
+// R, G, B y A, con el alfa premultiplicado. Con `aovs` el mismo archivo lleva también Z (la
+// profundidad, infinita en el cielo), N.X, N.Y y N.Z, y las capas albedo, direct e indirect.
+pub fn save_exr(framebuffer: &Framebuffer, aovs: Option<&Aovs>, output: &Path) -> ImageResult<()> {
+    create_parent(output)?;
+    let mut channels = Vec::new();
+    push_color_channels(&mut channels, "", &framebuffer.buffer);
+    channels.push(AnyChannel::new(
+        "A",
+        FlatSamples::F32(framebuffer.alpha.clone()),
+    ));
+    if let Some(aovs) = aovs {
+        let pixels = &aovs.pixels;
+        let channel = |name: &str, value: fn(&AovPixel) -> f32| {
+            AnyChannel::new(name, FlatSamples::F32(pixels.iter().map(value).collect()))
+        };
+        channels.push(channel("Z", |pixel| pixel.depth));
+        channels.push(channel("N.X", |pixel| pixel.normal.x));
+        channels.push(channel("N.Y", |pixel| pixel.normal.y));
+        channels.push(channel("N.Z", |pixel| pixel.normal.z));
+        let layer = |value: fn(&AovPixel) -> Color| pixels.iter().map(value).collect::<Vec<_>>();
+        let layers = [
+            ("albedo.", layer(|pixel| pixel.albedo)),
+            ("direct.", layer(|pixel| pixel.direct)),
+            ("indirect.", layer(|pixel| pixel.indirect)),
+        ];
+        for (layer, colors) in layers {
+            push_color_channels(&mut channels, layer, &colors);
+        }
+    }
+
+    let layer = Layer::new(
+        (framebuffer.width, framebuffer.height),
+        LayerAttributes::default(),
+        Encoding::FAST_LOSSLESS,
+        AnyChannels::sort(channels.into()),
+    );
+    Image::from_layer(layer)
+        .write()
+        .to_file(output)
+        .map_err(|err| io::Error::other(err.to_string()))?;
+    Ok(())
+}
+
+// Agrega los canales R, G y B de `colors`, con el prefijo de su capa
+fn push_color_channels(channels: &mut Vec<AnyChannel<FlatSamples>>, layer: &str, colors: &[Color]) {
+    let component = |value: fn(&Color) -> f32| colors.iter().map(value).collect::<Vec<_>>();
+    let components = [
+        ("R", component(|color| color.r)),
+        ("G", component(|color| color.g)),
+        ("B", component(|color| color.b)),
+    ];
+    for (name, samples) in components {
+        channels.push(AnyChannel::new(
+            format!("{}{}", layer, name).as_str(),
+            FlatSamples::F32(samples),
+        ));
+    }
+}
+
+fn create_parent(output: &Path) -> io::Result<()> {
+    if let Some(dir) = output.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    Ok(())
+}
+
+// Copia reducida para listar renders sin abrir las imágenes completas
+pub fn save_thumbnail(framebuffer: &Framebuffer, output: &Path) -> ImageResult<()> {
+    let scale = THUMBNAIL_SIZE as f32 / framebuffer.width.max(framebuffer.height) as f32;
+    let scale = scale.min(1.0);
+    let width = ((framebuffer.width as f32 * scale).round() as u32).max(1);
+    let height = ((framebuffer.height as f32 * scale).round() as u32).max(1);
+    thumbnail(&framebuffer.to_image(), width, height).save(output)
+}
+
+pub fn save_metadata(metadata: &RenderMetadata, output: &Path) -> ImageResult<()> {
+    let json = serde_json::to_string_pretty(metadata).map_err(io::Error::from)?;
+    fs::write(output, json)?;
+    Ok(())
+}
+
+pub fn render_sequence(scene: &mut Scene, settings: &SequenceSettings) -> ImageResult<PathBuf> {
+    fs::create_dir_all(&settings.output_dir)?;
+
+    let frames = settings.frames.max(1);
+    let (frame_width, frame_height) = match &settings.stereo {
+        Some(stereo) => stereo.frame_size(settings.width, settings.height),
+        None => (settings.width, settings.height),
+    };
+    let mut framebuffer = Framebuffer::new(frame_width, frame_height);
+    framebuffer.dither = scene.dither;
+    let orbit_step = 2.0 * PI / frames as f32;
+
+    let mut gif = match settings.format {
+        SequenceFormat::Gif => {
+            let file = File::create(settings.output_dir.join("secuencia.gif"))?;
+            let mut encoder = GifEncoder::new(BufWriter::new(file));
+            encoder.set_repeat(Repeat::Infinite)?;
+            Some(encoder)
+        }
+        SequenceFormat::Png => None,
+    };
+
+    let mut cubes = scene.cubes_at(0.0);
+    scene.apply_day_cycle(settings.time_of_day);
+    let frame_time = 1.0 / settings.fps.max(1) as f32;
+    let quality = RenderSettings {
+        max_depth: settings.max_depth,
+        ..RenderSettings::with_samples(settings.samples)
+    };
+
+    for frame in 0..frames {
+        // Las animaciones de los objetos avanzan al ritmo de reproducción de la secuencia
+        if frame > 0 && scene.is_animated() {
+            cubes = scene.cubes_at(frame as f32 * frame_time);
+        }
+
+        scene.animate_lights(frame as f32 * frame_time);
+        scene.animate_sky(frame as f32 * frame_time);
+        scene.animate_textures(frame as f32 * frame_time);
+
+        if let Some(camera_path) = &settings.camera_path {
+            camera_path.apply(&mut scene.camera, frame as f32 * frame_time);
+        }
+
+        if settings.mode == SequenceMode::DayCycle {
+            let day_progress = (settings.time_of_day + frame as f32 / frames as f32).fract();
+            scene.apply_day_cycle(day_progress);
+        }
+
+        match &settings.stereo {
+            Some(stereo) => render_stereo(&mut framebuffer, scene, &cubes, &quality, stereo),
+            None => {
+                render(&mut framebuffer, scene, &cubes, &quality);
+                apply_post(&scene.post, &mut framebuffer);
+            }
+        }
+
+        if frame == 0 {
+            save_thumbnail(&framebuffer, &settings.output_dir.join(SEQUENCE_THUMBNAIL))?;
+        }
+
+        match gif.as_mut() {
+            Some(encoder) => {
+                let delay = Delay::from_numer_denom_ms(1000, settings.fps.max(1));
+                encoder.encode_frame(Frame::from_parts(framebuffer.to_image(), 0, 0, delay))?;
+            }
+            None => {
+                let path = settings.output_dir.join(format!("frame_{:04}.png", frame));
+                framebuffer.to_image().save(path)?;
+            }
+        }
+
+        if settings.mode == SequenceMode::Orbit && settings.camera_path.is_none() {
+            scene.camera.rotate_around_target(orbit_step, 0.0);
+        }
+
+        println!("Frame {}/{}", frame + 1, frames);
+    }
+
+    Ok(settings.output_dir.clone())
+}
@@ -0,0 +1,41 @@
+// fog.rs
+
+use crate::color::Color;
+
+// Niebla que llena el aire entre la cámara y lo que ve: oscurece lo lejano hacia el color
+// de la niebla y, donde le llega el sol, se ilumina, así que las sombras de los bloques se
+// ven como haces de luz (rayos crepusculares). El trazador la recorre en los rayos de cámara.
+#[derive(Debug, Clone)]
+pub struct Fog {
+    // Fracción de la luz que se pierde por bloque recorrido
+    pub density: f32,
+    pub color: Color,
+    // Hasta dónde se recorre cada rayo, en bloques. Lo que está más lejos, incluido el
+    // cielo, se ve a través de la niebla de este tramo.
+    pub distance: f32,
+    // Cuánto se concentra el brillo al mirar hacia el sol: 0 lo reparte igual en todas las
+    // direcciones y cerca de 1 lo junta alrededor del sol
+    pub anisotropy: f32,
+}
+
+impl Default for Fog {
+    fn default() -> Self {
+        Fog {
+            density: 0.05,
+            color: Color::new(0.85, 0.88, 0.92),
+            distance: 48.0,
+            anisotropy: 0.5,
+        }
+    }
+}
+
+impl Fog {
+    // Fracción de la luz del sol que la niebla desvía hacia la cámara (Henyey-Greenstein),
+    // relativa a una niebla que la reparte por igual. `cosine` es el coseno del ángulo
+    // entre la dirección de vista y la del sol.
+    pub fn phase(&self, cosine: f32) -> f32 {
+        let g = self.anisotropy;
+        let denominator = (1.0 + g * g - 2.0 * g * cosine).max(1e-4);
+        (1.0 - g * g) / (denominator * denominator.sqrt())
+    }
+}
@@ -1,12 +1,22 @@
 
+use crate::blue_noise::blue_noise;
 use crate::color::Color;
+use image::{Rgba, RgbaImage};
 
 pub struct Framebuffer {
     pub width: usize,
     pub height: usize,
     pub buffer: Vec<Color>,
+    // Cobertura de cada pixel (1 = opaco); con fondo transparente el color ya viene
+    // multiplicado por este valor
+    pub alpha: Vec<f32>,
+    // Al pasar a 8 bits cada pixel suma un umbral de ruido azul antes de truncar, así los
+    // degradados suaves (el cielo) se ven con grano fino en lugar de escalones
+    pub dither: bool,
     background_color: Color,
     current_color: Color,
+    // Colores empaquetados para la ventana; se reutiliza entre frames
+    packed: Vec<u32>,
 }
 
 impl Framebuffer {
@@ -15,8 +25,11 @@ impl Framebuffer {
             width,
             height,
             buffer: vec![Color::new(0.0, 0.0, 0.0); width * height],
+            alpha: vec![1.0; width * height],
+            dither: false,
             background_color: Color::new(0.0, 0.0, 0.0),
-            current_color: Color::new(225.0, 225.0, 225.0)
+            current_color: Color::new(225.0, 225.0, 225.0),
+            packed: Vec::new(),
         }
     }
 
@@ -32,6 +45,96 @@ impl Framebuffer {
         }
     }
 
+    // Línea de Bresenham con el color actual; los puntos fuera de la imagen se ignoran
+    pub fn line(&mut self, x0: i32, y0: i32, x1: i32, y1: i32) {
+        let dx = (x1 - x0).abs();
+        let dy = -(y1 - y0).abs();
+        let step_x = if x0 < x1 { 1 } else { -1 };
+        let step_y = if y0 < y1 { 1 } else { -1 };
+        let mut error = dx + dy;
+        let (mut x, mut y) = (x0, y0);
+
+        loop {
+            if x >= 0 && y >= 0 {
+                self.point(x as usize, y as usize);
+            }
+            if x == x1 && y == y1 {
+                break;
+            }
+            let doubled = 2 * error;
+            if doubled >= dy {
+                error += dy;
+                x += step_x;
+            }
+            if doubled <= dx {
+                error += dx;
+                y += step_y;
+            }
+        }
+    }
+
+    // Contorno de un rectángulo con el color actual, con la esquina superior izquierda en
+    // (x, y); lo que queda fuera de la imagen se recorta
+    pub fn rect(&mut self, x: i32, y: i32, width: i32, height: i32) {
+        if width <= 0 || height <= 0 {
+            return;
+        }
+        let (right, bottom) = (x + width - 1, y + height - 1);
+        self.line(x, y, right, y);
+        self.line(right, y, right, bottom);
+        self.line(right, bottom, x, bottom);
+        self.line(x, bottom, x, y);
+    }
+
+    // Rellena un rectángulo mezclando el color actual con lo que hay debajo: `alpha` 1 lo
+    // tapa y 0 lo deja como estaba
+    pub fn fill_rect(&mut self, x: i32, y: i32, width: i32, height: i32, alpha: f32) {
+        let columns = x.max(0) as usize..(x + width).clamp(0, self.width as i32) as usize;
+        let rows = y.max(0) as usize..(y + height).clamp(0, self.height as i32) as usize;
+        for row in rows {
+            for column in columns.clone() {
+                self.blend(column, row, alpha);
+            }
+        }
+    }
+
+    // Rellena un cuadrilátero convexo con el color actual mezclado como en `fill_rect`. Las
+    // esquinas van en orden alrededor del borde, en cualquier sentido; cuenta cada pixel
+    // cuyo centro cae adentro.
+    pub fn fill_quad(&mut self, corners: [(f32, f32); 4], alpha: f32) {
+        let (mut left, mut top) = (f32::INFINITY, f32::INFINITY);
+        let (mut right, mut bottom) = (f32::NEG_INFINITY, f32::NEG_INFINITY);
+        for &(x, y) in &corners {
+            left = left.min(x);
+            top = top.min(y);
+            right = right.max(x);
+            bottom = bottom.max(y);
+        }
+        let columns = left.max(0.0) as usize..(right.ceil().max(0.0) as usize).min(self.width);
+        let rows = top.max(0.0) as usize..(bottom.ceil().max(0.0) as usize).min(self.height);
+
+        // Lado de cada borde en que queda el punto; adentro es el mismo para los cuatro
+        let side = |(px, py): (f32, f32), index: usize| {
+            let (x0, y0) = corners[index];
+            let (x1, y1) = corners[(index + 1) % 4];
+            (x1 - x0) * (py - y0) - (y1 - y0) * (px - x0)
+        };
+        for row in rows {
+            for column in columns.clone() {
+                let center = (column as f32 + 0.5, row as f32 + 0.5);
+                let sides = [0, 1, 2, 3].map(|index| side(center, index));
+                if sides.iter().all(|&s| s >= 0.0) || sides.iter().all(|&s| s <= 0.0) {
+                    self.blend(column, row, alpha);
+                }
+            }
+        }
+    }
+
+    fn blend(&mut self, x: usize, y: usize, alpha: f32) {
+        let pixel = &mut self.buffer[y * self.width + x];
+        *pixel = *pixel * (1.0 - alpha) + self.current_color * alpha;
+    }
+
     pub fn set_background_color(&mut self, color: Color) {
         self.background_color = color;
     }
@@ -39,4 +142,82 @@ impl Framebuffer {
     pub fn set_current_color(&mut self, color: Color) {
         self.current_color = color;
     }
+
+    // Llena todo el framebuffer con `source` estirada a su tamaño, interpolando entre los
+    // cuatro pixeles más cercanos (color y cobertura)
+    pub fn upscale_from(&mut self, source: &Framebuffer) {
+        if source.width == 0 || source.height == 0 {
+            return;
+        }
+        let scale_x = source.width as f32 / self.width as f32;
+        let scale_y = source.height as f32 / self.height as f32;
+        for y in 0..self.height {
+            let sy = ((y as f32 + 0.5) * scale_y - 0.5).clamp(0.0, (source.height - 1) as f32);
+            let (y0, ty) = (sy as usize, sy.fract());
+            let y1 = (y0 + 1).min(source.height - 1);
+            for x in 0..self.width {
+                let sx = ((x as f32 + 0.5) * scale_x - 0.5).clamp(0.0, (source.width - 1) as f32);
+                let (x0, tx) = (sx as usize, sx.fract());
+                let x1 = (x0 + 1).min(source.width - 1);
+                let corners = [
+                    (y0 * source.width + x0, (1.0 - tx) * (1.0 - ty)),
+                    (y0 * source.width + x1, tx * (1.0 - ty)),
+                    (y1 * source.width + x0, (1.0 - tx) * ty),
+                    (y1 * source.width + x1, tx * ty),
+                ];
+                let index = y * self.width + x;
+                self.buffer[index] = corners
+                    .iter()
+                    .fold(Color::black(), |sum, &(i, w)| sum + source.buffer[i] * w);
+                self.alpha[index] = corners.iter().map(|&(i, w)| source.alpha[i] * w).sum();
+            }
+        }
+    }
+
+    // Los colores como 0RGB de 8 bits por canal, listos para `update_with_buffer`. Se
+    // escriben sobre el mismo vector en cada llamada, sin pedir memoria nueva.
+    pub fn as_u32_buffer(&mut self) -> &[u32] {
+        self.packed.resize(self.buffer.len(), 0);
+        let (width, dither) = (self.width, self.dither);
+        for (index, (packed, color)) in self.packed.iter_mut().zip(&self.buffer).enumerate() {
+            *packed = quantize(*color, (index % width, index / width), dither);
+        }
+        &self.packed
+    }
+
+    // RGBA con alfa premultiplicado, listo para componer sobre otra imagen
+    pub fn to_image(&self) -> RgbaImage {
+        RgbaImage::from_fn(self.width as u32, self.height as u32, |x, y| {
+            let index = y as usize * self.width + x as usize;
+            let rgb = quantize(self.buffer[index], (x as usize, y as usize), self.dither);
+            let alpha = (self.alpha[index].clamp(0.0, 1.0) * 255.0) as u8;
+            Rgba([(rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8, alpha])
+        })
+    }
+}
+
+// Color en 0RGB de 8 bits. Con `dither` se suma al color un umbral de ruido azul de hasta un
+// nivel antes de truncar: en promedio el pixel conserva el valor que tenía entre dos niveles
+// y el error queda repartido como grano en lugar de bandas.
+fn quantize(color: Color, (x, y): (usize, usize), dither: bool) -> u32 {
+    if !dither {
+        return color.to_u32();
+    }
+    let threshold = blue_noise().sample(x, y, 0) / 255.0;
+    (color + Color::new(threshold, threshold, threshold)).to_u32()
+}
+
+// Posición del mouse en una ventana de `window` pixeles pasada a pixeles de un framebuffer
+// de `size`, que la ventana muestra estirado sin deformarlo y centrado. None sobre las
+// franjas que quedan a los lados.
+pub fn window_to_framebuffer(
+    (mouse_x, mouse_y): (f32, f32),
+    window: (usize, usize),
+    size: (usize, usize),
+) -> Option<(f32, f32)> {
+    let scale = (window.0 as f32 / size.0 as f32).min(window.1 as f32 / size.1 as f32);
+    let left = (window.0 as f32 - size.0 as f32 * scale) / 2.0;
+    let top = (window.1 as f32 - size.1 as f32 * scale) / 2.0;
+    let (x, y) = ((mouse_x - left) / scale, (mouse_y - top) / scale);
+    (x >= 0.0 && y >= 0.0 && x < size.0 as f32 && y < size.1 as f32).then_some((x, y))
 }
@@ -0,0 +1,181 @@
+// fxaa.rs
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use crate::color::Color;
+use crate::framebuffer::Framebuffer;
+
+// Contraste mínimo entre vecinos, relativo al más brillante, para tratar un pixel como borde
+const EDGE_THRESHOLD: f32 = 0.125;
+// Y en valor absoluto, para no suavizar el grano de las zonas oscuras
+const EDGE_THRESHOLD_MIN: f32 = 0.0312;
+// Cuánto se mezclan los detalles más finos que un pixel con sus vecinos
+const SUBPIXEL_QUALITY: f32 = 0.75;
+// Pasos con los que se sigue el borde a cada lado buscando su extremo; se alargan para
+// llegar lejos en los bordes casi horizontales o casi verticales
+const STEPS: [f32; 12] = [1.0, 1.0, 1.0, 1.0, 1.0, 1.5, 2.0, 2.0, 2.0, 2.0, 4.0, 8.0];
+
+// Antialiasing FXAA (Lottes, 2009) sobre la imagen ya lista para mostrar: encuentra los
+// bordes por el contraste de brillo entre vecinos, los sigue hasta sus extremos para saber
+// cuánto del pixel queda de cada lado y lo mezcla en esa proporción con el vecino del otro
+// lado. Es una sola pasada, mucho más barata que más muestras por pixel, a cambio de
+// suavizar un poco las texturas.
+pub fn fxaa(framebuffer: &mut Framebuffer) {
+    let (width, height) = (framebuffer.width, framebuffer.height);
+    if width < 3 || height < 3 {
+        return;
+    }
+    // Trabaja con los colores que se van a mostrar, ya recortados a 0..1
+    let colors: Vec<Color> = framebuffer
+        .buffer
+        .iter()
+        .map(|color| color.clamp())
+        .collect();
+    let lumas: Vec<f32> = colors.iter().map(Color::luminance).collect();
+    let image = Image {
+        colors: &colors,
+        lumas: &lumas,
+        width,
+        height,
+    };
+
+    #[cfg(feature = "parallel")]
+    let filtered: Vec<Color> = (0..width * height)
+        .into_par_iter()
+        .map(|index| image.pixel(index))
+        .collect();
+    #[cfg(not(feature = "parallel"))]
+    let filtered: Vec<Color> = (0..width * height)
+        .map(|index| image.pixel(index))
+        .collect();
+    framebuffer.buffer = filtered;
+}
+
+struct Image<'a> {
+    colors: &'a [Color],
+    lumas: &'a [f32],
+    width: usize,
+    height: usize,
+}
+
+impl Image<'_> {
+    // Brillo del pixel (x, y); fuera de la imagen se repite el borde
+    fn luma(&self, x: isize, y: isize) -> f32 {
+        let x = x.clamp(0, self.width as isize - 1) as usize;
+        let y = y.clamp(0, self.height as isize - 1) as usize;
+        self.lumas[y * self.width + x]
+    }
+
+    // Color en un punto cualquiera, interpolando los cuatro pixeles que lo rodean (los
+    // centros de los pixeles están en las coordenadas enteras)
+    fn sample(&self, x: f32, y: f32) -> Color {
+        let x = x.clamp(0.0, (self.width - 1) as f32);
+        let y = y.clamp(0.0, (self.height - 1) as f32);
+        let (x0, y0) = (x.floor() as usize, y.floor() as usize);
+        let (x1, y1) = ((x0 + 1).min(self.width - 1), (y0 + 1).min(self.height - 1));
+        let (tx, ty) = (x - x0 as f32, y - y0 as f32);
+        let at = |x: usize, y: usize| self.colors[y * self.width + x];
+        let top = at(x0, y0) * (1.0 - tx) + at(x1, y0) * tx;
+        let bottom = at(x0, y1) * (1.0 - tx) + at(x1, y1) * tx;
+        top * (1.0 - ty) + bottom * ty
+    }
+
+    fn pixel(&self, index: usize) -> Color {
+        let (x, y) = ((index % self.width) as isize, (index / self.width) as isize);
+        let center = self.lumas[index];
+        let north = self.luma(x, y - 1);
+        let south = self.luma(x, y + 1);
+        let west = self.luma(x - 1, y);
+        let east = self.luma(x + 1, y);
+        let lowest = center.min(north).min(south).min(west).min(east);
+        let highest = center.max(north).max(south).max(west).max(east);
+        let range = highest - lowest;
+        if range < EDGE_THRESHOLD_MIN.max(highest * EDGE_THRESHOLD) {
+            return self.colors[index];
+        }
+
+        let north_west = self.luma(x - 1, y - 1);
+        let north_east = self.luma(x + 1, y - 1);
+        let south_west = self.luma(x - 1, y + 1);
+        let south_east = self.luma(x + 1, y + 1);
+
+        // Un pixel que se despega de todos sus vecinos se acerca a su promedio
+        let average = (2.0 * (north + south + west + east)
+            + north_west
+            + north_east
+            + south_west
+            + south_east)
+            / 12.0;
+        let subpixel = ((average - center).abs() / range).clamp(0.0, 1.0);
+        let subpixel = (3.0 - 2.0 * subpixel) * subpixel * subpixel;
+        let subpixel = subpixel * subpixel * SUBPIXEL_QUALITY;
+
+        // En un borde horizontal el brillo cambia más entre filas que entre columnas
+        let across_rows = (north_west + south_west - 2.0 * west).abs()
+            + 2.0 * (north + south - 2.0 * center).abs()
+            + (north_east + south_east - 2.0 * east).abs();
+        let across_columns = (north_west + north_east - 2.0 * north).abs()
+            + 2.0 * (west + east - 2.0 * center).abs()
+            + (south_west + south_east - 2.0 * south).abs();
+        let horizontal = across_rows >= across_columns;
+
+        // El otro lado del borde es el vecino con más contraste: arriba o abajo en un borde
+        // horizontal, izquierda o derecha en uno vertical
+        let (before, after) = if horizontal {
+            (north, south)
+        } else {
+            (west, east)
+        };
+        let gradient = 0.25 * (before - center).abs().max((after - center).abs());
+        let (side, edge_luma) = if (before - center).abs() >= (after - center).abs() {
+            (-1.0, 0.5 * (before + center))
+        } else {
+            (1.0, 0.5 * (after + center))
+        };
+        let (normal, along) = if horizontal {
+            ((0.0, side), (1.0, 0.0))
+        } else {
+            ((side, 0.0), (0.0, 1.0))
+        };
+        // Sobre la línea entre el pixel y el del otro lado del borde
+        let start = (x as f32 + normal.0 * 0.5, y as f32 + normal.1 * 0.5);
+
+        // Se avanza por el borde hasta que el brillo deja de ser el del borde: ahí termina
+        let explore = |direction: f32| {
+            let mut distance = 0.0;
+            let mut delta = 0.0;
+            for step in STEPS {
+                distance += step;
+                let point = (
+                    start.0 + along.0 * direction * distance,
+                    start.1 + along.1 * direction * distance,
+                );
+                delta = self.sample(point.0, point.1).luminance() - edge_luma;
+                if delta.abs() >= gradient {
+                    break;
+                }
+            }
+            (distance, delta)
+        };
+        let (distance_back, delta_back) = explore(-1.0);
+        let (distance_forward, delta_forward) = explore(1.0);
+
+        // Cerca de un extremo el borde cruza el pixel: cuanto más cerca, más se mezcla. Solo
+        // vale si en ese extremo el brillo va en sentido contrario al del centro; si no, el
+        // pixel está del lado de afuera del escalón.
+        let (distance, delta) = if distance_back < distance_forward {
+            (distance_back, delta_back)
+        } else {
+            (distance_forward, delta_forward)
+        };
+        let offset = if (delta < 0.0) != (center < edge_luma) {
+            0.5 - distance / (distance_back + distance_forward)
+        } else {
+            0.0
+        };
+        let offset = offset.max(subpixel);
+
+        self.sample(x as f32 + normal.0 * offset, y as f32 + normal.1 * offset)
+    }
+}
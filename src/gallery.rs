@@ -0,0 +1,174 @@
+// gallery.rs
+//
+// Escenas pequeñas incluidas en el programa, cada una dedicada a una función del trazador,
+// para recorrerlas sin tener que buscar archivos. Se escriben en el formato .scene y usan
+// patrones procedurales en lugar de texturas, así que no dependen de nada en disco (salvo
+// el cielo, que tiene su reemplazo integrado).
+
+use std::path::Path;
+
+use crate::scene::Scene;
+use crate::scene_file::load_scene_source;
+
+#[derive(Debug)]
+pub struct Showcase {
+    // Para elegirla con --gallery
+    pub name: &'static str,
+    pub title: &'static str,
+    pub description: &'static str,
+    // Hora del día con la que se abre, entre 0 y 1
+    pub time_of_day: f32,
+    source: &'static str,
+}
+
+impl Showcase {
+    pub fn find(name: &str) -> Option<&'static Showcase> {
+        SHOWCASES.iter().find(|showcase| showcase.name == name)
+    }
+
+    pub fn build(&self) -> Scene {
+        let path = Path::new("galeria").join(format!("{}.scene", self.name));
+        // Las escenas son parte del programa: un error acá es un error del código
+        load_scene_source(self.source, &path)
+            .unwrap_or_else(|err| panic!("escena de la galería inválida: {}", err))
+    }
+}
+
+pub const SHOWCASES: &[Showcase] = &[
+    Showcase {
+        name: "refraccion",
+        title: "Refracción",
+        description: "Vidrio teñido y agua borrosa desvían el tablero de detrás",
+        time_of_day: 0.35,
+        source: REFRACTION,
+    },
+    Showcase {
+        name: "sombras",
+        title: "Sombras suaves",
+        description: "Luces con radio: la penumbra crece con la distancia",
+        time_of_day: 0.3,
+        source: SOFT_SHADOWS,
+    },
+    Showcase {
+        name: "noche",
+        title: "Noche",
+        description: "Bloques emisivos, antorchas que parpadean y cielo estrellado",
+        time_of_day: 0.85,
+        source: NIGHT,
+    },
+    Showcase {
+        name: "pbr",
+        title: "Materiales PBR",
+        description: "Metales y plásticos con distinta rugosidad",
+        time_of_day: 0.35,
+        source: PBR,
+    },
+    Showcase {
+        name: "patrones",
+        title: "Patrones",
+        description: "Tablero, ruido y madera calculados sin imágenes",
+        time_of_day: 0.35,
+        source: PATTERNS,
+    },
+    Showcase {
+        name: "niebla",
+        title: "Niebla y rayos de sol",
+        description: "El sol del amanecer atraviesa la niebla entre pilares de piedra",
+        time_of_day: 0.06,
+        source: FOG,
+    },
+];
+
+const REFRACTION: &str = "
+camera position 4.5 4 11 target 4.5 0.5 3 up 0 1 0 fov 55deg
+material piso diffuse 255 255 255 specular 5 albedo 0.9 0.1 0 0 pattern checker 235 235 235 40 40 40 1
+material pared diffuse 255 255 255 specular 5 albedo 0.9 0.1 0 0 pattern noise 190 90 70 110 45 35 0.25
+material vidrio diffuse 220 235 255 specular 80 albedo 0.05 0.4 0.1 0.85 ior 1.5 absorption 40 10 25
+material agua diffuse 60 110 200 specular 50 albedo 0.1 0.6 0.3 0.7 ior 1.33 blur 0.1 absorption 90 35 15
+light position 0 10 5 color 255 255 255 intensity 1.0
+light position 8 6 9 color 255 240 220 intensity 0.5
+fill piso min 0 -2 0 max 9 0 8
+fill agua min 1 -1 5 max 8 0 7
+fill pared min 0 0 0 max 9 3 1
+fill vidrio min 2 0 2 max 3 2 3
+fill vidrio min 5 0 2 max 8 1 3
+";
+
+const SOFT_SHADOWS: &str = "
+camera position 10 6 10 target 3.5 0.5 3.5 up 0 1 0 fov 50deg
+sun_distance 20
+material piso diffuse 225 225 215 specular 5 albedo 0.9 0.1 0 0
+material columna diffuse 200 120 80 specular 10 albedo 0.8 0.2 0 0
+light position 0 20 0 color 255 255 255 intensity 1.0 radius 1.5
+light position 3.5 4 3.5 color 255 200 150 intensity 0.6 radius 0.6
+fill piso min -3 -1 -3 max 11 0 11
+fill columna min 1 0 1 max 2 3 2
+fill columna min 5 0 1 max 6 1 2
+fill columna min 1 0 5 max 2 2 6
+cube columna min 4 1.5 4 max 6 1.7 6
+";
+
+const NIGHT: &str = "
+camera position 4.5 3 10 target 4.5 1 3 up 0 1 0 fov 55deg
+sky dynamic
+moon visible
+material piedra diffuse 255 255 255 specular 10 albedo 0.8 0.1 0 0 pattern noise 125 125 135 65 65 75 0.5
+material glowstone diffuse 255 223 128 specular 10 albedo 0.7 0.3 0 0 emission 255 210 110 flicker 180ms 0.3
+material cristal diffuse 120 200 255 specular 30 albedo 0.7 0.3 0 0 emission 80 180 255
+light position 0 10 5 color 50 50 100 intensity 0.5
+light position 2 1.6 3.5 color 255 180 90 intensity 0.8 flicker 120ms 0.4
+light position 5.5 1.5 3.5 color 110 200 255 intensity 0.7 pulse 2s 0.5
+fill piedra min 0 -1 0 max 9 0 7
+fill piedra min 0 0 0 max 9 3 1
+fill glowstone min 1 0 3 max 2 1 4
+fill cristal min 6 0 3 max 7 2 4
+fill glowstone min 4 2 0 max 5 3 1
+";
+
+const PBR: &str = "
+camera position 4.5 2.5 8 target 4.5 0.5 0.5 up 0 1 0 fov 50deg
+material piso diffuse 255 255 255 specular 5 albedo 0.9 0.1 0 0 pattern checker 200 200 200 60 60 60 1
+material oro diffuse 255 195 85 metallic 1 roughness 0.25
+material cobre diffuse 240 140 100 metallic 1 roughness 0.5
+material cromo diffuse 230 230 230 metallic 1 roughness 0.05
+material plastico diffuse 200 30 30 roughness 0.35 ior 1.5
+material goma diffuse 40 40 45 roughness 0.9 ior 1.5
+light position 0 10 5 color 255 255 255 intensity 1.0
+light position 9 4 5 color 255 235 210 intensity 0.6
+fill piso min -2 -1 -3 max 11 0 5
+cube oro min 0 0 0 max 1 1 1
+cube cobre min 2 0 0 max 3 1 1
+cube cromo min 4 0 0 max 5 1 1
+cube plastico min 6 0 0 max 7 1 1
+cube goma min 8 0 0 max 9 1 1
+";
+
+const PATTERNS: &str = "
+camera position 4.5 4 9 target 4.5 0.5 2 up 0 1 0 fov 55deg
+material tablero diffuse 255 255 255 specular 5 albedo 0.9 0.1 0 0 pattern checker 230 230 230 40 40 40 0.5
+material marmol diffuse 255 255 255 specular 40 albedo 0.8 0.2 0.05 0 pattern noise 245 245 240 110 110 125 0.3
+material madera diffuse 255 255 255 specular 5 albedo 0.9 0.1 0 0 pattern wood 205 150 90 110 60 30 0.4
+light position 0 10 5 color 255 255 255 intensity 1.0
+light position 8 5 7 color 255 240 220 intensity 0.5
+fill tablero min 0 -1 0 max 9 0 6
+fill marmol min 1 0 1 max 3 2 3
+fill madera min 6 0 1 max 8 2 3
+fill madera min 4 0 1 max 5 1 2
+";
+
+const FOG: &str = "
+camera position -4 2 11 target 6 2.5 3 up 0 1 0 fov 60deg
+sun_distance 40
+sky dynamic
+fog density 0.08 color 235 215 190 distance 24 anisotropy 0.6
+material piedra diffuse 255 255 255 specular 10 albedo 0.8 0.1 0 0 pattern noise 150 145 140 80 75 75 0.5
+material musgo diffuse 255 255 255 specular 5 albedo 0.9 0.1 0 0 pattern noise 95 120 70 55 75 45 0.7
+light position 37 15 0 color 255 200 140 intensity 1.0
+fill musgo min -6 -1 -4 max 12 0 13
+fill piedra min 6 0 0 max 7 4 1
+fill piedra min 6 0 2 max 7 4 3
+fill piedra min 6 0 4 max 7 4 5
+fill piedra min 6 0 6 max 7 4 7
+fill piedra min 6 0 8 max 7 4 9
+fill piedra min 6 4 0 max 7 5 9
+";
@@ -0,0 +1,135 @@
+// gallery_menu.rs
+
+use minifb::{Key, KeyRepeat, Window};
+
+use crate::color::Color;
+use crate::font::{draw_text, text_width, GLYPH_HEIGHT};
+use crate::framebuffer::Framebuffer;
+use crate::gallery::{Showcase, SHOWCASES};
+use crate::keymap::{Action, KeyMap};
+
+const TEXT_SCALE: usize = 2;
+const LINE_HEIGHT: usize = (GLYPH_HEIGHT + 3) * TEXT_SCALE;
+const PADDING: usize = 10;
+// Cuánto se oscurece la imagen detrás del menú
+const BACKDROP: f32 = 0.3;
+
+// Menú de la galería dibujado sobre la imagen: G lo abre y lo cierra, las flechas eligen
+// una escena y Enter la abre
+pub struct GalleryMenu {
+    pub open: bool,
+    selected: usize,
+}
+
+impl GalleryMenu {
+    pub fn new() -> Self {
+        GalleryMenu {
+            open: false,
+            selected: 0,
+        }
+    }
+
+    // Marca en el menú la escena que ya está abierta
+    pub fn select(&mut self, showcase: &Showcase) {
+        if let Some(index) = SHOWCASES
+            .iter()
+            .position(|candidate| candidate.name == showcase.name)
+        {
+            self.selected = index;
+        }
+    }
+
+    // Devuelve la escena que se eligió abrir en este frame
+    pub fn update(&mut self, window: &Window, keys: &KeyMap) -> Option<&'static Showcase> {
+        if keys.pressed(window, Action::Gallery) {
+            self.open = !self.open;
+        }
+        if !self.open {
+            return None;
+        }
+
+        let count = SHOWCASES.len();
+        if window.is_key_pressed(Key::Up, KeyRepeat::Yes) {
+            self.selected = (self.selected + count - 1) % count;
+        }
+        if window.is_key_pressed(Key::Down, KeyRepeat::Yes) {
+            self.selected = (self.selected + 1) % count;
+        }
+        if window.is_key_pressed(Key::Enter, KeyRepeat::No) {
+            self.open = false;
+            return Some(&SHOWCASES[self.selected]);
+        }
+        None
+    }
+
+    pub fn draw(&self, framebuffer: &mut Framebuffer, keys: &KeyMap) {
+        if !self.open {
+            return;
+        }
+
+        let help = format!(
+            "flechas eligen, enter abre, {:?} cierra",
+            keys.key(Action::Gallery)
+        );
+        let selected = &SHOWCASES[self.selected];
+        let lines = SHOWCASES.len() + 4;
+        let width = SHOWCASES
+            .iter()
+            .map(|showcase| text_width(&format!("> {}", showcase.title), TEXT_SCALE))
+            .chain([
+                text_width(selected.description, TEXT_SCALE),
+                text_width(&help, TEXT_SCALE),
+            ])
+            .max()
+            .unwrap_or(0)
+            + 2 * PADDING;
+        let height = lines * LINE_HEIGHT + 2 * PADDING;
+        let left = framebuffer.width.saturating_sub(width) / 2;
+        let top = framebuffer.height.saturating_sub(height) / 2;
+
+        framebuffer.set_current_color(Color::black());
+        framebuffer.fill_rect(
+            left as i32,
+            top as i32,
+            width as i32,
+            height as i32,
+            1.0 - BACKDROP,
+        );
+
+        let x = left + PADDING;
+        let mut y = top + PADDING;
+        framebuffer.set_current_color(Color::new(1.0, 0.85, 0.2));
+        draw_text(framebuffer, x, y, "galería", TEXT_SCALE);
+        y += 2 * LINE_HEIGHT;
+
+        for (index, showcase) in SHOWCASES.iter().enumerate() {
+            let (marker, color) = if index == self.selected {
+                ("> ", Color::new(1.0, 1.0, 1.0))
+            } else {
+                ("  ", Color::new(0.6, 0.6, 0.6))
+            };
+            framebuffer.set_current_color(color);
+            draw_text(
+                framebuffer,
+                x,
+                y,
+                &format!("{}{}", marker, showcase.title),
+                TEXT_SCALE,
+            );
+            y += LINE_HEIGHT;
+        }
+
+        y += LINE_HEIGHT / 2;
+        framebuffer.set_current_color(Color::new(0.8, 0.8, 0.8));
+        draw_text(framebuffer, x, y, selected.description, TEXT_SCALE);
+        y += LINE_HEIGHT;
+        framebuffer.set_current_color(Color::new(0.5, 0.5, 0.5));
+        draw_text(framebuffer, x, y, &help, TEXT_SCALE);
+    }
+}
+
+impl Default for GalleryMenu {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,98 @@
+// hooks.rs
+//
+// Callbacks que un binario puede registrar para extender la ventana interactiva
+// (HUDs propios, telemetría, experimentos) sin copiar el ciclo principal:
+//
+//   let mut hooks = Hooks::new();
+//   hooks.on_post_render(|framebuffer, _, frame| { /* dibujar sobre la imagen */ });
+//   run_interactive(scene, &InteractiveSettings::new(world_path), &mut hooks);
+
+use minifb::Key;
+
+use crate::framebuffer::Framebuffer;
+use crate::scene::Scene;
+
+// Datos del frame actual que reciben todos los callbacks
+#[derive(Debug, Clone, Copy)]
+pub struct FrameInfo {
+    // Número de frame desde que se abrió la ventana
+    pub frame: u64,
+    // Segundos desde el frame anterior
+    pub delta_time: f32,
+    // Segundos desde que se abrió la ventana
+    pub elapsed: f32,
+    // Hora del día entre 0 y 1
+    pub time_of_day: f32,
+}
+
+type SceneHook = Box<dyn FnMut(&mut Scene, &FrameInfo)>;
+type FramebufferHook = Box<dyn FnMut(&mut Framebuffer, &Scene, &FrameInfo)>;
+type KeyHook = Box<dyn FnMut(Key, &mut Scene, &FrameInfo)>;
+
+#[derive(Default)]
+pub struct Hooks {
+    tick: Vec<SceneHook>,
+    pre_render: Vec<SceneHook>,
+    post_render: Vec<FramebufferHook>,
+    key: Vec<KeyHook>,
+}
+
+impl Hooks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Al inicio de cada frame, después de avanzar el ciclo de día y antes de leer la entrada
+    pub fn on_tick(&mut self, hook: impl FnMut(&mut Scene, &FrameInfo) + 'static) -> &mut Self {
+        self.tick.push(Box::new(hook));
+        self
+    }
+
+    // Justo antes de renderizar, con la cámara y el mundo ya actualizados por la entrada
+    pub fn on_pre_render(
+        &mut self,
+        hook: impl FnMut(&mut Scene, &FrameInfo) + 'static,
+    ) -> &mut Self {
+        self.pre_render.push(Box::new(hook));
+        self
+    }
+
+    // Sobre la imagen terminada, antes de mostrarla; lo que se dibuje no sale en las capturas
+    pub fn on_post_render(
+        &mut self,
+        hook: impl FnMut(&mut Framebuffer, &Scene, &FrameInfo) + 'static,
+    ) -> &mut Self {
+        self.post_render.push(Box::new(hook));
+        self
+    }
+
+    // Una vez por cada tecla presionada en el frame (sin repetición al mantenerla)
+    pub fn on_key(&mut self, hook: impl FnMut(Key, &mut Scene, &FrameInfo) + 'static) -> &mut Self {
+        self.key.push(Box::new(hook));
+        self
+    }
+
+    pub fn tick(&mut self, scene: &mut Scene, frame: &FrameInfo) {
+        for hook in &mut self.tick {
+            hook(scene, frame);
+        }
+    }
+
+    pub fn pre_render(&mut self, scene: &mut Scene, frame: &FrameInfo) {
+        for hook in &mut self.pre_render {
+            hook(scene, frame);
+        }
+    }
+
+    pub fn post_render(&mut self, framebuffer: &mut Framebuffer, scene: &Scene, frame: &FrameInfo) {
+        for hook in &mut self.post_render {
+            hook(framebuffer, scene, frame);
+        }
+    }
+
+    pub fn key(&mut self, key: Key, scene: &mut Scene, frame: &FrameInfo) {
+        for hook in &mut self.key {
+            hook(key, scene, frame);
+        }
+    }
+}
@@ -0,0 +1,199 @@
+// hot_reload.rs
+
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver};
+
+use crate::assets::{texture_cache, ImageTexture, Texture};
+use crate::material::Material;
+use crate::scene::Scene;
+
+// Lo que cambió en disco desde la revisión anterior
+#[derive(Debug, Default)]
+pub struct Changes {
+    pub scene: bool,
+    // Imágenes modificadas, con su ruta canónica
+    pub textures: Vec<PathBuf>,
+}
+
+// Vigila el archivo de escena, las imágenes de una carpeta de texturas y las que usan los
+// materiales de la escena. El sistema avisa de cada cambio por un canal que se vacía en
+// cada frame, así se pueden retocar las texturas con la ventana abierta. Se vigilan las
+// carpetas de los archivos y no los archivos, porque muchos editores guardan escribiendo
+// uno nuevo y renombrándolo encima del viejo.
+pub struct HotReload {
+    scene_path: Option<PathBuf>,
+    texture_dir: Option<PathBuf>,
+    // None si el sistema no dejó vigilar los archivos; entonces no se recarga nada
+    watcher: Option<RecommendedWatcher>,
+    events: Receiver<notify::Result<notify::Event>>,
+    // Carpetas vigiladas, por ruta canónica
+    dirs: HashSet<PathBuf>,
+    // La escena se recargó y puede usar texturas de otras carpetas
+    rescan: bool,
+}
+
+impl HotReload {
+    pub fn new(scene_path: Option<PathBuf>, texture_dir: Option<PathBuf>, scene: &Scene) -> Self {
+        let (sender, events) = channel();
+        let watcher = notify::recommended_watcher(sender)
+            .map_err(|err| eprintln!("No se pueden vigilar los archivos: {}", err))
+            .ok();
+        let mut hot_reload = HotReload {
+            scene_path,
+            texture_dir,
+            watcher,
+            events,
+            dirs: HashSet::new(),
+            rescan: false,
+        };
+        hot_reload.watch(scene);
+        hot_reload
+    }
+
+    // Los archivos vigilados que cambiaron desde la última llamada
+    pub fn poll(&mut self, scene: &Scene) -> Changes {
+        let mut changes = Changes::default();
+        if self.rescan {
+            self.rescan = false;
+            self.watch(scene);
+        }
+
+        let mut touched = HashSet::new();
+        for event in self.events.try_iter().filter_map(Result::ok) {
+            if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                touched.extend(event.paths.iter().map(|path| canonical(path)));
+            }
+        }
+        if touched.is_empty() {
+            return changes;
+        }
+
+        let scene_path = self.scene_path.as_deref().map(canonical);
+        for path in self.watched(scene) {
+            if !touched.contains(&path) {
+                continue;
+            }
+            if Some(&path) == scene_path.as_ref() {
+                changes.scene = true;
+                self.rescan = true;
+            } else {
+                changes.textures.push(path);
+            }
+        }
+        changes
+    }
+
+    // Empieza a vigilar las carpetas de los archivos de `scene` que todavía no se vigilan
+    fn watch(&mut self, scene: &Scene) {
+        let mut dirs: Vec<PathBuf> = self.texture_dir.iter().map(|dir| canonical(dir)).collect();
+        dirs.extend(
+            self.watched(scene)
+                .iter()
+                .filter_map(|path| path.parent())
+                .map(Path::to_path_buf),
+        );
+        let Some(watcher) = self.watcher.as_mut() else {
+            return;
+        };
+        for dir in dirs {
+            if self.dirs.contains(&dir) || !dir.is_dir() {
+                continue;
+            }
+            match watcher.watch(&dir, RecursiveMode::NonRecursive) {
+                Ok(()) => {
+                    self.dirs.insert(dir);
+                }
+                Err(err) => eprintln!("No se puede vigilar {}: {}", dir.display(), err),
+            }
+        }
+    }
+
+    fn watched(&self, scene: &Scene) -> Vec<PathBuf> {
+        let mut paths: Vec<PathBuf> = self.scene_path.iter().map(|path| canonical(path)).collect();
+        if let Some(entries) = self
+            .texture_dir
+            .as_ref()
+            .and_then(|dir| fs::read_dir(dir).ok())
+        {
+            paths.extend(
+                entries
+                    .filter_map(|entry| entry.ok())
+                    .map(|entry| entry.path())
+                    .filter(|path| path.is_file())
+                    .map(|path| canonical(&path)),
+            );
+        }
+        for (_, _, material) in scene.world.materials() {
+            paths.extend(texture_paths(material).map(|path| canonical(Path::new(path))));
+        }
+        paths.sort();
+        paths.dedup();
+        paths
+    }
+}
+
+// Vuelve a leer las imágenes de `changed` y se las pone a los materiales que las usan.
+// Devuelve cuántos materiales cambiaron.
+pub fn reload_textures(scene: &mut Scene, changed: &[PathBuf]) -> usize {
+    let affected: Vec<_> = scene
+        .world
+        .materials()
+        .filter(|(_, _, material)| {
+            texture_paths(material).any(|path| changed.contains(&canonical(Path::new(path))))
+        })
+        .map(|(block, _, _)| block)
+        .collect();
+
+    // Cada archivo se decodifica una vez aunque lo usen varios materiales
+    let mut fresh: HashMap<PathBuf, ImageTexture> = HashMap::new();
+    let mut reload = |path: &Option<String>| -> Option<ImageTexture> {
+        let path = path.as_ref()?;
+        let key = canonical(Path::new(path));
+        if !changed.contains(&key) {
+            return None;
+        }
+        Some(
+            fresh
+                .entry(key)
+                .or_insert_with(|| texture_cache().reload(path))
+                .clone(),
+        )
+    };
+    for &block in &affected {
+        let mut material = scene.world.material_mut(block);
+        if let Some(Texture::Image(_)) = material.texture {
+            if let Some(texture) = reload(&material.texture_path) {
+                material.texture = Some(Texture::Image(texture));
+            }
+        }
+        if let Some(texture) = reload(&material.normal_map_path) {
+            material.normal_map = Some(texture);
+        }
+        if let Some(texture) = reload(&material.emission_map_path) {
+            material.emission_map = Some(texture);
+        }
+        if let Some(texture) = reload(&material.specular_map_path) {
+            material.specular_map = Some(texture);
+        }
+    }
+    affected.len()
+}
+
+fn texture_paths(material: &Material) -> impl Iterator<Item = &String> {
+    [
+        &material.texture_path,
+        &material.normal_map_path,
+        &material.emission_map_path,
+        &material.specular_map_path,
+    ]
+    .into_iter()
+    .flatten()
+}
+
+// Rutas distintas al mismo archivo (./a.png y a.png) se comparan igual
+fn canonical(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
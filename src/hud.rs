@@ -0,0 +1,86 @@
+// hud.rs
+
+use crate::color::Color;
+use crate::font::{draw_text, text_width, GLYPH_HEIGHT};
+use crate::framebuffer::Framebuffer;
+
+const TEXT_SCALE: usize = 2;
+const LINE_HEIGHT: usize = (GLYPH_HEIGHT + 2) * TEXT_SCALE;
+const PADDING: usize = 6;
+// Cuánto se oscurece la imagen detrás del texto
+const BACKDROP: f32 = 0.4;
+// Peso del frame nuevo en el promedio de los fps, para que el número se pueda leer
+const FPS_SMOOTHING: f32 = 0.1;
+
+// Texto sobre la esquina superior izquierda de la ventana con los fps, la cámara, el
+// material elegido, la hora del día y lo que se cambió de la vista; F3 lo oculta
+pub struct Hud {
+    pub visible: bool,
+    fps: Option<f32>,
+}
+
+impl Hud {
+    pub fn new() -> Self {
+        Hud {
+            visible: true,
+            fps: None,
+        }
+    }
+
+    // Suma la duración del último frame al promedio de los fps
+    pub fn tick(&mut self, frame_duration: f32) {
+        if frame_duration <= 0.0 {
+            return;
+        }
+        let fps = 1.0 / frame_duration;
+        self.fps = Some(match self.fps {
+            Some(average) => average + (fps - average) * FPS_SMOOTHING,
+            None => fps,
+        });
+    }
+
+    pub fn fps(&self) -> f32 {
+        self.fps.unwrap_or(0.0)
+    }
+
+    // Dibuja una línea de texto debajo de la otra sobre un fondo oscurecido
+    pub fn draw(&self, framebuffer: &mut Framebuffer, lines: &[String]) {
+        if !self.visible || lines.is_empty() {
+            return;
+        }
+
+        let width = lines
+            .iter()
+            .map(|line| text_width(line, TEXT_SCALE))
+            .max()
+            .unwrap_or(0)
+            + 2 * PADDING;
+        let height = lines.len() * LINE_HEIGHT + 2 * PADDING - 2 * TEXT_SCALE;
+        framebuffer.set_current_color(Color::black());
+        framebuffer.fill_rect(0, 0, width as i32, height as i32, 1.0 - BACKDROP);
+
+        framebuffer.set_current_color(Color::new(1.0, 1.0, 1.0));
+        for (index, line) in lines.iter().enumerate() {
+            draw_text(
+                framebuffer,
+                PADDING,
+                PADDING + index * LINE_HEIGHT,
+                line,
+                TEXT_SCALE,
+            );
+        }
+    }
+}
+
+impl Default for Hud {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Hora del reloj para un punto del ciclo del día: 0 es el amanecer (6:00) y 0.25 el
+// mediodía, cuando el sol está arriba
+pub fn clock_time(time_of_day: f32) -> String {
+    let minutes = ((time_of_day.rem_euclid(1.0) * 24.0 + 6.0) * 60.0) as u32 % (24 * 60);
+    format!("{:02}:{:02}", minutes / 60, minutes % 60)
+}
@@ -0,0 +1,808 @@
+// interactive.rs
+
+use minifb::{Key, KeyRepeat, ScaleMode, Window, WindowOptions};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::accumulation::Accumulator;
+use crate::beauty::beauty_shot;
+use crate::brush::RegionBrush;
+use crate::camera_motion::CameraMotion;
+use crate::day_cycle::DayNightCycle;
+use crate::debug_view::DebugView;
+use crate::denoise::Denoiser;
+use crate::editor::BlockEditor;
+use crate::framebuffer::Framebuffer;
+use crate::fxaa::fxaa;
+use crate::gallery::Showcase;
+use crate::gallery_menu::GalleryMenu;
+use crate::hooks::{FrameInfo, Hooks};
+use crate::hot_reload::{reload_textures, HotReload};
+use crate::hud::{clock_time, Hud};
+use crate::keymap::{Action, KeyMap, LIGHT_GROUP_ACTIONS};
+use crate::light_cache::{LightCache, REFRESH_BUDGET};
+use crate::light_groups::LightGroups;
+use crate::post::apply_post;
+use crate::render::{render, RenderSettings};
+use crate::scene::Scene;
+use crate::scene_file::{load_scene, save_scene};
+use crate::screenshot::{save_screenshot, SCREENSHOT_DIR};
+use crate::settings_panel::{secondary_label, SettingsPanel, MAX_PREVIEW_SAMPLES};
+use crate::temporal::TemporalFilter;
+use crate::tile_scheduler::{dirty_region, TileScheduler};
+use crate::wireframe::{draw_wireframe, Wireframe};
+use crate::world_file::save_world;
+
+// Shift+F12 guarda una captura a mayor resolución y con más muestras por pixel
+const SCREENSHOT_SCALE: usize = 2;
+const SCREENSHOT_SAMPLES: u32 = 3;
+// Límites de lo que se puede subir la calidad de la vista con el teclado
+const MAX_PREVIEW_DEPTH: u32 = 8;
+// Mientras la cámara se mueve la vista se traza a la mitad del ancho y del alto
+const MOVING_SCALE: usize = 2;
+// Tiempo de render por vuelta del ciclo con la vista quieta; un frame más lento se
+// termina en las vueltas siguientes y se abandona si se mueve la cámara
+const FRAME_BUDGET: Duration = Duration::from_millis(30);
+// F5 guarda la escena actual, incluidas las animaciones de los objetos
+const SESSION_SCENE: &str = "scenes/sesion.scene";
+
+// Carpeta de las texturas integradas; sus imágenes se recargan al cambiar en disco
+pub const TEXTURE_DIR: &str = "src/textures";
+
+// Tamaño con el que se abre la ventana cuando el render sigue su tamaño
+const WINDOW_WIDTH: usize = 600;
+const WINDOW_HEIGHT: usize = 400;
+
+// Pantalla que ocupa el modo de pantalla completa si no se pide otra; minifb no informa
+// el tamaño del monitor
+pub const DEFAULT_SCREEN_SIZE: (usize, usize) = (1920, 1080);
+
+// Frames por segundo a los que se limita la ventana si no se pide otra cosa
+pub const DEFAULT_TARGET_FPS: f32 = 60.0;
+
+// Paso fijo del modo demo, el de una sesión a 60 fps
+const DEMO_FRAME_TIME: f32 = 1.0 / 60.0;
+// Hora del día con la que empieza el modo demo, la misma de las secuencias exportadas
+const DEMO_TIME_OF_DAY: f32 = 0.3;
+
+// De dónde sale el tiempo que avanza cada frame (ciclo de día, animaciones, zoom, hooks)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FrameClock {
+    // Tiempo real transcurrido desde el frame anterior
+    WallClock,
+    // Los mismos segundos en cada frame, sin importar cuánto tardó el render
+    Fixed(f32),
+}
+
+// De dónde sale el tamaño del framebuffer de la ventana
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Resolution {
+    // Un pixel del render por cada n x n pixeles de la ventana; al cambiar el tamaño de
+    // la ventana el framebuffer se vuelve a crear y la imagen no se deforma
+    Window(usize),
+    // Siempre ancho x alto; la ventana lo estira sin deformarlo, con franjas negras
+    Fixed(usize, usize),
+}
+
+impl Resolution {
+    // Tamaño del framebuffer para una ventana de `width` x `height` pixeles
+    pub fn framebuffer_size(self, (width, height): (usize, usize)) -> (usize, usize) {
+        match self {
+            Resolution::Window(scale) => {
+                let scale = scale.max(1);
+                ((width / scale).max(1), (height / scale).max(1))
+            }
+            Resolution::Fixed(width, height) => (width.max(1), height.max(1)),
+        }
+    }
+}
+
+// Cómo se muestra la ventana; F11 pasa de uno al siguiente volviendo a crearla
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DisplayMode {
+    Windowed,
+    // Mismo tamaño y lugar que la ventana normal, sin bordes ni barra de título
+    Borderless,
+    // Sin bordes, encima de las demás ventanas y ocupando la pantalla desde la esquina
+    Fullscreen,
+}
+
+impl DisplayMode {
+    pub const ALL: [DisplayMode; 3] = [
+        DisplayMode::Windowed,
+        DisplayMode::Borderless,
+        DisplayMode::Fullscreen,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            DisplayMode::Windowed => "ventana",
+            DisplayMode::Borderless => "sin bordes",
+            DisplayMode::Fullscreen => "pantalla completa",
+        }
+    }
+
+    // El siguiente en el orden de `ALL`, volviendo al primero después del último
+    pub fn next(&self) -> Self {
+        let index = DisplayMode::ALL
+            .iter()
+            .position(|mode| mode == self)
+            .unwrap_or(0);
+        DisplayMode::ALL[(index + 1) % DisplayMode::ALL.len()]
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct InteractiveSettings {
+    // Mundo que se guarda con F6 y al salir si se editó; sin ruta no se guarda
+    pub world_path: Option<PathBuf>,
+    pub clock: FrameClock,
+    // Hora del día inicial entre 0 y 1
+    pub time_of_day: f32,
+    // Escena de la galería con la que se empieza; el menú de la galería arranca abierto
+    pub gallery: Option<&'static Showcase>,
+    // Frames por segundo máximos: cada frame que termina antes espera lo que le falta.
+    // None renderiza un frame tras otro sin esperar.
+    pub target_fps: Option<f32>,
+    pub resolution: Resolution,
+    pub display: DisplayMode,
+    // Tamaño de la pantalla completa, en pixeles
+    pub screen_size: (usize, usize),
+    // Archivo .scene de la escena, que se vuelve a cargar si cambia en disco
+    pub scene_path: Option<PathBuf>,
+    // Carpeta cuyas imágenes se vigilan además de las que usan los materiales
+    pub texture_dir: Option<PathBuf>,
+    pub keys: KeyMap,
+}
+
+impl InteractiveSettings {
+    pub fn new(world_path: impl Into<PathBuf>) -> Self {
+        InteractiveSettings {
+            world_path: Some(world_path.into()),
+            clock: FrameClock::WallClock,
+            time_of_day: 0.0,
+            gallery: None,
+            target_fps: Some(DEFAULT_TARGET_FPS),
+            resolution: Resolution::Window(1),
+            display: DisplayMode::Windowed,
+            screen_size: DEFAULT_SCREEN_SIZE,
+            scene_path: None,
+            texture_dir: Some(PathBuf::from(TEXTURE_DIR)),
+            keys: KeyMap::new(),
+        }
+    }
+
+    // Sesión reproducible: paso fijo, hora inicial fija y sin tocar el mundo guardado,
+    // así un video o una medición dan lo mismo frame a frame en cualquier máquina
+    pub fn demo() -> Self {
+        InteractiveSettings {
+            world_path: None,
+            clock: FrameClock::Fixed(DEMO_FRAME_TIME),
+            time_of_day: DEMO_TIME_OF_DAY,
+            gallery: None,
+            target_fps: Some(DEFAULT_TARGET_FPS),
+            resolution: Resolution::Window(1),
+            display: DisplayMode::Windowed,
+            screen_size: DEFAULT_SCREEN_SIZE,
+            scene_path: None,
+            texture_dir: Some(PathBuf::from(TEXTURE_DIR)),
+            keys: KeyMap::new(),
+        }
+    }
+
+    // Recorrido de la galería empezando por `showcase`, sin tocar el mundo guardado
+    pub fn gallery(showcase: &'static Showcase) -> Self {
+        InteractiveSettings {
+            world_path: None,
+            clock: FrameClock::WallClock,
+            time_of_day: showcase.time_of_day,
+            gallery: Some(showcase),
+            target_fps: Some(DEFAULT_TARGET_FPS),
+            resolution: Resolution::Window(1),
+            display: DisplayMode::Windowed,
+            screen_size: DEFAULT_SCREEN_SIZE,
+            scene_path: None,
+            texture_dir: Some(PathBuf::from(TEXTURE_DIR)),
+            keys: KeyMap::new(),
+        }
+    }
+}
+
+// Abre la ventana y corre el ciclo principal hasta que se cierre; `hooks` permite
+// extender cada frame desde afuera.
+pub fn run_interactive(mut scene: Scene, settings: &InteractiveSettings, hooks: &mut Hooks) {
+    let (window_width, window_height, scale_mode) = match settings.resolution {
+        Resolution::Window(_) => (WINDOW_WIDTH, WINDOW_HEIGHT, ScaleMode::Stretch),
+        Resolution::Fixed(width, height) => (width, height, ScaleMode::AspectRatioStretch),
+    };
+    let frame_period = settings
+        .target_fps
+        .map(|fps| Duration::from_secs_f32(1.0 / fps));
+    let mut last_frame = Instant::now();
+    // F1 abre el panel de ajustes
+    let mut panel = SettingsPanel::new();
+    // T detiene el día, las teclas , y . lo hacen más lento o más rápido y J salta al
+    // siguiente momento (amanecer, mediodía, atardecer, medianoche)
+    let mut cycle = DayNightCycle::new(settings.time_of_day);
+    let mut elapsed = 0.0;
+    // Tiempo real de render acumulado, para el resumen del modo demo
+    let mut render_time = Duration::ZERO;
+
+    // Tamaño y lugar de la ventana normal, para volver a ellos al dejar la pantalla completa
+    let mut windowed = ((window_width, window_height), None);
+    let mut display = settings.display;
+    let mut window = open_window(display, windowed, settings.screen_size, scale_mode);
+
+    let (framebuffer_width, framebuffer_height) =
+        settings.resolution.framebuffer_size(window.get_size());
+    let mut framebuffer = Framebuffer::new(framebuffer_width, framebuffer_height);
+
+    let mut motion = CameraMotion::new();
+    let mut cubes = scene.cubes_at(0.0);
+    let mut editor = BlockEditor::new();
+    let mut brush = RegionBrush::new(framebuffer_width, framebuffer_height);
+    let mut gallery = GalleryMenu::new();
+    if let Some(showcase) = settings.gallery {
+        gallery.select(showcase);
+        gallery.open = true;
+    }
+    // Al abrir una escena de la galería el mundo deja de guardarse
+    let mut world_path = settings.world_path.clone();
+    // Solo se guarda el mundo al salir si se editó algún bloque desde el último guardado
+    let mut edited = false;
+    let mut frame_count: u64 = 0;
+    // V alterna entre la imagen y las vistas de depuración
+    let mut view = DebugView::Shaded;
+    // B alterna las líneas de los bloques y de los chunks sobre la imagen
+    let mut wireframe = Wireframe::Off;
+    // K / L cambian los rebotes, N / M las muestras por pixel y H apaga las sombras
+    let mut quality = RenderSettings::preview();
+    // Con la vista quieta los frames se promedian; la calidad y la vista del último frame
+    // sirven para notar cuándo hay que empezar de nuevo
+    let mut accumulator = Accumulator::new();
+    let mut last_look = None;
+    let mut scheduler = TileScheduler::new();
+    // Con la cámara en movimiento cada frame se mezcla con el anterior reproyectado
+    let mut temporal = TemporalFilter::new();
+    // U filtra el ruido de la imagen quieta, guiado por las normales y la profundidad
+    let mut denoiser = Denoiser::new();
+    // Y suaviza los bordes de la imagen que se muestra con FXAA
+    let mut edge_smoothing = false;
+    // R guarda las sombras de las caras de los bloques y las va recalculando de a poco; la
+    // escena las tiene solo mientras se traza la vista, las capturas se trazan completas
+    let mut light_cache: Option<LightCache> = None;
+    // La cámara del frame anterior: si cambió, por las teclas, un recorrido o un hook, la
+    // vista se renderiza como en movimiento
+    let mut last_camera = scene.camera.clone();
+    // Los cambios a la escena y a las texturas en disco se ven sin cerrar la ventana
+    let mut hot_reload = HotReload::new(
+        settings.scene_path.clone(),
+        settings.texture_dir.clone(),
+        &scene,
+    );
+    // F3 oculta el texto con los fps, la cámara y el material elegido
+    let mut hud = Hud::new();
+    let keys = &settings.keys;
+    // F2, F4, F7 y F8 encienden y apagan los primeros grupos de luces de la escena
+    let mut light_groups = LightGroups::new(&scene);
+
+    while window.is_open() && !window.is_key_down(Key::Escape) {
+        let current_frame = Instant::now();
+        let frame_duration = current_frame.duration_since(last_frame).as_secs_f32();
+        let delta_time = match settings.clock {
+            FrameClock::WallClock => frame_duration,
+            FrameClock::Fixed(step) => step,
+        };
+        last_frame = current_frame;
+        elapsed += delta_time;
+
+        cycle.advance(delta_time);
+        scene.apply_day_cycle(cycle.progress());
+        scene.animate_lights(elapsed);
+        panel.apply(&mut scene);
+        light_groups.apply(&mut scene);
+        scene.animate_sky(elapsed);
+        scene.animate_textures(elapsed);
+
+        let frame = FrameInfo {
+            frame: frame_count,
+            delta_time,
+            elapsed,
+            time_of_day: cycle.progress(),
+        };
+        frame_count += 1;
+        hooks.tick(&mut scene, &frame);
+
+        for key in window.get_keys_pressed(KeyRepeat::No) {
+            hooks.key(key, &mut scene, &frame);
+        }
+
+        let mut switched = false;
+        if let Some(showcase) = gallery.update(&window, keys) {
+            switched = true;
+            if let (true, Some(path)) = (edited, &world_path) {
+                save_session_world(&scene, path);
+            }
+            if world_path.take().is_some() {
+                println!("El mundo ya no se guarda en esta sesión");
+            }
+            edited = false;
+            scene = showcase.build();
+            cubes = scene.cubes_at(elapsed);
+            cycle.set_progress(showcase.time_of_day);
+            light_groups = LightGroups::new(&scene);
+            editor = BlockEditor::new();
+            motion.stop();
+            // El archivo de escena ya no es el de la escena abierta
+            hot_reload = HotReload::new(None, settings.texture_dir.clone(), &scene);
+        }
+
+        let changes = hot_reload.poll(&scene);
+        let mut reloaded = false;
+        if let (true, Some(path)) = (changes.scene, &settings.scene_path) {
+            match load_scene(path) {
+                // Se conserva la cámara para seguir mirando lo que se estaba retocando
+                Ok(fresh) => {
+                    let camera = scene.camera.clone();
+                    scene = fresh;
+                    scene.camera = camera;
+                    cubes = scene.cubes_at(elapsed);
+                    editor = BlockEditor::new();
+                    light_groups.refresh(&scene);
+                    reloaded = true;
+                    println!("Escena recargada desde {}", path.display());
+                }
+                Err(err) => eprintln!("No se pudo recargar la escena: {}", err),
+            }
+        }
+        if !changes.textures.is_empty() && reload_textures(&mut scene, &changes.textures) > 0 {
+            reloaded = true;
+            println!("Texturas recargadas");
+        }
+
+        if scene.is_animated() {
+            cubes = scene.cubes_at(frame.elapsed);
+        }
+
+        if keys.pressed(&window, Action::DebugView) {
+            view = view.next();
+        }
+        if keys.pressed(&window, Action::Wireframe) {
+            wireframe = wireframe.next();
+        }
+        // Con el día detenido el sol queda quieto y la imagen puede acumularse
+        if keys.pressed(&window, Action::PauseDay) {
+            cycle.toggle_pause();
+        }
+        if keys.pressed(&window, Action::DaySlower) {
+            cycle.slower();
+        }
+        if keys.pressed(&window, Action::DayFaster) {
+            cycle.faster();
+        }
+        if keys.pressed(&window, Action::SkipTime) {
+            cycle.skip_to_next_moment();
+        }
+        for (index, action) in LIGHT_GROUP_ACTIONS.iter().enumerate() {
+            if keys.pressed(&window, *action) {
+                light_groups.toggle(index);
+            }
+        }
+        if keys.pressed(&window, Action::Denoise) {
+            denoiser.enabled = !denoiser.enabled;
+        }
+        if keys.pressed(&window, Action::Fxaa) {
+            edge_smoothing = !edge_smoothing;
+        }
+        if keys.pressed(&window, Action::Shadows) {
+            quality.shadows = !quality.shadows;
+        }
+        if keys.pressed(&window, Action::FewerBounces) {
+            quality.max_depth = quality.max_depth.saturating_sub(1);
+        }
+        if keys.pressed(&window, Action::MoreBounces) {
+            quality.max_depth = (quality.max_depth + 1).min(MAX_PREVIEW_DEPTH);
+        }
+        if keys.pressed(&window, Action::FewerSamples) {
+            quality.samples = (quality.samples - 1).max(1);
+        }
+        if keys.pressed(&window, Action::MoreSamples) {
+            quality.samples = (quality.samples + 1).min(MAX_PREVIEW_SAMPLES);
+        }
+
+        // Con el menú de la galería abierto las flechas son suyas
+        let mut tuned = false;
+        if !gallery.open {
+            tuned = panel.update(
+                &window,
+                keys,
+                &mut scene,
+                editor.selected,
+                &mut quality,
+                &mut cycle,
+            );
+        }
+        // Las sombras guardadas no son exactamente las trazadas: el promedio empieza de nuevo
+        if keys.pressed(&window, Action::LightCache) {
+            light_cache = if light_cache.is_some() {
+                None
+            } else {
+                Some(LightCache::new())
+            };
+            tuned = true;
+        }
+
+        if keys.pressed(&window, Action::Hud) {
+            hud.visible = !hud.visible;
+        }
+        hud.tick(frame_duration);
+
+        if let Some(scroll) = window.get_scroll_wheel() {
+            motion.push_dolly(scroll.1);
+        }
+
+        if keys.pressed(&window, Action::Projection) {
+            scene.camera.toggle_projection();
+        }
+
+        if keys.pressed(&window, Action::Isometric) {
+            scene.camera.set_isometric();
+            motion.stop();
+        }
+
+        let axis = |negative: Action, positive: Action| {
+            keys.down(&window, positive) as i32 as f32 - keys.down(&window, negative) as i32 as f32
+        };
+        motion.update(
+            &mut scene.camera,
+            axis(Action::OrbitRight, Action::OrbitLeft),
+            axis(Action::OrbitUp, Action::OrbitDown),
+            axis(Action::ZoomIn, Action::ZoomOut),
+            axis(Action::RollRight, Action::RollLeft),
+            delta_time,
+        );
+
+        if keys.pressed(&window, Action::DisplayMode) {
+            if display != DisplayMode::Fullscreen {
+                windowed = (window.get_size(), Some(window.get_position()));
+            }
+            display = display.next();
+            window = open_window(display, windowed, settings.screen_size, scale_mode);
+        }
+
+        // Si la ventana cambió de tamaño se renderiza de nuevo al tamaño nuevo; la relación
+        // de aspecto de la cámara sale del framebuffer
+        let size = settings.resolution.framebuffer_size(window.get_size());
+        let resized = size != (framebuffer.width, framebuffer.height);
+        if resized {
+            framebuffer = Framebuffer::new(size.0, size.1);
+            brush = RegionBrush::new(size.0, size.1);
+        }
+
+        let edited_block = editor.update(&window, keys, &mut scene, &cubes, &framebuffer);
+        let edited_now = edited_block.is_some();
+        edited |= edited_now;
+        // Con objetos animados sus sombras se mueven: se trazan siempre
+        let cache_lights = !scene.is_animated();
+        if let Some(cache) = light_cache.as_mut().filter(|_| cache_lights) {
+            if edited_now || switched || reloaded {
+                cache.invalidate();
+            }
+            cache.refresh(&scene, &cubes, REFRESH_BUDGET);
+        }
+
+        brush.update(&window, keys);
+
+        hooks.pre_render(&mut scene, &frame);
+        // Los hooks pueden mover la cámara cambiando sus campos directamente
+        scene.camera.update_view();
+        let render_start = Instant::now();
+        // La resolución completa vuelve en el primer frame con la cámara quieta
+        let moving = motion.is_moving() || scene.camera != last_camera;
+        last_camera = scene.camera.clone();
+        let settings = RenderSettings {
+            scale: if moving { MOVING_SCALE } else { 1 },
+            ..quality
+        };
+        let look_changed = last_look != Some((settings, view));
+        last_look = Some((settings, view));
+        // Un frame a medio renderizar no sirve si cambió lo que se ve; las luces y el cielo
+        // animados no lo cancelan, si no con el ciclo del día nunca se terminaría
+        if moving || switched || look_changed || resized || tuned || reloaded {
+            scheduler.cancel();
+        } else if let Some(pos) = edited_block {
+            // Al quitar o poner un bloque primero se vuelven a trazar solo los pixeles donde
+            // se ve; el resto se actualiza con los frames siguientes
+            let partial = dirty_region(&scene.camera, pos, size)
+                .is_some_and(|region| scheduler.invalidate(size, region));
+            if !partial {
+                scheduler.cancel();
+            }
+        }
+        let changed = moving
+            || edited_now
+            || switched
+            || look_changed
+            || resized
+            || tuned
+            || reloaded
+            || scene.is_animated();
+        let pass = accumulator.begin(&scene, changed);
+        // La historia solo sirve mientras la cámara se mueve sobre la misma escena
+        let reproject = moving && view == DebugView::Shaded;
+        if !reproject || edited_now || switched || resized || tuned || reloaded {
+            temporal.reset();
+        }
+        let pass = if reproject { temporal.pass() } else { pass };
+        // El filtro solo se ve con la vista quieta; en movimiento alcanza con la reproyección
+        let denoise = denoiser.enabled && view == DebugView::Shaded && !moving;
+        if cache_lights {
+            scene.light_cache = light_cache.take();
+        }
+        // Si en esta vuelta se agregó un frame al promedio
+        let fresh = if accumulator.converged() {
+            accumulator.restore(&mut framebuffer);
+            false
+        } else {
+            // En movimiento cada frame se completa en la vuelta en que empieza
+            let budget = if moving { Duration::MAX } else { FRAME_BUDGET };
+            let completed = scheduler.step(
+                size,
+                &scene,
+                &cubes,
+                &RenderSettings { pass, ..settings },
+                view,
+                budget,
+            );
+            if completed {
+                if reproject {
+                    temporal.blend(scheduler.canvas_mut(), &scene, &cubes);
+                }
+                framebuffer.upscale_from(scheduler.canvas());
+                if view == DebugView::Shaded && !moving {
+                    brush.apply(&mut framebuffer, &scene, &cubes);
+                }
+                accumulator.add(&mut framebuffer);
+            } else if accumulator.passes() > 0 {
+                // Mientras tanto se sigue viendo el promedio de los frames anteriores
+                accumulator.restore(&mut framebuffer);
+            } else {
+                framebuffer.upscale_from(scheduler.canvas());
+            }
+            completed
+        };
+        if let Some(cache) = scene.light_cache.take() {
+            light_cache = Some(cache);
+        }
+        // Se filtra el promedio acumulado, no un frame a medio renderizar
+        if denoise && accumulator.passes() > 0 {
+            denoiser.present(&mut framebuffer, &scene, &cubes, fresh, changed);
+        } else {
+            denoiser.clear();
+        }
+        // El posproceso va sobre la imagen terminada; las vistas de depuración se ven tal cual
+        if view == DebugView::Shaded {
+            apply_post(&scene.post, &mut framebuffer);
+        }
+        framebuffer.dither = scene.dither;
+        if edge_smoothing {
+            fxaa(&mut framebuffer);
+        }
+        render_time += render_start.elapsed();
+
+        if keys.pressed(&window, Action::Screenshot) {
+            let high_quality =
+                window.is_key_down(Key::LeftShift) || window.is_key_down(Key::RightShift);
+            let result = if high_quality {
+                let mut capture = Framebuffer::new(
+                    framebuffer.width * SCREENSHOT_SCALE,
+                    framebuffer.height * SCREENSHOT_SCALE,
+                );
+                render(
+                    &mut capture,
+                    &scene,
+                    &cubes,
+                    &RenderSettings::with_samples(SCREENSHOT_SAMPLES),
+                );
+                apply_post(&scene.post, &mut capture);
+                capture.dither = scene.dither;
+                save_screenshot(&capture, Path::new(SCREENSHOT_DIR))
+            } else {
+                save_screenshot(&framebuffer, Path::new(SCREENSHOT_DIR))
+            };
+
+            match result {
+                Ok(path) => println!("Captura guardada en {}", path.display()),
+                Err(err) => eprintln!("No se pudo guardar la captura: {}", err),
+            }
+        }
+
+        // F9 congela la vista y la renderiza con la máxima calidad en varios pasos
+        if keys.pressed(&window, Action::BeautyShot) {
+            beauty_shot(&mut window, &scene, &cubes, &framebuffer);
+            // El tiempo de la captura no cuenta para el ciclo de día
+            last_frame = Instant::now();
+        }
+
+        if keys.pressed(&window, Action::SaveScene) {
+            match save_scene(&scene, Path::new(SESSION_SCENE)) {
+                Ok(()) => println!("Escena guardada en {}", SESSION_SCENE),
+                Err(err) => eprintln!("No se pudo guardar la escena: {}", err),
+            }
+        }
+
+        if keys.pressed(&window, Action::SaveWorld) {
+            match &world_path {
+                Some(path) => {
+                    save_session_world(&scene, path);
+                    edited = false;
+                }
+                None => println!("El mundo no se guarda en esta sesión"),
+            }
+        }
+
+        // Los contornos se dibujan después de las capturas para que no aparezcan en ellas
+        draw_wireframe(&mut framebuffer, &scene, &cubes, wireframe);
+        editor.draw_highlight(&mut framebuffer, &scene);
+        editor.draw_crosshair(&mut framebuffer);
+        brush.draw_outline(&mut framebuffer);
+        hooks.post_render(&mut framebuffer, &scene, &frame);
+        if hud.visible {
+            let position = scene.camera.position;
+            let mut lines = vec![
+                format!("fps: {:.1}", hud.fps()),
+                format!(
+                    "cámara: {:.1} {:.1} {:.1}",
+                    position.x, position.y, position.z
+                ),
+                format!("material: {}", scene.world.material_name(editor.selected)),
+                format!(
+                    "hora: {}{}",
+                    clock_time(cycle.progress()),
+                    day_state(&cycle)
+                ),
+            ];
+            if !light_groups.is_empty() {
+                lines.push(group_line(&light_groups, keys));
+            }
+            if view != DebugView::Shaded {
+                lines.push(format!("vista: {}", view.label()));
+            }
+            if wireframe != Wireframe::Off {
+                lines.push(format!("líneas: {}", wireframe.label()));
+            }
+            if accumulator.passes() > 1 {
+                lines.push(format!("acumulado: {}", accumulator.passes()));
+            }
+            if denoiser.enabled {
+                lines.push("ruido: filtrado".to_string());
+            }
+            if edge_smoothing {
+                lines.push("bordes: fxaa".to_string());
+            }
+            if let Some(cache) = &light_cache {
+                let (ready, exposed) = cache.progress();
+                lines.push(format!("luz guardada: {}/{} caras", ready, exposed));
+            }
+            if quality != RenderSettings::preview() {
+                lines.push(format!(
+                    "rebotes: {} muestras: {}x{} sombras: {}",
+                    quality.max_depth,
+                    quality.samples,
+                    quality.samples,
+                    if quality.shadows { "sí" } else { "no" }
+                ));
+            }
+            if quality.secondary_scale > 1 {
+                lines.push(format!(
+                    "reflejos: {}",
+                    secondary_label(quality.secondary_scale)
+                ));
+            }
+            hud.draw(&mut framebuffer, &lines);
+        }
+        panel.draw(
+            &mut framebuffer,
+            keys,
+            &scene,
+            editor.selected,
+            &quality,
+            &cycle,
+        );
+        gallery.draw(&mut framebuffer, keys);
+
+        let (width, height) = (framebuffer.width, framebuffer.height);
+        window
+            .update_with_buffer(framebuffer.as_u32_buffer(), width, height)
+            .unwrap();
+
+        // Se espera solo lo que le falta al frame para durar un período, no un período
+        // entero encima del tiempo de render
+        if let Some(period) = frame_period {
+            let spent = current_frame.elapsed();
+            if spent < period {
+                std::thread::sleep(period - spent);
+            }
+        }
+    }
+
+    if let (true, Some(path)) = (edited, &world_path) {
+        save_session_world(&scene, path);
+    }
+
+    if settings.clock != FrameClock::WallClock && frame_count > 0 {
+        println!(
+            "{} frames, render promedio {:.2} ms",
+            frame_count,
+            render_time.as_secs_f64() * 1000.0 / frame_count as f64
+        );
+    }
+}
+
+// Lo que se agrega a la hora en el texto de la esquina si el día no corre normal
+fn day_state(cycle: &DayNightCycle) -> String {
+    if cycle.paused {
+        " (detenida)".to_string()
+    } else if cycle.speed != 1.0 {
+        format!(" (x{})", cycle.speed)
+    } else {
+        String::new()
+    }
+}
+
+// Los grupos de luces con la tecla que los cambia y los que están apagados
+fn group_line(light_groups: &LightGroups, keys: &KeyMap) -> String {
+    let groups: Vec<String> = light_groups
+        .groups()
+        .enumerate()
+        .map(|(index, (name, enabled))| {
+            let key = match LIGHT_GROUP_ACTIONS.get(index) {
+                Some(&action) => format!(" {:?}", keys.key(action)),
+                None => String::new(),
+            };
+            let state = if enabled { "" } else { " (apagado)" };
+            format!("{}{}{}", name, key, state)
+        })
+        .collect();
+    format!("grupos: {}", groups.join(", "))
+}
+
+// Crea la ventana en el modo `display`; la ventana normal y la sin bordes usan el tamaño y
+// el lugar de `windowed`, la pantalla completa ocupa `screen` desde la esquina
+fn open_window(
+    display: DisplayMode,
+    (size, position): ((usize, usize), Option<(isize, isize)>),
+    screen: (usize, usize),
+    scale_mode: ScaleMode,
+) -> Window {
+    let fullscreen = display == DisplayMode::Fullscreen;
+    let (width, height) = if fullscreen { screen } else { size };
+    let decorated = display == DisplayMode::Windowed;
+    let mut window = Window::new(
+        "Minecraft",
+        width,
+        height,
+        WindowOptions {
+            borderless: !decorated,
+            title: decorated,
+            resize: !fullscreen,
+            topmost: fullscreen,
+            scale_mode,
+            ..WindowOptions::default()
+        },
+    )
+    .unwrap();
+    match (fullscreen, position) {
+        (true, _) => window.set_position(0, 0),
+        (false, Some((x, y))) => window.set_position(x, y),
+        (false, None) => {}
+    }
+    window
+}
+
+fn save_session_world(scene: &Scene, path: &Path) {
+    match save_world(scene, path) {
+        Ok(()) => println!("Mundo guardado en {}", path.display()),
+        Err(err) => eprintln!("No se pudo guardar el mundo: {}", err),
+    }
+}
@@ -0,0 +1,363 @@
+// keymap.rs
+//
+// Teclas de la ventana interactiva. Se pueden cambiar con un archivo de texto, una acción
+// por línea con el nombre de la tecla que la dispara:
+//
+//   # teclado AZERTY
+//   orbit_up Z
+//   orbit_left Q
+//   roll_left A
+//   zoom_in W
+//
+// Las acciones que no aparecen conservan su tecla. Los números del 1 al 9 (materiales),
+// las flechas, Enter, Retroceso, Escape y Shift no se pueden asignar.
+
+use minifb::{Key, KeyRepeat, Window};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::scene_file::SceneError;
+
+// Archivo de teclas que se carga al abrir la ventana si existe
+pub const DEFAULT_KEYMAP: &str = "teclas.cfg";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    OrbitUp,
+    OrbitDown,
+    OrbitLeft,
+    OrbitRight,
+    RollLeft,
+    RollRight,
+    ZoomIn,
+    ZoomOut,
+    Projection,
+    Isometric,
+    Focus,
+    DebugView,
+    Wireframe,
+    PauseDay,
+    DaySlower,
+    DayFaster,
+    SkipTime,
+    Shadows,
+    FewerBounces,
+    MoreBounces,
+    FewerSamples,
+    MoreSamples,
+    ClearBrush,
+    PausePath,
+    Screenshot,
+    BeautyShot,
+    SaveScene,
+    SaveWorld,
+    DisplayMode,
+    Hud,
+    Settings,
+    Gallery,
+    LightGroup1,
+    LightGroup2,
+    LightGroup3,
+    LightGroup4,
+    Denoise,
+    Fxaa,
+    LightCache,
+}
+
+// Encienden y apagan los grupos de luces de la escena, en el orden en que aparecen
+pub const LIGHT_GROUP_ACTIONS: [Action; 4] = [
+    Action::LightGroup1,
+    Action::LightGroup2,
+    Action::LightGroup3,
+    Action::LightGroup4,
+];
+
+// Cada acción con su nombre en el archivo y su tecla por defecto
+const DEFAULTS: [(Action, &str, Key); 39] = [
+    (Action::OrbitUp, "orbit_up", Key::W),
+    (Action::OrbitDown, "orbit_down", Key::S),
+    (Action::OrbitLeft, "orbit_left", Key::A),
+    (Action::OrbitRight, "orbit_right", Key::D),
+    (Action::RollLeft, "roll_left", Key::Q),
+    (Action::RollRight, "roll_right", Key::E),
+    (Action::ZoomIn, "zoom_in", Key::Z),
+    (Action::ZoomOut, "zoom_out", Key::X),
+    (Action::Projection, "projection", Key::O),
+    (Action::Isometric, "isometric", Key::I),
+    (Action::Focus, "focus", Key::F),
+    (Action::DebugView, "debug_view", Key::V),
+    (Action::Wireframe, "wireframe", Key::B),
+    (Action::PauseDay, "pause_day", Key::T),
+    (Action::DaySlower, "day_slower", Key::Comma),
+    (Action::DayFaster, "day_faster", Key::Period),
+    (Action::SkipTime, "skip_time", Key::J),
+    (Action::Shadows, "shadows", Key::H),
+    (Action::FewerBounces, "fewer_bounces", Key::K),
+    (Action::MoreBounces, "more_bounces", Key::L),
+    (Action::FewerSamples, "fewer_samples", Key::N),
+    (Action::MoreSamples, "more_samples", Key::M),
+    (Action::ClearBrush, "clear_brush", Key::C),
+    (Action::PausePath, "pause_path", Key::P),
+    (Action::Screenshot, "screenshot", Key::F12),
+    (Action::BeautyShot, "beauty_shot", Key::F9),
+    (Action::SaveScene, "save_scene", Key::F5),
+    (Action::SaveWorld, "save_world", Key::F6),
+    (Action::DisplayMode, "display_mode", Key::F11),
+    (Action::Hud, "hud", Key::F3),
+    (Action::Settings, "settings", Key::F1),
+    (Action::Gallery, "gallery", Key::G),
+    (Action::LightGroup1, "light_group_1", Key::F2),
+    (Action::LightGroup2, "light_group_2", Key::F4),
+    (Action::LightGroup3, "light_group_3", Key::F7),
+    (Action::LightGroup4, "light_group_4", Key::F8),
+    (Action::Denoise, "denoise", Key::U),
+    (Action::Fxaa, "fxaa", Key::Y),
+    (Action::LightCache, "light_cache", Key::R),
+];
+
+// Teclas con un uso fijo en la ventana o en los menús
+const RESERVED: [Key; 20] = [
+    Key::Key1,
+    Key::Key2,
+    Key::Key3,
+    Key::Key4,
+    Key::Key5,
+    Key::Key6,
+    Key::Key7,
+    Key::Key8,
+    Key::Key9,
+    Key::Up,
+    Key::Down,
+    Key::Left,
+    Key::Right,
+    Key::Enter,
+    Key::Backspace,
+    Key::Escape,
+    Key::LeftShift,
+    Key::RightShift,
+    Key::Unknown,
+    Key::Count,
+];
+
+// Nombres de las teclas que se pueden asignar además de las letras y de F1 a F12
+const KEY_NAMES: &[(&str, Key)] = &[
+    ("Space", Key::Space),
+    ("Tab", Key::Tab),
+    ("Insert", Key::Insert),
+    ("Delete", Key::Delete),
+    ("Home", Key::Home),
+    ("End", Key::End),
+    ("PageUp", Key::PageUp),
+    ("PageDown", Key::PageDown),
+    ("Comma", Key::Comma),
+    ("Period", Key::Period),
+    ("Minus", Key::Minus),
+    ("Equal", Key::Equal),
+    ("Semicolon", Key::Semicolon),
+    ("Apostrophe", Key::Apostrophe),
+    ("Slash", Key::Slash),
+    ("Backslash", Key::Backslash),
+    ("LeftBracket", Key::LeftBracket),
+    ("RightBracket", Key::RightBracket),
+    ("Backquote", Key::Backquote),
+    ("LeftCtrl", Key::LeftCtrl),
+    ("RightCtrl", Key::RightCtrl),
+    ("LeftAlt", Key::LeftAlt),
+    ("RightAlt", Key::RightAlt),
+    ("NumPad0", Key::NumPad0),
+    ("NumPad1", Key::NumPad1),
+    ("NumPad2", Key::NumPad2),
+    ("NumPad3", Key::NumPad3),
+    ("NumPad4", Key::NumPad4),
+    ("NumPad5", Key::NumPad5),
+    ("NumPad6", Key::NumPad6),
+    ("NumPad7", Key::NumPad7),
+    ("NumPad8", Key::NumPad8),
+    ("NumPad9", Key::NumPad9),
+    ("NumPadPlus", Key::NumPadPlus),
+    ("NumPadMinus", Key::NumPadMinus),
+];
+
+const LETTERS: [Key; 26] = [
+    Key::A,
+    Key::B,
+    Key::C,
+    Key::D,
+    Key::E,
+    Key::F,
+    Key::G,
+    Key::H,
+    Key::I,
+    Key::J,
+    Key::K,
+    Key::L,
+    Key::M,
+    Key::N,
+    Key::O,
+    Key::P,
+    Key::Q,
+    Key::R,
+    Key::S,
+    Key::T,
+    Key::U,
+    Key::V,
+    Key::W,
+    Key::X,
+    Key::Y,
+    Key::Z,
+];
+
+const FUNCTION_KEYS: [Key; 12] = [
+    Key::F1,
+    Key::F2,
+    Key::F3,
+    Key::F4,
+    Key::F5,
+    Key::F6,
+    Key::F7,
+    Key::F8,
+    Key::F9,
+    Key::F10,
+    Key::F11,
+    Key::F12,
+];
+
+#[derive(Debug, Clone)]
+pub struct KeyMap {
+    keys: HashMap<Action, Key>,
+}
+
+impl KeyMap {
+    // Las teclas de siempre, pensadas para un teclado QWERTY
+    pub fn new() -> Self {
+        KeyMap {
+            keys: DEFAULTS
+                .iter()
+                .map(|&(action, _, key)| (action, key))
+                .collect(),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, SceneError> {
+        let error = |line: usize, message: String| SceneError {
+            path: path.to_path_buf(),
+            line,
+            column: if line == 0 { 0 } else { 1 },
+            message,
+        };
+        let source = fs::read_to_string(path)
+            .map_err(|err| error(0, format!("no se pudo leer el archivo: {}", err)))?;
+
+        let mut keymap = KeyMap::new();
+        for (index, line) in source.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let number = index + 1;
+            let mut words = line.split_whitespace();
+            let (Some(name), Some(key_name), None) = (words.next(), words.next(), words.next())
+            else {
+                return Err(error(number, "se esperaba '<acción> <tecla>'".to_string()));
+            };
+            let action = DEFAULTS
+                .iter()
+                .find(|(_, candidate, _)| *candidate == name)
+                .map(|&(action, _, _)| action)
+                .ok_or_else(|| error(number, format!("acción desconocida '{}'", name)))?;
+            let key = parse_key(key_name)
+                .ok_or_else(|| error(number, format!("tecla desconocida '{}'", key_name)))?;
+            if RESERVED.contains(&key) {
+                return Err(error(
+                    number,
+                    format!("la tecla '{}' no se puede asignar", key_name),
+                ));
+            }
+            keymap.keys.insert(action, key);
+        }
+
+        // Dos acciones con la misma tecla se dispararían juntas
+        for (index, &(first, first_name, _)) in DEFAULTS.iter().enumerate() {
+            for &(second, second_name, _) in &DEFAULTS[index + 1..] {
+                if keymap.key(first) == keymap.key(second) {
+                    return Err(error(
+                        0,
+                        format!(
+                            "{} y {} usan la misma tecla ({:?})",
+                            first_name,
+                            second_name,
+                            keymap.key(first)
+                        ),
+                    ));
+                }
+            }
+        }
+        Ok(keymap)
+    }
+
+    // El archivo de `path` si existe, si no las teclas por defecto
+    pub fn load_or_default(path: &Path) -> Result<Self, SceneError> {
+        if path.exists() {
+            KeyMap::load(path)
+        } else {
+            Ok(KeyMap::new())
+        }
+    }
+
+    pub fn key(&self, action: Action) -> Key {
+        self.keys[&action]
+    }
+
+    // Si la tecla de la acción se presionó en este frame
+    pub fn pressed(&self, window: &Window, action: Action) -> bool {
+        window.is_key_pressed(self.key(action), KeyRepeat::No)
+    }
+
+    pub fn down(&self, window: &Window, action: Action) -> bool {
+        window.is_key_down(self.key(action))
+    }
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Una letra, un número del 0 al 9, F1 a F12 o uno de los nombres de `KEY_NAMES`, sin
+// distinguir mayúsculas
+pub fn parse_key(name: &str) -> Option<Key> {
+    let upper = name.to_ascii_uppercase();
+    let mut chars = upper.chars();
+    if let (Some(letter @ 'A'..='Z'), None) = (chars.next(), chars.next()) {
+        return Some(LETTERS[(letter as u8 - b'A') as usize]);
+    }
+    if let Some(digit) = name.parse::<usize>().ok().filter(|digit| *digit <= 9) {
+        return Some(
+            [
+                Key::Key0,
+                Key::Key1,
+                Key::Key2,
+                Key::Key3,
+                Key::Key4,
+                Key::Key5,
+                Key::Key6,
+                Key::Key7,
+                Key::Key8,
+                Key::Key9,
+            ][digit],
+        );
+    }
+    if let Some(number) = upper
+        .strip_prefix('F')
+        .and_then(|number| number.parse::<usize>().ok())
+        .filter(|number| (1..=12).contains(number))
+    {
+        return Some(FUNCTION_KEYS[number - 1]);
+    }
+    KEY_NAMES
+        .iter()
+        .find(|(candidate, _)| candidate.eq_ignore_ascii_case(name))
+        .map(|&(_, key)| key)
+}
@@ -0,0 +1,78 @@
+pub mod accumulation;
+pub mod animation;
+pub mod aov;
+pub mod assets;
+#[cfg(all(feature = "window", feature = "export"))]
+pub mod beauty;
+pub mod blue_noise;
+#[cfg(feature = "window")]
+pub mod brush;
+pub mod camera;
+pub mod camera_motion;
+pub mod camera_path;
+pub mod chunk;
+pub mod clouds;
+pub mod color;
+pub mod cube;
+pub mod day_cycle;
+pub mod debug_view;
+pub mod denoise;
+pub mod dynamic_sky;
+#[cfg(feature = "window")]
+pub mod editor;
+#[cfg(feature = "export")]
+pub mod export;
+pub mod fog;
+pub mod font;
+pub mod framebuffer;
+pub mod fxaa;
+pub mod gallery;
+#[cfg(feature = "window")]
+pub mod gallery_menu;
+#[cfg(feature = "window")]
+pub mod hooks;
+#[cfg(feature = "window")]
+pub mod hot_reload;
+pub mod hud;
+#[cfg(all(feature = "window", feature = "export"))]
+pub mod interactive;
+#[cfg(feature = "window")]
+pub mod keymap;
+pub mod light;
+pub mod light_cache;
+pub mod light_groups;
+pub mod material;
+pub mod material_library;
+pub mod mesh;
+pub mod moon;
+pub mod obj_export;
+pub mod object;
+pub mod overrides;
+pub mod packet;
+pub mod paged_world;
+pub mod parity;
+pub mod portal;
+pub mod post;
+pub mod procedural;
+pub mod ray_intersect;
+pub mod render;
+pub mod resource_pack;
+pub mod sampler;
+pub mod scene;
+pub mod scene_file;
+pub mod schematic;
+#[cfg(feature = "export")]
+pub mod screenshot;
+pub mod secondary;
+#[cfg(feature = "window")]
+pub mod settings_panel;
+pub mod skybox;
+pub mod stereo;
+pub mod sweep;
+pub mod temporal;
+pub mod terrain;
+pub mod tile_scheduler;
+pub mod vox;
+pub mod wireframe;
+pub mod world;
+pub mod world_file;
@@ -1,10 +1,18 @@
 use nalgebra_glm::Vec3;
+use std::f32::consts::PI;
 use crate::color::Color;
 
 pub struct Light {
     pub position: Vec3,
     pub color: Color,
     pub intensity: f32,
+    // Radio de la esfera emisora; 0 es una luz puntual con sombras duras
+    pub radius: f32,
+    pub animation: Option<LightAnimation>,
+    // Multiplicador de la intensidad en este momento; lo actualiza `Scene::animate_lights`
+    pub intensity_scale: f32,
+    // Nombre del grupo con el que se enciende y apaga junto a otras luces en la ventana
+    pub group: Option<String>,
 }
 
 impl Light {
@@ -13,6 +21,66 @@ impl Light {
             position,
             color,
             intensity,
+            radius: 0.0,
+            animation: None,
+            intensity_scale: 1.0,
+            group: None,
         }
     }
 }
+
+// Variación de la intensidad en el tiempo, como multiplicador de la intensidad base
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LightAnimation {
+    // Oscila suavemente entre la intensidad completa y `1 - depth` veces, una vez por `period`
+    Pulse { period: f32, depth: f32 },
+    // Parpadeo irregular de antorcha: ruido suave que cambia de valor cada `period` segundos
+    Flicker { period: f32, depth: f32 },
+    // Encendida durante la fracción `duty` de cada `period` y apagada el resto (relámpagos)
+    Strobe { period: f32, duty: f32 },
+}
+
+impl LightAnimation {
+    // `seed` desfasa el parpadeo de luces iguales para que no titilen al unísono
+    pub fn factor(&self, time: f32, seed: u32) -> f32 {
+        match *self {
+            LightAnimation::Pulse { period, depth } => {
+                let wave = 0.5 - 0.5 * (2.0 * PI * time / period).cos();
+                1.0 - depth * wave
+            }
+            LightAnimation::Flicker { period, depth } => {
+                // Dos octavas para que el parpadeo no se vea periódico
+                let t = time / period;
+                let noise = 0.65 * value_noise(t, seed) + 0.35 * value_noise(t * 2.3, !seed);
+                1.0 - depth * noise
+            }
+            LightAnimation::Strobe { period, duty } => {
+                if (time / period).rem_euclid(1.0) < duty {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+        }
+    }
+}
+
+// Ruido de valor en una dimensión, entre 0 y 1, interpolado con smoothstep
+fn value_noise(t: f32, seed: u32) -> f32 {
+    let cell = t.floor();
+    let f = t - cell;
+    let smooth = f * f * (3.0 - 2.0 * f);
+    let a = hash(cell as i32, seed);
+    let b = hash(cell as i32 + 1, seed);
+    a + (b - a) * smooth
+}
+
+fn hash(n: i32, seed: u32) -> f32 {
+    let mut x = (n as u32).wrapping_mul(0x27d4_eb2d) ^ seed.wrapping_mul(0x1656_67b1);
+    x ^= x >> 15;
+    x = x.wrapping_mul(0x2c1b_3c6d);
+    x ^= x >> 12;
+    x = x.wrapping_mul(0x297a_2d39);
+    x ^= x >> 15;
+    x as f32 / u32::MAX as f32
+}
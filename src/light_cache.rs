@@ -0,0 +1,264 @@
+// light_cache.rs
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use nalgebra_glm::Vec3;
+use std::collections::HashMap;
+
+use crate::color::Color;
+use crate::cube::Cube;
+use crate::mesh::hides_face;
+use crate::ray_intersect::Intersect;
+use crate::render::surface_shadows;
+use crate::scene::Scene;
+use crate::world::BlockPos;
+
+// Texels por lado de la cuadrícula de cada cara; entre sus centros se interpola
+const FACE_TEXELS: usize = 8;
+// Valores de ruido con los que se promedian las sombras suaves y los portales de cada texel
+const TEXEL_SAMPLES: u32 = 4;
+// Cuánto puede haberse movido una luz desde que se calculó la cara, como fracción de su
+// distancia al punto, para seguir usando la sombra guardada
+const MAX_LIGHT_DRIFT: f32 = 0.02;
+// Caras que se recalculan en cada frame
+pub const REFRESH_BUDGET: usize = 256;
+
+// Una cara de un bloque del mundo: el eje de su normal y hacia dónde apunta, 1 o -1
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct FaceKey {
+    block: BlockPos,
+    axis: usize,
+    side: i32,
+}
+
+// La luz directa guardada de una cara
+struct FaceLight {
+    // Posición de cada luz activa cuando se calculó
+    lights: Vec<Vec3>,
+    // Filtro de sombra de cada luz en cada texel, en el orden texel * luces + luz
+    shadows: Vec<Color>,
+    // Luz del cielo que entra por los portales en cada texel
+    portal: Vec<Color>,
+}
+
+// Sombras y luz de los portales guardadas por cara de bloque, para que la ventana solo
+// trace los rayos de cámara y lea lo demás de acá. Sirve para la geometría quieta: cada
+// frame se recalcula una parte de las caras que se ven, así un sol que se mueve despacio
+// las va actualizando, y las que quedaron atrás (una luz se movió demasiado desde que se
+// calcularon) se trazan como siempre hasta que les toque.
+pub struct LightCache {
+    faces: HashMap<FaceKey, FaceLight>,
+    // Caras de bloques opacos que dan al aire, en el orden en que se recalculan; se buscan
+    // de nuevo después de editar el mundo
+    exposed: Option<Vec<FaceKey>>,
+    next: usize,
+}
+
+// Lo guardado en un punto de una cara: los cuatro texels más cercanos con sus pesos
+pub struct CachedFace<'a> {
+    face: &'a FaceLight,
+    texels: [(usize, f32); 4],
+}
+
+impl CachedFace<'_> {
+    // Filtro de sombra de la luz número `light` entre las activas de la escena
+    pub fn shadow(&self, light: usize) -> Color {
+        let lights = self.face.lights.len();
+        self.texels
+            .iter()
+            .fold(Color::black(), |sum, &(texel, weight)| {
+                sum + self.face.shadows[texel * lights + light] * weight
+            })
+    }
+
+    pub fn portal(&self) -> Color {
+        self.texels
+            .iter()
+            .fold(Color::black(), |sum, &(texel, weight)| {
+                sum + self.face.portal[texel] * weight
+            })
+    }
+}
+
+impl Default for LightCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LightCache {
+    pub fn new() -> Self {
+        LightCache {
+            faces: HashMap::new(),
+            exposed: None,
+            next: 0,
+        }
+    }
+
+    // Olvida todo: al quitar o poner un bloque cambian las caras que se ven y las sombras
+    // que caen sobre ellas
+    pub fn invalidate(&mut self) {
+        self.faces.clear();
+        self.exposed = None;
+        self.next = 0;
+    }
+
+    // Caras calculadas y caras que se ven, para mostrar cuánto falta
+    pub fn progress(&self) -> (usize, usize) {
+        let exposed = self.exposed.as_ref().map_or(0, Vec::len);
+        (self.faces.len(), exposed)
+    }
+
+    // Recalcula las próximas `budget` caras y sigue desde ahí en el frame siguiente,
+    // volviendo a empezar después de la última
+    pub fn refresh(&mut self, scene: &Scene, objects: &[Cube], budget: usize) {
+        let exposed = self.exposed.get_or_insert_with(|| exposed_faces(scene));
+        if exposed.is_empty() {
+            return;
+        }
+        let count = budget.min(exposed.len());
+        let keys: Vec<FaceKey> = (0..count)
+            .map(|offset| exposed[(self.next + offset) % exposed.len()])
+            .collect();
+        self.next = (self.next + count) % exposed.len();
+
+        let light = |key: &FaceKey| (*key, face_light(scene, objects, key));
+        #[cfg(feature = "parallel")]
+        let computed: Vec<(FaceKey, FaceLight)> = keys.par_iter().map(light).collect();
+        #[cfg(not(feature = "parallel"))]
+        let computed: Vec<(FaceKey, FaceLight)> = keys.iter().map(light).collect();
+        self.faces.extend(computed);
+    }
+
+    // Lo guardado en el punto de `intersect`, si es la cara de un bloque opaco del mundo
+    // que ya se calculó con las mismas luces encendidas y casi donde están ahora
+    pub fn sample(&self, scene: &Scene, intersect: &Intersect) -> Option<CachedFace<'_>> {
+        if !intersect.material.is_opaque() {
+            return None;
+        }
+        // Los bloques del mundo son cajas de lado 1 en coordenadas enteras
+        let (min, max) = intersect.bounds;
+        if max - min != Vec3::new(1.0, 1.0, 1.0) || min.iter().any(|value| value.fract() != 0.0) {
+            return None;
+        }
+        // La cara es el plano más cercano al punto; la normal puede venir torcida por el
+        // normal map
+        let point = intersect.point;
+        let (axis, side, _) = (0..3)
+            .flat_map(|axis| {
+                [
+                    (axis, -1, (point[axis] - min[axis]).abs()),
+                    (axis, 1, (max[axis] - point[axis]).abs()),
+                ]
+            })
+            .min_by(|a, b| a.2.total_cmp(&b.2))?;
+        let key = FaceKey {
+            block: (min.x as i32, min.y as i32, min.z as i32),
+            axis,
+            side,
+        };
+        let face = self.faces.get(&key)?;
+
+        let mut lights = scene.active_lights();
+        for stored in &face.lights {
+            let light = lights.next()?;
+            let drift = (light.position - stored).magnitude();
+            if drift > MAX_LIGHT_DRIFT * (light.position - point).magnitude() {
+                return None;
+            }
+        }
+        if lights.next().is_some() {
+            return None;
+        }
+
+        let (u_axis, v_axis) = ((axis + 1) % 3, (axis + 2) % 3);
+        let last = (FACE_TEXELS - 1) as f32;
+        let coordinate =
+            |axis: usize| ((point[axis] - min[axis]) * FACE_TEXELS as f32 - 0.5).clamp(0.0, last);
+        let (u, v) = (coordinate(u_axis), coordinate(v_axis));
+        let (u0, v0) = (u.floor() as usize, v.floor() as usize);
+        let (u1, v1) = ((u0 + 1).min(FACE_TEXELS - 1), (v0 + 1).min(FACE_TEXELS - 1));
+        let (tu, tv) = (u - u0 as f32, v - v0 as f32);
+        let texel = |u: usize, v: usize| v * FACE_TEXELS + u;
+        Some(CachedFace {
+            face,
+            texels: [
+                (texel(u0, v0), (1.0 - tu) * (1.0 - tv)),
+                (texel(u1, v0), tu * (1.0 - tv)),
+                (texel(u0, v1), (1.0 - tu) * tv),
+                (texel(u1, v1), tu * tv),
+            ],
+        })
+    }
+}
+
+// Caras de los bloques opacos del mundo que no tapa su vecino
+fn exposed_faces(scene: &Scene) -> Vec<FaceKey> {
+    let world = &scene.world;
+    let mut faces = Vec::new();
+    for (block_pos, block) in world.blocks() {
+        if !world.material(block).is_opaque() {
+            continue;
+        }
+        for axis in 0..3 {
+            for side in [-1, 1] {
+                let mut neighbor = [block_pos.0, block_pos.1, block_pos.2];
+                neighbor[axis] += side;
+                let neighbor = world.get_block((neighbor[0], neighbor[1], neighbor[2]));
+                if !hides_face(world, block, neighbor) {
+                    faces.push(FaceKey {
+                        block: block_pos,
+                        axis,
+                        side,
+                    });
+                }
+            }
+        }
+    }
+    faces
+}
+
+// Sombras y portales en el centro de cada texel de la cara, con la normal de la cara,
+// promediados entre `TEXEL_SAMPLES` valores de ruido
+fn face_light(scene: &Scene, objects: &[Cube], key: &FaceKey) -> FaceLight {
+    let (u_axis, v_axis) = ((key.axis + 1) % 3, (key.axis + 2) % 3);
+    let min = Vec3::new(key.block.0 as f32, key.block.1 as f32, key.block.2 as f32);
+    let bounds = (min, min + Vec3::new(1.0, 1.0, 1.0));
+    let mut normal = Vec3::zeros();
+    normal[key.axis] = key.side as f32;
+    let material = scene.world.material(scene.world.get_block(key.block));
+    let lights: Vec<Vec3> = scene.active_lights().map(|light| light.position).collect();
+
+    let weight = 1.0 / TEXEL_SAMPLES as f32;
+    let mut shadows = Vec::with_capacity(FACE_TEXELS * FACE_TEXELS * lights.len());
+    let mut portal = Vec::with_capacity(FACE_TEXELS * FACE_TEXELS);
+    for v in 0..FACE_TEXELS {
+        for u in 0..FACE_TEXELS {
+            let mut point = min;
+            point[key.axis] += if key.side > 0 { 1.0 } else { 0.0 };
+            point[u_axis] += (u as f32 + 0.5) / FACE_TEXELS as f32;
+            point[v_axis] += (v as f32 + 0.5) / FACE_TEXELS as f32;
+            let intersect = Intersect::new(point, normal, 0.0, material, (0.0, 0.0), 1.0, bounds);
+
+            let mut texel_shadows = vec![Color::black(); lights.len()];
+            let mut texel_portal = Color::black();
+            for sample in 0..TEXEL_SAMPLES {
+                let noise = (sample as f32 + 0.5) * weight;
+                let (light_shadows, portal_light) =
+                    surface_shadows(&intersect, scene, objects, noise);
+                for (sum, shadow) in texel_shadows.iter_mut().zip(light_shadows) {
+                    *sum = *sum + shadow * weight;
+                }
+                texel_portal = texel_portal + portal_light * weight;
+            }
+            shadows.extend(texel_shadows);
+            portal.push(texel_portal);
+        }
+    }
+    FaceLight {
+        lights,
+        shadows,
+        portal,
+    }
+}
@@ -0,0 +1,79 @@
+// light_groups.rs
+
+use crate::scene::Scene;
+
+// Grupos de luces de la escena (`group` en el archivo de escena), en el orden en que
+// aparece su primera luz. Apagar un grupo deja sus luces en cero sin sacarlas de la
+// escena, así se pueden comparar iluminaciones y volver a la original.
+pub struct LightGroups {
+    names: Vec<String>,
+    enabled: Vec<bool>,
+}
+
+impl LightGroups {
+    pub fn new(scene: &Scene) -> Self {
+        let mut names: Vec<String> = Vec::new();
+        for group in scene.lights.iter().filter_map(|light| light.group.as_ref()) {
+            if !names.contains(group) {
+                names.push(group.clone());
+            }
+        }
+        let enabled = vec![true; names.len()];
+        LightGroups { names, enabled }
+    }
+
+    // Vuelve a leer los grupos de una escena recargada; los que siguen existiendo conservan
+    // su estado
+    pub fn refresh(&mut self, scene: &Scene) {
+        let fresh = LightGroups::new(scene);
+        let enabled = fresh
+            .names
+            .iter()
+            .map(|name| {
+                !self
+                    .groups()
+                    .any(|(group, enabled)| group == name && !enabled)
+            })
+            .collect();
+        *self = LightGroups {
+            names: fresh.names,
+            enabled,
+        };
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    // Enciende o apaga el grupo `index`, si existe
+    pub fn toggle(&mut self, index: usize) {
+        if let Some(enabled) = self.enabled.get_mut(index) {
+            *enabled = !*enabled;
+        }
+    }
+
+    // Cada grupo con si está encendido
+    pub fn groups(&self) -> impl Iterator<Item = (&str, bool)> {
+        self.names
+            .iter()
+            .map(String::as_str)
+            .zip(self.enabled.iter().copied())
+    }
+
+    // Apaga las luces de los grupos apagados; va después de animarlas
+    pub fn apply(&self, scene: &mut Scene) {
+        for light in &mut scene.lights {
+            let Some(group) = &light.group else {
+                continue;
+            };
+            let off = self
+                .names
+                .iter()
+                .zip(&self.enabled)
+                .any(|(name, enabled)| name == group && !enabled);
+            if off {
+                light.intensity_scale = 0.0;
+            }
+        }
+    }
+}
@@ -1,503 +1,303 @@
-mod camera;
-mod color;
-mod cube;
-mod framebuffer;
-mod light;
-mod material;
-mod ray_intersect;
-mod skybox; 
-use rayon::prelude::*;
+mod cli;
+
+use minifb::Key;
+use std::cell::Cell;
+use std::path::Path;
+use std::rc::Rc;
+use std::time::Instant;
+
+use crate::cli::{parse_args, Command};
+use proyecto2::camera_path::CameraPath;
+use proyecto2::denoise::denoise;
+use proyecto2::export::{
+    render_image, render_image_aovs, render_sequence, save_metadata, save_render, RenderMetadata,
+    SEQUENCE_METADATA,
+};
+use proyecto2::hooks::Hooks;
+use proyecto2::interactive::{run_interactive, InteractiveSettings};
+use proyecto2::keymap::{Action, KeyMap, DEFAULT_KEYMAP};
+use proyecto2::moon::Moon;
+use proyecto2::obj_export::export_obj;
+use proyecto2::overrides::apply_override;
+use proyecto2::parity::check_parity;
+use proyecto2::post::apply_post;
+use proyecto2::scene::Scene;
+use proyecto2::scene_file::{load_camera_path, load_scene};
+use proyecto2::sweep::{render_contact_sheet, ContactSheetSettings};
+use proyecto2::world_file::load_world;
 
-use image::open;
-use minifb::{Key, Window, WindowOptions};
-use nalgebra_glm::{normalize, Vec3};
-use std::f32::consts::PI;
-use std::time::{Duration, Instant};
-
-use crate::camera::Camera;
-use crate::color::Color;
-use crate::cube::Cube;
-use crate::framebuffer::Framebuffer;
-use crate::light::Light;
-use crate::material::Material;
-use crate::ray_intersect::{Intersect, RayIntersect};
-use crate::skybox::Skybox; 
-
-const ORIGIN_BIAS: f32 = 1e-4;
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let options = parse_args(std::env::args()).unwrap_or_else(|err| err.exit());
+
+    // El modo demo y la galería no dependen del mundo guardado
+    let saved_world = !options.demo && options.gallery.is_none() && options.world.exists();
+    let mut scene = match (&options.scene, &options.terrain, options.gallery) {
+        (None, None, Some(showcase)) => showcase.build(),
+        (Some(path), _, _) => match load_scene(path) {
+            Ok(scene) => scene,
+            Err(err) => {
+                eprintln!("Error en la escena: {}", err);
+                std::process::exit(1);
+            }
+        },
+        (None, Some(terrain), _) => Scene::terrain(terrain),
+        (None, None, None) if saved_world => match load_world(&options.world) {
+            Ok(scene) => scene,
+            Err(err) => {
+                eprintln!("Error en el mundo guardado: {}", err);
+                std::process::exit(1);
+            }
+        },
+        (None, None, None) => Scene::diorama(),
+    };
 
-fn offset_origin(intersect: &Intersect, direction: &Vec3) -> Vec3 {
-    let offset = intersect.normal * ORIGIN_BIAS;
-    if direction.dot(&intersect.normal) < 0.0 {
-        intersect.point - offset
-    } else {
-        intersect.point + offset
+    if let Some(clamp) = options.firefly_clamp {
+        scene.firefly_clamp = clamp;
     }
-}
-
-fn reflect(incident: &Vec3, normal: &Vec3) -> Vec3 {
-    incident - 2.0 * incident.dot(normal) * normal
-}
-
-fn refract(incident: &Vec3, normal: &Vec3, eta_t: f32) -> Vec3 {
-    let cosi = -incident.dot(normal).max(-1.0).min(1.0);
-    let (n_cosi, eta, n_normal);
-
-    if cosi < 0.0 {
-        n_cosi = -cosi;
-        eta = 1.0 / eta_t;
-        n_normal = -normal;
-    } else {
-        n_cosi = cosi;
-        eta = eta_t;
-        n_normal = *normal;
+    if let Some(roulette) = options.roulette {
+        scene.roulette = roulette;
     }
-
-    let k = 1.0 - eta * eta * (1.0 - n_cosi * n_cosi);
-
-    if k < 0.0 {
-        reflect(incident, &n_normal)
-    } else {
-        eta * incident + (eta * n_cosi - k.sqrt()) * n_normal
+    if options.transparent {
+        scene.transparent_background = true;
     }
-}
-
-
-fn fresnel(incident: &Vec3, normal: &Vec3, ior: f32) -> f32 {
-    let mut cosi = incident.dot(normal).clamp(-1.0, 1.0);
-    let etai = 1.0;
-    let etat = ior;
-    let sint = etai / etat * (1.0 - cosi * cosi).sqrt();
-
-    if sint >= 1.0 {
-        return 1.0;
-    } else {
-        let cost = (1.0 - sint * sint).sqrt();
-        cosi = cosi.abs();
-        let rs = ((etat * cosi) - (etai * cost)) / ((etat * cosi) + (etai * cost));
-        let rp = ((etai * cosi) - (etat * cost)) / ((etai * cosi) + (etat * cost));
-        return (rs * rs + rp * rp) / 2.0;
+    if options.dither {
+        scene.dither = true;
     }
-}
-
-fn cast_shadow(
-    intersect: &Intersect,
-    lights: &[Light],
-    objects: &[Cube],
-    light_index: usize,
-) -> f32 {
-    let light = &lights[light_index];
-    let light_dir = (light.position - intersect.point).normalize();
-    let light_distance = (light.position - intersect.point).magnitude();
-
-    let shadow_ray_origin = offset_origin(intersect, &light_dir);
-    let mut shadow_intensity = 0.0;
-
-    for object in objects {
-        let shadow_intersect = object.ray_intersect(&shadow_ray_origin, &light_dir);
-        if shadow_intersect.is_intersecting && shadow_intersect.distance < light_distance {
-            let distance_ratio = shadow_intersect.distance / light_distance;
-            shadow_intensity = 1.0 - distance_ratio.powf(2.0).min(1.0);
-            break;
-        }
+    if options.dynamic_sky {
+        scene.use_dynamic_sky();
     }
-
-    shadow_intensity
-}
-
-pub fn cast_ray(
-    ray_origin: &Vec3,
-    ray_direction: &Vec3,
-    objects: &[Cube],
-    lights: &[Light],
-    depth: u32,
-    skybox: &Skybox,
-) -> Color {
-    if depth > 3 {
-        return skybox.get_color_from_direction(ray_direction);
+    if options.moon {
+        scene.moon = Some(Moon::new(true));
     }
-
-    let mut closest_intersect = Intersect::empty();
-    let mut min_distance = f32::INFINITY;
-
-    for object in objects {
-        let intersect = object.ray_intersect(ray_origin, ray_direction);
-        if intersect.is_intersecting && intersect.distance < min_distance {
-            min_distance = intersect.distance;
-            closest_intersect = intersect;
-        }
+    if let Some(near) = options.near {
+        scene.camera.near = near;
     }
-
-    if !closest_intersect.is_intersecting {
-        return skybox.get_color_from_direction(ray_direction);
+    if let Some(far) = options.far {
+        scene.camera.far = Some(far);
     }
-
-    let intersect = closest_intersect;
-
-    let mut color = intersect.material.emission;
-
-    let mut diffuse = Color::black();
-    let mut specular = Color::black();
-
-    for (i, light) in lights.iter().enumerate() {
-        let light_dir = (light.position - intersect.point).normalize();
-        let view_dir = (ray_origin - intersect.point).normalize();
-        let reflect_dir = reflect(&-light_dir, &intersect.normal).normalize();
-
-        let shadow_intensity = cast_shadow(&intersect, lights, objects, i);
-        let light_intensity = light.intensity * (1.0 - shadow_intensity);
-
-        let diffuse_intensity = intersect.normal.dot(&light_dir).max(0.0);
-        diffuse = diffuse
-            + (intersect.material.diffuse * light.color) * diffuse_intensity * light_intensity;
-
-        let specular_intensity = view_dir
-            .dot(&reflect_dir)
-            .max(0.0)
-            .powf(intersect.material.specular);
-        specular = specular + light.color * specular_intensity * light_intensity;
-    }
-
-    let kr = fresnel(
-        ray_direction,
-        &intersect.normal,
-        intersect.material.refractive_index,
-    );
-    let reflectivity = kr * intersect.material.albedo[2];
-    let transparency = (1.0 - kr) * intersect.material.albedo[3];
-
-    let mut reflect_color = Color::black();
-    if reflectivity > 0.0 {
-        let reflect_dir = reflect(&ray_direction, &intersect.normal).normalize();
-        let reflect_origin = offset_origin(&intersect, &reflect_dir);
-        reflect_color = cast_ray(
-            &reflect_origin,
-            &reflect_dir,
-            objects,
-            lights,
-            depth + 1,
-            skybox,
-        );
-    }
-
-    let mut refract_color = Color::black();
-    if transparency > 0.0 {
-        let refract_dir = refract(
-            &ray_direction,
-            &intersect.normal,
-            intersect.material.refractive_index,
-        )
-        .normalize();
-        let refract_origin = offset_origin(&intersect, &refract_dir);
-        refract_color = cast_ray(
-            &refract_origin,
-            &refract_dir,
-            objects,
-            lights,
-            depth + 1,
-            skybox,
-        );
+    if scene.camera.far.is_some_and(|far| far <= scene.camera.near) {
+        eprintln!("El plano lejano debe estar más lejos que el cercano");
+        std::process::exit(2);
     }
 
-    color = color
-        + (diffuse * intersect.material.albedo[0] + specular * intersect.material.albedo[1])
-            * (1.0 - reflectivity - transparency)
-        + (reflect_color * reflectivity)
-        + (refract_color * transparency);
-
-    color.clamp()
-}
-
-pub fn render(
-    framebuffer: &mut Framebuffer,
-    objects: &[Cube],
-    camera: &Camera,
-    lights: &[Light],
-    skybox: &Skybox,
-) {
-    let width = framebuffer.width as f32;
-    let height = framebuffer.height as f32;
-    let aspect_ratio = width / height;
-    let fov = PI / 3.0;
-    let perspective_scale = (fov * 0.5).tan();
-
-    // Itera paralelamente sobre cada fila del framebuffer usando `par_iter_mut`
-    framebuffer
-        .buffer
-        .par_chunks_mut(framebuffer.width as usize)
-        .enumerate()
-        .for_each(|(y, row)| {
-            let screen_y = -(2.0 * y as f32) / height + 1.0;
-            let screen_y = screen_y * perspective_scale;
-
-            for (x, pixel) in row.iter_mut().enumerate() {
-                let screen_x = (2.0 * x as f32) / width - 1.0;
-                let screen_x = screen_x * aspect_ratio * perspective_scale;
-
-                let ray_direction = normalize(&Vec3::new(screen_x, screen_y, -1.0));
-                let rotated_direction = camera.transform_vector(&ray_direction);
+    let camera_path = options
+        .camera_path
+        .as_ref()
+        .map(|path| match load_camera_path(path) {
+            Ok(camera_path) => camera_path,
+            Err(err) => {
+                eprintln!("Error en el recorrido de cámara: {}", err);
+                std::process::exit(1);
+            }
+        });
 
-                let pixel_color = cast_ray(
-                    &camera.position,
-                    &rotated_direction,
-                    objects,
-                    lights,
-                    0,
-                    skybox,
+    match options.command {
+        Command::Export(mut settings) => {
+            let mut metadata = RenderMetadata {
+                command: args,
+                scene: options.scene.clone(),
+                seed: options
+                    .terrain
+                    .as_ref()
+                    .filter(|_| options.scene.is_none())
+                    .map(|terrain| terrain.seed),
+                time_of_day: Some(settings.time_of_day),
+                frames: Some(settings.frames),
+                max_depth: settings.max_depth,
+                ..RenderMetadata::new(&scene, settings.width, settings.height, settings.samples)
+            };
+            settings.camera_path = camera_path;
+            let start = Instant::now();
+            let result = render_sequence(&mut scene, &settings).and_then(|output| {
+                metadata.render_seconds = start.elapsed().as_secs_f32();
+                save_metadata(&metadata, &output.join(SEQUENCE_METADATA))?;
+                Ok(output)
+            });
+            match result {
+                Ok(output) => println!("Secuencia guardada en {}", output.display()),
+                Err(err) => {
+                    eprintln!("No se pudo exportar la secuencia: {}", err);
+                    std::process::exit(1);
+                }
+            }
+        }
+        Command::Render(settings) => {
+            if let Some(time_of_day) = settings.time_of_day {
+                scene.apply_day_cycle(time_of_day);
+            }
+            for assignment in &settings.overrides {
+                if let Err(err) = apply_override(&mut scene, assignment) {
+                    eprintln!("No se pudo aplicar --set {}: {}", assignment, err);
+                    std::process::exit(2);
+                }
+            }
+            let output = &settings.output;
+            let (width, height) = (settings.width, settings.height);
+            let mut metadata = RenderMetadata {
+                command: args,
+                scene: options.scene.clone(),
+                overrides: settings.overrides.clone(),
+                sweep: settings
+                    .sweep
+                    .iter()
+                    .map(|axis| format!("{}={}", axis.path, axis.values.join("|")))
+                    .collect(),
+                time_of_day: settings.time_of_day,
+                max_depth: settings.max_depth,
+                denoised: settings.denoise,
+                aovs: settings.aovs,
+                ..RenderMetadata::new(&scene, width, height, settings.samples)
+            };
+            let start = Instant::now();
+            let mut aovs = None;
+            let image = if settings.sweep.is_empty() {
+                let (samples, max_depth) = (settings.samples, settings.max_depth);
+                let mut image = if settings.aovs {
+                    let (image, passes) =
+                        render_image_aovs(&scene, width, height, samples, max_depth);
+                    aovs = Some(passes);
+                    image
+                } else {
+                    render_image(&scene, width, height, samples, max_depth)
+                };
+                if settings.denoise {
+                    denoise(&mut image, &scene, &scene.cubes_at(0.0));
+                }
+                apply_post(&scene.post, &mut image);
+                image
+            } else {
+                let sheet_settings = ContactSheetSettings {
+                    axes: settings.sweep,
+                    cell_width: width,
+                    cell_height: height,
+                    samples: settings.samples,
+                    max_depth: settings.max_depth,
+                };
+                match render_contact_sheet(&mut scene, &sheet_settings) {
+                    Ok(sheet) => sheet,
+                    Err(err) => {
+                        eprintln!("No se pudo generar la hoja de contactos: {}", err);
+                        std::process::exit(2);
+                    }
+                }
+            };
+            metadata.render_seconds = start.elapsed().as_secs_f32();
+            match save_render(&image, aovs.as_ref(), output, &metadata) {
+                Ok(()) => println!("Imagen guardada en {}", output.display()),
+                Err(err) => {
+                    eprintln!("No se pudo guardar la imagen: {}", err);
+                    std::process::exit(1);
+                }
+            }
+        }
+        Command::Obj(output) => match export_obj(&scene, &output) {
+            Ok(()) => println!("Modelo guardado en {}", output.display()),
+            Err(err) => {
+                eprintln!("No se pudo exportar el modelo: {}", err);
+                std::process::exit(1);
+            }
+        },
+        Command::Parity(settings) => {
+            if let Some(time_of_day) = settings.time_of_day {
+                scene.apply_day_cycle(time_of_day);
+            }
+            let reports = check_parity(
+                &mut scene,
+                settings.width,
+                settings.height,
+                settings.samples,
+                settings.tolerance,
+            );
+            println!(
+                "{:<10} {:>10} {:>10} {:>8} {:>11} {:>8}",
+                "camino", "error máx", "error medio", "pixeles", "peor pixel", "tiempo"
+            );
+            for report in &reports {
+                println!(
+                    "{:<10} {:>10.6} {:>10.6} {:>8} {:>11} {:>7.2}s",
+                    report.backend.name(),
+                    report.max_error,
+                    report.mean_error,
+                    report.differing_pixels,
+                    format!("{},{}", report.worst_pixel.0, report.worst_pixel.1),
+                    report.render_seconds,
                 );
-
-                *pixel = pixel_color;
             }
-        });
+            let failed: Vec<&str> = reports
+                .iter()
+                .filter(|report| !report.passes(settings.tolerance))
+                .map(|report| report.backend.name())
+                .collect();
+            if !failed.is_empty() {
+                eprintln!(
+                    "Superan la tolerancia de {}: {}",
+                    settings.tolerance,
+                    failed.join(", ")
+                );
+                std::process::exit(1);
+            }
+        }
+        Command::Interactive => {
+            let keys = match &options.keys {
+                Some(path) => KeyMap::load(path),
+                None => KeyMap::load_or_default(Path::new(DEFAULT_KEYMAP)),
+            };
+            let keys = match keys {
+                Ok(keys) => keys,
+                Err(err) => {
+                    eprintln!("Error en las teclas: {}", err);
+                    std::process::exit(1);
+                }
+            };
+            let mut hooks = Hooks::new();
+            if let Some(camera_path) = camera_path {
+                follow_camera_path(&mut hooks, camera_path, keys.key(Action::PausePath));
+            }
+            let mut settings = match options.gallery {
+                Some(showcase) => InteractiveSettings::gallery(showcase),
+                None if options.demo => InteractiveSettings::demo(),
+                None => InteractiveSettings::new(&options.world),
+            };
+            // --demo con --gallery: la escena elegida con el reloj fijo del demo
+            if options.demo {
+                settings.clock = InteractiveSettings::demo().clock;
+            }
+            if let Some(fps) = options.max_fps {
+                settings.target_fps = (fps > 0.0).then_some(fps);
+            }
+            if let Some(resolution) = options.resolution {
+                settings.resolution = resolution;
+            }
+            if let Some(display) = options.display {
+                settings.display = display;
+            }
+            if let Some(screen_size) = options.screen_size {
+                settings.screen_size = screen_size;
+            }
+            settings.scene_path = options.scene.clone();
+            settings.keys = keys;
+            run_interactive(scene, &settings, &mut hooks);
+        }
+    }
 }
 
+// La cámara sigue el recorrido mientras se reproduce; `pause` (P) lo pausa para moverla
+// a mano
+fn follow_camera_path(hooks: &mut Hooks, camera_path: CameraPath, pause: Key) {
+    let playing = Rc::new(Cell::new(true));
+    let toggle = Rc::clone(&playing);
+    hooks.on_key(move |key, _, _| {
+        if key == pause {
+            toggle.set(!toggle.get());
+        }
+    });
 
-
-fn main() {
-  let window_width = 800;
-  let window_height = 600;
-  let framebuffer_width = 600;
-  let framebuffer_height = 400;
-  let frame_delay = Duration::from_millis(16);
-  let mut last_frame = Instant::now();
-  let mut time_of_day = 0.0;
-  let day_duration = 60.0;
-
-  let mut framebuffer = Framebuffer::new(framebuffer_width, framebuffer_height);
-
-  let mut window = Window::new(
-      "Minecraft",
-      window_width,
-      window_height,
-      WindowOptions::default(),
-  )
-  .unwrap();
-
-  let stone_texture = open("./src/textures/old-cobblestone-texture.png")
-      .unwrap()
-      .to_rgba8();
-
-  let grass_texture = open("./src/textures/grass.png").unwrap().to_rgba8();
-  let wood_texture = open("./src/textures/wood.png").unwrap().to_rgba8();
-  let glowstone_texture = open("./src/textures/glowstone.png").unwrap().to_rgba8();
-
-  let skybox = Skybox::new(
-      open("./src/textures/sky.jpg").unwrap().to_rgba8(),
-      open("./src/textures/sky.jpg").unwrap().to_rgba8(),
-      open("./src/textures/sky.jpg").unwrap().to_rgba8(),
-      open("./src/textures/sky.jpg").unwrap().to_rgba8(),
-      open("./src/textures/sky.jpg").unwrap().to_rgba8(),
-      open("./src/textures/sky.jpg").unwrap().to_rgba8(),
-  );
-
-  let stone = Material::new(
-    Color::from_u8(90, 90, 90),
-    10.0,
-    [0.6, 0.1, 0.1, 0.0], 
-    1.0,
-    Some(stone_texture),
-    None,
-    Color::black(),
-);
-
-// Material de Césped
-let grass = Material::new(
-    Color::from_u8(100, 200, 100),
-    10.0,
-    [0.6, 0.1, 0.1, 0.0], 
-    1.0,
-    Some(grass_texture),
-    None,
-    Color::black(),
-);
-
-
-  let water_textures = vec![
-      open("./src/textures/water1.png").unwrap().to_rgba8(),
-      open("./src/textures/water2.png").unwrap().to_rgba8(),
-  ];
-
-  let water = Material::new(
-    Color::from_u8(50, 50, 200),
-    50.0,
-    [0.1, 0.7, 0.4, 0.7], 
-    1.33,
-    Some(water_textures[0].clone()),
-    None,
-    Color::black(),
-);
-
-let wood = Material::new(
-  Color::from_u8(139, 69, 19),
-  5.0,
-  [0.6, 0.3, 0.1, 0.0], 
-  1.0,
-  Some(wood_texture),
-  None,
-  Color::black(),
-);
-
-let glowstone = Material::new(
-  Color::from_u8(255, 223, 128),
-  10.0,
-  [0.7, 0.3, 0.0, 0.0], 
-  1.0,
-  Some(glowstone_texture),
-  None,
-  Color::from_u8(255, 223, 128),
-);
-
-
-
-  let mut objects = Vec::new();
-
-
-  let water_positions = [(1, 2), (2, 2), (3, 2)];
-
-  for x in 0..5 {
-      for z in 0..5 {
-          if water_positions.contains(&(x, z)) {
-              objects.push(Cube {
-                  min_corner: Vec3::new(x as f32, -1.0, z as f32),
-                  max_corner: Vec3::new(x as f32 + 1.0, 0.0, z as f32 + 1.0),
-                  material: water.clone(),
-              });
-          } else {
-              // Añadir bloque de césped
-              objects.push(Cube {
-                  min_corner: Vec3::new(x as f32, -1.0, z as f32),
-                  max_corner: Vec3::new(x as f32 + 1.0, 0.0, z as f32 + 1.0),
-                  material: grass.clone(),
-              });
-          }
-      }
-  }
-
-  for y in 0..=3 {
-      objects.push(Cube {
-          min_corner: Vec3::new(0.0, y as f32, 0.0),
-          max_corner: Vec3::new(1.0, y as f32 + 1.0, 1.0),
-          material: wood.clone(),
-      });
-  }
-
-  objects.push(Cube {
-      min_corner: Vec3::new(0.0, 0.0, 4.0),
-      max_corner: Vec3::new(1.0, 1.0, 5.0),
-      material: glowstone.clone(),
-  });
-  objects.push(Cube {
-      min_corner: Vec3::new(4.0, 0.0, 0.0),
-      max_corner: Vec3::new(5.0, 1.0, 1.0),
-      material: glowstone.clone(),
-  });
-
-
-  for x in 1..=3 {
-      for y in 0..=2 {
-          if !(x == 2 && y == 1) {
-              objects.push(Cube {
-                  min_corner: Vec3::new(x as f32, y as f32, 4.0),
-                  max_corner: Vec3::new(x as f32 + 1.0, y as f32 + 1.0, 5.0),
-                  material: stone.clone(),
-              });
-          }
-      }
-  }
-
-  let mut camera = Camera::new(
-      Vec3::new(2.5, 2.0, 10.0), 
-      Vec3::new(2.5, 0.0, 2.5),
-      Vec3::new(0.0, 1.0, 0.0),
-  );
-
-  let mut lights = vec![Light::new(
-      Vec3::new(0.0, 10.0, 5.0),
-      Color::from_u8(255, 255, 255),
-      1.0,
-  )];
-
-  let rotation_speed = PI / 16.0;
-
-  while window.is_open() && !window.is_key_down(Key::Escape) {
-      let current_frame = Instant::now();
-      let delta_time = current_frame.duration_since(last_frame).as_secs_f32();
-      last_frame = current_frame;
-
-      time_of_day += delta_time;
-      if time_of_day > day_duration {
-          time_of_day -= day_duration;
-      }
-
-      let day_progress = time_of_day / day_duration;
-      let sun_angle = day_progress * 2.0 * PI;
-
-      let sun_position = Vec3::new(10.0 * sun_angle.cos(), 10.0 * sun_angle.sin(), 0.0);
-      lights[0].position = sun_position;
-
-      let (intensity, color) = if day_progress < 0.25 {
-          let factor = day_progress / 0.25;
-          (
-              0.5 + 0.5 * factor,
-              Color::from_u8(255, 183, 76) * factor
-                  + Color::from_u8(50, 50, 100) * (1.0 - factor),
-          )
-      } else if day_progress < 0.5 {
-          (1.0, Color::from_u8(255, 255, 255))
-      } else if day_progress < 0.75 {
-          let factor = (day_progress - 0.5) / 0.25;
-          (
-              1.0 - 0.5 * factor,
-              Color::from_u8(255, 183, 76) * (1.0 - factor)
-                  + Color::from_u8(50, 50, 100) * factor,
-          )
-      } else {
-
-          (0.5, Color::from_u8(50, 50, 100))
-      };
-      lights[0].intensity = intensity;
-      lights[0].color = color;
-
-      window.set_title(&format!("Minecraft - FPS: {:.2}", 1.0 / delta_time));
-
-      if let Some(scroll) = window.get_scroll_wheel() {
-          if scroll.1 > 0.0 {
-              camera.move_towards_target(0.2 * scroll.1);
-          } else if scroll.1 < 0.0 {
-              camera.move_away_from_target(-0.2 * scroll.1);
-          }
-      }
-
-      if window.is_key_down(Key::A) {
-          camera.rotate_around_target(rotation_speed, 0.0);
-      }
-
-      if window.is_key_down(Key::D) {
-          camera.rotate_around_target(-rotation_speed, 0.0);
-      }
-
-      if window.is_key_down(Key::W) {
-          camera.rotate_around_target(0.0, -rotation_speed);
-      }
-
-      if window.is_key_down(Key::S) {
-          camera.rotate_around_target(0.0, rotation_speed);
-      }
-
-
-      render(&mut framebuffer, &objects, &camera, &lights, &skybox);
-
-      window
-          .update_with_buffer(
-              &framebuffer
-                  .buffer
-                  .iter()
-                  .map(|c| c.to_u32())
-                  .collect::<Vec<u32>>(),
-              framebuffer_width,
-              framebuffer_height,
-          )
-          .unwrap();
-
-      std::thread::sleep(frame_delay);
-  }
+    let mut path_time = 0.0;
+    hooks.on_pre_render(move |scene, frame| {
+        if playing.get() {
+            path_time += frame.delta_time;
+            camera_path.apply(&mut scene.camera, path_time);
+        }
+    });
 }
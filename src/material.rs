@@ -1,15 +1,59 @@
 use crate::color::Color;
-use image::RgbaImage;
+use crate::assets::{sample_texture, ImageTexture, Texture, TextureAnimation, TextureFilter};
+use crate::light::LightAnimation;
+
+pub const DEFAULT_ALPHA_CUTOFF: f32 = 0.5;
+// Rugosidad de un material que pasa al modelo PBR sin indicarla
+pub const DEFAULT_ROUGHNESS: f32 = 0.5;
 
 #[derive(Debug, Clone)]
 pub struct Material {
+    // Color difuso, o color base en el modelo PBR
     pub diffuse: Color,
     pub specular: f32,
     pub albedo: [f32; 4],
+    pub model: ShadingModel,
+    // Rugosidad de los reflejos y refracciones del modelo clásico: 0 es un espejo y con
+    // más los rayos se abren en un lóbulo y la imagen reflejada sale borrosa
+    pub blur: f32,
     pub refractive_index: f32,
-    pub texture: Option<RgbaImage>,
-    pub normal_map: Option<RgbaImage>, 
+    // Fracción de cada canal que absorbe el interior por cada bloque que recorre un rayo
+    // refractado (Beer-Lambert): oscurece el agua profunda y tiñe el vidrio grueso
+    pub absorption: Color,
+    pub texture: Option<Texture>,
+    // Cuadros de una textura animada; `Scene::animate_textures` pone en `texture` el que toca
+    pub texture_animation: Option<TextureAnimation>,
+    pub normal_map: Option<ImageTexture>, 
     pub emission: Color,               
+    // Pulso o parpadeo del brillo de `emission`, como las animaciones de las luces; cada
+    // bloque va desfasado del resto
+    pub emission_animation: Option<LightAnimation>,
+    // Máscaras en escala de grises que se leen con el filtro de la textura: la de emisión
+    // multiplica `emission` (solo brillan las partes claras) y la especular apaga el brillo y
+    // los reflejos donde es oscura (en el modelo PBR la superficie se vuelve rugosa)
+    pub emission_map: Option<ImageTexture>,
+    pub specular_map: Option<ImageTexture>,
+    // Filtrado de cada textura; por defecto el texel más cercano
+    pub texture_filter: TextureFilter,
+    pub normal_map_filter: TextureFilter,
+    pub transparency: TransparencyMode,
+    // Los texels de la textura con alfa menor a este valor no existen: los rayos (también
+    // los de sombra) pasan a través, como en hojas o rejas. 0 los deja todos opacos.
+    pub alpha_cutoff: f32,
+    // Vidrio de color: en lugar de tapar la luz, su sombra la deja pasar teñida con `diffuse`
+    // y atenuada según la transparencia (albedo[3])
+    pub stained: bool,
+    // Rebotes hasta los que se siguen los rayos reflejados y refractados en este material;
+    // sin valor, los de la calidad del render (más para los transparentes)
+    pub max_depth: Option<u32>,
+    // Rutas de origen de las texturas, para poder guardar la escena
+    pub texture_path: Option<String>,
+    pub normal_map_path: Option<String>,
+    pub emission_map_path: Option<String>,
+    pub specular_map_path: Option<String>,
+    // Resource pack del que salió la textura (entonces no tiene ruta propia); al cargar lo
+    // guardado se vuelve a leer de ahí
+    pub texture_pack: Option<String>,
 }
 
 impl Material {
@@ -18,30 +62,173 @@ impl Material {
         specular: f32,
         albedo: [f32; 4],
         refractive_index: f32,
-        texture: Option<RgbaImage>,
-        normal_map: Option<RgbaImage>, 
+        texture: Option<Texture>,
+        normal_map: Option<ImageTexture>, 
         emission: Color,               
     ) -> Self {
         Material {
             diffuse,
             specular,
             albedo,
+            model: ShadingModel::Legacy,
+            blur: 0.0,
             refractive_index,
+            absorption: Color::black(),
             texture,
+            texture_animation: None,
             normal_map,
             emission,
+            emission_animation: None,
+            emission_map: None,
+            specular_map: None,
+            texture_filter: TextureFilter::Nearest,
+            normal_map_filter: TextureFilter::Nearest,
+            transparency: TransparencyMode::Refract,
+            alpha_cutoff: DEFAULT_ALPHA_CUTOFF,
+            stained: false,
+            max_depth: None,
+            texture_path: None,
+            normal_map_path: None,
+            emission_map_path: None,
+            specular_map_path: None,
+            texture_pack: None,
         }
     }
 
-    pub fn black() -> Self {
+    pub const fn black() -> Self {
         Material {
             diffuse: Color::black(),
             specular: 0.0,
             albedo: [0.0, 0.0, 0.0, 0.0],
+            model: ShadingModel::Legacy,
+            blur: 0.0,
             refractive_index: 1.0,
+            absorption: Color::black(),
             texture: None,
+            texture_animation: None,
             normal_map: None,
             emission: Color::black(),
+            emission_animation: None,
+            emission_map: None,
+            specular_map: None,
+            texture_filter: TextureFilter::Nearest,
+            normal_map_filter: TextureFilter::Nearest,
+            transparency: TransparencyMode::Refract,
+            alpha_cutoff: DEFAULT_ALPHA_CUTOFF,
+            stained: false,
+            max_depth: None,
+            texture_path: None,
+            normal_map_path: None,
+            emission_map_path: None,
+            specular_map_path: None,
+            texture_pack: None,
+        }
+    }
+
+    // Pasan el material al modelo PBR; el otro parámetro se conserva si ya lo usaba
+    pub fn set_metallic(&mut self, metallic: f32) {
+        let (_, roughness) = self.model.pbr_parameters();
+        self.model = ShadingModel::Pbr { metallic, roughness };
+    }
+
+    pub fn set_roughness(&mut self, roughness: f32) {
+        let (metallic, _) = self.model.pbr_parameters();
+        self.model = ShadingModel::Pbr { metallic, roughness };
+    }
+
+    // Fracción de la luz que atraviesa la superficie; los materiales PBR son opacos
+    pub fn transmission(&self) -> f32 {
+        match self.model {
+            ShadingModel::Legacy => self.albedo[3],
+            ShadingModel::Pbr { .. } => 0.0,
+        }
+    }
+
+    // Si lo que hay detrás nunca se ve: ni transparencia ni texels que se puedan atravesar
+    pub fn is_opaque(&self) -> bool {
+        self.transmission() <= 0.0 && !self.has_cut_outs()
+    }
+
+    // Si la textura tiene texels que se atraviesan, como las hojas
+    pub fn has_cut_outs(&self) -> bool {
+        matches!(
+            &self.texture,
+            Some(Texture::Image(image)) if self.alpha_cutoff > 0.0 && image.has_alpha()
+        )
+    }
+
+    // Cuánto se desvían los rayos reflejados y refractados; los materiales PBR usan su
+    // rugosidad, que aumenta donde el mapa especular (`shine`) es oscuro
+    pub fn glossy_roughness(&self, shine: f32) -> f32 {
+        match self.model {
+            ShadingModel::Legacy => self.blur,
+            ShadingModel::Pbr { roughness, .. } => dulled_roughness(roughness, shine),
+        }
+    }
+
+    // Si el rayo atraviesa la superficie en (u, v) porque el texel es transparente. Las
+    // imágenes sin transparencia no se leen.
+    pub fn is_cut_out(&self, uv: (f32, f32)) -> bool {
+        match &self.texture {
+            Some(Texture::Image(image)) if self.alpha_cutoff > 0.0 && image.has_alpha() => {
+                let (u, v) = uv;
+                sample_texture(image, u, v, self.texture_filter)[3] < self.alpha_cutoff
+            }
+            _ => false,
+        }
+    }
+}
+
+// Rugosidad PBR en un punto con brillo `shine`: con 1 la del material y con 0 totalmente rugosa
+pub fn dulled_roughness(roughness: f32, shine: f32) -> f32 {
+    roughness + (1.0 - roughness) * (1.0 - shine)
+}
+
+// Cómo se calcula la luz que refleja un material
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ShadingModel {
+    // Phong con `specular` como exponente y `albedo` como pesos de difusa, especular,
+    // reflejo y transparencia
+    Legacy,
+    // Metálico/rugosidad: difusa de Lambert y especular GGX sobre el color base, con la
+    // reflectancia de `refractive_index` en los dieléctricos; `specular` y `albedo` no se usan
+    Pbr { metallic: f32, roughness: f32 },
+}
+
+impl ShadingModel {
+    // (metallic, roughness); el modelo clásico equivale a un dieléctrico de rugosidad media
+    pub fn pbr_parameters(&self) -> (f32, f32) {
+        match *self {
+            ShadingModel::Legacy => (0.0, DEFAULT_ROUGHNESS),
+            ShadingModel::Pbr { metallic, roughness } => (metallic, roughness),
+        }
+    }
+}
+
+// Cómo se ve a través de un material transparente (albedo[3] > 0)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TransparencyMode {
+    // Rayo refractado con el índice del material: exacto pero cuesta un rayo por capa
+    #[default]
+    Refract,
+    // En la ventana interactiva las capas se mezclan en línea recta, pesadas por distancia
+    // (weighted blended OIT). Mucho más rápido con mucho vidrio; no desvía la imagen.
+    Blended,
+}
+
+impl TransparencyMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TransparencyMode::Refract => "refract",
+            TransparencyMode::Blended => "blended",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "refract" => Some(TransparencyMode::Refract),
+            "blended" => Some(TransparencyMode::Blended),
+            _ => None,
         }
     }
 }
@@ -0,0 +1,147 @@
+// material_library.rs
+
+use std::sync::OnceLock;
+
+use crate::assets::{load_texture, Texture};
+use crate::chunk::BlockId;
+use crate::color::Color;
+use crate::material::Material;
+use crate::world::World;
+
+// Materiales registrados por nombre. Los integrados arman el diorama y el terreno, y las
+// escenas y el editor los usan sin tener que definirlos.
+pub struct MaterialLibrary {
+    names: Vec<String>,
+    materials: Vec<Material>,
+}
+
+impl MaterialLibrary {
+    pub fn new() -> Self {
+        MaterialLibrary {
+            names: Vec::new(),
+            materials: Vec::new(),
+        }
+    }
+
+    // Los materiales del diorama; las texturas se cargan la primera vez que se pide
+    pub fn builtin() -> &'static MaterialLibrary {
+        static BUILTIN: OnceLock<MaterialLibrary> = OnceLock::new();
+        BUILTIN.get_or_init(|| {
+            let mut library = MaterialLibrary::new();
+            library.register(
+                "piedra",
+                textured(
+                    Color::from_u8(90, 90, 90),
+                    10.0,
+                    [0.6, 0.1, 0.1, 0.0],
+                    "./src/textures/old-cobblestone-texture.png",
+                ),
+            );
+            library.register(
+                "cesped",
+                textured(
+                    Color::from_u8(100, 200, 100),
+                    10.0,
+                    [0.6, 0.1, 0.1, 0.0],
+                    "./src/textures/grass.png",
+                ),
+            );
+
+            let mut water = textured(
+                Color::from_u8(50, 50, 200),
+                50.0,
+                [0.1, 0.7, 0.4, 0.7],
+                "./src/textures/water1.png",
+            );
+            water.refractive_index = 1.33;
+            water.blur = 0.08;
+            water.absorption = Color::from_u8(90, 35, 15);
+            library.register("agua", water);
+
+            library.register(
+                "madera",
+                textured(
+                    Color::from_u8(139, 69, 19),
+                    5.0,
+                    [0.6, 0.3, 0.1, 0.0],
+                    "./src/textures/wood.png",
+                ),
+            );
+
+            let mut glowstone = textured(
+                Color::from_u8(255, 223, 128),
+                10.0,
+                [0.7, 0.3, 0.0, 0.0],
+                "./src/textures/glowstone.png",
+            );
+            glowstone.emission = Color::from_u8(255, 223, 128);
+            library.register("glowstone", glowstone);
+
+            library.register(
+                "tierra",
+                Material::new(
+                    Color::from_u8(121, 85, 58),
+                    5.0,
+                    [0.7, 0.1, 0.0, 0.0],
+                    1.0,
+                    None,
+                    None,
+                    Color::black(),
+                ),
+            );
+            library
+        })
+    }
+
+    // Registra un material; si el nombre ya existe lo reemplaza
+    pub fn register(&mut self, name: &str, material: Material) {
+        match self.names.iter().position(|candidate| candidate == name) {
+            Some(index) => self.materials[index] = material,
+            None => {
+                self.names.push(name.to_string());
+                self.materials.push(material);
+            }
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Material> {
+        self.names
+            .iter()
+            .position(|candidate| candidate == name)
+            .map(|index| &self.materials[index])
+    }
+
+    // Nombres en el orden en que se registraron
+    pub fn names(&self) -> impl Iterator<Item = &str> + '_ {
+        self.names.iter().map(String::as_str)
+    }
+
+    // Id del material `name` en la paleta del mundo. Si el mundo no lo tiene y la biblioteca
+    // sí, se agrega una copia; un material del mismo nombre definido en el mundo tiene prioridad.
+    pub fn block(&self, world: &mut World, name: &str) -> Option<BlockId> {
+        world.find_material(name).or_else(|| {
+            self.get(name)
+                .map(|material| world.add_material(name, material.clone()))
+        })
+    }
+}
+
+impl Default for MaterialLibrary {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn textured(diffuse: Color, specular: f32, albedo: [f32; 4], path: &str) -> Material {
+    let mut material = Material::new(
+        diffuse,
+        specular,
+        albedo,
+        1.0,
+        Some(Texture::Image(load_texture(path))),
+        None,
+        Color::black(),
+    );
+    material.texture_path = Some(path.to_string());
+    material
+}
@@ -0,0 +1,324 @@
+// mesh.rs
+
+use nalgebra_glm::Vec3;
+
+use crate::chunk::{BlockId, Chunk, AIR, CHUNK_SIZE};
+use crate::world::{BlockPos, World};
+
+// Una cara de bloque que se ve, o un rectángulo de caras vecinas del mismo material unidas
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quad {
+    // Eje de la normal (0 es x, 1 es y, 2 es z) y hacia dónde apunta, 1 o -1
+    pub axis: usize,
+    pub side: i32,
+    // Coordenada del plano de la cara en ese eje
+    pub plane: i32,
+    // Esquinas en los otros dos ejes, en el orden (axis + 1, axis + 2) módulo 3; `max` es
+    // exclusivo, como en los bloques
+    pub min: (i32, i32),
+    pub max: (i32, i32),
+    pub block: BlockId,
+}
+
+impl Quad {
+    // Las cuatro esquinas en el mundo, en sentido antihorario vistas desde afuera
+    pub fn corners(&self) -> [Vec3; 4] {
+        let (u_axis, v_axis) = ((self.axis + 1) % 3, (self.axis + 2) % 3);
+        let corner = |u: i32, v: i32| {
+            let mut point = Vec3::zeros();
+            point[self.axis] = self.plane as f32;
+            point[u_axis] = u as f32;
+            point[v_axis] = v as f32;
+            point
+        };
+        let (min, max) = (self.min, self.max);
+        let corners = [
+            corner(min.0, min.1),
+            corner(max.0, min.1),
+            corner(max.0, max.1),
+            corner(min.0, max.1),
+        ];
+        // (u, v, normal) es una base directa: con la normal hacia el lado negativo el mismo
+        // recorrido se ve al revés
+        if self.side > 0 {
+            corners
+        } else {
+            [corners[0], corners[3], corners[2], corners[1]]
+        }
+    }
+
+    // Distancia a la que el rayo cruza el quad de frente; None si no lo cruza, si lo ve de
+    // espaldas o si el quad queda detrás del origen
+    pub fn ray_distance(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Option<f32> {
+        if !self.faces(ray_direction) {
+            return None;
+        }
+        let distance = (self.plane as f32 - ray_origin[self.axis]) / ray_direction[self.axis];
+        let covered = distance >= 0.0
+            && self.covers(ray_cells(ray_origin, ray_direction, self.axis, distance));
+        covered.then_some(distance)
+    }
+
+    // Si un rayo con esta dirección ve la cara de frente
+    pub fn faces(&self, ray_direction: &Vec3) -> bool {
+        ray_direction[self.axis] * (self.side as f32) < 0.0
+    }
+
+    // Si el rectángulo cubre la celda (u, v) de su plano, la de `ray_cells`
+    pub fn covers(&self, (u, v): (i32, i32)) -> bool {
+        (self.min.0..self.max.0).contains(&u) && (self.min.1..self.max.1).contains(&v)
+    }
+
+    // Bloque al que pertenece la cara en la celda (u, v) de su plano, la de `ray_cells`
+    pub fn block_at(&self, (u, v): (i32, i32)) -> BlockPos {
+        let (u_axis, v_axis) = ((self.axis + 1) % 3, (self.axis + 2) % 3);
+        let mut pos = [0; 3];
+        pos[self.axis] = if self.side > 0 {
+            self.plane - 1
+        } else {
+            self.plane
+        };
+        pos[u_axis] = u;
+        pos[v_axis] = v;
+        (pos[0], pos[1], pos[2])
+    }
+}
+
+// Planos de un chunk en cada eje: uno más que bloques, por las caras del borde
+const PLANES: usize = CHUNK_SIZE + 1;
+
+// Quads de un chunk agrupados por eje, plano y sentido de la normal, para que un rayo pruebe
+// solo los que ve de frente en los planos que cruza dentro del chunk
+#[derive(Debug)]
+pub struct ChunkMesh {
+    origin: [i32; 3],
+    quads: Vec<Quad>,
+    // Para cada eje, dónde empiezan en `quads` los de cada plano del chunk, primero los que
+    // miran hacia atrás y después los que miran hacia adelante, y al final dónde terminan
+    starts: [[u32; 2 * PLANES + 1]; 3],
+    // Para cada eje, plano y sentido, las caras cubiertas por algún quad: una fila de bits
+    // por cada v y un bit por cada u, para descartar un plano sin recorrer sus quads
+    covered: [[[u16; CHUNK_SIZE]; 2 * PLANES]; 3],
+    // Para cada eje y sentido, los planos con algún quad, un bit por plano
+    layers: [[u32; 2]; 3],
+}
+
+// Cada fila de caras cubiertas entra en un u16 y los planos de un eje en un u32
+const _: () = assert!(CHUNK_SIZE <= u16::BITS as usize && PLANES <= u32::BITS as usize);
+
+impl ChunkMesh {
+    pub fn build(world: &World, chunk_pos: BlockPos) -> Self {
+        let mut quads = Vec::new();
+        if let Some(chunk) = world.chunk(chunk_pos) {
+            mesh_chunk(world, chunk_pos, &chunk, &mut quads);
+        }
+        let origin = [chunk_pos.0, chunk_pos.1, chunk_pos.2]
+            .map(|coordinate| coordinate * CHUNK_SIZE as i32);
+        let group = |quad: &Quad| {
+            let layer = (quad.plane - origin[quad.axis]) as usize;
+            (quad.axis, 2 * layer + (quad.side > 0) as usize)
+        };
+        quads.sort_by_key(group);
+
+        let mut starts = [[0; 2 * PLANES + 1]; 3];
+        for (axis, starts) in starts.iter_mut().enumerate() {
+            for (index, start) in starts.iter_mut().enumerate() {
+                *start = quads.partition_point(|quad| group(quad) < (axis, index)) as u32;
+            }
+        }
+        let mut covered = [[[0; CHUNK_SIZE]; 2 * PLANES]; 3];
+        let mut layers = [[0; 2]; 3];
+        for quad in &quads {
+            let (axis, index) = group(quad);
+            layers[axis][index % 2] |= 1 << (index / 2);
+            let (u_axis, v_axis) = ((axis + 1) % 3, (axis + 2) % 3);
+            let (u0, u1) = (quad.min.0 - origin[u_axis], quad.max.0 - origin[u_axis]);
+            let (v0, v1) = (quad.min.1 - origin[v_axis], quad.max.1 - origin[v_axis]);
+            let row = ((1u32 << u1) - (1u32 << u0)) as u16;
+            for v in v0..v1 {
+                covered[axis][index][v as usize] |= row;
+            }
+        }
+        ChunkMesh {
+            origin,
+            quads,
+            starts,
+            covered,
+            layers,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.quads.is_empty()
+    }
+
+    // Planos del chunk con algún quad con normal en `axis` hacia `side`, un bit por plano
+    pub fn layers(&self, axis: usize, side: i32) -> u32 {
+        self.layers[axis][(side > 0) as usize]
+    }
+
+    // Quad del plano `layer` (0 a CHUNK_SIZE) del chunk con normal en `axis` hacia `side`
+    // que cubre la celda (u, v) de ese plano, en coordenadas de mundo
+    pub fn quad_at(
+        &self,
+        (axis, layer, side): (usize, usize, i32),
+        cells: (i32, i32),
+    ) -> Option<&Quad> {
+        let (u_axis, v_axis) = ((axis + 1) % 3, (axis + 2) % 3);
+        let u = cells.0 - self.origin[u_axis];
+        let v = cells.1 - self.origin[v_axis];
+        let size = CHUNK_SIZE as i32;
+        if !(0..size).contains(&u) || !(0..size).contains(&v) {
+            return None;
+        }
+        let index = 2 * layer + (side > 0) as usize;
+        if self.covered[axis][index][v as usize] & (1 << u) == 0 {
+            return None;
+        }
+        let starts = &self.starts[axis];
+        self.quads[starts[index] as usize..starts[index + 1] as usize]
+            .iter()
+            .find(|quad| quad.covers(cells))
+    }
+}
+
+// Distancia desde la que un punto se considera sobre el borde entre dos celdas, muy por
+// encima del error de redondear el punto en un mundo de coordenadas moderadas
+const EDGE: f32 = 1e-3;
+
+// Celdas en los otros dos ejes, en el orden (axis + 1, axis + 2) módulo 3, donde el rayo
+// cruza un plano de `axis` a la distancia `distance`. Un punto sobre el borde entre dos
+// celdas no se decide por sus coordenadas, que el redondeo puede correr a cualquier lado,
+// sino por si el rayo ya cruzó el plano de ese borde, con la misma cuenta que al recorrer
+// los planos y a igual distancia primero el eje más alto: la arista queda para la cara del
+// eje más bajo, la que elige `intersect_box` con las slabs. Así dos quads vecinos no
+// comparten ningún punto, el rayo que pasa justo por la arista de una esquina choca con una
+// de las dos caras y uno paralelo a un eje que va por el límite entre dos chunks solo toca
+// los del chunk de adelante, el que recorre.
+pub fn ray_cells(
+    ray_origin: &Vec3,
+    ray_direction: &Vec3,
+    axis: usize,
+    distance: f32,
+) -> (i32, i32) {
+    let cell = |other: usize| {
+        let x = ray_origin[other] + ray_direction[other] * distance;
+        let direction = ray_direction[other];
+        let edge = floor(x + 0.5);
+        if direction == 0.0 || (x - edge as f32).abs() > EDGE {
+            return floor(x);
+        }
+        let edge_distance = (edge as f32 - ray_origin[other]) / direction;
+        let crossed = edge_distance < distance || (edge_distance == distance && other > axis);
+        // Del lado hacia el que avanza si ya lo cruzó y del otro si todavía no
+        edge - (crossed != (direction > 0.0)) as i32
+    };
+    (cell((axis + 1) % 3), cell((axis + 2) % 3))
+}
+
+// Redondeo hacia abajo sin `f32::floor`, que sin SSE4.1 es una llamada a libm y se hace en
+// cada plano que cruza un rayo
+fn floor(x: f32) -> i32 {
+    let truncated = x as i32;
+    truncated - (x < truncated as f32) as i32
+}
+
+// Si la cara de `block` que da a `neighbor` queda tapada: el vecino es opaco, o es el mismo
+// material transparente (entre dos bloques de agua no hay superficie). Entre dos bloques con
+// texels que se atraviesan, como las hojas, las caras se conservan para ver las de adentro.
+pub fn hides_face(world: &World, block: BlockId, neighbor: BlockId) -> bool {
+    if neighbor == AIR {
+        return false;
+    }
+    let material = world.material(neighbor);
+    material.is_opaque() || (neighbor == block && !material.has_cut_outs())
+}
+
+// Caras visibles del mundo unidas en rectángulos (greedy meshing), chunk por chunk. En cada
+// capa de cada eje y sentido se arma la máscara de las caras que se ven con su material y se
+// recorre juntando cada cara con las vecinas iguales, primero a lo largo de la fila y
+// después hacia las filas siguientes mientras la fila entera siga igual.
+pub fn greedy_mesh(world: &World) -> Vec<Quad> {
+    let mut quads = Vec::new();
+    for (chunk_pos, chunk) in world.chunks() {
+        mesh_chunk(world, chunk_pos, &chunk, &mut quads);
+    }
+    quads
+}
+
+fn mesh_chunk(world: &World, chunk_pos: BlockPos, chunk: &Chunk, quads: &mut Vec<Quad>) {
+    let size = CHUNK_SIZE as i32;
+    let origin = [chunk_pos.0 * size, chunk_pos.1 * size, chunk_pos.2 * size];
+    // El vecino de una cara, que puede estar en el chunk de al lado
+    let neighbor = |mut local: [i32; 3], axis: usize, side: i32| {
+        local[axis] += side;
+        if (0..size).contains(&local[axis]) {
+            chunk.get(local[0] as usize, local[1] as usize, local[2] as usize)
+        } else {
+            world.get_block((
+                origin[0] + local[0],
+                origin[1] + local[1],
+                origin[2] + local[2],
+            ))
+        }
+    };
+
+    let mut mask = [AIR; CHUNK_SIZE * CHUNK_SIZE];
+    for axis in 0..3 {
+        let (u_axis, v_axis) = ((axis + 1) % 3, (axis + 2) % 3);
+        for side in [-1, 1] {
+            for layer in 0..size {
+                for v in 0..size {
+                    for u in 0..size {
+                        let mut local = [0; 3];
+                        local[axis] = layer;
+                        local[u_axis] = u;
+                        local[v_axis] = v;
+                        let block =
+                            chunk.get(local[0] as usize, local[1] as usize, local[2] as usize);
+                        let hidden =
+                            block == AIR || hides_face(world, block, neighbor(local, axis, side));
+                        mask[(v * size + u) as usize] = if hidden { AIR } else { block };
+                    }
+                }
+
+                let plane = origin[axis] + layer + i32::from(side > 0);
+                for v in 0..size {
+                    let mut u = 0;
+                    while u < size {
+                        let block = mask[(v * size + u) as usize];
+                        if block == AIR {
+                            u += 1;
+                            continue;
+                        }
+                        let at = |u: i32, v: i32| mask[(v * size + u) as usize];
+                        let mut width = 1;
+                        while u + width < size && at(u + width, v) == block {
+                            width += 1;
+                        }
+                        let mut height = 1;
+                        while v + height < size
+                            && (u..u + width).all(|column| at(column, v + height) == block)
+                        {
+                            height += 1;
+                        }
+                        for row in v..v + height {
+                            for column in u..u + width {
+                                mask[(row * size + column) as usize] = AIR;
+                            }
+                        }
+                        quads.push(Quad {
+                            axis,
+                            side,
+                            plane,
+                            min: (origin[u_axis] + u, origin[v_axis] + v),
+                            max: (origin[u_axis] + u + width, origin[v_axis] + v + height),
+                            block,
+                        });
+                        u += width;
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,44 @@
+// moon.rs
+
+use nalgebra_glm::Vec3;
+
+use crate::color::Color;
+use crate::light::Light;
+
+// Radio aparente del disco de la luna en el cielo, en radianes, y el borde que se difumina
+const MOON_RADIUS: f32 = 0.035;
+const MOON_EDGE: f32 = 0.006;
+// Color del disco; se suma al cielo para que las nubes y la niebla lo tapen igual que a él
+const MOON_SURFACE: Color = Color::new(0.9, 0.92, 0.98);
+
+// Segunda luz del ciclo del día, del lado opuesto al sol: de día está bajo el horizonte y
+// apagada, de noche ilumina la escena con una luz fría y débil. `Scene::apply_day_cycle`
+// la mueve y le da su color e intensidad; sin ciclo queda apagada.
+pub struct Moon {
+    pub light: Light,
+    // Si el disco de la luna se dibuja en el cielo
+    pub visible: bool,
+}
+
+impl Moon {
+    pub fn new(visible: bool) -> Self {
+        Moon {
+            light: Light::new(Vec3::new(0.0, -1.0, 0.0), Color::black(), 0.0),
+            visible,
+        }
+    }
+}
+
+// El cielo `background` con el disco de la luna encima si `direction` apunta hacia ella
+pub fn moon_disk(direction: &Vec3, moon_direction: &Vec3, background: Color) -> Color {
+    let angle = direction
+        .normalize()
+        .dot(moon_direction)
+        .clamp(-1.0, 1.0)
+        .acos();
+    let coverage = ((MOON_RADIUS - angle) / MOON_EDGE).clamp(0.0, 1.0);
+    if coverage <= 0.0 {
+        return background;
+    }
+    background * (1.0 - coverage) + MOON_SURFACE * coverage
+}
@@ -0,0 +1,301 @@
+// obj_export.rs
+
+use nalgebra_glm::Vec3;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::assets::Texture;
+use crate::chunk::BlockId;
+use crate::color::Color;
+use crate::material::{Material, ShadingModel};
+use crate::mesh::greedy_mesh;
+use crate::scene::Scene;
+use crate::scene_file::relative_path;
+
+// Normales de las caras en el orden de los `vn` del archivo: eje x, y, z, primero hacia el
+// lado negativo
+const NORMALS: [[i32; 3]; 6] = [
+    [-1, 0, 0],
+    [1, 0, 0],
+    [0, -1, 0],
+    [0, 1, 0],
+    [0, 0, -1],
+    [0, 0, 1],
+];
+
+// Exporta la escena como OBJ con sus materiales en un MTL del mismo nombre, para seguir
+// trabajándola en Blender u otro programa 3D. El mundo sale como un objeto con las caras
+// visibles unidas por `greedy_mesh` y cada objeto de cubos libres como otro, en su
+// posición al empezar la animación. Las texturas se referencian por su ruta; las que no
+// tienen archivo (las de un resource pack) se guardan como PNG al lado.
+pub fn export_obj(scene: &Scene, path: &Path) -> io::Result<()> {
+    let base_dir = path.parent().unwrap_or(Path::new(""));
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "escena".to_string());
+    let mtl_name = format!("{}.mtl", stem);
+
+    let mut obj = ObjWriter::default();
+    let _ = writeln!(obj.out, "mtllib {}", mtl_name);
+    for [x, y, z] in NORMALS {
+        let _ = writeln!(obj.out, "vn {} {} {}", x, y, z);
+    }
+
+    // Caras del mundo agrupadas por material
+    let mut quads = greedy_mesh(&scene.world);
+    quads.sort_by_key(|quad| quad.block);
+    let _ = writeln!(obj.out, "o mundo");
+    let mut current = None;
+    for quad in &quads {
+        if current != Some(quad.block) {
+            obj.use_material(scene, quad.block);
+            current = Some(quad.block);
+        }
+        // Las texturas de los bloques se repiten una vez por bloque
+        let corners = quad.corners();
+        let uvs = corners.map(|corner| texture_uv(&corner, quad.axis, quad.side));
+        obj.face(&corners, &uvs, quad.axis, quad.side);
+    }
+
+    // Los cubos libres estiran la textura sobre cada cara, como al renderizar
+    for object in &scene.objects {
+        let name = if object.name.is_empty() {
+            "cubos"
+        } else {
+            &object.name
+        };
+        let _ = writeln!(obj.out, "o {}", obj_name(name));
+        let mut current = None;
+        for (part, cube) in object.parts.iter().zip(object.cubes_at(&scene.world, 0.0)) {
+            if current != Some(part.block) {
+                obj.use_material(scene, part.block);
+                current = Some(part.block);
+            }
+            let extent = cube.max_corner - cube.min_corner;
+            for axis in 0..3 {
+                for side in [-1, 1] {
+                    let corners = box_face(&cube.min_corner, &cube.max_corner, axis, side);
+                    let uvs = corners.map(|corner| {
+                        let local = (corner - cube.min_corner).component_div(&extent);
+                        texture_uv(&local, axis, side)
+                    });
+                    obj.face(&corners, &uvs, axis, side);
+                }
+            }
+        }
+    }
+    fs::write(path, obj.out)?;
+
+    let mut mtl = String::new();
+    for (block, name, material) in scene.world.materials() {
+        let texture = texture_file(material, block, &stem, base_dir)?;
+        write_material(&mut mtl, name, material, texture, base_dir);
+    }
+    fs::write(base_dir.join(mtl_name), mtl)
+}
+
+// Texto del OBJ y los vértices y coordenadas de textura ya escritos, que las caras vecinas
+// comparten
+#[derive(Default)]
+struct ObjWriter {
+    out: String,
+    vertices: HashMap<[u32; 3], usize>,
+    uvs: HashMap<[u32; 2], usize>,
+}
+
+impl ObjWriter {
+    fn use_material(&mut self, scene: &Scene, block: BlockId) {
+        let name = scene.world.material_name(block);
+        let _ = writeln!(self.out, "usemtl {}", obj_name(name));
+    }
+
+    // Cara con las esquinas en sentido antihorario vistas desde afuera
+    fn face(&mut self, corners: &[Vec3; 4], uvs: &[[f32; 2]; 4], axis: usize, side: i32) {
+        let normal = axis * 2 + usize::from(side > 0) + 1;
+        let mut face = String::from("f");
+        for (corner, uv) in corners.iter().zip(uvs) {
+            let vertex = self.vertex(corner);
+            let uv = self.uv(*uv);
+            let _ = write!(face, " {}/{}/{}", vertex, uv, normal);
+        }
+        let _ = writeln!(self.out, "{}", face);
+    }
+
+    // Índice del vértice, desde 1 como en el formato; se escribe la primera vez
+    fn vertex(&mut self, point: &Vec3) -> usize {
+        let key = [point.x.to_bits(), point.y.to_bits(), point.z.to_bits()];
+        let next = self.vertices.len() + 1;
+        *self.vertices.entry(key).or_insert_with(|| {
+            let _ = writeln!(self.out, "v {} {} {}", point.x, point.y, point.z);
+            next
+        })
+    }
+
+    fn uv(&mut self, [u, v]: [f32; 2]) -> usize {
+        let key = [u.to_bits(), v.to_bits()];
+        let next = self.uvs.len() + 1;
+        *self.uvs.entry(key).or_insert_with(|| {
+            let _ = writeln!(self.out, "vt {} {}", u, v);
+            next
+        })
+    }
+}
+
+// Coordenadas de textura de un punto de la cara con normal en `axis` hacia `side`, como las
+// de `face_uv` al renderizar pero con v hacia arriba como en OBJ. `local` va de 0 a 1 sobre
+// la cara; con coordenadas del mundo la textura se repite en cada bloque.
+fn texture_uv(local: &Vec3, axis: usize, side: i32) -> [f32; 2] {
+    let (x, y, z) = (local.x, local.y, local.z);
+    match (axis, side > 0) {
+        (0, true) => [1.0 - z, y],
+        (0, false) => [z, y],
+        (1, true) => [x, 1.0 - z],
+        (1, false) => [x, z],
+        (2, true) => [x, y],
+        _ => [1.0 - x, y],
+    }
+}
+
+// Las cuatro esquinas de una cara de la caja, en sentido antihorario vistas desde afuera
+fn box_face(min: &Vec3, max: &Vec3, axis: usize, side: i32) -> [Vec3; 4] {
+    let (u_axis, v_axis) = ((axis + 1) % 3, (axis + 2) % 3);
+    let corner = |u: f32, v: f32| {
+        let mut point = Vec3::zeros();
+        point[axis] = if side > 0 { max[axis] } else { min[axis] };
+        point[u_axis] = u;
+        point[v_axis] = v;
+        point
+    };
+    let corners = [
+        corner(min[u_axis], min[v_axis]),
+        corner(max[u_axis], min[v_axis]),
+        corner(max[u_axis], max[v_axis]),
+        corner(min[u_axis], max[v_axis]),
+    ];
+    if side > 0 {
+        corners
+    } else {
+        [corners[0], corners[3], corners[2], corners[1]]
+    }
+}
+
+// Archivo de la textura del material para `map_Kd`: su ruta, o un PNG nuevo con la imagen
+// si no tiene. Los patrones se calculan al sombrear y no tienen imagen.
+fn texture_file(
+    material: &Material,
+    block: BlockId,
+    stem: &str,
+    base_dir: &Path,
+) -> io::Result<Option<String>> {
+    match (&material.texture, &material.texture_path) {
+        (Some(Texture::Image(_)), Some(path)) => Ok(Some(path.clone())),
+        (Some(Texture::Image(image)), None) => {
+            let path = base_dir.join(format!("{}_textura_{}.png", stem, block));
+            image
+                .save(&path)
+                .map_err(|err| io::Error::other(err.to_string()))?;
+            Ok(Some(path.to_string_lossy().into_owned()))
+        }
+        _ => Ok(None),
+    }
+}
+
+fn write_material(
+    out: &mut String,
+    name: &str,
+    material: &Material,
+    texture: Option<String>,
+    base_dir: &Path,
+) {
+    let color = |color: Color| format!("{} {} {}", color.r, color.g, color.b);
+    let transmission = material.transmission();
+    let _ = writeln!(out, "newmtl {}", obj_name(name));
+    let _ = writeln!(out, "Kd {}", color(material.diffuse));
+    let _ = writeln!(out, "Ks {0} {0} {0}", material.albedo[1]);
+    let _ = writeln!(out, "Ns {}", material.specular);
+    let _ = writeln!(out, "Ke {}", color(material.emission));
+    let _ = writeln!(out, "Ni {}", material.refractive_index);
+    let _ = writeln!(out, "d {}", 1.0 - transmission);
+    // 2 es difuso con brillo; 4 agrega reflejos y transparencia
+    let illum = if transmission > 0.0 || material.albedo[2] > 0.0 {
+        4
+    } else {
+        2
+    };
+    let _ = writeln!(out, "illum {}", illum);
+    if let ShadingModel::Pbr {
+        metallic,
+        roughness,
+    } = material.model
+    {
+        let _ = writeln!(out, "Pm {}", metallic);
+        let _ = writeln!(out, "Pr {}", roughness);
+    }
+    if let Some(texture) = texture {
+        let _ = writeln!(out, "map_Kd {}", relative_path(&texture, base_dir));
+    }
+    if let Some(normal_map) = &material.normal_map_path {
+        let _ = writeln!(out, "norm {}", relative_path(normal_map, base_dir));
+    }
+    if let Some(emission_map) = &material.emission_map_path {
+        let _ = writeln!(out, "map_Ke {}", relative_path(emission_map, base_dir));
+    }
+    let _ = writeln!(out);
+}
+
+// Los nombres de OBJ y MTL terminan en el primer espacio
+fn obj_name(name: &str) -> String {
+    name.split_whitespace().collect::<Vec<_>>().join("_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene_file::load_scene_source;
+
+    #[test]
+    fn exports_two_blocks_with_their_materials() {
+        let dir = std::env::temp_dir().join(format!("obj_export_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("texturas")).unwrap();
+        fs::create_dir_all(dir.join("salida")).unwrap();
+        image::RgbImage::new(2, 2)
+            .save(dir.join("texturas").join("piedra.png"))
+            .unwrap();
+        let source = "
+material piedra diffuse 90 90 90 texture texturas/piedra.png
+material madera diffuse 139 69 19
+fill piedra min 0 0 0 max 1 1 1
+fill madera min 1 0 0 max 2 1 1
+";
+        let scene = load_scene_source(source, &dir.join("escena.scene")).unwrap();
+        let output = dir.join("salida").join("modelo.obj");
+        export_obj(&scene, &output).unwrap();
+
+        let obj = fs::read_to_string(&output).unwrap();
+        let count =
+            |text: &str, prefix: &str| text.lines().filter(|line| line.starts_with(prefix)).count();
+        assert_eq!(obj.lines().next(), Some("mtllib modelo.mtl"));
+        // Las dos cajas comparten las esquinas del medio y la cara entre ellas no se ve
+        assert_eq!(count(&obj, "v "), 12);
+        assert_eq!(count(&obj, "f "), 10);
+        assert_eq!(count(&obj, "usemtl "), 2);
+
+        let mtl = fs::read_to_string(dir.join("salida").join("modelo.mtl")).unwrap();
+        assert_eq!(count(&mtl, "newmtl "), 2);
+        let textures: Vec<&str> = mtl
+            .lines()
+            .filter_map(|line| line.strip_prefix("map_Kd "))
+            .collect();
+        assert_eq!(textures.len(), 1);
+        let texture = Path::new(textures[0]);
+        assert!(texture.is_relative(), "{}", texture.display());
+        assert!(dir.join("salida").join(texture).is_file());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
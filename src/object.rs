@@ -0,0 +1,78 @@
+// object.rs
+
+use nalgebra_glm::Vec3;
+
+use crate::animation::Track;
+use crate::chunk::BlockId;
+use crate::cube::Cube;
+use crate::world::World;
+
+// Caja libre de un objeto, con el material tomado de la paleta del mundo
+#[derive(Debug, Clone)]
+pub struct ObjectPart {
+    pub block: BlockId,
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+#[derive(Debug, Clone)]
+pub struct SceneObject {
+    // Nombre vacío para los cubos sueltos que no pertenecen a ningún objeto
+    pub name: String,
+    // Punto alrededor del cual se aplica la escala
+    pub pivot: Vec3,
+    pub parts: Vec<ObjectPart>,
+    pub translation: Option<Track<Vec3>>,
+    pub scale: Option<Track<Vec3>>,
+}
+
+impl SceneObject {
+    pub fn new(name: &str, pivot: Vec3) -> Self {
+        SceneObject {
+            name: name.to_string(),
+            pivot,
+            parts: Vec::new(),
+            translation: None,
+            scale: None,
+        }
+    }
+
+    pub fn is_animated(&self) -> bool {
+        self.translation.is_some() || self.scale.is_some()
+    }
+
+    // Duración de la pista más larga, en segundos
+    pub fn duration(&self) -> f32 {
+        let translation = self
+            .translation
+            .as_ref()
+            .map_or(0.0, |track| track.duration());
+        let scale = self.scale.as_ref().map_or(0.0, |track| track.duration());
+        translation.max(scale)
+    }
+
+    // Cubos del objeto con sus transformaciones evaluadas en `time` segundos
+    pub fn cubes_at<'a>(&'a self, world: &'a World, time: f32) -> impl Iterator<Item = Cube> + 'a {
+        let offset = self
+            .translation
+            .as_ref()
+            .and_then(|track| track.sample(time))
+            .unwrap_or_else(Vec3::zeros);
+        let scale = self
+            .scale
+            .as_ref()
+            .and_then(|track| track.sample(time))
+            .unwrap_or_else(|| Vec3::new(1.0, 1.0, 1.0));
+
+        self.parts.iter().map(move |part| {
+            let a = self.pivot + (part.min - self.pivot).component_mul(&scale) + offset;
+            let b = self.pivot + (part.max - self.pivot).component_mul(&scale) + offset;
+            // Una escala negativa invierte las esquinas
+            Cube {
+                min_corner: a.inf(&b),
+                max_corner: a.sup(&b),
+                material: world.material(part.block).clone(),
+            }
+        })
+    }
+}
@@ -0,0 +1,218 @@
+// overrides.rs
+//
+// Cambios puntuales a una escena ya cargada, escritos como `ruta=valor`:
+//
+//   lights[0].intensity=2.0
+//   lights[1].color=255,200,120
+//   materials.agua.ior=1.4
+//   materials.agua.albedo[3]=0.5
+//   materials.hojas.alpha_cutoff=0.3
+//   materials.oro.roughness=0.2
+//   materials.agua.blur=0.15
+//   materials.agua.absorption=120,40,10
+//   materials.vidrio.max_depth=10
+//   camera.fov=45deg
+//   firefly_clamp=0.5
+//   roulette=0.1
+//
+// Los colores van de 0 a 255 y los ángulos llevan unidad, igual que en los archivos .scene.
+
+use nalgebra_glm::Vec3;
+
+use crate::color::Color;
+use crate::scene::Scene;
+
+pub fn apply_override(scene: &mut Scene, assignment: &str) -> Result<(), String> {
+    let (path, value) = assignment
+        .split_once('=')
+        .ok_or_else(|| format!("'{}' no tiene la forma ruta=valor", assignment))?;
+    let (path, value) = (path.trim(), value.trim());
+    let segments: Vec<&str> = path.split('.').collect();
+
+    match segments.as_slice() {
+        [light, property] if light.starts_with("lights[") => {
+            let index = index(light, "lights")?;
+            let count = scene.lights.len();
+            let light = scene.lights.get_mut(index).ok_or_else(|| {
+                format!(
+                    "la escena tiene {} luces, no existe lights[{}]",
+                    count, index
+                )
+            })?;
+            match *property {
+                "intensity" => light.intensity = number(value)?,
+                "radius" => light.radius = non_negative(value)?,
+                "color" => light.color = color(value)?,
+                "position" => light.position = vector(value)?,
+                other => return Err(unknown_property("la luz", other)),
+            }
+        }
+        ["materials", name, property] => {
+            let block = scene
+                .world
+                .find_material(name)
+                .ok_or_else(|| format!("no hay un material llamado '{}'", name))?;
+            let mut material = scene.world.material_mut(block);
+            match *property {
+                "diffuse" => material.diffuse = color(value)?,
+                "emission" => material.emission = color(value)?,
+                "absorption" => material.absorption = color(value)?,
+                "specular" => material.specular = number(value)?,
+                "ior" => material.refractive_index = number(value)?,
+                "max_depth" => material.max_depth = Some(integer(value)?),
+                "alpha_cutoff" => {
+                    let cutoff = number(value)?;
+                    if !(0.0..=1.0).contains(&cutoff) {
+                        return Err("alpha_cutoff va de 0 a 1".to_string());
+                    }
+                    material.alpha_cutoff = cutoff;
+                }
+                "blur" => {
+                    let blur = number(value)?;
+                    if !(0.0..=1.0).contains(&blur) {
+                        return Err("blur va de 0 a 1".to_string());
+                    }
+                    material.blur = blur;
+                }
+                // Pasan el material al modelo PBR si todavía no lo usaba
+                "metallic" | "roughness" => {
+                    let amount = number(value)?;
+                    if !(0.0..=1.0).contains(&amount) {
+                        return Err(format!("{} va de 0 a 1", property));
+                    }
+                    if *property == "metallic" {
+                        material.set_metallic(amount);
+                    } else {
+                        material.set_roughness(amount);
+                    }
+                }
+                "albedo" => {
+                    let values = numbers(value)?;
+                    material.albedo = values.try_into().map_err(|_| {
+                        format!(
+                            "albedo necesita 4 valores separados por comas, no '{}'",
+                            value
+                        )
+                    })?;
+                }
+                other if other.starts_with("albedo[") => {
+                    let index = self::index(other, "albedo")?;
+                    let slot = material.albedo.get_mut(index).ok_or_else(|| {
+                        format!("albedo tiene 4 valores, no existe albedo[{}]", index)
+                    })?;
+                    *slot = number(value)?;
+                }
+                other => return Err(unknown_property("el material", other)),
+            }
+        }
+        ["camera", property] => {
+            let camera = &mut scene.camera;
+            match *property {
+                "position" => camera.position = vector(value)?,
+                "target" => camera.target = vector(value)?,
+                "fov" => {
+                    let fov = angle(value)?;
+                    if fov <= 0.0 || fov >= std::f32::consts::PI {
+                        return Err("fov debe estar entre 0deg y 180deg".to_string());
+                    }
+                    camera.fov = fov;
+                }
+                "roll" => camera.roll = angle(value)?,
+                "near" => camera.near = non_negative(value)?,
+                "far" => camera.far = Some(non_negative(value)?),
+                "aperture" => camera.aperture = non_negative(value)?,
+                "focus" => camera.focus_distance = Some(non_negative(value)?),
+                other => return Err(unknown_property("la cámara", other)),
+            }
+            if camera.far.is_some_and(|far| far <= camera.near) {
+                return Err("far debe ser mayor que near".to_string());
+            }
+            camera.update_view();
+        }
+        ["sun_distance"] => scene.sun_distance = number(value)?,
+        ["firefly_clamp"] => scene.firefly_clamp = non_negative(value)?,
+        ["roulette"] => {
+            let roulette = number(value)?;
+            if !(0.0..=1.0).contains(&roulette) {
+                return Err("roulette va de 0 a 1".to_string());
+            }
+            scene.roulette = roulette;
+        }
+        _ => return Err(format!("ruta desconocida '{}'", path)),
+    }
+    Ok(())
+}
+
+// Índice entre corchetes de un segmento como `lights[2]`
+fn index(segment: &str, name: &str) -> Result<usize, String> {
+    segment
+        .strip_prefix(name)
+        .and_then(|rest| rest.strip_prefix('['))
+        .and_then(|rest| rest.strip_suffix(']'))
+        .and_then(|index| index.parse().ok())
+        .ok_or_else(|| format!("índice inválido en '{}' (ej. {}[0])", segment, name))
+}
+
+fn unknown_property(owner: &str, property: &str) -> String {
+    format!("{} no tiene la propiedad '{}'", owner, property)
+}
+
+fn number(value: &str) -> Result<f32, String> {
+    value
+        .parse::<f32>()
+        .ok()
+        .filter(|number| number.is_finite())
+        .ok_or_else(|| format!("'{}' no es un número", value))
+}
+
+fn non_negative(value: &str) -> Result<f32, String> {
+    let number = number(value)?;
+    if number < 0.0 {
+        return Err(format!("'{}' no puede ser negativo", value));
+    }
+    Ok(number)
+}
+
+fn integer(value: &str) -> Result<u32, String> {
+    value
+        .parse()
+        .map_err(|_| format!("'{}' no es un número entero", value))
+}
+
+fn numbers(value: &str) -> Result<Vec<f32>, String> {
+    value.split(',').map(|part| number(part.trim())).collect()
+}
+
+fn vector(value: &str) -> Result<Vec3, String> {
+    match numbers(value)?.as_slice() {
+        &[x, y, z] => Ok(Vec3::new(x, y, z)),
+        _ => Err(format!("se esperaban 3 valores x,y,z, no '{}'", value)),
+    }
+}
+
+fn color(value: &str) -> Result<Color, String> {
+    let channels: Option<Vec<u8>> = value
+        .split(',')
+        .map(|part| part.trim().parse().ok())
+        .collect();
+    match channels.as_deref() {
+        Some(&[r, g, b]) => Ok(Color::from_u8(r, g, b)),
+        _ => Err(format!(
+            "se esperaba un color r,g,b de 0 a 255, no '{}'",
+            value
+        )),
+    }
+}
+
+fn angle(value: &str) -> Result<f32, String> {
+    if let Some(degrees) = value.strip_suffix("deg") {
+        Ok(number(degrees)?.to_radians())
+    } else if let Some(radians) = value.strip_suffix("rad") {
+        number(radians)
+    } else {
+        Err(format!(
+            "'{}' necesita unidad explícita: usa 'deg' o 'rad' (ej. {}deg)",
+            value, value
+        ))
+    }
+}
@@ -0,0 +1,99 @@
+// packet.rs
+
+use nalgebra_glm::Vec3;
+use std::ops::Deref;
+
+use crate::cube::{broadcast, lane_entry_distances, Cube, Lanes, LANES};
+
+// Lado del bloque de pixeles cuyos rayos de cámara forman un paquete
+pub const PACKET_SIDE: usize = 2;
+
+// Pixeles (x, y) de un bloque de hasta 2x2, guardados en el stack: se arma uno por cada
+// paquete de rayos de cámara
+#[derive(Debug, Clone, Copy)]
+pub struct PixelBlock {
+    pixels: [(usize, usize); LANES],
+    count: usize,
+}
+
+impl PixelBlock {
+    pub fn new() -> Self {
+        PixelBlock {
+            pixels: [(0, 0); LANES],
+            count: 0,
+        }
+    }
+
+    // Agrega un pixel; los que pasan de `LANES` se ignoran
+    pub fn push(&mut self, pixel: (usize, usize)) {
+        if self.count < LANES {
+            self.pixels[self.count] = pixel;
+            self.count += 1;
+        }
+    }
+}
+
+impl Default for PixelBlock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deref for PixelBlock {
+    type Target = [(usize, usize)];
+
+    fn deref(&self) -> &[(usize, usize)] {
+        &self.pixels[..self.count]
+    }
+}
+
+// Rayos de cámara de un bloque de 2x2 pixeles, uno por pixel y de la misma muestra. Salen
+// casi del mismo lugar casi en la misma dirección, así que suelen chocar con lo mismo: cada
+// cubo libre se prueba contra los cuatro a la vez, con sus coordenadas agrupadas por eje, y
+// el mundo los recorre uno detrás del otro sobre los mismos chunks.
+pub struct RayPacket {
+    rays: [(Vec3, Vec3); LANES],
+    count: usize,
+    origins: Lanes,
+    directions: Lanes,
+}
+
+impl RayPacket {
+    // Paquete con los primeros `LANES` rayos (origen y dirección) de `rays`
+    pub fn new(rays: &[(Vec3, Vec3)]) -> Self {
+        let mut packet = RayPacket {
+            rays: [(Vec3::zeros(), Vec3::zeros()); LANES],
+            count: rays.len().min(LANES),
+            origins: [[0.0; LANES]; 3],
+            directions: [[0.0; LANES]; 3],
+        };
+        for (lane, &(origin, direction)) in rays.iter().take(LANES).enumerate() {
+            packet.rays[lane] = (origin, direction);
+            for axis in 0..3 {
+                packet.origins[axis][lane] = origin[axis];
+                packet.directions[axis][lane] = direction[axis];
+            }
+        }
+        packet
+    }
+
+    pub fn rays(&self) -> &[(Vec3, Vec3)] {
+        &self.rays[..self.count]
+    }
+
+    // Distancia a la que cada rayo entra en `cube`, o infinito si no lo toca o le queda
+    // detrás
+    pub fn entry_distances(&self, cube: &Cube) -> [f32; LANES] {
+        let mut distances = lane_entry_distances(
+            &broadcast(&cube.min_corner),
+            &broadcast(&cube.max_corner),
+            &self.origins,
+            &self.directions,
+        );
+        // Los carriles que sobran no tienen rayo
+        for distance in distances.iter_mut().skip(self.count) {
+            *distance = f32::INFINITY;
+        }
+        distances
+    }
+}
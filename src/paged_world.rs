@@ -0,0 +1,301 @@
+// paged_world.rs
+//
+// Bloques del mundo en un archivo binario paginado, para mundos que no caben en memoria.
+// El archivo se proyecta en memoria (mmap) y cada chunk se descomprime recién cuando un
+// rayo o una edición lo necesita; los últimos usados quedan en una caché LRU.
+//
+// Formato, con enteros little endian:
+//
+//   "GPCPAGES"                                   8 bytes
+//   versión (u32), cantidad de chunks (u32), inicio del índice (u64)
+//   contenido de cada chunk: tramos (bloque u16, cantidad u16) en orden y, z, x
+//   índice al final: por chunk x, y, z (i32), inicio (u64) y largo (u32) del contenido
+//
+// El índice va al final para poder escribir el archivo en una sola pasada, sin tener
+// todos los chunks en memoria.
+
+use memmap2::Mmap;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use crate::chunk::{BlockId, Chunk, CHUNK_VOLUME};
+use crate::world::{BlockPos, World};
+
+const MAGIC: &[u8; 8] = b"GPCPAGES";
+const FORMAT_VERSION: u32 = 1;
+const HEADER_SIZE: usize = 24;
+const INDEX_ENTRY_SIZE: usize = 24;
+const RUN_SIZE: usize = 4;
+// Chunks descomprimidos que se conservan, unos 8 MB
+const CACHED_CHUNKS: usize = 1024;
+// Partes de la caché, cada una con su propio lock, para que los hilos del render que piden
+// chunks distintos no se esperen entre sí
+#[cfg(feature = "parallel")]
+const CACHE_SHARDS: usize = 16;
+#[cfg(not(feature = "parallel"))]
+const CACHE_SHARDS: usize = 1;
+
+pub struct PagedChunks {
+    map: Mmap,
+    // Inicio y largo del contenido de cada chunk dentro del archivo
+    index: HashMap<BlockPos, (usize, usize)>,
+    palette_size: usize,
+    bounds: Option<(BlockPos, BlockPos)>,
+    cache: [Mutex<ChunkCache>; CACHE_SHARDS],
+}
+
+// Una parte de la caché LRU. `order` tiene los chunks por su último uso, así el usado hace
+// más tiempo es el primero y tanto usar uno como descartarlo cuestan O(log n).
+#[derive(Default)]
+struct ChunkCache {
+    chunks: HashMap<BlockPos, (Arc<Chunk>, u64)>,
+    order: BTreeMap<u64, BlockPos>,
+    // Avanza en cada acceso
+    clock: u64,
+}
+
+impl ChunkCache {
+    fn get(&mut self, pos: BlockPos) -> Option<Arc<Chunk>> {
+        self.clock += 1;
+        let (chunk, last_used) = self.chunks.get_mut(&pos)?;
+        self.order.remove(last_used);
+        *last_used = self.clock;
+        self.order.insert(self.clock, pos);
+        Some(Arc::clone(chunk))
+    }
+
+    // Guarda `chunk`, descartando el usado hace más tiempo si la parte está llena. Si otro
+    // hilo ya lo había guardado queda el nuevo.
+    fn insert(&mut self, pos: BlockPos, chunk: Arc<Chunk>) {
+        self.clock += 1;
+        if let Some((_, last_used)) = self.chunks.remove(&pos) {
+            self.order.remove(&last_used);
+        } else if self.chunks.len() >= CACHED_CHUNKS / CACHE_SHARDS {
+            if let Some((_, oldest)) = self.order.pop_first() {
+                self.chunks.remove(&oldest);
+            }
+        }
+        self.chunks.insert(pos, (chunk, self.clock));
+        self.order.insert(self.clock, pos);
+    }
+}
+
+impl PagedChunks {
+    // Abre el archivo y lee su índice; los chunks se leen al pedirlos. `palette_size`
+    // cuenta el aire y sirve para rechazar bloques que no existen.
+    pub fn open(path: &Path, palette_size: usize) -> io::Result<Self> {
+        let file = File::open(path)?;
+        // SAFETY: mientras el mapa existe sus bytes se tratan como memoria inmutable. Si
+        // otro programa escribe o recorta el archivo en ese tiempo es comportamiento
+        // indefinido (no solo chunks corruptos: recortarlo puede matar el proceso con
+        // SIGBUS), y no hay forma de impedirlo desde acá. Este programa nunca escribe sobre
+        // un archivo proyectado: `write_pages` escribe uno temporal y lo renombra, así el
+        // mapa sigue apuntando al archivo viejo.
+        let map = unsafe { Mmap::map(&file)? };
+
+        let header = map
+            .get(..HEADER_SIZE)
+            .ok_or_else(|| invalid("el archivo está cortado"))?;
+        if &header[..8] != MAGIC {
+            return Err(invalid("no es un archivo de chunks paginados"));
+        }
+        let version = read_u32(&header[8..]);
+        if version != FORMAT_VERSION {
+            return Err(invalid(&format!(
+                "versión {} no soportada (se esperaba {})",
+                version, FORMAT_VERSION
+            )));
+        }
+        let count = read_u32(&header[12..]) as usize;
+        let index_start = read_u64(&header[16..]) as usize;
+        let index_bytes = index_start
+            .checked_add(count * INDEX_ENTRY_SIZE)
+            .and_then(|end| map.get(index_start..end))
+            .ok_or_else(|| invalid("el índice está fuera del archivo"))?;
+
+        let mut index = HashMap::with_capacity(count);
+        let mut bounds: Option<(BlockPos, BlockPos)> = None;
+        for entry in index_bytes.chunks_exact(INDEX_ENTRY_SIZE) {
+            let pos = (
+                read_u32(&entry[0..]) as i32,
+                read_u32(&entry[4..]) as i32,
+                read_u32(&entry[8..]) as i32,
+            );
+            let start = read_u64(&entry[12..]) as usize;
+            let length = read_u32(&entry[20..]) as usize;
+            if !length.is_multiple_of(RUN_SIZE) || start.saturating_add(length) > index_start {
+                return Err(invalid(&format!("el chunk {:?} está dañado", pos)));
+            }
+            index.insert(pos, (start, length));
+            bounds = Some(match bounds {
+                Some((min, max)) => (
+                    (min.0.min(pos.0), min.1.min(pos.1), min.2.min(pos.2)),
+                    (max.0.max(pos.0), max.1.max(pos.1), max.2.max(pos.2)),
+                ),
+                None => (pos, pos),
+            });
+        }
+
+        Ok(PagedChunks {
+            map,
+            index,
+            palette_size,
+            bounds,
+            cache: std::array::from_fn(|_| Mutex::default()),
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    pub fn contains(&self, pos: BlockPos) -> bool {
+        self.index.contains_key(&pos)
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = BlockPos> + '_ {
+        self.index.keys().copied()
+    }
+
+    // Chunks mínimo y máximo (inclusivos) del archivo
+    pub fn bounds(&self) -> Option<(BlockPos, BlockPos)> {
+        self.bounds
+    }
+
+    // El chunk descomprimido, desde la caché si se usó hace poco
+    pub fn chunk(&self, pos: BlockPos) -> Option<Arc<Chunk>> {
+        let &(start, length) = self.index.get(&pos)?;
+        let shard = &self.cache[shard(pos)];
+        if let Some(chunk) = shard.lock().unwrap().get(pos) {
+            return Some(chunk);
+        }
+
+        // Se descomprime sin bloquear a los otros hilos; si dos piden el mismo chunk a la
+        // vez los dos lo leen y queda el último
+        let chunk = Arc::new(
+            decode(&self.map[start..start + length], self.palette_size).unwrap_or_else(|| {
+                eprintln!(
+                    "El chunk {:?} del mundo paginado está dañado; se deja vacío",
+                    pos
+                );
+                Chunk::new()
+            }),
+        );
+
+        shard.lock().unwrap().insert(pos, Arc::clone(&chunk));
+        Some(chunk)
+    }
+}
+
+// Parte de la caché que guarda el chunk `pos`; los vecinos caen en partes distintas
+fn shard(pos: BlockPos) -> usize {
+    let hash = (pos.0 as u32)
+        .wrapping_mul(0x8DA6_B343)
+        .wrapping_add((pos.1 as u32).wrapping_mul(0xD816_3841))
+        .wrapping_add((pos.2 as u32).wrapping_mul(0xCB1A_B31F));
+    ((hash as u64 * CACHE_SHARDS as u64) >> 32) as usize
+}
+
+// Escribe los chunks con bloques del mundo (los editados y los que siguen en su archivo
+// paginado). Se escribe a un archivo temporal que después reemplaza al destino, así se
+// puede guardar sobre el mismo archivo del que se están leyendo los chunks.
+pub fn write_pages(world: &World, path: &Path) -> io::Result<()> {
+    let mut positions: Vec<BlockPos> = world.chunk_positions().collect();
+    // Orden fijo para que guardar dos veces el mismo mundo dé el mismo archivo
+    positions.sort();
+
+    let temp = path.with_extension("pages.tmp");
+    let mut out = BufWriter::new(File::create(&temp)?);
+    out.write_all(&[0; HEADER_SIZE])?;
+
+    let mut entries = Vec::new();
+    let mut offset = HEADER_SIZE as u64;
+    let mut payload = Vec::new();
+    for pos in positions {
+        let Some(chunk) = world.chunk(pos) else {
+            continue;
+        };
+        if chunk.is_empty() {
+            continue;
+        }
+        payload.clear();
+        encode(chunk.as_slice(), &mut payload);
+        out.write_all(&payload)?;
+        entries.push((pos, offset, payload.len() as u32));
+        offset += payload.len() as u64;
+    }
+
+    for (pos, start, length) in &entries {
+        out.write_all(&pos.0.to_le_bytes())?;
+        out.write_all(&pos.1.to_le_bytes())?;
+        out.write_all(&pos.2.to_le_bytes())?;
+        out.write_all(&start.to_le_bytes())?;
+        out.write_all(&length.to_le_bytes())?;
+    }
+
+    let mut file = out.into_inner().map_err(|err| err.into_error())?;
+    let mut header = Vec::with_capacity(HEADER_SIZE);
+    header.extend_from_slice(MAGIC);
+    header.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    header.extend_from_slice(&(entries.len() as u32).to_le_bytes());
+    header.extend_from_slice(&offset.to_le_bytes());
+    write_header(&mut file, &header)?;
+    file.sync_all()?;
+    drop(file);
+
+    fs::rename(&temp, path)
+}
+
+fn write_header(file: &mut File, header: &[u8]) -> io::Result<()> {
+    use std::io::{Seek, SeekFrom};
+    file.seek(SeekFrom::Start(0))?;
+    file.write_all(header)
+}
+
+// Tramos (bloque, cantidad) de los bloques en orden y, z, x
+fn encode(blocks: &[BlockId], out: &mut Vec<u8>) {
+    let mut iter = blocks.iter().peekable();
+    while let Some(&block) = iter.next() {
+        let mut count: u16 = 1;
+        while iter.next_if(|&&next| next == block).is_some() {
+            count += 1;
+        }
+        out.extend_from_slice(&block.to_le_bytes());
+        out.extend_from_slice(&count.to_le_bytes());
+    }
+}
+
+// None si los tramos no llenan el chunk exactamente o usan bloques fuera de la paleta
+fn decode(bytes: &[u8], palette_size: usize) -> Option<Chunk> {
+    let mut blocks = Vec::with_capacity(CHUNK_VOLUME);
+    for run in bytes.chunks_exact(RUN_SIZE) {
+        let block = u16::from_le_bytes([run[0], run[1]]);
+        let count = u16::from_le_bytes([run[2], run[3]]) as usize;
+        if block as usize >= palette_size || blocks.len() + count > CHUNK_VOLUME {
+            return None;
+        }
+        blocks.resize(blocks.len() + count, block);
+    }
+    Chunk::from_slice(&blocks)
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    let mut value = [0; 8];
+    value.copy_from_slice(&bytes[..8]);
+    u64::from_le_bytes(value)
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
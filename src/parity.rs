@@ -0,0 +1,163 @@
+// parity.rs
+//
+// Comprueba que los caminos rápidos del render den la misma imagen que uno de referencia
+// simple: las caras del mundo como quads probados uno por uno, en un solo hilo. Cada
+// camino renderiza la misma escena (el ruido del muestreo ya es fijo) y se compara pixel
+// por pixel con la referencia.
+
+use std::time::Instant;
+
+use crate::framebuffer::Framebuffer;
+use crate::render::{render, render_rows, render_serial, RenderSettings};
+use crate::scene::Scene;
+
+// Un nivel de un canal de 8 bits: las diferencias menores no se ven en la imagen guardada
+pub const DEFAULT_TOLERANCE: f32 = 1.0 / 255.0;
+// Filas de cada franja; no divide los tamaños habituales, así la última queda incompleta
+const STRIP_ROWS: usize = 7;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    // Todos los quads del mundo probados uno por uno, en un solo hilo
+    Reference,
+    // Recorrido DDA de los chunks del mundo, en un solo hilo
+    Grid,
+    // El render normal: DDA con las filas repartidas entre hilos
+    Parallel,
+    // La imagen en franjas de filas, como la captura de alta calidad
+    Strips,
+}
+
+impl Backend {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Backend::Reference => "referencia",
+            Backend::Grid => "dda",
+            Backend::Parallel => "paralelo",
+            Backend::Strips => "franjas",
+        }
+    }
+
+    // Caminos que se comparan con la referencia; sin la feature parallel el render normal
+    // es el mismo que `Grid`
+    pub fn candidates() -> Vec<Backend> {
+        let mut backends = vec![Backend::Grid];
+        if cfg!(feature = "parallel") {
+            backends.push(Backend::Parallel);
+        }
+        backends.push(Backend::Strips);
+        backends
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ParityReport {
+    pub backend: Backend,
+    // Diferencia absoluta por canal (r, g, b y cobertura) respecto a la referencia
+    pub max_error: f32,
+    pub mean_error: f32,
+    // Pixeles con algún canal fuera de la tolerancia
+    pub differing_pixels: usize,
+    pub worst_pixel: (usize, usize),
+    pub render_seconds: f32,
+}
+
+impl ParityReport {
+    pub fn passes(&self, tolerance: f32) -> bool {
+        self.max_error <= tolerance
+    }
+}
+
+// Renderiza la escena con un camino; los cubos libres se evalúan en el instante 0 como en
+// el subcomando render
+pub fn render_backend(
+    scene: &mut Scene,
+    backend: Backend,
+    width: usize,
+    height: usize,
+    samples: u32,
+) -> Framebuffer {
+    let mut framebuffer = Framebuffer::new(width, height);
+    let objects = scene.cubes_at(0.0);
+    let settings = RenderSettings::with_samples(samples);
+    match backend {
+        Backend::Reference => {
+            scene.world.set_exhaustive(true);
+            render_serial(&mut framebuffer, scene, &objects, &settings);
+            scene.world.set_exhaustive(false);
+        }
+        Backend::Grid => render_serial(&mut framebuffer, scene, &objects, &settings),
+        Backend::Parallel => render(&mut framebuffer, scene, &objects, &settings),
+        Backend::Strips => {
+            for first_row in (0..height).step_by(STRIP_ROWS) {
+                let rows = first_row..first_row + STRIP_ROWS;
+                render_rows(&mut framebuffer, scene, &objects, &settings, rows);
+            }
+        }
+    }
+    framebuffer
+}
+
+// Un informe por cada camino de `Backend::candidates`, comparado con la referencia
+pub fn check_parity(
+    scene: &mut Scene,
+    width: usize,
+    height: usize,
+    samples: u32,
+    tolerance: f32,
+) -> Vec<ParityReport> {
+    let reference = render_backend(scene, Backend::Reference, width, height, samples);
+    Backend::candidates()
+        .into_iter()
+        .map(|backend| {
+            let start = Instant::now();
+            let image = render_backend(scene, backend, width, height, samples);
+            let render_seconds = start.elapsed().as_secs_f32();
+            ParityReport {
+                render_seconds,
+                ..compare(backend, &reference, &image, tolerance)
+            }
+        })
+        .collect()
+}
+
+fn compare(
+    backend: Backend,
+    reference: &Framebuffer,
+    image: &Framebuffer,
+    tolerance: f32,
+) -> ParityReport {
+    let mut report = ParityReport {
+        backend,
+        max_error: 0.0,
+        mean_error: 0.0,
+        differing_pixels: 0,
+        worst_pixel: (0, 0),
+        render_seconds: 0.0,
+    };
+    let mut total = 0.0;
+    let pixels = reference
+        .buffer
+        .iter()
+        .zip(&reference.alpha)
+        .zip(image.buffer.iter().zip(&image.alpha));
+    for (index, ((expected, expected_alpha), (actual, actual_alpha))) in pixels.enumerate() {
+        let errors = [
+            (expected.r - actual.r).abs(),
+            (expected.g - actual.g).abs(),
+            (expected.b - actual.b).abs(),
+            (expected_alpha - actual_alpha).abs(),
+        ];
+        total += errors.iter().sum::<f32>();
+        let error = errors.into_iter().fold(0.0, f32::max);
+        if error > tolerance {
+            report.differing_pixels += 1;
+        }
+        if error > report.max_error {
+            report.max_error = error;
+            report.worst_pixel = (index % reference.width, index / reference.width);
+        }
+    }
+    report.mean_error = total / (reference.buffer.len() * 4).max(1) as f32;
+    report
+}
@@ -0,0 +1,49 @@
+// portal.rs
+
+use nalgebra_glm::Vec3;
+
+// Abertura rectangular (ventana, puerta) por donde entra la luz del cielo a un interior.
+// Es una caja con grosor cero en uno de sus ejes.
+#[derive(Debug, Clone)]
+pub struct Portal {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Portal {
+    // Devuelve None si la caja no es plana en exactamente un eje
+    pub fn new(min: Vec3, max: Vec3) -> Option<Self> {
+        let size = max - min;
+        let flat_axes = (0..3).filter(|&axis| size[axis] == 0.0).count();
+        let valid = (0..3).all(|axis| size[axis] >= 0.0);
+        (flat_axes == 1 && valid).then_some(Portal { min, max })
+    }
+
+    fn flat_axis(&self) -> usize {
+        (0..3)
+            .find(|&axis| self.max[axis] == self.min[axis])
+            .unwrap_or(0)
+    }
+
+    pub fn normal(&self) -> Vec3 {
+        let mut normal = Vec3::zeros();
+        normal[self.flat_axis()] = 1.0;
+        normal
+    }
+
+    pub fn area(&self) -> f32 {
+        let size = self.max - self.min;
+        let axis = self.flat_axis();
+        size[(axis + 1) % 3] * size[(axis + 2) % 3]
+    }
+
+    // Punto del rectángulo para coordenadas (u, v) entre 0 y 1
+    pub fn point(&self, u: f32, v: f32) -> Vec3 {
+        let axis = self.flat_axis();
+        let mut point = self.min;
+        let (a, b) = ((axis + 1) % 3, (axis + 2) % 3);
+        point[a] += (self.max[a] - self.min[a]) * u;
+        point[b] += (self.max[b] - self.min[b]) * v;
+        point
+    }
+}
@@ -0,0 +1,279 @@
+// post.rs
+
+use std::fs;
+
+use crate::color::Color;
+use crate::framebuffer::Framebuffer;
+
+// Los halos del bloom se calculan a un cuarto de la resolución: son suaves, así que no se
+// nota, y el desenfoque cuesta dieciséis veces menos
+const BLOOM_DOWNSAMPLE: usize = 4;
+
+// Un paso del posproceso. Trabaja sobre la imagen terminada, antes de pasarla a 8 bits para
+// mostrarla o guardarla, así el bloom ve el brillo de verdad de la glowstone y el sol. La
+// escena los lista en el orden en que se aplican.
+#[derive(Debug, Clone)]
+pub enum PostEffect {
+    // Multiplica la imagen por 2 elevado a este valor (pasos de diafragma)
+    Exposure(f32),
+    Bloom(Bloom),
+    // Cuánto se oscurecen las esquinas: 0 nada, 1 quedan negras
+    Vignette(f32),
+    // Corrección de color con una tabla 3D
+    Grade(ColorLut),
+}
+
+// Halo alrededor de lo muy brillante: lo que pasa de `threshold` de luminancia se desenfoca
+// y se suma a la imagen multiplicado por `strength`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bloom {
+    pub threshold: f32,
+    pub strength: f32,
+    // Radio del halo como fracción del alto de la imagen, así se ve igual en la ventana y en
+    // una captura más grande
+    pub radius: f32,
+}
+
+impl Default for Bloom {
+    fn default() -> Self {
+        Bloom {
+            threshold: 0.8,
+            strength: 0.5,
+            radius: 0.02,
+        }
+    }
+}
+
+// Tabla de corrección de color de un archivo `.cube` (el formato que exportan los programas
+// de gradación): el color de salida para una cuadrícula de colores de entrada. Entre sus
+// puntos se interpola.
+#[derive(Debug, Clone)]
+pub struct ColorLut {
+    // Archivo del que se leyó, para guardarlo con la escena
+    pub path: String,
+    size: usize,
+    domain: (Color, Color),
+    // size³ colores con el rojo variando más rápido, como en el archivo
+    table: Vec<Color>,
+}
+
+impl ColorLut {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let source = fs::read_to_string(path)
+            .map_err(|err| format!("no se pudo leer la tabla de color {}: {}", path, err))?;
+        let mut lut = ColorLut::parse(&source).map_err(|err| format!("{}: {}", path, err))?;
+        lut.path = path.to_string();
+        Ok(lut)
+    }
+
+    fn parse(source: &str) -> Result<Self, String> {
+        let mut size = None;
+        let mut domain = (Color::black(), Color::new(1.0, 1.0, 1.0));
+        let mut table = Vec::new();
+        for (index, line) in source.lines().enumerate() {
+            let number = index + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut words = line.split_whitespace();
+            let first = words.next().unwrap_or_default();
+            match first {
+                "TITLE" => {}
+                "LUT_1D_SIZE" => {
+                    return Err(format!(
+                        "línea {}: las tablas 1D no se admiten, usa LUT_3D_SIZE",
+                        number
+                    ))
+                }
+                "LUT_3D_SIZE" => {
+                    let value = words.next().and_then(|word| word.parse::<usize>().ok());
+                    match value {
+                        Some(value) if value >= 2 => size = Some(value),
+                        _ => {
+                            return Err(format!(
+                                "línea {}: LUT_3D_SIZE tiene que ser un entero de 2 o más",
+                                number
+                            ))
+                        }
+                    }
+                }
+                "DOMAIN_MIN" => domain.0 = triplet(words, number)?,
+                "DOMAIN_MAX" => domain.1 = triplet(words, number)?,
+                _ => table.push(triplet(std::iter::once(first).chain(words), number)?),
+            }
+        }
+
+        let size = size.ok_or_else(|| "falta LUT_3D_SIZE".to_string())?;
+        if table.len() != size.pow(3) {
+            return Err(format!(
+                "se esperaban {} colores y hay {}",
+                size.pow(3),
+                table.len()
+            ));
+        }
+        Ok(ColorLut {
+            path: String::new(),
+            size,
+            domain,
+            table,
+        })
+    }
+
+    // Color corregido de `color`, interpolando entre los ocho puntos de la tabla que lo
+    // rodean; lo que cae fuera del dominio toma el borde
+    pub fn sample(&self, color: Color) -> Color {
+        let (low, high) = self.domain;
+        let last = self.size - 1;
+        let coordinate = |value: f32, low: f32, high: f32| {
+            ((value - low) / (high - low).max(1e-6)).clamp(0.0, 1.0) * last as f32
+        };
+        let (r, g, b) = (
+            coordinate(color.r, low.r, high.r),
+            coordinate(color.g, low.g, high.g),
+            coordinate(color.b, low.b, high.b),
+        );
+        let (r0, g0, b0) = (r.floor() as usize, g.floor() as usize, b.floor() as usize);
+        let (r1, g1, b1) = ((r0 + 1).min(last), (g0 + 1).min(last), (b0 + 1).min(last));
+        let (tr, tg, tb) = (r - r0 as f32, g - g0 as f32, b - b0 as f32);
+
+        let at = |r: usize, g: usize, b: usize| self.table[(b * self.size + g) * self.size + r];
+        let c00 = mix(at(r0, g0, b0), at(r1, g0, b0), tr);
+        let c10 = mix(at(r0, g1, b0), at(r1, g1, b0), tr);
+        let c01 = mix(at(r0, g0, b1), at(r1, g0, b1), tr);
+        let c11 = mix(at(r0, g1, b1), at(r1, g1, b1), tr);
+        mix(mix(c00, c10, tg), mix(c01, c11, tg), tb)
+    }
+}
+
+// Tres números de una línea de la tabla
+fn triplet<'a>(words: impl Iterator<Item = &'a str>, line: usize) -> Result<Color, String> {
+    let values: Option<Vec<f32>> = words.map(|word| word.parse().ok()).collect();
+    match values.as_deref() {
+        Some(&[r, g, b]) => Ok(Color::new(r, g, b)),
+        _ => Err(format!("línea {}: se esperaban tres números", line)),
+    }
+}
+
+fn mix(a: Color, b: Color, t: f32) -> Color {
+    a * (1.0 - t) + b * t
+}
+
+// Aplica los efectos a `framebuffer` en orden
+pub fn apply_post(effects: &[PostEffect], framebuffer: &mut Framebuffer) {
+    for effect in effects {
+        match effect {
+            PostEffect::Exposure(stops) => {
+                let scale = 2.0_f32.powf(*stops);
+                for pixel in &mut framebuffer.buffer {
+                    *pixel = *pixel * scale;
+                }
+            }
+            PostEffect::Bloom(bloom) => apply_bloom(bloom, framebuffer),
+            PostEffect::Vignette(amount) => apply_vignette(*amount, framebuffer),
+            PostEffect::Grade(lut) => {
+                for pixel in &mut framebuffer.buffer {
+                    *pixel = lut.sample(*pixel);
+                }
+            }
+        }
+    }
+}
+
+fn apply_bloom(bloom: &Bloom, framebuffer: &mut Framebuffer) {
+    let (width, height) = (framebuffer.width, framebuffer.height);
+    if width == 0 || height == 0 {
+        return;
+    }
+    let small = (
+        width.div_ceil(BLOOM_DOWNSAMPLE),
+        height.div_ceil(BLOOM_DOWNSAMPLE),
+    );
+
+    // Lo que pasa del umbral, promediado por bloques
+    let block = 1.0 / (BLOOM_DOWNSAMPLE * BLOOM_DOWNSAMPLE) as f32;
+    let mut bright = vec![Color::black(); small.0 * small.1];
+    for (index, color) in framebuffer.buffer.iter().enumerate() {
+        let luminance = color.luminance();
+        if luminance > bloom.threshold {
+            let (x, y) = (index % width, index / width);
+            let cell = (y / BLOOM_DOWNSAMPLE) * small.0 + x / BLOOM_DOWNSAMPLE;
+            bright[cell] =
+                bright[cell] + *color * ((luminance - bloom.threshold) / luminance * block);
+        }
+    }
+
+    // El radio abarca tres desvíos de la gaussiana
+    let sigma = (bloom.radius * height as f32 / BLOOM_DOWNSAMPLE as f32 / 3.0).max(0.5);
+    let halo = blur(&bright, small, sigma);
+    for (index, pixel) in framebuffer.buffer.iter_mut().enumerate() {
+        let (x, y) = (index % width, index / width);
+        let glow = bilinear(
+            &halo,
+            small,
+            (x as f32 + 0.5) / BLOOM_DOWNSAMPLE as f32 - 0.5,
+            (y as f32 + 0.5) / BLOOM_DOWNSAMPLE as f32 - 0.5,
+        );
+        *pixel = *pixel + glow * bloom.strength;
+    }
+}
+
+// Desenfoque gaussiano separable: una pasada por filas y otra por columnas. En los bordes los
+// pesos se renormalizan para no oscurecerlos.
+fn blur(source: &[Color], (width, height): (usize, usize), sigma: f32) -> Vec<Color> {
+    let reach = (3.0 * sigma).ceil() as isize;
+    let weights: Vec<f32> = (-reach..=reach)
+        .map(|offset| (-((offset * offset) as f32) / (2.0 * sigma * sigma)).exp())
+        .collect();
+    let pass = |source: &[Color], horizontal: bool| -> Vec<Color> {
+        (0..width * height)
+            .map(|index| {
+                let (x, y) = ((index % width) as isize, (index / width) as isize);
+                let mut sum = Color::black();
+                let mut total = 0.0;
+                for (weight, offset) in weights.iter().zip(-reach..=reach) {
+                    let (sx, sy) = if horizontal {
+                        (x + offset, y)
+                    } else {
+                        (x, y + offset)
+                    };
+                    if sx < 0 || sy < 0 || sx >= width as isize || sy >= height as isize {
+                        continue;
+                    }
+                    sum = sum + source[sy as usize * width + sx as usize] * *weight;
+                    total += weight;
+                }
+                sum * (1.0 / total)
+            })
+            .collect()
+    };
+    let rows = pass(source, true);
+    pass(&rows, false)
+}
+
+fn bilinear(buffer: &[Color], (width, height): (usize, usize), x: f32, y: f32) -> Color {
+    let x = x.clamp(0.0, (width - 1) as f32);
+    let y = y.clamp(0.0, (height - 1) as f32);
+    let (x0, y0) = (x.floor() as usize, y.floor() as usize);
+    let (x1, y1) = ((x0 + 1).min(width - 1), (y0 + 1).min(height - 1));
+    let (tx, ty) = (x - x0 as f32, y - y0 as f32);
+    let at = |x: usize, y: usize| buffer[y * width + x];
+    mix(
+        mix(at(x0, y0), at(x1, y0), tx),
+        mix(at(x0, y1), at(x1, y1), tx),
+        ty,
+    )
+}
+
+// Oscurece hacia las esquinas con una caída cuadrática desde el centro; cada eje va de -1 a
+// 1, así las cuatro esquinas quedan igual de oscuras sea cual sea la proporción
+fn apply_vignette(amount: f32, framebuffer: &mut Framebuffer) {
+    let (width, height) = (framebuffer.width as f32, framebuffer.height as f32);
+    let row = framebuffer.width.max(1);
+    for (index, pixel) in framebuffer.buffer.iter_mut().enumerate() {
+        let x = ((index % row) as f32 + 0.5) / width * 2.0 - 1.0;
+        let y = ((index / row) as f32 + 0.5) / height * 2.0 - 1.0;
+        let distance = (x * x + y * y) * 0.5;
+        *pixel = *pixel * (1.0 - amount * distance).max(0.0);
+    }
+}
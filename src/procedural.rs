@@ -0,0 +1,162 @@
+// procedural.rs
+
+use nalgebra_glm::Vec3;
+
+use crate::color::Color;
+
+// Cuánto se mete el punto dentro de la superficie antes de evaluar el patrón, en bloques.
+// Las caras de los bloques caen justo sobre los bordes de las casillas y sin esto
+// quedarían indecisas entre un color y el otro.
+const SURFACE_OFFSET: f32 = 1e-3;
+const NOISE_OCTAVES: u32 = 4;
+// La suma de octavas casi nunca se acerca a 0 o 1; se estira alrededor de 0.5 para que el
+// patrón llegue a los dos colores
+const NOISE_CONTRAST: f32 = 1.8;
+// Cuánto deforma el ruido los anillos de la madera, en anillos
+const WOOD_WARP: f32 = 0.6;
+// Promedio de `wood_rings` sobre un anillo completo, lo que se ve desde muy lejos
+const WOOD_MEAN: f32 = 0.2734;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatternKind {
+    // Tablero de ajedrez en 3D: cada casilla es un cubo de `size` de lado
+    Checker,
+    // Ruido de valores con varias octavas, detalles de `size` bloques
+    Noise,
+    // Anillos alrededor del eje vertical separados por `size` bloques, deformados con ruido
+    Wood,
+}
+
+impl PatternKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            PatternKind::Checker => "checker",
+            PatternKind::Noise => "noise",
+            PatternKind::Wood => "wood",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "checker" => Some(PatternKind::Checker),
+            "noise" => Some(PatternKind::Noise),
+            "wood" => Some(PatternKind::Wood),
+            _ => None,
+        }
+    }
+}
+
+// Textura calculada al sombrear a partir de la posición en el mundo: no necesita
+// imágenes y sigue de un bloque al siguiente sin costuras
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    pub kind: PatternKind,
+    // El patrón mezcla entre estos dos colores
+    pub colors: [Color; 2],
+    // En bloques
+    pub size: f32,
+}
+
+impl Pattern {
+    // `footprint` es el ancho que cubre el pixel sobre la superficie, en bloques; los
+    // detalles más finos que eso se reemplazan por su promedio en lugar de parpadear
+    pub fn sample(&self, point: &Vec3, normal: &Vec3, footprint: f32) -> Color {
+        let point = (point - normal * SURFACE_OFFSET) / self.size;
+        let width = footprint / self.size;
+        let t = match self.kind {
+            PatternKind::Checker => checker(&point, normal, width),
+            PatternKind::Noise => (fractal_noise(&point, width) - 0.5) * NOISE_CONTRAST + 0.5,
+            PatternKind::Wood => wood(&point, width),
+        }
+        .clamp(0.0, 1.0);
+        self.colors[0] * (1.0 - t) + self.colors[1] * t
+    }
+}
+
+// 0 en las casillas del primer color y 1 en las del segundo
+fn checker(point: &Vec3, normal: &Vec3, width: f32) -> f32 {
+    // A lo largo de la normal el pixel no se extiende
+    let product: f32 = (0..3)
+        .map(|axis| square_wave(point[axis], width * (1.0 - normal[axis].abs())))
+        .product();
+    0.5 - 0.5 * product
+}
+
+// Promedio de una onda cuadrada (1 en [0, 1), -1 en [1, 2)) sobre un tramo de ancho
+// `width` centrado en `x`, a partir de su integral (una onda triangular)
+fn square_wave(x: f32, width: f32) -> f32 {
+    let triangle = |x: f32| ((x * 0.5).rem_euclid(1.0) - 0.5).abs();
+    if width < 1e-4 {
+        return if (x * 0.5).rem_euclid(1.0) < 0.5 {
+            1.0
+        } else {
+            -1.0
+        };
+    }
+    2.0 * (triangle(x - 0.5 * width) - triangle(x + 0.5 * width)) / width
+}
+
+// Suma de octavas de ruido de valores entre 0 y 1; las octavas más finas que el pixel se
+// desvanecen hacia 0.5. Las nubes también lo usan, con `width` 0.
+pub fn fractal_noise(point: &Vec3, width: f32) -> f32 {
+    let mut sum = 0.0;
+    let mut total = 0.0;
+    let mut amplitude = 1.0;
+    let mut frequency = 1.0;
+    for octave in 0..NOISE_OCTAVES {
+        let detail = (1.0 - width * frequency).clamp(0.0, 1.0);
+        // Cada octava se desplaza para que sus celdas no coincidan con las de la anterior
+        let offset = octave as f32 * 17.31;
+        let noise = value_noise(&(point * frequency + Vec3::new(offset, offset, offset)));
+        sum += amplitude * (noise * detail + 0.5 * (1.0 - detail));
+        total += amplitude;
+        amplitude *= 0.5;
+        frequency *= 2.0;
+    }
+    sum / total
+}
+
+fn wood(point: &Vec3, width: f32) -> f32 {
+    let warp = (fractal_noise(&(point * 0.5), width * 0.5) - 0.5) * WOOD_WARP;
+    let radius = (point.x * point.x + point.z * point.z).sqrt() + warp;
+    // Con más de un anillo por pixel se ve el tono medio
+    let detail = (1.0 - width).clamp(0.0, 1.0);
+    wood_rings(radius) * detail + WOOD_MEAN * (1.0 - detail)
+}
+
+// Vetas finas y oscuras sobre fondo claro: 1 en el centro de cada veta
+fn wood_rings(radius: f32) -> f32 {
+    let wave = (radius * std::f32::consts::TAU).cos() * 0.5 + 0.5;
+    wave.powi(4)
+}
+
+// Ruido de valores 3D entre 0 y 1: un valor al azar en cada vértice de la cuadrícula,
+// interpolado suavemente
+fn value_noise(point: &Vec3) -> f32 {
+    let cell = [point.x.floor(), point.y.floor(), point.z.floor()];
+    let local = [point.x - cell[0], point.y - cell[1], point.z - cell[2]];
+    let fade = local.map(|t| t * t * (3.0 - 2.0 * t));
+    let cell = cell.map(|value| value as i32);
+
+    let corner =
+        |dx: i32, dy: i32, dz: i32| lattice_value(cell[0] + dx, cell[1] + dy, cell[2] + dz);
+    let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+    let edge = |dy: i32, dz: i32| lerp(corner(0, dy, dz), corner(1, dy, dz), fade[0]);
+    let face = |dz: i32| lerp(edge(0, dz), edge(1, dz), fade[1]);
+    lerp(face(0), face(1), fade[2])
+}
+
+// Valor fijo entre 0 y 1 para cada vértice, con un hash de enteros. El cielo dinámico lo
+// usa para ubicar las estrellas.
+pub fn lattice_value(x: i32, y: i32, z: i32) -> f32 {
+    let mut hash = (x as u32)
+        .wrapping_mul(0x8DA6_B343)
+        .wrapping_add((y as u32).wrapping_mul(0xD816_3841))
+        .wrapping_add((z as u32).wrapping_mul(0xCB1A_B31F));
+    hash ^= hash >> 15;
+    hash = hash.wrapping_mul(0x2C1B_3C6D);
+    hash ^= hash >> 12;
+    hash = hash.wrapping_mul(0x297A_2D39);
+    hash ^= hash >> 15;
+    (hash >> 8) as f32 / (1u32 << 24) as f32
+}
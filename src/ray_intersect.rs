@@ -1,25 +1,63 @@
 
 use nalgebra_glm::Vec3;
+use crate::assets::{sample_mipmapped, Texture};
+use crate::color::Color;
 use crate::material::Material;
 
+// Material de las intersecciones vacías; nunca se usa para sombrear
+static NO_MATERIAL: Material = Material::black();
+// Límite del estiramiento del pixel sobre caras vistas casi de costado
+const MIN_COSINE: f32 = 0.05;
+// Distancia a la que salen de la superficie los rayos de un impacto si el render no pide otra
+pub const ORIGIN_BIAS: f32 = 1e-4;
+
+// El material se toma prestado de la paleta del mundo o del cubo, sin copiar sus texturas
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
-pub struct Intersect {
+pub struct Intersect<'a> {
     pub point: Vec3,
     pub normal: Vec3,
     pub distance: f32,
     pub is_intersecting: bool,
-    pub material: Material,
+    pub material: &'a Material,
+    // Color difuso en el punto; la textura del material se aplica con `apply_textures`
+    pub diffuse: Color,
+    // Emisión y brillo (de 0 a 1) en el punto, según los mapas de emisión y especular
+    pub emission: Color,
+    pub shine: f32,
+    // Coordenadas de textura en la cara y tamaño de la cara en el mundo
+    pub uv: (f32, f32),
+    pub face_size: f32,
+    // Esquinas de la caja impactada, para medir cuánto recorre dentro un rayo refractado
+    pub bounds: (Vec3, Vec3),
+    // Cuánto se despegan de la superficie los rayos que salen del punto (sombras, reflejos y
+    // refracciones), para que no choquen con la misma cara; lo ajusta el render
+    pub bias: f32,
 }
 
-impl Intersect {
-    pub fn new(point: Vec3, normal: Vec3, distance: f32, material: Material) -> Self {
+impl<'a> Intersect<'a> {
+    pub fn new(
+        point: Vec3,
+        normal: Vec3,
+        distance: f32,
+        material: &'a Material,
+        uv: (f32, f32),
+        face_size: f32,
+        bounds: (Vec3, Vec3),
+    ) -> Self {
         Intersect {
             point,
             normal,
             distance,
             is_intersecting: true,
             material,
+            diffuse: material.diffuse,
+            emission: material.emission,
+            shine: 1.0,
+            uv,
+            face_size,
+            bounds,
+            bias: ORIGIN_BIAS,
         }
     }
 
@@ -29,11 +67,84 @@ impl Intersect {
             normal: Vec3::zeros(),
             distance: 0.0,
             is_intersecting: false,
-            material: Material::black(),
+            material: &NO_MATERIAL,
+            diffuse: Color::black(),
+            emission: Color::black(),
+            shine: 1.0,
+            uv: (0.0, 0.0),
+            face_size: 1.0,
+            bounds: (Vec3::zeros(), Vec3::zeros()),
+            bias: ORIGIN_BIAS,
+        }
+    }
+
+    // Aplica la textura y el normal map del material. Solo hace falta en los puntos que se
+    // van a sombrear, no en los rayos de sombra. `footprint` es el ancho que cubre el pixel
+    // en el punto, en unidades del mundo, y elige el nivel de mipmap (0 usa la imagen original)
+    // o cuánto detalle de un patrón procedural se promedia.
+    pub fn apply_textures(&mut self, ray_direction: &Vec3, footprint: f32) {
+        let material = self.material;
+        if material.texture.is_none()
+            && material.normal_map.is_none()
+            && material.emission_map.is_none()
+            && material.specular_map.is_none()
+        {
+            return;
+        }
+
+        // Visto de costado, el pixel se estira sobre la cara
+        let cosine = ray_direction.normalize().dot(&self.normal).abs().max(MIN_COSINE);
+        let footprint = footprint / cosine;
+        let footprint_uv = footprint / self.face_size;
+        let (u, v) = self.uv;
+
+        match &material.texture {
+            Some(Texture::Image(image)) => {
+                let [r, g, b, _] =
+                    sample_mipmapped(image, u, v, material.texture_filter, footprint_uv);
+                self.diffuse = Color::new(r, g, b);
+            }
+            Some(Texture::Pattern(pattern)) => {
+                self.diffuse = pattern.sample(&self.point, &self.normal, footprint);
+            }
+            None => {}
+        }
+
+        if let Some(emission_map) = &material.emission_map {
+            let [r, g, b, _] =
+                sample_mipmapped(emission_map, u, v, material.texture_filter, footprint_uv);
+            self.emission = material.emission * Color::new(r, g, b);
+        }
+
+        if let Some(specular_map) = &material.specular_map {
+            self.shine =
+                sample_mipmapped(specular_map, u, v, material.texture_filter, footprint_uv)[0];
+        }
+
+        // Ajustar la normal con el normal map si está disponible
+        if let Some(normal_map) = &material.normal_map {
+            let pixel = sample_mipmapped(normal_map, u, v, material.normal_map_filter, footprint_uv);
+
+            let normal_tangent = Vec3::new(
+                pixel[0] * 2.0 - 1.0,
+                pixel[1] * 2.0 - 1.0,
+                pixel[2] * 2.0 - 1.0,
+            )
+            .normalize();
+
+            // Asumir que la normal calculada ya actúa como la base para ajustar
+            let normal = self.normal;
+            let tangent = normal.cross(&Vec3::new(0.0, 1.0, 0.0)).normalize();
+            let bitangent = normal.cross(&tangent);
+
+            self.normal = (tangent * normal_tangent.x
+                + bitangent * normal_tangent.y
+                + normal * normal_tangent.z)
+                .normalize();
         }
     }
 }
 
 pub trait RayIntersect {
-    fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Intersect;
+    fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Intersect<'_>;
 }
\ No newline at end of file
@@ -0,0 +1,2138 @@
+// render.rs
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use nalgebra_glm::Vec3;
+use std::f32::consts::PI;
+use std::ops::Range;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use crate::aov::{AovPixel, Aovs};
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::cube::{entry_distances, Cube, LANES};
+use crate::debug_view::{heat, DebugView, MAX_HEAT_RAYS};
+use crate::framebuffer::Framebuffer;
+use crate::light::Light;
+use crate::light_cache::CachedFace;
+use crate::material::{dulled_roughness, Material, ShadingModel, TransparencyMode};
+use crate::packet::{PixelBlock, RayPacket, PACKET_SIDE};
+use crate::ray_intersect::{Intersect, RayIntersect, ORIGIN_BIAS};
+use crate::sampler::{ggx_normal, orthonormal_basis, stratified, stratified_2d, PixelSampler};
+use crate::scene::Scene;
+use crate::secondary::{SecondaryBuffer, SecondarySample};
+
+// Error relativo de las coordenadas con el que crece el sesgo adaptativo: unas decenas de
+// veces la precisión de un f32
+const ADAPTIVE_BIAS_PRECISION: f32 = 32.0 * f32::EPSILON;
+// Rebotes de reflexión y refracción del render normal
+pub const MAX_DEPTH: u32 = 3;
+// Rebotes de los rayos que salen de materiales transparentes: una cadena de vidrio dentro
+// del agua atraviesa varias superficies antes de llegar a algo opaco
+pub const MAX_REFRACTION_DEPTH: u32 = 8;
+// Rebote desde el que juega la ruleta: los reflejos de lo que ve la cámara siempre se trazan
+const ROULETTE_START_DEPTH: u32 = 2;
+// Probabilidad mínima de seguir, para que los rayos que sobreviven no salgan como puntos
+// demasiado brillantes
+const MIN_SURVIVAL: f32 = 0.05;
+// Muestras por eje en cada portal de luz del cielo
+const PORTAL_SAMPLES: u32 = 2;
+// Rayos de sombra hacia luces de área: mínimo, máximo y cuántos más por unidad de penumbra
+const MIN_SHADOW_SAMPLES: u32 = 4;
+const MAX_SHADOW_SAMPLES: u32 = 32;
+const PENUMBRA_SAMPLE_DENSITY: f32 = 16.0;
+const GOLDEN_ANGLE: f32 = 2.399_963;
+const GOLDEN_RATIO: f32 = 0.618_034;
+// Capas de transparencia aproximada que se atraviesan antes de sombrear lo de detrás
+const MAX_BLENDED_LAYERS: u32 = 8;
+// Con menos rugosidad el brillo GGX de una luz puntual se vuelve un punto de un pixel
+const MIN_ROUGHNESS: f32 = 0.045;
+// Los reflejos PBR más tenues que esto no lanzan rayo
+const MIN_REFLECTANCE: f32 = 0.01;
+// Rayos de cada reflejo o refracción rugosos en el primer impacto según la calidad del
+// render; en los rebotes siguientes se lanza uno solo
+const PREVIEW_GLOSSY_SAMPLES: u32 = 1;
+const GLOSSY_SAMPLES: u32 = 4;
+const HIGH_QUALITY_GLOSSY_SAMPLES: u32 = 12;
+// Pasos con los que se recorre la niebla en cada rayo de cámara según la calidad del render
+const PREVIEW_FOG_STEPS: u32 = 8;
+const FOG_STEPS: u32 = 24;
+const HIGH_QUALITY_FOG_STEPS: u32 = 48;
+// Fracción del color del cenit que ilumina la niebla desde todas las direcciones, también
+// donde no llega el sol
+const FOG_AMBIENT: f32 = 0.4;
+// Bloques contiguos de un mismo medio que un rayo refractado atraviesa sin sombrear sus caras
+const MAX_MEDIUM_BLOCKS: u32 = 64;
+// Medios anidados que se recuerdan (agua dentro de vidrio dentro de agua...)
+const MAX_NESTED_MEDIA: usize = 4;
+// Reflexiones totales internas que se siguen antes de dar el rayo por perdido
+const MAX_INTERNAL_REFLECTIONS: u32 = 4;
+
+fn offset_origin(intersect: &Intersect, direction: &Vec3) -> Vec3 {
+    let offset = intersect.normal * intersect.bias;
+    if direction.dot(&intersect.normal) < 0.0 {
+        intersect.point - offset
+    } else {
+        intersect.point + offset
+    }
+}
+
+fn reflect(incident: &Vec3, normal: &Vec3) -> Vec3 {
+    incident - 2.0 * incident.dot(normal) * normal
+}
+
+// Dirección al pasar de un medio con índice n1 a otro con n2, con `eta` = n1 / n2. La
+// normal puede apuntar a cualquiera de los dos lados. None si hay reflexión total interna.
+fn refract(incident: &Vec3, normal: &Vec3, eta: f32) -> Option<Vec3> {
+    let cosi = -incident.dot(normal).clamp(-1.0, 1.0);
+    let (cosi, normal) = if cosi < 0.0 {
+        (-cosi, -normal)
+    } else {
+        (cosi, *normal)
+    };
+
+    let k = 1.0 - eta * eta * (1.0 - cosi * cosi);
+    (k >= 0.0).then(|| eta * incident + (eta * cosi - k.sqrt()) * normal)
+}
+
+// Normal de la microfaceta para el rayo i de n de un reflejo o refracción rugosos. Se
+// reparten con la distribución GGX alrededor de la normal, en una espiral girada por el
+// ruido del pixel como los puntos de las luces de área.
+fn glossy_normal(normal: &Vec3, roughness: f32, i: u32, n: u32, noise: f32) -> Vec3 {
+    if roughness <= 0.0 {
+        return *normal;
+    }
+    let u = stratified(i, n, (noise + GOLDEN_RATIO).fract());
+    let angle = i as f32 * GOLDEN_ANGLE + noise * 2.0 * PI;
+    ggx_normal(normal, roughness, u, angle)
+}
+
+// Peso de un reflejo que salió de una normal de `glossy_normal`, G·(v·h) / ((n·v)(n·h)):
+// como las normales ya siguen el lóbulo GGX, solo queda bajar los rayos rasantes que las
+// microfacetas vecinas taparían. Con una sola muestra el peso no cambia nada.
+fn glossy_weight(normal: &Vec3, view: &Vec3, reflected: &Vec3, roughness: f32) -> f32 {
+    let half = (view + reflected).normalize();
+    let n_dot_v = normal.dot(view).abs().max(1e-4);
+    let n_dot_l = normal.dot(reflected).abs().max(1e-4);
+    let n_dot_h = normal.dot(&half).abs().max(1e-4);
+    smith_geometry(n_dot_v, n_dot_l, roughness) * half.dot(view).abs() / (n_dot_v * n_dot_h)
+}
+
+// Distancia hasta el borde de la caja de un rayo que sale desde un punto de su interior, y
+// la normal hacia afuera de la cara por la que sale
+fn box_exit(
+    (min_corner, max_corner): &(Vec3, Vec3),
+    origin: &Vec3,
+    direction: &Vec3,
+) -> (f32, Vec3) {
+    let mut exit = (f32::INFINITY, Vec3::zeros());
+    for axis in (0..3).filter(|&axis| direction[axis] != 0.0) {
+        let (plane, side) = if direction[axis] > 0.0 {
+            (max_corner[axis], 1.0)
+        } else {
+            (min_corner[axis], -1.0)
+        };
+        let distance = ((plane - origin[axis]) / direction[axis]).max(0.0);
+        if distance < exit.0 {
+            exit.0 = distance;
+            exit.1 = Vec3::zeros();
+            exit.1[axis] = side;
+        }
+    }
+    exit
+}
+
+// Hasta dónde llegó un rayo por el interior de un medio
+struct MediumPath<'a> {
+    // Punto desde el que sigue el rayo, dentro de la caja `bounds`
+    origin: Vec3,
+    bounds: (Vec3, Vec3),
+    travelled: f32,
+    // Lo primero que choca desde `origin`
+    next_hit: Option<Intersect<'a>>,
+}
+
+// Sigue un rayo refractado por el interior de un medio desde la caja `bounds`: las caras
+// entre bloques contiguos del mismo material se atraviesan sin sombrearlas, como en el agua
+// profunda. `bias` es el sesgo de la superficie por la que entró el rayo.
+fn through_medium<'a>(
+    material: &Material,
+    (origin, direction): (Vec3, &Vec3),
+    bounds: (Vec3, Vec3),
+    bias: f32,
+    scene: &'a Scene,
+    objects: &'a [Cube],
+) -> MediumPath<'a> {
+    let mut path = MediumPath {
+        origin,
+        bounds,
+        travelled: 0.0,
+        next_hit: None,
+    };
+    for _ in 0..MAX_MEDIUM_BLOCKS {
+        let (inside, _) = box_exit(&path.bounds, &path.origin, direction);
+        path.next_hit = closest_hit(&path.origin, direction, scene, objects);
+        match &path.next_hit {
+            // Solo si la caja siguiente empieza donde termina esta
+            Some(hit) if std::ptr::eq(hit.material, material) && hit.distance <= inside + bias => {
+                path.travelled += hit.distance;
+                path.bounds = hit.bounds;
+                path.origin = hit.point + direction * bias;
+            }
+            _ => return path,
+        }
+    }
+    path.next_hit = closest_hit(&path.origin, direction, scene, objects);
+    path
+}
+
+// Color que llega por un rayo refractado hacia el interior de `intersect`, con la
+// absorción del medio; `settings.media` ya incluye el medio. Adentro el rayo sigue hasta
+// chocar con otra cosa (algo sumergido o un medio pegado) o hasta salir por una cara, donde
+// se refracta hacia el medio de afuera o, si es muy rasante, se refleja hacia adentro.
+fn refracted(
+    intersect: &Intersect,
+    (origin, direction): (Vec3, Vec3),
+    scene: &Scene,
+    objects: &[Cube],
+    depth: u32,
+    settings: TraceSettings,
+    noise: f32,
+) -> Color {
+    let material = intersect.material;
+    let outer = TraceSettings {
+        media: settings.media.exit(),
+        ..settings
+    };
+    let eta = settings.media.current() / outer.media.current();
+    let mut origin = origin;
+    let mut direction = direction;
+    let mut bounds = intersect.bounds;
+    let mut travelled = 0.0;
+
+    for _ in 0..=MAX_INTERNAL_REFLECTIONS {
+        let path = through_medium(
+            material,
+            (origin, &direction),
+            bounds,
+            intersect.bias,
+            scene,
+            objects,
+        );
+        travelled += path.travelled;
+        let (inside, exit_normal) = box_exit(&path.bounds, &path.origin, &direction);
+
+        if let Some(hit) = path
+            .next_hit
+            .filter(|hit| hit.distance <= inside + intersect.bias)
+        {
+            let media = settings
+                .media
+                .leaving(hit.distance >= inside - intersect.bias);
+            let color = cast(
+                &path.origin,
+                &direction,
+                scene,
+                objects,
+                depth,
+                TraceSettings { media, ..settings },
+                noise,
+            );
+            return color * transmittance(material.absorption, travelled + hit.distance);
+        }
+
+        travelled += inside;
+        let exit_point = path.origin + direction * inside;
+        match refract(&direction, &exit_normal, eta) {
+            Some(exit_dir) => {
+                let color = cast(
+                    &(exit_point + exit_normal * intersect.bias),
+                    &exit_dir.normalize(),
+                    scene,
+                    objects,
+                    depth,
+                    outer,
+                    noise,
+                );
+                return color * transmittance(material.absorption, travelled);
+            }
+            // Reflexión total interna: el rayo vuelve a cruzar el medio
+            None => {
+                direction = reflect(&direction, &exit_normal).normalize();
+                origin = exit_point - exit_normal * intersect.bias;
+                bounds = path.bounds;
+            }
+        }
+    }
+    Color::black()
+}
+
+// Beer-Lambert: fracción de cada canal que sobrevive a `distance` bloques de un medio que
+// absorbe `absorption` por bloque
+fn transmittance(absorption: Color, distance: f32) -> Color {
+    let channel = |absorbed: f32| (1.0 - absorbed).clamp(0.0, 1.0).powf(distance);
+    Color::new(
+        channel(absorption.r),
+        channel(absorption.g),
+        channel(absorption.b),
+    )
+}
+
+// Fracción reflejada al pasar del medio con índice `etai` al de índice `etat`
+fn fresnel(incident: &Vec3, normal: &Vec3, etai: f32, etat: f32) -> f32 {
+    let mut cosi = incident.dot(normal).clamp(-1.0, 1.0);
+    let sint = etai / etat * (1.0 - cosi * cosi).sqrt();
+
+    if sint >= 1.0 {
+        1.0
+    } else {
+        let cost = (1.0 - sint * sint).sqrt();
+        cosi = cosi.abs();
+        let rs = ((etat * cosi) - (etai * cost)) / ((etat * cosi) + (etai * cost));
+        let rp = ((etai * cosi) - (etat * cost)) / ((etai * cosi) + (etat * cost));
+        (rs * rs + rp * rp) / 2.0
+    }
+}
+
+// Fracción de cada canal de la luz que llega al punto: blanco sin sombra, negro en la
+// sombra y del color de los vidrios que atraviesa por el camino
+fn cast_shadow(
+    intersect: &Intersect,
+    scene: &Scene,
+    objects: &[Cube],
+    light: &Light,
+    noise: f32,
+) -> Color {
+    if light.radius > 0.0 {
+        return area_light_shadow(intersect, scene, objects, light, noise);
+    }
+
+    let light_dir = (light.position - intersect.point).normalize();
+    let light_distance = (light.position - intersect.point).magnitude();
+
+    let shadow_ray_origin = offset_origin(intersect, &light_dir);
+
+    let (filter, blocker) = shadow_ray(
+        &shadow_ray_origin,
+        &light_dir,
+        light_distance,
+        intersect.bias,
+        scene,
+        objects,
+    );
+    // La sombra se aclara según la distancia al obstáculo más cercano
+    blocker.map_or(filter, |distance| {
+        let distance_ratio = distance / light_distance;
+        filter * distance_ratio.powf(2.0).min(1.0)
+    })
+}
+
+// Sigue un rayo de sombra hasta `max_distance`: devuelve el color que dejan pasar los
+// vidrios de color que cruza y la distancia al primer obstáculo opaco, si hay alguno. Al
+// atravesar un vidrio se despega de sus caras `bias`, el sesgo del punto sombreado.
+fn shadow_ray(
+    origin: &Vec3,
+    direction: &Vec3,
+    max_distance: f32,
+    bias: f32,
+    scene: &Scene,
+    objects: &[Cube],
+) -> (Color, Option<f32>) {
+    let mut filter = Color::new(1.0, 1.0, 1.0);
+    let mut origin = *origin;
+    let mut travelled = 0.0;
+    for _ in 0..MAX_MEDIUM_BLOCKS {
+        let Some(mut hit) = closest_hit(&origin, direction, scene, objects)
+            .filter(|hit| travelled + hit.distance < max_distance)
+        else {
+            return (filter, None);
+        };
+        if !hit.material.stained {
+            return (filter, Some(travelled + hit.distance));
+        }
+        // Cada bloque de vidrio tiñe una vez: el rayo sigue desde la cara por la que sale
+        hit.apply_textures(direction, 0.0);
+        filter = filter * hit.diffuse * hit.material.transmission();
+        let inside = hit.point + direction * bias;
+        let (exit, _) = box_exit(&hit.bounds, &inside, direction);
+        travelled += hit.distance + exit + 2.0 * bias;
+        origin = inside + direction * (exit + bias);
+    }
+    (Color::black(), Some(travelled))
+}
+
+// Punto i de n sobre el disco de la luz visto desde el punto sombreado (espiral de Fibonacci)
+// La espiral se gira según el ruido azul del pixel para que pixeles vecinos no usen los mismos puntos
+fn light_disk_point(
+    light: &Light,
+    tangent: &Vec3,
+    bitangent: &Vec3,
+    i: u32,
+    n: u32,
+    noise: f32,
+) -> Vec3 {
+    let radius = light.radius * ((i as f32 + 0.5) / n as f32).sqrt();
+    let angle = i as f32 * GOLDEN_ANGLE + noise * 2.0 * PI;
+    light.position + (tangent * angle.cos() + bitangent * angle.sin()) * radius
+}
+
+// Sombra suave de una luz esférica. Primero se lanzan pocas muestras; si todas coinciden
+// el punto está totalmente iluminado o en la umbra. Si no, está en la penumbra y se
+// estima su ancho con la distancia al obstáculo para decidir cuántas muestras más lanzar.
+fn area_light_shadow(
+    intersect: &Intersect,
+    scene: &Scene,
+    objects: &[Cube],
+    light: &Light,
+    noise: f32,
+) -> Color {
+    let to_light = light.position - intersect.point;
+    let light_distance = to_light.magnitude();
+    let light_dir = to_light / light_distance;
+
+    let (tangent, bitangent) = orthonormal_basis(&light_dir);
+
+    // Devuelve la luz que llega sumada de las `count` muestras, cuántas están tapadas y el
+    // obstáculo más cercano
+    let trace = |count: u32| {
+        let mut lit = Color::black();
+        let mut blocked = 0;
+        let mut nearest_blocker = f32::INFINITY;
+        for i in 0..count {
+            let target = light_disk_point(light, &tangent, &bitangent, i, count, noise);
+            let to_target = target - intersect.point;
+            let distance = to_target.magnitude();
+            let direction = to_target / distance;
+            let origin = offset_origin(intersect, &direction);
+            match shadow_ray(
+                &origin,
+                &direction,
+                distance,
+                intersect.bias,
+                scene,
+                objects,
+            ) {
+                (_, Some(blocker)) => {
+                    blocked += 1;
+                    nearest_blocker = nearest_blocker.min(blocker);
+                }
+                (filter, None) => lit = lit + filter,
+            }
+        }
+        (lit, blocked, nearest_blocker)
+    };
+
+    let (lit, blocked, nearest_blocker) = trace(MIN_SHADOW_SAMPLES);
+    if blocked == 0 {
+        return lit * (1.0 / MIN_SHADOW_SAMPLES as f32);
+    }
+    if blocked == MIN_SHADOW_SAMPLES {
+        return Color::black();
+    }
+
+    // Ancho de la penumbra en el punto sombreado por semejanza de triángulos
+    let blocker = nearest_blocker.max(intersect.bias);
+    let penumbra = light.radius * 2.0 * (light_distance - blocker) / blocker;
+    let samples = (MIN_SHADOW_SAMPLES as f32 + penumbra * PENUMBRA_SAMPLE_DENSITY)
+        .ceil()
+        .min(MAX_SHADOW_SAMPLES as f32) as u32;
+
+    let (lit, _, _) = trace(samples);
+    lit * (1.0 / samples as f32)
+}
+
+// Reduce una muestra estocástica a la luminancia `max` conservando su tono; con 0 no limita
+fn clamp_radiance(sample: Color, max: f32) -> Color {
+    let luminance = sample.luminance();
+    if max > 0.0 && luminance > max {
+        sample * (max / luminance)
+    } else {
+        sample
+    }
+}
+
+fn is_occluded(
+    origin: &Vec3,
+    direction: &Vec3,
+    max_distance: f32,
+    scene: &Scene,
+    objects: &[Cube],
+) -> bool {
+    let blocks =
+        |intersect: Intersect| intersect.is_intersecting && intersect.distance < max_distance;
+    blocks(scene.world.ray_intersect(origin, direction))
+        || objects.chunks(LANES).any(|group| {
+            let distances = entry_distances(group, origin, direction);
+            group.iter().zip(distances).any(|(object, distance)| {
+                distance < max_distance && blocks(object.ray_intersect(origin, direction))
+            })
+        })
+}
+
+// Filtro de sombra de cada luz activa y luz de los portales en el punto de `intersect`, lo
+// que la luz directa traza en cada punto y `LightCache` guarda por cara
+pub fn surface_shadows(
+    intersect: &Intersect,
+    scene: &Scene,
+    objects: &[Cube],
+    noise: f32,
+) -> (Vec<Color>, Color) {
+    let shadows = scene
+        .active_lights()
+        .map(|light| cast_shadow(intersect, scene, objects, light, noise))
+        .collect();
+    let portal = if scene.portals.is_empty() {
+        Color::black()
+    } else {
+        portal_sky_light(intersect, scene, objects, noise)
+    };
+    (shadows, portal)
+}
+
+// Luz del cielo que llega al punto a través de los portales (ventanas) de la escena.
+// Cada portal se muestrea con una cuadrícula fija y cada muestra aporta el color del cielo
+// en esa dirección, pesado por el ángulo sólido que cubre. Cerca del portal el ángulo
+// sólido crece sin límite, así que cada muestra se recorta con `firefly_clamp`.
+fn portal_sky_light(intersect: &Intersect, scene: &Scene, objects: &[Cube], noise: f32) -> Color {
+    let mut light = Color::black();
+    let sample_weight = 1.0 / (PORTAL_SAMPLES * PORTAL_SAMPLES) as f32;
+
+    for portal in &scene.portals {
+        let portal_normal = portal.normal();
+        let area = portal.area();
+
+        let count = PORTAL_SAMPLES * PORTAL_SAMPLES;
+        for i in 0..count {
+            // Cuadrícula desplazada dentro de cada celda según el ruido del pixel
+            let (u, v) = stratified_2d(i, count, (noise, (noise + GOLDEN_RATIO).fract()));
+            let target = portal.point(u, v);
+            let to_portal = target - intersect.point;
+            let distance = to_portal.magnitude();
+            if distance <= intersect.bias {
+                continue;
+            }
+            let direction = to_portal / distance;
+
+            let cos_surface = intersect.normal.dot(&direction);
+            if cos_surface <= 0.0 {
+                continue;
+            }
+
+            let origin = offset_origin(intersect, &direction);
+            if is_occluded(&origin, &direction, distance, scene, objects) {
+                continue;
+            }
+
+            // Estimación del portal completo a partir de esta muestra
+            let solid_angle = area * portal_normal.dot(&direction).abs() / (distance * distance);
+            let estimate = scene.sky_color(&origin, &direction)
+                * (cos_surface * solid_angle.min(2.0 * PI) / PI);
+            light = light + clamp_radiance(estimate, scene.firefly_clamp) * sample_weight;
+        }
+    }
+
+    light
+}
+
+// Niebla a lo largo de un rayo de cámara hasta `distance`: qué fracción de lo de detrás
+// se sigue viendo y cuánta luz suma la niebla. El tramo se recorre en `steps` pasos
+// desplazados según el ruido del pixel y en cada uno se prueba si llega el sol (la primera
+// luz); así las sombras de los bloques quedan como haces oscuros entre haces de luz.
+fn fog_scattering(
+    ray_origin: &Vec3,
+    ray_direction: &Vec3,
+    distance: f32,
+    scene: &Scene,
+    objects: &[Cube],
+    steps: u32,
+    noise: f32,
+) -> (f32, Color) {
+    let Some(fog) = &scene.fog else {
+        return (1.0, Color::black());
+    };
+    // El último paso no llega a la superficie, donde el rayo de sombra chocaría con ella
+    let step = (distance - ORIGIN_BIAS).min(fog.distance).max(0.0) / steps.max(1) as f32;
+    let step_transmittance = (-fog.density * step).exp();
+    let ambient = fog.color
+        * scene
+            .skybox
+            .get_color_from_direction(&Vec3::new(0.0, 1.0, 0.0))
+        * FOG_AMBIENT;
+    let sun = scene.lights.first().map(|sun| {
+        let cosine = ray_direction.dot(&(sun.position - ray_origin).normalize());
+        let intensity = sun.intensity * sun.intensity_scale * fog.phase(cosine);
+        (sun.position, fog.color * sun.color * intensity)
+    });
+
+    let mut transmittance = 1.0;
+    let mut light = Color::black();
+    for i in 0..steps {
+        let point = ray_origin + ray_direction * ((i as f32 + noise) * step);
+        let mut scattered = ambient;
+        if let Some((sun_position, sun_light)) = sun {
+            let to_sun = sun_position - point;
+            let sun_distance = to_sun.magnitude();
+            if !is_occluded(
+                &point,
+                &(to_sun / sun_distance),
+                sun_distance,
+                scene,
+                objects,
+            ) {
+                scattered = scattered + sun_light;
+            }
+        }
+        light = light + scattered * (transmittance * (1.0 - step_transmittance));
+        transmittance *= step_transmittance;
+    }
+    (transmittance, light)
+}
+
+// Ancho de un rayo a lo largo de su recorrido (un cono): `width` en el origen y `spread`
+// por unidad de distancia. Indica cuánto de la textura cae en un pixel para elegir el mipmap.
+#[derive(Debug, Clone, Copy, Default)]
+struct RayCone {
+    width: f32,
+    spread: f32,
+}
+
+impl RayCone {
+    fn width_at(&self, distance: f32) -> f32 {
+        self.width + self.spread * distance
+    }
+
+    // El mismo cono continuado desde un punto a esa distancia, para reflexiones y refracciones
+    fn continued(&self, distance: f32) -> Self {
+        RayCone {
+            width: self.width_at(distance),
+            spread: self.spread,
+        }
+    }
+}
+
+// Índices de refracción de los medios en los que está metido un rayo, del más externo al
+// actual; vacía es el aire. Un rayo que entra a un vidrio sumergido apila el vidrio sobre
+// el agua y al salir del vidrio vuelve al agua.
+#[derive(Debug, Clone, Copy)]
+struct MediumStack {
+    iors: [f32; MAX_NESTED_MEDIA],
+    len: usize,
+    // El rayo deja el medio actual en la próxima superficie porque las dos cajas se tocan
+    // (agua pegada a vidrio): el medio siguiente reemplaza al actual en lugar de apilarse
+    leaving: bool,
+}
+
+impl MediumStack {
+    const fn air() -> Self {
+        MediumStack {
+            iors: [1.0; MAX_NESTED_MEDIA],
+            len: 0,
+            leaving: false,
+        }
+    }
+
+    fn current(&self) -> f32 {
+        match self.len {
+            0 => 1.0,
+            len => self.iors[len - 1],
+        }
+    }
+
+    fn enter(mut self, ior: f32) -> Self {
+        // Con el mismo índice se sigue en el mismo medio, como al pasar de un bloque de agua a
+        // otro después de cruzar algo sumergido. Pasados los anidados que se recuerdan, el
+        // más interno se reemplaza.
+        let same = self.len > 0 && self.current() == ior;
+        if (same || self.leaving || self.len == MAX_NESTED_MEDIA) && self.len > 0 {
+            self.len -= 1;
+        }
+        self.iors[self.len] = ior;
+        self.len += 1;
+        self.leaving = false;
+        self
+    }
+
+    fn exit(mut self) -> Self {
+        self.len = self.len.saturating_sub(1);
+        self.leaving = false;
+        self
+    }
+
+    fn leaving(mut self, leaving: bool) -> Self {
+        self.leaving = leaving;
+        self
+    }
+}
+
+// Hasta dónde llega un rayo, si puede usar la transparencia aproximada, qué ancho tiene,
+// cuántos rayos lanzan los reflejos rugosos del primer impacto, en qué medios está, con
+// cuántos pasos se recorre la niebla y cuántas muestras por eje tiene cada pixel. `view`
+// elige si los pixeles muestran la imagen o un dato de depuración, y `rays` cuenta los
+// rayos trazados para el mapa de rebotes.
+#[derive(Debug, Clone, Copy)]
+struct TraceSettings<'a> {
+    max_depth: u32,
+    refraction_depth: u32,
+    // Rebotes hasta los que sigue el rayo actual, según el material del que salió
+    depth_limit: u32,
+    // Fracción del color del pixel que trae el rayo actual, producto de los reflejos y
+    // transparencias que lo desviaron
+    throughput: f32,
+    shadows: bool,
+    blended_transparency: bool,
+    cone: RayCone,
+    glossy_samples: u32,
+    media: MediumStack,
+    fog_steps: u32,
+    samples: u32,
+    pass: u32,
+    view: DebugView,
+    rays: Option<&'a AtomicU32>,
+    secondary_scale: usize,
+    bias: SurfaceBias,
+    // Reflejos y refracciones ya trazados en una cuadrícula reducida, que los rayos de
+    // cámara interpolan en lugar de trazar los suyos
+    secondary: Option<&'a SecondaryBuffer>,
+}
+
+impl TraceSettings<'_> {
+    fn exact(settings: &RenderSettings) -> Self {
+        TraceSettings {
+            max_depth: settings.max_depth,
+            refraction_depth: settings.refraction_depth,
+            depth_limit: settings.max_depth,
+            throughput: 1.0,
+            shadows: settings.shadows,
+            blended_transparency: false,
+            cone: RayCone::default(),
+            glossy_samples: settings.glossy_samples,
+            media: MediumStack::air(),
+            fog_steps: settings.fog_steps,
+            samples: settings.samples,
+            pass: settings.pass,
+            view: DebugView::Shaded,
+            rays: None,
+            secondary_scale: settings.secondary_scale,
+            bias: settings.bias,
+            secondary: None,
+        }
+    }
+}
+
+// Distancia a la que salen de la superficie los rayos de sombra, reflexión y refracción.
+// Poca hace que el rayo vuelva a chocar con la cara de la que salió y la llene de manchas
+// (acné), sobre todo lejos del origen donde las coordenadas tienen menos precisión; mucha
+// despega las sombras de los bloques que las proyectan.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SurfaceBias {
+    // La misma distancia en todos los puntos
+    Fixed(f32),
+    // Esa distancia más el error de redondeo del punto, que crece con el tamaño de sus
+    // coordenadas y con la distancia que recorrió el rayo hasta él
+    Adaptive(f32),
+}
+
+impl SurfaceBias {
+    fn at(&self, intersect: &Intersect, distance: f32) -> f32 {
+        match *self {
+            SurfaceBias::Fixed(bias) => bias,
+            SurfaceBias::Adaptive(bias) => {
+                bias + ADAPTIVE_BIAS_PRECISION * (intersect.point.amax() + distance)
+            }
+        }
+    }
+}
+
+// Calidad del render, que se puede cambiar mientras corre la ventana: muestras por eje de
+// cada pixel, rebotes de reflexión y refracción, si se lanzan rayos de sombra, rayos de
+// los reflejos rugosos del primer impacto y pasos con los que se recorre la niebla
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RenderSettings {
+    pub samples: u32,
+    pub max_depth: u32,
+    // Rebotes de los rayos que salen de materiales transparentes, que necesitan más que los
+    // reflejos para atravesar vidrio y agua; un material puede fijar los suyos
+    pub refraction_depth: u32,
+    pub shadows: bool,
+    pub glossy_samples: u32,
+    pub fog_steps: u32,
+    // Divisor de la resolución: con 2 se traza la mitad del ancho y del alto (un cuarto de
+    // los pixeles) y la imagen se amplía al tamaño del framebuffer. Lo usan `render` y
+    // `render_preview`.
+    pub scale: usize,
+    // Lado en pixeles de las celdas en las que se trazan los reflejos y refracciones de lo
+    // que ven los rayos de cámara (ver `SecondaryBuffer`); con 1 cada pixel traza los suyos
+    pub secondary_scale: usize,
+    // Cuánto se despegan de la superficie los rayos que salen de cada impacto
+    pub bias: SurfaceBias,
+    // Número de la imagen al acumular varias de la misma vista: cada una lee el ruido de las
+    // muestras en otro lugar, y con una muestra por pixel también mueve su posición
+    pub pass: u32,
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        RenderSettings {
+            samples: 1,
+            max_depth: MAX_DEPTH,
+            refraction_depth: MAX_REFRACTION_DEPTH,
+            shadows: true,
+            glossy_samples: GLOSSY_SAMPLES,
+            fog_steps: FOG_STEPS,
+            scale: 1,
+            secondary_scale: 1,
+            bias: SurfaceBias::Adaptive(ORIGIN_BIAS),
+            pass: 0,
+        }
+    }
+}
+
+impl RenderSettings {
+    // La calidad normal con `samples` x `samples` muestras por pixel
+    pub fn with_samples(samples: u32) -> Self {
+        RenderSettings {
+            samples,
+            ..RenderSettings::default()
+        }
+    }
+
+    // La de la ventana interactiva: una muestra y el mínimo de rayos rugosos y de niebla
+    pub fn preview() -> Self {
+        RenderSettings {
+            glossy_samples: PREVIEW_GLOSSY_SAMPLES,
+            fog_steps: PREVIEW_FOG_STEPS,
+            ..RenderSettings::default()
+        }
+    }
+
+    // La del pincel de alta calidad: más rayos rugosos y más pasos de niebla
+    pub fn high_quality() -> Self {
+        RenderSettings {
+            glossy_samples: HIGH_QUALITY_GLOSSY_SAMPLES,
+            fog_steps: HIGH_QUALITY_FOG_STEPS,
+            ..RenderSettings::default()
+        }
+    }
+}
+
+// Color que ve un rayo que parte desde el aire
+pub fn cast_ray(
+    ray_origin: &Vec3,
+    ray_direction: &Vec3,
+    scene: &Scene,
+    objects: &[Cube],
+    depth: u32,
+    settings: &RenderSettings,
+    noise: f32,
+) -> Color {
+    cast(
+        ray_origin,
+        ray_direction,
+        scene,
+        objects,
+        depth,
+        TraceSettings::exact(settings),
+        noise,
+    )
+}
+
+fn cast(
+    ray_origin: &Vec3,
+    ray_direction: &Vec3,
+    scene: &Scene,
+    objects: &[Cube],
+    depth: u32,
+    settings: TraceSettings,
+    noise: f32,
+) -> Color {
+    trace(
+        ray_origin,
+        ray_direction,
+        scene,
+        objects,
+        depth,
+        settings,
+        noise,
+    )
+    .map_or_else(
+        || scene.sky_color(ray_origin, ray_direction),
+        |(color, _, _)| color,
+    )
+}
+
+fn closest_hit<'a>(
+    ray_origin: &Vec3,
+    ray_direction: &Vec3,
+    scene: &'a Scene,
+    objects: &'a [Cube],
+) -> Option<Intersect<'a>> {
+    // Los bloques del mundo se recorren con DDA; los cubos libres se prueban de a cuatro y
+    // solo los que el rayo toca antes de lo más cercano hasta ahora se intersecan del todo
+    let mut closest_intersect = scene.world.ray_intersect(ray_origin, ray_direction);
+    let mut min_distance = if closest_intersect.is_intersecting {
+        closest_intersect.distance
+    } else {
+        f32::INFINITY
+    };
+
+    for group in objects.chunks(LANES) {
+        let distances = entry_distances(group, ray_origin, ray_direction);
+        for (object, distance) in group.iter().zip(distances) {
+            // Un texel transparente puede llevar el impacto más lejos, nunca más cerca
+            if distance >= min_distance {
+                continue;
+            }
+            let intersect = object.ray_intersect(ray_origin, ray_direction);
+            if intersect.is_intersecting && intersect.distance < min_distance {
+                min_distance = intersect.distance;
+                closest_intersect = intersect;
+            }
+        }
+    }
+
+    closest_intersect
+        .is_intersecting
+        .then_some(closest_intersect)
+}
+
+// `closest_hit` de cada rayo de `packet`: cada cubo libre se prueba contra todos los rayos
+// a la vez y solo se interseca del todo con los que lo tocan antes de lo más cercano. Los
+// carriles sin rayo quedan en None.
+fn closest_hits<'a>(
+    packet: &RayPacket,
+    scene: &'a Scene,
+    objects: &'a [Cube],
+) -> [Option<Intersect<'a>>; LANES] {
+    let rays = packet.rays();
+    let mut closest: [Intersect; LANES] = std::array::from_fn(|_| Intersect::empty());
+    for (lane, intersect) in scene.world.ray_intersect_packet(rays).enumerate() {
+        closest[lane] = intersect;
+    }
+    let mut min_distance = [f32::INFINITY; LANES];
+    for (distance, intersect) in min_distance.iter_mut().zip(&closest) {
+        if intersect.is_intersecting {
+            *distance = intersect.distance;
+        }
+    }
+
+    for object in objects {
+        let distances = packet.entry_distances(object);
+        for (lane, (origin, direction)) in rays.iter().enumerate() {
+            if distances[lane] >= min_distance[lane] {
+                continue;
+            }
+            let intersect = object.ray_intersect(origin, direction);
+            if intersect.is_intersecting && intersect.distance < min_distance[lane] {
+                min_distance[lane] = intersect.distance;
+                closest[lane] = intersect;
+            }
+        }
+    }
+
+    closest.map(|intersect| intersect.is_intersecting.then_some(intersect))
+}
+
+// Lo primero que ve el centro de un pixel
+#[derive(Debug, Clone, Copy)]
+pub struct Surface {
+    pub point: Vec3,
+    pub normal: Vec3,
+    // Color difuso con la textura aplicada
+    pub albedo: Color,
+}
+
+// La superficie que se ve en el centro de cada pixel de una imagen de `width` x `height`,
+// o None donde se ve el cielo: sirve para reproyectar un frame con otra cámara y para
+// guiar el filtro de ruido por los bordes de la geometría
+pub fn primary_surfaces(
+    scene: &Scene,
+    objects: &[Cube],
+    (width, height): (usize, usize),
+) -> Vec<Option<Surface>> {
+    let camera = &scene.camera;
+    let surface_at = |index: usize| {
+        let (x, y) = ((index % width) as f32, (index / width) as f32);
+        let (origin, direction) = camera.primary_ray(x, y, width as f32, height as f32);
+        closest_hit(&origin, &direction, scene, objects).map(|mut hit| {
+            hit.apply_textures(&direction, 0.0);
+            Surface {
+                point: hit.point,
+                normal: hit.normal,
+                albedo: hit.diffuse,
+            }
+        })
+    };
+
+    #[cfg(feature = "parallel")]
+    return (0..width * height)
+        .into_par_iter()
+        .map(surface_at)
+        .collect();
+    #[cfg(not(feature = "parallel"))]
+    (0..width * height).map(surface_at).collect()
+}
+
+// Emisión del punto con la animación de su material a la hora de las luces de la escena.
+// Cada bloque usa su propia semilla, así dos glowstones vecinas no titilan al unísono.
+fn animated_emission(intersect: &Intersect, scene: &Scene) -> Color {
+    let (Some(animation), Some(time)) = (intersect.material.emission_animation, scene.light_time)
+    else {
+        return intersect.emission;
+    };
+    let cell = intersect.point - intersect.normal * 0.5;
+    let seed = (cell.x.floor() as i32 as u32).wrapping_mul(73_856_093)
+        ^ (cell.y.floor() as i32 as u32).wrapping_mul(19_349_663)
+        ^ (cell.z.floor() as i32 as u32).wrapping_mul(83_492_791);
+    intersect.emission * animation.factor(time, seed)
+}
+
+// Luz difusa y especular que llega directamente al punto (luces, portales y la luz
+// ambiente del cielo). Sin `shadows` no se lanzan rayos de sombra ni se muestrean los
+// portales. Si la escena tiene `light_cache` y la cara ya está calculada, las sombras y los
+// portales se leen de ahí.
+fn direct_light(
+    intersect: &Intersect,
+    ray_origin: &Vec3,
+    scene: &Scene,
+    objects: &[Cube],
+    noise: f32,
+    shadows: bool,
+) -> (Color, Color) {
+    let mut diffuse = Color::black();
+    let mut specular = Color::black();
+    let cached = cached_face(intersect, scene, shadows);
+
+    for (index, light) in scene.active_lights().enumerate() {
+        let light_dir = (light.position - intersect.point).normalize();
+        let view_dir = (ray_origin - intersect.point).normalize();
+        let reflect_dir = reflect(&-light_dir, &intersect.normal).normalize();
+
+        let light_color = match (&cached, shadows) {
+            (Some(face), _) => light.color * face.shadow(index),
+            (None, true) => light.color * cast_shadow(intersect, scene, objects, light, noise),
+            (None, false) => light.color,
+        };
+        let light_intensity = light.intensity * light.intensity_scale;
+
+        let diffuse_intensity = intersect.normal.dot(&light_dir).max(0.0);
+        diffuse = diffuse + (intersect.diffuse * light_color) * diffuse_intensity * light_intensity;
+
+        let specular_intensity = view_dir
+            .dot(&reflect_dir)
+            .max(0.0)
+            .powf(intersect.material.specular);
+        specular = specular + light_color * specular_intensity * light_intensity;
+    }
+
+    if let Some(face) = &cached {
+        diffuse = diffuse + intersect.diffuse * face.portal();
+    } else if shadows && !scene.portals.is_empty() {
+        diffuse = diffuse + intersect.diffuse * portal_sky_light(intersect, scene, objects, noise);
+    }
+    diffuse = diffuse + intersect.diffuse * scene.ambient;
+
+    (diffuse, specular)
+}
+
+// Luz directa con el modelo metálico/rugosidad: difusa de Lambert y especular GGX con
+// sombreado de Smith y Fresnel de Schlick. Como en `direct_light`, la intensidad de la
+// luz ya incluye el factor π de la difusa, así que un material PBR blanco y mate se ve
+// igual de claro que uno clásico. Las sombras y los portales dependen de `shadows` igual
+// que en `direct_light`.
+fn pbr_direct_light(
+    intersect: &Intersect,
+    ray_origin: &Vec3,
+    scene: &Scene,
+    objects: &[Cube],
+    noise: f32,
+    (metallic, roughness): (f32, f32),
+    shadows: bool,
+) -> Color {
+    let base = intersect.diffuse;
+    let f0 = base_reflectance(intersect.material, base, metallic);
+    let normal = intersect.normal;
+    let view_dir = (ray_origin - intersect.point).normalize();
+    let n_dot_v = normal.dot(&view_dir).max(1e-4);
+    // Lo que no refleja la superficie se difunde, salvo en los metales
+    let diffuse_color = base * (1.0 - metallic);
+    let mut color = Color::black();
+    let cached = cached_face(intersect, scene, shadows);
+
+    for (index, light) in scene.active_lights().enumerate() {
+        let light_dir = (light.position - intersect.point).normalize();
+        let n_dot_l = normal.dot(&light_dir);
+        if n_dot_l <= 0.0 {
+            continue;
+        }
+        let light_color = match (&cached, shadows) {
+            (Some(face), _) => light.color * face.shadow(index),
+            (None, true) => light.color * cast_shadow(intersect, scene, objects, light, noise),
+            (None, false) => light.color,
+        };
+        let radiance = light_color * (light.intensity * light.intensity_scale);
+
+        let half = (view_dir + light_dir).normalize();
+        let fresnel = schlick(f0, half.dot(&light_dir));
+        let specular = ggx_distribution(normal.dot(&half).max(0.0), roughness)
+            * smith_geometry(n_dot_v, n_dot_l, roughness)
+            / (4.0 * n_dot_v * n_dot_l);
+        let diffuse = diffuse_color * complement(fresnel);
+        color = color + (diffuse + fresnel * (specular * PI)) * radiance * n_dot_l;
+    }
+
+    if let Some(face) = &cached {
+        color = color + diffuse_color * face.portal();
+    } else if shadows && !scene.portals.is_empty() {
+        color = color + diffuse_color * portal_sky_light(intersect, scene, objects, noise);
+    }
+    color + diffuse_color * scene.ambient
+}
+
+// Las sombras guardadas de la cara de `intersect`, si hay que calcular sombras
+fn cached_face<'a>(
+    intersect: &Intersect,
+    scene: &'a Scene,
+    shadows: bool,
+) -> Option<CachedFace<'a>> {
+    if !shadows {
+        return None;
+    }
+    scene.light_cache.as_ref()?.sample(scene, intersect)
+}
+
+// Reflectancia a incidencia normal: la del índice de refracción en los dieléctricos (0.04
+// con 1.5) y el color base en los metales
+fn base_reflectance(material: &Material, base: Color, metallic: f32) -> Color {
+    let dielectric =
+        ((material.refractive_index - 1.0) / (material.refractive_index + 1.0)).powi(2);
+    Color::new(dielectric, dielectric, dielectric) * (1.0 - metallic) + base * metallic
+}
+
+// Aproximación de Schlick a Fresnel, por canal
+fn schlick(f0: Color, cosine: f32) -> Color {
+    let weight = (1.0 - cosine.clamp(0.0, 1.0)).powi(5);
+    f0 * (1.0 - weight) + Color::new(weight, weight, weight)
+}
+
+fn complement(color: Color) -> Color {
+    Color::new(1.0 - color.r, 1.0 - color.g, 1.0 - color.b)
+}
+
+// Distribución de microfacetas GGX (Trowbridge-Reitz), con alpha = rugosidad²
+fn ggx_distribution(n_dot_h: f32, roughness: f32) -> f32 {
+    let alpha2 = roughness.powi(4);
+    let denominator = n_dot_h * n_dot_h * (alpha2 - 1.0) + 1.0;
+    alpha2 / (PI * denominator * denominator)
+}
+
+// Oclusión entre microfacetas de Smith con la aproximación de Schlick para luces directas
+fn smith_geometry(n_dot_v: f32, n_dot_l: f32, roughness: f32) -> f32 {
+    let k = (roughness + 1.0).powi(2) / 8.0;
+    let visibility = |cosine: f32| cosine / (cosine * (1.0 - k) + k);
+    visibility(n_dot_v) * visibility(n_dot_l)
+}
+
+// Aplica las texturas del punto con el ancho del cono del rayo y le da el sesgo con el que
+// salen sus rayos
+fn with_textures<'a>(
+    mut intersect: Intersect<'a>,
+    ray_origin: &Vec3,
+    ray_direction: &Vec3,
+    settings: TraceSettings,
+) -> Intersect<'a> {
+    let distance = (intersect.point - ray_origin).magnitude();
+    intersect.apply_textures(ray_direction, settings.cone.width_at(distance));
+    intersect.bias = settings.bias.at(&intersect, distance);
+    intersect
+}
+
+fn is_blended(intersect: &Intersect) -> bool {
+    intersect.material.transparency == TransparencyMode::Blended
+        && intersect.material.transmission() > 0.0
+}
+
+// Capas transparentes acumuladas a lo largo de un rayo (weighted blended OIT de McGuire y
+// Bavoil): cada capa suma su color pesado por opacidad y distancia, y lo que queda detrás
+// se ve en la proporción que dejan pasar todas juntas
+struct BlendedLayers {
+    color: Color,
+    weight: f32,
+    revealage: f32,
+}
+
+impl BlendedLayers {
+    fn new() -> Self {
+        BlendedLayers {
+            color: Color::black(),
+            weight: 0.0,
+            revealage: 1.0,
+        }
+    }
+
+    fn add(&mut self, color: Color, opacity: f32, distance: f32) {
+        // Las capas cercanas pesan más, sin depender del orden en que se sumen
+        let depth_weight = (10.0 / (1e-5 + (distance / 5.0).powi(2) + (distance / 200.0).powi(6)))
+            .clamp(1e-2, 3e3);
+        let weight = opacity * depth_weight;
+        self.color = self.color + color * weight;
+        self.weight += weight;
+        self.revealage *= 1.0 - opacity;
+    }
+
+    fn composite(&self, background: Color) -> Color {
+        if self.weight <= 0.0 {
+            return background;
+        }
+        self.color * ((1.0 - self.revealage) / self.weight) + background * self.revealage
+    }
+}
+
+// Ruleta rusa (`scene.roulette`): un rayo secundario que trae menos de esa fracción del
+// color del pixel sigue con probabilidad proporcional a lo que trae, y si sigue su color
+// se divide por esa probabilidad, así en promedio aporta lo mismo. Los caminos que casi no
+// se ven terminan antes del límite de rebotes, que sigue siendo el de la calidad o el del
+// material. La suerte de cada rebote sale del rayo (`origin`, `direction`) y la pasada, no
+// del ruido del pixel, que ya decide la posición de la muestra.
+// Devuelve la probabilidad con la que siguió, o `None` si se corta.
+fn roulette(
+    scene: &Scene,
+    throughput: f32,
+    depth: u32,
+    (origin, direction): (&Vec3, &Vec3),
+    pass: u32,
+) -> Option<f32> {
+    if scene.roulette <= 0.0 || throughput >= scene.roulette || depth < ROULETTE_START_DEPTH {
+        return Some(1.0);
+    }
+    let survival = (throughput / scene.roulette).max(MIN_SURVIVAL);
+    let chance = bounce_random(origin, direction, depth, pass);
+    (chance < survival).then_some(survival)
+}
+
+// Número en [0, 1) propio de un rayo secundario: mezcla los bits de su origen y dirección
+// con el rebote y la pasada
+fn bounce_random(origin: &Vec3, direction: &Vec3, depth: u32, pass: u32) -> f32 {
+    let mut x = depth.wrapping_mul(0x27d4_eb2d) ^ pass.wrapping_mul(0x1656_67b1);
+    for value in origin.iter().chain(direction.iter()) {
+        x = (x ^ value.to_bits()).wrapping_mul(0x9e37_79b1);
+        x ^= x >> 15;
+    }
+    x = x.wrapping_mul(0x2c1b_3c6d);
+    x ^= x >> 12;
+    x = x.wrapping_mul(0x297a_2d39);
+    x ^= x >> 15;
+    // Los 24 bits altos, para que el resultado no redondee a 1
+    (x >> 8) as f32 / (1 << 24) as f32
+}
+
+// Como `cast`, pero devuelve `None` cuando el rayo se pierde y se vería el cielo; si
+// choca, devuelve también el punto de impacto y la parte del color que es luz directa
+// (brillo propio y luces sobre la superficie, sin reflejos ni refracciones)
+fn trace(
+    ray_origin: &Vec3,
+    ray_direction: &Vec3,
+    scene: &Scene,
+    objects: &[Cube],
+    depth: u32,
+    settings: TraceSettings,
+    noise: f32,
+) -> Option<(Color, Vec3, Color)> {
+    if depth > settings.depth_limit {
+        return None;
+    }
+    if let Some(rays) = settings.rays {
+        rays.fetch_add(1, Ordering::Relaxed);
+    }
+
+    let hit = closest_hit(ray_origin, ray_direction, scene, objects)?;
+    shade_hit(
+        hit,
+        (ray_origin, ray_direction),
+        scene,
+        objects,
+        depth,
+        settings,
+        noise,
+    )
+}
+
+// Color de `hit`, lo primero que ve el rayo, con el resto de `trace`
+fn shade_hit(
+    hit: Intersect,
+    (ray_origin, ray_direction): (&Vec3, &Vec3),
+    scene: &Scene,
+    objects: &[Cube],
+    depth: u32,
+    settings: TraceSettings,
+    noise: f32,
+) -> Option<(Color, Vec3, Color)> {
+    let mut intersect = with_textures(hit, ray_origin, ray_direction, settings);
+    let first_point = intersect.point;
+    // Los reflejos y refracciones de lo que ve la cámara pueden venir de la cuadrícula
+    // reducida; se suman al final, sobre las capas de vidrio como al trazarlos
+    let upsampled = settings
+        .secondary
+        .filter(|_| depth == 0)
+        .and_then(|buffer| buffer.sample(&scene.camera, &first_point, &intersect.normal));
+
+    // Transparencia aproximada: las capas de vidrio se atraviesan en línea recta con su
+    // iluminación directa sin sombras, y se sombrea normalmente lo primero opaco de detrás
+    let mut layers = None;
+    if settings.blended_transparency && is_blended(&intersect) {
+        let mut blended = BlendedLayers::new();
+        let mut next = Some(intersect);
+        for _ in 0..MAX_BLENDED_LAYERS {
+            let layer = match &next {
+                Some(layer) if is_blended(layer) => layer,
+                _ => break,
+            };
+            let (diffuse, specular) = direct_light(layer, ray_origin, scene, objects, noise, false);
+            let color = animated_emission(layer, scene)
+                + diffuse * layer.material.albedo[0]
+                + specular * layer.material.albedo[1] * layer.shine;
+            blended.add(
+                color.clamp(),
+                1.0 - layer.material.albedo[3],
+                layer.distance,
+            );
+
+            let origin = offset_origin(layer, ray_direction);
+            next = closest_hit(&origin, ray_direction, scene, objects).map(|hit| {
+                let mut hit = with_textures(hit, ray_origin, ray_direction, settings);
+                // La distancia se sigue midiendo desde el origen del rayo
+                hit.distance = (hit.point - ray_origin).magnitude();
+                hit
+            });
+        }
+        match next {
+            Some(behind) => {
+                intersect = behind;
+                layers = Some(blended);
+            }
+            None => {
+                let sky = scene.sky_color(ray_origin, ray_direction);
+                let color = blended.composite(sky).clamp();
+                return Some((color, first_point, color));
+            }
+        }
+    }
+
+    // Los rayos secundarios siguen ensanchándose desde el punto de impacto. Lo que se
+    // refleja vuelve al medio de donde vino el rayo.
+    let media = settings.media;
+    let outside = media.current();
+    // Los rayos que salen de un material transparente siguen más rebotes que los de uno
+    // opaco, salvo que el material fije los suyos. La ruleta rusa puede cortarlos antes.
+    let material_depth = if intersect.material.transmission() > 0.0 {
+        settings.refraction_depth
+    } else {
+        settings.max_depth
+    };
+    let depth_limit = intersect.material.max_depth.unwrap_or(material_depth);
+    let settings = TraceSettings {
+        cone: settings
+            .cone
+            .continued((intersect.point - ray_origin).magnitude()),
+        media: media.leaving(false),
+        depth_limit,
+        ..settings
+    };
+
+    // Luz de la superficie, cuánto de cada canal se refleja como espejo y qué fracción se
+    // refracta, según el modelo del material
+    let (surface, reflectance, transparency) = match intersect.material.model {
+        ShadingModel::Legacy => {
+            let (diffuse, specular) = direct_light(
+                &intersect,
+                ray_origin,
+                scene,
+                objects,
+                noise,
+                settings.shadows,
+            );
+            let kr = fresnel(
+                ray_direction,
+                &intersect.normal,
+                outside,
+                intersect.material.refractive_index,
+            );
+            let albedo = intersect.material.albedo;
+            let reflectivity = kr * albedo[2] * intersect.shine;
+            let transparency = (1.0 - kr) * albedo[3];
+            let surface = (diffuse * albedo[0] + specular * albedo[1] * intersect.shine)
+                * (1.0 - reflectivity - transparency);
+            let reflectance = Color::new(reflectivity, reflectivity, reflectivity);
+            (surface, reflectance, transparency)
+        }
+        ShadingModel::Pbr {
+            metallic,
+            roughness,
+        } => {
+            let roughness = dulled_roughness(roughness, intersect.shine).max(MIN_ROUGHNESS);
+            let surface = pbr_direct_light(
+                &intersect,
+                ray_origin,
+                scene,
+                objects,
+                noise,
+                (metallic, roughness),
+                settings.shadows,
+            );
+            // Sin reflejos difusos, además de borroso el entorno se refleja cada vez más
+            // tenue en las superficies rugosas
+            let f0 = base_reflectance(intersect.material, intersect.diffuse, metallic);
+            let n_dot_v = -intersect.normal.dot(&ray_direction.normalize());
+            let mut reflectance = schlick(f0, n_dot_v) * (1.0 - roughness).powi(2);
+            if reflectance.r.max(reflectance.g).max(reflectance.b) < MIN_REFLECTANCE {
+                reflectance = Color::black();
+            }
+            (surface, reflectance, 0.0)
+        }
+    };
+
+    // En una superficie rugosa cada rayo sale de una microfaceta distinta y se promedian
+    let roughness = intersect.material.glossy_roughness(intersect.shine);
+    let samples = if upsampled.is_some() {
+        0
+    } else if roughness > 0.0 && depth == 0 {
+        settings.glossy_samples.max(1)
+    } else {
+        1
+    };
+    let mirror_dir = reflect(ray_direction, &intersect.normal).normalize();
+    let view_dir = -ray_direction.normalize();
+    let mut reflect_color = Color::black();
+    let mut reflect_weight = 0.0;
+    let mut refract_color = Color::black();
+    for i in 0..samples {
+        let normal = glossy_normal(&intersect.normal, roughness, i, samples, noise);
+
+        if reflectance.r.max(reflectance.g).max(reflectance.b) > 0.0 {
+            let mut reflect_dir = reflect(ray_direction, &normal).normalize();
+            // Las microfacetas muy inclinadas reflejarían hacia dentro de la superficie
+            if reflect_dir.dot(&intersect.normal) * mirror_dir.dot(&intersect.normal) <= 0.0 {
+                reflect_dir = mirror_dir;
+            }
+            let reflect_origin = offset_origin(&intersect, &reflect_dir);
+            let weight = glossy_weight(&intersect.normal, &view_dir, &reflect_dir, roughness);
+            reflect_weight += weight;
+            let throughput =
+                settings.throughput * reflectance.r.max(reflectance.g).max(reflectance.b);
+            let ray = (&reflect_origin, &reflect_dir);
+            if let Some(survival) = roulette(scene, throughput, depth + 1, ray, settings.pass) {
+                let settings = TraceSettings {
+                    throughput: throughput / survival,
+                    ..settings
+                };
+                reflect_color = reflect_color
+                    + cast(
+                        &reflect_origin,
+                        &reflect_dir,
+                        scene,
+                        objects,
+                        depth + 1,
+                        settings,
+                        noise,
+                    ) * (weight / survival);
+            }
+        }
+
+        // La refracción juega su propia ruleta, con su rayo
+        let eta = outside / intersect.material.refractive_index;
+        let throughput = settings.throughput * transparency;
+        if let Some(refract_dir) = refract(ray_direction, &normal, eta)
+            .filter(|_| transparency > 0.0)
+            .map(|direction| direction.normalize())
+        {
+            let refract_origin = offset_origin(&intersect, &refract_dir);
+            let ray = (&refract_origin, &refract_dir);
+            let Some(survival) = roulette(scene, throughput, depth + 1, ray, settings.pass) else {
+                continue;
+            };
+            let settings = TraceSettings {
+                media: media.enter(intersect.material.refractive_index),
+                throughput: throughput / survival,
+                ..settings
+            };
+            refract_color = refract_color
+                + refracted(
+                    &intersect,
+                    (refract_origin, refract_dir),
+                    scene,
+                    objects,
+                    depth + 1,
+                    settings,
+                    noise,
+                ) * (1.0 / survival);
+        }
+    }
+    // Promedio pesado: los reflejos se mantienen igual de claros y los rasantes meten menos ruido
+    if reflect_weight > 0.0 {
+        reflect_color = reflect_color * (1.0 / reflect_weight);
+    }
+    let refract_color = refract_color * (1.0 / samples.max(1) as f32);
+
+    let direct = animated_emission(&intersect, scene) + surface;
+    let color = direct + (reflect_color * reflectance) + (refract_color * transparency);
+
+    let (color, direct) = match layers {
+        Some(layers) => (
+            layers.composite(color.clamp()),
+            layers.composite(direct.clamp()),
+        ),
+        None => (color, direct),
+    };
+    let color = match upsampled {
+        Some(bounced) => direct.clamp() + bounced,
+        None => color,
+    };
+    Some((color.clamp(), first_point, direct.clamp()))
+}
+
+pub fn render(
+    framebuffer: &mut Framebuffer,
+    scene: &Scene,
+    objects: &[Cube],
+    settings: &RenderSettings,
+) {
+    render_scaled(framebuffer, settings.scale, |target| {
+        let height = target.height;
+        render_rows(target, scene, objects, settings, 0..height);
+    });
+}
+
+// Como `render`, siempre a la resolución del framebuffer, y además devuelve los datos
+// auxiliares de cada pixel: profundidad, normal, albedo y luz directa e indirecta
+pub fn render_aovs(
+    framebuffer: &mut Framebuffer,
+    scene: &Scene,
+    objects: &[Cube],
+    settings: &RenderSettings,
+) -> Aovs {
+    let size = (framebuffer.width, framebuffer.height);
+    let settings = TraceSettings::exact(settings);
+    let mut aovs = Aovs::new(framebuffer.width, framebuffer.height);
+
+    #[cfg(feature = "parallel")]
+    let rows = framebuffer
+        .buffer
+        .par_chunks_mut(framebuffer.width)
+        .zip(framebuffer.alpha.par_chunks_mut(framebuffer.width))
+        .zip(aovs.pixels.par_chunks_mut(framebuffer.width));
+    #[cfg(not(feature = "parallel"))]
+    let rows = framebuffer
+        .buffer
+        .chunks_mut(framebuffer.width)
+        .zip(framebuffer.alpha.chunks_mut(framebuffer.width))
+        .zip(aovs.pixels.chunks_mut(framebuffer.width));
+
+    rows.enumerate()
+        .for_each(|(y, ((row, row_alpha), row_aovs))| {
+            let pixels = row.iter_mut().zip(row_alpha).zip(row_aovs);
+            for (x, ((pixel, coverage), aov)) in pixels.enumerate() {
+                (*pixel, *coverage) =
+                    render_pixel(scene, objects, (x, y), size, settings, Some(aov), None);
+            }
+        });
+    aovs
+}
+
+// Con `scale` mayor a 1 `draw` renderiza en una imagen más chica que después se amplía
+// sobre el framebuffer
+fn render_scaled(framebuffer: &mut Framebuffer, scale: usize, draw: impl FnOnce(&mut Framebuffer)) {
+    if scale <= 1 {
+        draw(framebuffer);
+        return;
+    }
+    let mut reduced = Framebuffer::new(
+        (framebuffer.width / scale).max(1),
+        (framebuffer.height / scale).max(1),
+    );
+    draw(&mut reduced);
+    framebuffer.upscale_from(&reduced);
+}
+
+// Igual que `render` pero en un solo hilo aunque esté la feature parallel; sirve de
+// referencia para comprobar el render multihilo
+pub fn render_serial(
+    framebuffer: &mut Framebuffer,
+    scene: &Scene,
+    objects: &[Cube],
+    settings: &RenderSettings,
+) {
+    let height = framebuffer.height;
+    render_region(
+        framebuffer,
+        scene,
+        objects,
+        0..height,
+        TraceSettings::exact(settings),
+        false,
+    );
+}
+
+// Render de la ventana interactiva, normalmente con `RenderSettings::preview`: los
+// materiales con transparencia `Blended` se mezclan en lugar de refractar. Con una vista
+// de depuración los pixeles muestran ese dato en lugar de la imagen.
+pub fn render_preview(
+    framebuffer: &mut Framebuffer,
+    scene: &Scene,
+    objects: &[Cube],
+    settings: &RenderSettings,
+    view: DebugView,
+) {
+    let trace_settings = TraceSettings {
+        blended_transparency: true,
+        view,
+        ..TraceSettings::exact(settings)
+    };
+    render_scaled(framebuffer, settings.scale, |target| {
+        let height = target.height;
+        render_region(target, scene, objects, 0..height, trace_settings, true);
+    });
+}
+
+// Rectángulo de pixeles que se renderiza de una vez, en coordenadas del framebuffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tile {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+// Como `render_preview` pero solo en los bloques indicados, cada uno en un hilo, y sin
+// cambiar la resolución: `settings.scale` lo aplica quien elige el tamaño del framebuffer
+pub fn render_preview_tiles(
+    framebuffer: &mut Framebuffer,
+    scene: &Scene,
+    objects: &[Cube],
+    settings: &RenderSettings,
+    view: DebugView,
+    tiles: &[Tile],
+) {
+    let size = (framebuffer.width, framebuffer.height);
+    let trace_settings = TraceSettings {
+        blended_transparency: true,
+        view,
+        ..TraceSettings::exact(settings)
+    };
+    let render_tile = |tile: &Tile| {
+        let mut pixels = vec![(Color::black(), 0.0); tile.width * tile.height];
+        let (columns, rows) = (tile.x..tile.x + tile.width, tile.y..tile.y + tile.height);
+        // Cada bloque traza su propia cuadrícula reducida, con una celda de más en los bordes
+        let secondary = (settings.secondary_scale > 1 && view == DebugView::Shaded).then(|| {
+            let pixels = (columns.clone(), rows.clone());
+            secondary_buffer(scene, objects, pixels, size, trace_settings, false)
+        });
+        let trace_settings = TraceSettings {
+            secondary: secondary.as_ref(),
+            ..trace_settings
+        };
+        let mut hits = Vec::new();
+        for block in packet_blocks(columns, rows) {
+            let rendered = render_block(scene, objects, &block, size, trace_settings, &mut hits);
+            for (&(x, y), pixel) in block.iter().zip(rendered) {
+                pixels[(y - tile.y) * tile.width + x - tile.x] = pixel;
+            }
+        }
+        pixels
+    };
+
+    #[cfg(feature = "parallel")]
+    let rendered: Vec<_> = tiles.par_iter().map(render_tile).collect();
+    #[cfg(not(feature = "parallel"))]
+    let rendered: Vec<_> = tiles.iter().map(render_tile).collect();
+
+    for (tile, pixels) in tiles.iter().zip(rendered) {
+        let rows = pixels.chunks(tile.width);
+        for (y, row) in (tile.y..tile.y + tile.height).zip(rows) {
+            let start = y * framebuffer.width + tile.x;
+            for (offset, &(color, coverage)) in row.iter().enumerate() {
+                framebuffer.buffer[start + offset] = color;
+                framebuffer.alpha[start + offset] = coverage;
+            }
+        }
+    }
+}
+
+// Renderiza solo las filas indicadas, para repartir una imagen costosa en varios pasos
+pub fn render_rows(
+    framebuffer: &mut Framebuffer,
+    scene: &Scene,
+    objects: &[Cube],
+    settings: &RenderSettings,
+    rows: Range<usize>,
+) {
+    render_region(
+        framebuffer,
+        scene,
+        objects,
+        rows,
+        TraceSettings::exact(settings),
+        true,
+    );
+}
+
+fn render_region(
+    framebuffer: &mut Framebuffer,
+    scene: &Scene,
+    objects: &[Cube],
+    rows: Range<usize>,
+    settings: TraceSettings,
+    parallel: bool,
+) {
+    let size = (framebuffer.width, framebuffer.height);
+    let first_row = rows.start.min(framebuffer.height);
+    let last_row = rows.end.min(framebuffer.height);
+    let span = first_row * framebuffer.width..last_row * framebuffer.width;
+    let region = &mut framebuffer.buffer[span.clone()];
+    let alpha = &mut framebuffer.alpha[span];
+    let secondary =
+        (settings.secondary_scale > 1 && settings.view == DebugView::Shaded).then(|| {
+            let pixels = (0..size.0, first_row..last_row);
+            secondary_buffer(scene, objects, pixels, size, settings, parallel)
+        });
+    let settings = TraceSettings {
+        secondary: secondary.as_ref(),
+        ..settings
+    };
+
+    // De a dos filas, para trazar los rayos de cámara en paquetes de 2x2 pixeles
+    let band = PACKET_SIDE * framebuffer.width;
+    let render_band = |(index, (pixels, coverage)): (usize, (&mut [Color], &mut [f32]))| {
+        let y = first_row + index * PACKET_SIDE;
+        let height = pixels.len() / size.0;
+        let mut hits = Vec::new();
+        for block in packet_blocks(0..size.0, y..y + height) {
+            let rendered = render_block(scene, objects, &block, size, settings, &mut hits);
+            for (&(x, block_y), pixel) in block.iter().zip(rendered) {
+                let offset = (block_y - y) * size.0 + x;
+                (pixels[offset], coverage[offset]) = pixel;
+            }
+        }
+    };
+
+    // Itera paralelamente sobre cada par de filas del framebuffer usando `par_iter_mut`
+    #[cfg(feature = "parallel")]
+    if parallel {
+        region
+            .par_chunks_mut(band)
+            .zip(alpha.par_chunks_mut(band))
+            .enumerate()
+            .for_each(render_band);
+        return;
+    }
+    // Sin rayon todo se renderiza en un solo hilo
+    #[cfg(not(feature = "parallel"))]
+    let _ = parallel;
+
+    region
+        .chunks_mut(band)
+        .zip(alpha.chunks_mut(band))
+        .enumerate()
+        .for_each(render_band);
+}
+
+// Traza los reflejos y refracciones de la cuadrícula reducida que cubre los pixeles
+// `columns` x `rows`, un rayo por el centro de cada celda. Se guarda lo que el rayo suma
+// sobre la luz directa de lo que ve, con la profundidad y la normal de esa superficie.
+fn secondary_buffer(
+    scene: &Scene,
+    objects: &[Cube],
+    pixels: (Range<usize>, Range<usize>),
+    size: (usize, usize),
+    settings: TraceSettings,
+    parallel: bool,
+) -> SecondaryBuffer {
+    let mut buffer = SecondaryBuffer::new(pixels, size, settings.secondary_scale);
+    let camera = &scene.camera;
+    // Cada rayo cubre una celda entera
+    let scale = settings.secondary_scale as f32;
+    let (width_at_camera, spread) = camera.pixel_footprint(size.1 as f32);
+    let settings = TraceSettings {
+        cone: RayCone {
+            width: width_at_camera * scale,
+            spread: spread * scale,
+        },
+        secondary: None,
+        ..settings
+    };
+    let sample_at = |&(x, y): &(usize, usize)| {
+        let (center_x, center_y) = buffer.center((x, y));
+        let (origin, direction) =
+            camera.primary_ray(center_x, center_y, size.0 as f32, size.1 as f32);
+        let noise = PixelSampler::new((x, y), settings.pass, 1).shading(0);
+        let hit = closest_hit(&origin, &direction, scene, objects)?;
+        let surface = with_textures(hit.clone(), &origin, &direction, settings);
+        let (color, _, direct) = shade_hit(
+            hit,
+            (&origin, &direction),
+            scene,
+            objects,
+            0,
+            settings,
+            noise,
+        )?;
+        Some(SecondarySample {
+            light: Color::new(color.r - direct.r, color.g - direct.g, color.b - direct.b),
+            depth: camera.depth(&surface.point),
+            normal: surface.normal,
+        })
+    };
+
+    let cells = buffer.cells();
+    #[cfg(feature = "parallel")]
+    let samples = if parallel {
+        cells.par_iter().map(sample_at).collect()
+    } else {
+        cells.iter().map(sample_at).collect()
+    };
+    #[cfg(not(feature = "parallel"))]
+    let samples = {
+        let _ = parallel;
+        cells.iter().map(sample_at).collect()
+    };
+    buffer.fill(samples);
+    buffer
+}
+
+// Los pixeles de `columns` x `rows` en bloques de hasta 2x2, que comparten sus paquetes de
+// rayos de cámara
+fn packet_blocks(columns: Range<usize>, rows: Range<usize>) -> impl Iterator<Item = PixelBlock> {
+    let (right, bottom) = (columns.end, rows.end);
+    rows.step_by(PACKET_SIDE).flat_map(move |y| {
+        columns.clone().step_by(PACKET_SIDE).map(move |x| {
+            let mut block = PixelBlock::new();
+            for y in y..(y + PACKET_SIDE).min(bottom) {
+                for x in x..(x + PACKET_SIDE).min(right) {
+                    block.push((x, y));
+                }
+            }
+            block
+        })
+    })
+}
+
+// Color y cobertura de los pixeles de un bloque de `packet_blocks`, en su orden; los
+// carriles que sobran quedan negros. En la imagen normal lo primero que ve cada muestra se
+// busca con un paquete por muestra, guardándolo en `hits`, que se reusa de un bloque al
+// siguiente; las vistas de depuración trazan pixel por pixel.
+fn render_block<'a>(
+    scene: &'a Scene,
+    objects: &'a [Cube],
+    pixels: &[(usize, usize)],
+    size: (usize, usize),
+    settings: TraceSettings,
+    hits: &mut Vec<Option<Intersect<'a>>>,
+) -> [(Color, f32); LANES] {
+    let mut rendered = [(Color::black(), 0.0); LANES];
+    if settings.view != DebugView::Shaded {
+        for (pixel, rendered) in pixels.iter().zip(&mut rendered) {
+            *rendered = render_pixel(scene, objects, *pixel, size, settings, None, None);
+        }
+        return rendered;
+    }
+    let count = packet_hits(scene, objects, pixels, size, settings, hits);
+    for (lane, (pixel, rendered)) in pixels.iter().zip(&mut rendered).enumerate() {
+        let primary = &hits[lane * count..(lane + 1) * count];
+        *rendered = render_pixel(scene, objects, *pixel, size, settings, None, Some(primary));
+    }
+    rendered
+}
+
+// Lo primero que ve cada muestra de cada uno de `pixels`, trazando juntos los rayos de la
+// misma muestra de los distintos pixeles. Deja en `hits` las de cada pixel una detrás de
+// la otra y devuelve cuántas muestras tiene cada uno.
+fn packet_hits<'a>(
+    scene: &'a Scene,
+    objects: &'a [Cube],
+    pixels: &[(usize, usize)],
+    size: (usize, usize),
+    settings: TraceSettings,
+    hits: &mut Vec<Option<Intersect<'a>>>,
+) -> usize {
+    let Some(&first) = pixels.first() else {
+        return 0;
+    };
+    let samplers: [PixelSampler; LANES] = std::array::from_fn(|lane| {
+        let pixel = pixels.get(lane).copied().unwrap_or(first);
+        PixelSampler::new(pixel, settings.pass, settings.samples)
+    });
+    let count = samplers[0].count() as usize;
+    hits.clear();
+    hits.resize(pixels.len() * count, None);
+    for index in 0..count {
+        let mut rays = [(Vec3::zeros(), Vec3::zeros()); LANES];
+        for ((ray, &pixel), sampler) in rays.iter_mut().zip(pixels).zip(&samplers) {
+            *ray = sample_ray(&scene.camera, sampler, index as u32, pixel, size);
+        }
+        let packet = RayPacket::new(&rays[..pixels.len()]);
+        let closest = closest_hits(&packet, scene, objects);
+        for (lane, hit) in closest.into_iter().take(pixels.len()).enumerate() {
+            hits[lane * count + index] = hit;
+        }
+    }
+    count
+}
+
+// Vuelve a renderizar solo los pixeles marcados en `mask` (uno por pixel del framebuffer),
+// normalmente con más muestras y rebotes que el render interactivo
+pub fn render_masked(
+    framebuffer: &mut Framebuffer,
+    scene: &Scene,
+    objects: &[Cube],
+    settings: &RenderSettings,
+    mask: &[bool],
+) {
+    let size = (framebuffer.width, framebuffer.height);
+    let settings = TraceSettings::exact(settings);
+
+    #[cfg(feature = "parallel")]
+    let rows = framebuffer
+        .buffer
+        .par_chunks_mut(framebuffer.width)
+        .zip(framebuffer.alpha.par_chunks_mut(framebuffer.width))
+        .zip(mask.par_chunks(framebuffer.width));
+    #[cfg(not(feature = "parallel"))]
+    let rows = framebuffer
+        .buffer
+        .chunks_mut(framebuffer.width)
+        .zip(framebuffer.alpha.chunks_mut(framebuffer.width))
+        .zip(mask.chunks(framebuffer.width));
+
+    rows.enumerate()
+        .for_each(|(y, ((row, row_alpha), row_mask))| {
+            let pixels = row.iter_mut().zip(row_alpha).zip(row_mask);
+            for (x, ((pixel, coverage), &marked)) in pixels.enumerate() {
+                if marked {
+                    (*pixel, *coverage) =
+                        render_pixel(scene, objects, (x, y), size, settings, None, None);
+                }
+            }
+        });
+}
+
+// Color del pixel y su cobertura: la fracción de muestras que chocaron con algo cuando el
+// fondo es transparente (las que se pierden no suman color), o 1 si se ve el cielo. Con
+// `aov` también se llenan los datos auxiliares del pixel. `primary` trae lo primero que ve
+// cada muestra si ya se buscó con `packet_hits`.
+fn render_pixel<'a>(
+    scene: &'a Scene,
+    objects: &'a [Cube],
+    (x, y): (usize, usize),
+    (width, height): (usize, usize),
+    settings: TraceSettings,
+    aov: Option<&mut AovPixel>,
+    primary: Option<&[Option<Intersect<'a>>]>,
+) -> (Color, f32) {
+    let camera = &scene.camera;
+    let samples = settings.samples.max(1);
+    let sample_weight = 1.0 / (samples * samples) as f32;
+    // Cada muestra cubre una celda de 1/samples del pixel
+    let (width_at_camera, spread) = camera.pixel_footprint(height as f32);
+    let settings = TraceSettings {
+        cone: RayCone {
+            width: width_at_camera / samples as f32,
+            spread: spread / samples as f32,
+        },
+        ..settings
+    };
+    let sampler = PixelSampler::new((x, y), settings.pass, samples);
+    let mut pixel_color = Color::black();
+    let mut coverage = 0.0;
+    let mut aov_sum = AovPixel {
+        depth: 0.0,
+        ..AovPixel::empty()
+    };
+    let mut aov_weight = 0.0;
+
+    // Cuadrícula de samples x samples sub-pixeles (samples = 1 es el centro original).
+    // Con más de una muestra, cada una se desplaza dentro de su celda según el ruido azul.
+    for sy in 0..samples {
+        for sx in 0..samples {
+            let index = sy * samples + sx;
+            let shading_noise = sampler.shading(index);
+            let (ray_origin, ray_direction) =
+                sample_ray(camera, &sampler, index, (x, y), (width, height));
+
+            if settings.view != DebugView::Shaded {
+                let color = debug_sample(
+                    &ray_origin,
+                    &ray_direction,
+                    scene,
+                    objects,
+                    settings,
+                    shading_noise,
+                );
+                pixel_color = pixel_color + color * sample_weight;
+                coverage += sample_weight;
+                continue;
+            }
+
+            let hit = match primary {
+                Some(hits) => hits[index as usize].clone().and_then(|hit| {
+                    shade_hit(
+                        hit,
+                        (&ray_origin, &ray_direction),
+                        scene,
+                        objects,
+                        0,
+                        settings,
+                        shading_noise,
+                    )
+                }),
+                None => trace(
+                    &ray_origin,
+                    &ray_direction,
+                    scene,
+                    objects,
+                    0,
+                    settings,
+                    shading_noise,
+                ),
+            };
+
+            // La niebla se interpone entre la cámara y lo que ve, sea un bloque o el cielo
+            let (fog_transmittance, fog_light) = fog_scattering(
+                &ray_origin,
+                &ray_direction,
+                hit.as_ref().map_or(f32::INFINITY, |(_, point, _)| {
+                    (point - ray_origin).magnitude()
+                }),
+                scene,
+                objects,
+                settings.fog_steps,
+                sampler.fog(index),
+            );
+            let fogged = |color: Color| color * fog_transmittance + fog_light;
+
+            // Lo que queda fuera de los planos de recorte cuenta como si el rayo no chocara;
+            // en la franja de desvanecimiento se mezcla con el fondo
+            let visibility = hit
+                .as_ref()
+                .map_or(0.0, |(_, point, _)| camera.clip_visibility(point));
+            if let Some((color, point, direct)) = hit.filter(|_| visibility > 0.0) {
+                let weight = visibility * sample_weight;
+                pixel_color = pixel_color + fogged(color) * weight;
+                coverage += weight;
+
+                if aov.is_some() {
+                    if let Some(hit) = closest_hit(&ray_origin, &ray_direction, scene, objects) {
+                        let surface = with_textures(hit, &ray_origin, &ray_direction, settings);
+                        aov_sum.normal += surface.normal * weight;
+                        aov_sum.albedo = aov_sum.albedo + surface.diffuse * weight;
+                    }
+                    // Lo que no es luz directa llegó rebotando o lo dispersó la niebla
+                    let bounced =
+                        Color::new(color.r - direct.r, color.g - direct.g, color.b - direct.b);
+                    aov_sum.depth += camera.depth(&point) * weight;
+                    aov_sum.direct = aov_sum.direct + direct * (fog_transmittance * weight);
+                    aov_sum.indirect =
+                        aov_sum.indirect + (bounced * fog_transmittance + fog_light) * weight;
+                    aov_weight += weight;
+                }
+            }
+            if visibility < 1.0 && !scene.transparent_background {
+                let background = fogged(scene.sky_color(&ray_origin, &ray_direction));
+                pixel_color = pixel_color + background * ((1.0 - visibility) * sample_weight);
+                coverage += (1.0 - visibility) * sample_weight;
+            }
+        }
+    }
+
+    if let Some(aov) = aov {
+        *aov = AovPixel::empty();
+        if aov_weight > 0.0 {
+            let scale = 1.0 / aov_weight;
+            aov.depth = aov_sum.depth * scale;
+            aov.normal = aov_sum
+                .normal
+                .try_normalize(f32::EPSILON)
+                .unwrap_or_else(Vec3::zeros);
+            aov.albedo = aov_sum.albedo * scale;
+            aov.direct = aov_sum.direct * scale;
+            aov.indirect = aov_sum.indirect * scale;
+        }
+    }
+    (pixel_color, coverage)
+}
+
+// Rayo de cámara de la muestra `index` del pixel (x, y): su lugar dentro del pixel y en la
+// lente salen de `sampler`
+fn sample_ray(
+    camera: &Camera,
+    sampler: &PixelSampler,
+    index: u32,
+    (x, y): (usize, usize),
+    (width, height): (usize, usize),
+) -> (Vec3, Vec3) {
+    let (offset_x, offset_y) = sampler.subpixel(index);
+    let (lens_u, lens_v) = sampler.lens(index);
+    let (pixel_origin, pixel_direction) = camera.primary_ray(
+        x as f32 + offset_x,
+        y as f32 + offset_y,
+        width as f32,
+        height as f32,
+    );
+    camera.lens_ray(&pixel_origin, &pixel_direction, lens_u, lens_v)
+}
+
+// El dato de `settings.view` para un rayo de cámara; lo que no choca con nada es negro
+fn debug_sample(
+    ray_origin: &Vec3,
+    ray_direction: &Vec3,
+    scene: &Scene,
+    objects: &[Cube],
+    settings: TraceSettings,
+    noise: f32,
+) -> Color {
+    if settings.view == DebugView::Bounces {
+        let rays = AtomicU32::new(0);
+        let settings = TraceSettings {
+            rays: Some(&rays),
+            ..settings
+        };
+        trace(
+            ray_origin,
+            ray_direction,
+            scene,
+            objects,
+            0,
+            settings,
+            noise,
+        );
+        return heat(rays.into_inner() as f32 / MAX_HEAT_RAYS as f32);
+    }
+
+    let Some(hit) = closest_hit(ray_origin, ray_direction, scene, objects) else {
+        return Color::black();
+    };
+    let intersect = with_textures(hit, ray_origin, ray_direction, settings);
+    let gray = |value: f32| Color::new(value, value, value);
+    match settings.view {
+        DebugView::Normals => {
+            let normal = intersect.normal * 0.5 + Vec3::new(0.5, 0.5, 0.5);
+            Color::new(normal.x, normal.y, normal.z)
+        }
+        DebugView::Depth => {
+            let range = 2.0 * scene.camera.focus().max(ORIGIN_BIAS);
+            gray(1.0 - ((intersect.point - ray_origin).magnitude() / range).min(1.0))
+        }
+        DebugView::Uv => Color::new(intersect.uv.0, intersect.uv.1, 0.0),
+        DebugView::Shadow => {
+            let lights = scene.active_lights().count();
+            if lights == 0 {
+                return gray(1.0);
+            }
+            // La luz que atraviesa vidrios de color se ve de su color
+            let lit = scene
+                .active_lights()
+                .map(|light| cast_shadow(&intersect, scene, objects, light, noise))
+                .fold(Color::black(), |sum, filter| sum + filter);
+            lit * (1.0 / lights as f32)
+        }
+        DebugView::Shaded | DebugView::Bounces => Color::black(),
+    }
+}
@@ -0,0 +1,599 @@
+// resource_pack.rs
+
+use flate2::read::DeflateDecoder;
+use image::{imageops, RgbaImage};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::assets::{MipChain, Texture, TextureAnimation};
+use crate::material::Material;
+use crate::material_library::MaterialLibrary;
+use crate::world::World;
+
+// Carpetas de las texturas de bloques: la de 1.13 en adelante y la de las versiones viejas
+const BLOCK_FOLDERS: [&str; 2] = [
+    "assets/minecraft/textures/block/",
+    "assets/minecraft/textures/blocks/",
+];
+// Minecraft avanza sus animaciones 20 veces por segundo
+const TICK: f32 = 0.05;
+// Tintes de bioma (llanura) de las texturas que vienen en gris
+const GRASS_TINT: [u8; 3] = [145, 189, 89];
+const WATER_TINT: [u8; 3] = [63, 118, 228];
+// Tamaño máximo de un archivo del pack ya descomprimido; las texturas más grandes de los
+// packs en alta resolución rondan unos pocos MiB
+const MAX_ENTRY_SIZE: usize = 64 << 20;
+
+// Textura de un material integrado en el pack: sus nombres (el nuevo y el viejo) y el
+// tinte que le agrega el juego
+struct PackTexture {
+    material: &'static str,
+    names: &'static [&'static str],
+    tint: Option<[u8; 3]>,
+}
+
+const PACK_TEXTURES: [PackTexture; 6] = [
+    PackTexture {
+        material: "piedra",
+        names: &["cobblestone"],
+        tint: None,
+    },
+    PackTexture {
+        material: "cesped",
+        names: &["grass_block_top", "grass_top"],
+        tint: Some(GRASS_TINT),
+    },
+    PackTexture {
+        material: "agua",
+        names: &["water_still"],
+        tint: Some(WATER_TINT),
+    },
+    PackTexture {
+        material: "madera",
+        names: &["oak_planks", "planks_oak"],
+        tint: None,
+    },
+    PackTexture {
+        material: "glowstone",
+        names: &["glowstone"],
+        tint: None,
+    },
+    PackTexture {
+        material: "tierra",
+        names: &["dirt"],
+        tint: None,
+    },
+];
+
+// Un resource pack de Minecraft, como carpeta o comprimido en .zip
+pub struct ResourcePack {
+    // Ruta con la que se abrió, que recuerdan los materiales que texturiza
+    path: String,
+    source: Source,
+    // Carpeta del pack dentro del zip, con la barra final; vacía si `pack.mcmeta` está en
+    // la raíz
+    prefix: String,
+}
+
+enum Source {
+    Folder(PathBuf),
+    Zip(ZipArchive),
+}
+
+// Archivo .mcmeta que acompaña a una textura; solo interesa la animación
+#[derive(Deserialize)]
+struct TextureMeta {
+    animation: Option<AnimationMeta>,
+}
+
+#[derive(Deserialize)]
+struct AnimationMeta {
+    #[serde(default)]
+    interpolate: bool,
+    // Proporción de cada cuadro; sin valor los cuadros son cuadrados
+    width: Option<u32>,
+    height: Option<u32>,
+    // Ticks que dura cada cuadro que no indica el suyo
+    #[serde(default = "default_frametime")]
+    frametime: u32,
+    // Orden de los cuadros; sin valor, de arriba hacia abajo
+    frames: Option<Vec<FrameMeta>>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum FrameMeta {
+    Index(usize),
+    Timed { index: usize, time: u32 },
+}
+
+fn default_frametime() -> u32 {
+    1
+}
+
+impl ResourcePack {
+    pub fn open(path: &Path) -> Result<ResourcePack, String> {
+        if path.is_dir() {
+            return Ok(ResourcePack {
+                path: path.to_string_lossy().into_owned(),
+                source: Source::Folder(path.to_path_buf()),
+                prefix: String::new(),
+            });
+        }
+        let bytes = fs::read(path).map_err(|err| format!("no se pudo leer el pack: {}", err))?;
+        ResourcePack::from_zip(path.to_string_lossy().into_owned(), bytes)
+    }
+
+    fn from_zip(path: String, bytes: Vec<u8>) -> Result<ResourcePack, String> {
+        let archive = ZipArchive::parse(bytes)?;
+        // Muchos packs se comprimen desde afuera de su carpeta
+        let prefix = archive
+            .entries
+            .keys()
+            .filter_map(|name| name.strip_suffix("pack.mcmeta"))
+            .filter(|prefix| prefix.is_empty() || prefix.ends_with('/'))
+            .min_by_key(|prefix| prefix.len())
+            .unwrap_or("")
+            .to_string();
+        Ok(ResourcePack {
+            path,
+            source: Source::Zip(archive),
+            prefix,
+        })
+    }
+
+    // Biblioteca con los materiales integrados que tienen textura en el pack (piedra,
+    // césped, agua, madera, glowstone y tierra), ya texturizados
+    pub fn library(&self) -> Result<MaterialLibrary, String> {
+        let builtin = MaterialLibrary::builtin();
+        let mut library = MaterialLibrary::new();
+        for texture in &PACK_TEXTURES {
+            let Some(material) = builtin.get(texture.material) else {
+                continue;
+            };
+            let mut material = material.clone();
+            if self.texture(texture.material, &mut material)? {
+                library.register(texture.material, material);
+            }
+        }
+        if library.names().next().is_none() {
+            return Err("el pack no tiene texturas de bloques conocidas".to_string());
+        }
+        Ok(library)
+    }
+
+    // Pone las texturas del pack a los materiales integrados, agregándolos al mundo si no
+    // están; un material del mundo con ese nombre conserva todo lo demás. Devuelve cuántos
+    // se texturizaron.
+    pub fn apply(&self, world: &mut World) -> Result<usize, String> {
+        let library = self.library()?;
+        for name in library.names() {
+            let Some(textured) = library.get(name) else {
+                continue;
+            };
+            match world.find_material(name) {
+                Some(block) => {
+                    let mut material = world.material_mut(block);
+                    material.texture = textured.texture.clone();
+                    material.texture_path = None;
+                    material.texture_animation = textured.texture_animation.clone();
+                    material.texture_pack = textured.texture_pack.clone();
+                }
+                None => {
+                    world.add_material(name, textured.clone());
+                }
+            }
+        }
+        Ok(library.names().count())
+    }
+
+    // Pone a `material` la textura del pack que le corresponde al integrado `name` y le
+    // deja anotado el pack, así se vuelve a texturizar al cargar lo guardado. Devuelve
+    // false si el pack no tiene esa textura.
+    pub fn texture(&self, name: &str, material: &mut Material) -> Result<bool, String> {
+        let Some(texture) = PACK_TEXTURES
+            .iter()
+            .find(|texture| texture.material == name)
+        else {
+            return Ok(false);
+        };
+        let Some(animation) = self.block_texture(texture)? else {
+            return Ok(false);
+        };
+        material.texture = Some(Texture::Image(Arc::clone(&animation.frames[0].0)));
+        material.texture_path = None;
+        material.texture_animation = (animation.frames.len() > 1).then_some(animation);
+        material.texture_pack = Some(self.path.clone());
+        Ok(true)
+    }
+
+    // Cuadros de la primera textura de `texture` que tenga el pack, teñidos; una textura
+    // quieta es una animación de un cuadro
+    fn block_texture(&self, texture: &PackTexture) -> Result<Option<TextureAnimation>, String> {
+        let found = texture.names.iter().find_map(|name| {
+            BLOCK_FOLDERS.iter().find_map(|folder| {
+                let path = format!("{}{}.png", folder, name);
+                Some((self.read(&path)?, path))
+            })
+        });
+        let Some((bytes, path)) = found else {
+            return Ok(None);
+        };
+        let mut image = image::load_from_memory(&bytes)
+            .map_err(|err| format!("{}: {}", path, err))?
+            .to_rgba8();
+        if let Some(tint) = texture.tint {
+            for pixel in image.pixels_mut() {
+                for (channel, tint) in pixel.0.iter_mut().zip(tint) {
+                    *channel = ((*channel as u32 * tint as u32 + 127) / 255) as u8;
+                }
+            }
+        }
+
+        let meta = match self.read(&format!("{}.mcmeta", path)) {
+            Some(bytes) => {
+                serde_json::from_slice::<TextureMeta>(&bytes)
+                    .map_err(|err| format!("{}.mcmeta: {}", path, err))?
+                    .animation
+            }
+            None => None,
+        };
+        let Some(meta) = meta else {
+            let frame = Arc::new(MipChain::new(image));
+            return Ok(Some(TextureAnimation {
+                frames: vec![(frame, 0.0)],
+                interpolate: false,
+            }));
+        };
+        animation_frames(image, &meta)
+            .map(Some)
+            .map_err(|err| format!("{}: {}", path, err))
+    }
+
+    // Contenido del archivo `name`, con la ruta relativa a la raíz del pack
+    fn read(&self, name: &str) -> Option<Vec<u8>> {
+        match &self.source {
+            Source::Folder(folder) => fs::read(folder.join(name)).ok(),
+            Source::Zip(archive) => archive.read(&format!("{}{}", self.prefix, name)),
+        }
+    }
+}
+
+// Corta la tira vertical de cuadros de una textura animada y los ordena según su .mcmeta
+fn animation_frames(image: RgbaImage, meta: &AnimationMeta) -> Result<TextureAnimation, String> {
+    let (width, height) = image.dimensions();
+    // En u64 porque el .mcmeta puede pedir proporciones enormes
+    let frame_height = match (meta.width, meta.height) {
+        (Some(w), Some(h)) if w > 0 => width as u64 * h as u64 / w as u64,
+        _ => width as u64,
+    };
+    if frame_height == 0 || frame_height > height as u64 {
+        return Err("la imagen es más chica que un cuadro".to_string());
+    }
+    let frame_height = frame_height as u32;
+    let cuts: Vec<Arc<MipChain>> = (0..height / frame_height)
+        .map(|index| {
+            let frame = imageops::crop_imm(&image, 0, index * frame_height, width, frame_height);
+            Arc::new(MipChain::new(frame.to_image()))
+        })
+        .collect();
+
+    let order: Vec<(usize, u32)> = match &meta.frames {
+        Some(frames) => frames
+            .iter()
+            .map(|frame| match *frame {
+                FrameMeta::Index(index) => (index, meta.frametime),
+                FrameMeta::Timed { index, time } => (index, time),
+            })
+            .collect(),
+        None => (0..cuts.len())
+            .map(|index| (index, meta.frametime))
+            .collect(),
+    };
+    let mut frames = Vec::with_capacity(order.len());
+    for (index, ticks) in order {
+        let frame = cuts
+            .get(index)
+            .ok_or_else(|| format!("el cuadro {} no está en la imagen", index))?;
+        frames.push((Arc::clone(frame), ticks.max(1) as f32 * TICK));
+    }
+    if frames.is_empty() {
+        return Err("la animación no tiene cuadros".to_string());
+    }
+    Ok(TextureAnimation {
+        frames,
+        interpolate: meta.interpolate,
+    })
+}
+
+// Lectura de un .zip en memoria: el directorio central al final del archivo dice dónde
+// empieza cada entrada y cómo está comprimida (guardada tal cual o con deflate)
+struct ZipArchive {
+    bytes: Vec<u8>,
+    entries: HashMap<String, ZipEntry>,
+}
+
+struct ZipEntry {
+    // Inicio del encabezado local de la entrada
+    offset: usize,
+    method: u16,
+    compressed: usize,
+    // Tamaño descomprimido que declara el directorio
+    size: usize,
+}
+
+impl ZipArchive {
+    fn parse(bytes: Vec<u8>) -> Result<ZipArchive, String> {
+        // El registro final mide 22 bytes más un comentario de hasta 64 KiB
+        let search_start = bytes.len().saturating_sub(22 + u16::MAX as usize);
+        let end = (search_start..bytes.len().saturating_sub(21))
+            .rev()
+            .find(|&at| read_u32(&bytes, at) == Some(0x0605_4b50))
+            .ok_or_else(|| "no es un archivo .zip".to_string())?;
+        let count = read_u16(&bytes, end + 10).unwrap_or(0) as usize;
+        let mut at = read_u32(&bytes, end + 16).unwrap_or(u32::MAX) as usize;
+
+        let truncated = || "el directorio del .zip está incompleto".to_string();
+        let mut entries = HashMap::new();
+        for _ in 0..count {
+            if read_u32(&bytes, at) != Some(0x0201_4b50) {
+                return Err(truncated());
+            }
+            let field = |offset: usize| read_u32(&bytes, at + offset).ok_or_else(truncated);
+            let short = |offset: usize| read_u16(&bytes, at + offset).ok_or_else(truncated);
+            let method = short(10)?;
+            let compressed = field(20)?;
+            let size = field(24)?;
+            let name_length = short(28)? as usize;
+            let extra_length = short(30)? as usize;
+            let comment_length = short(32)? as usize;
+            let offset = field(42)?;
+            if [compressed, size, offset].contains(&u32::MAX) {
+                return Err("los .zip de más de 4 GiB (ZIP64) no están soportados".to_string());
+            }
+            let name = bytes
+                .get(at + 46..at + 46 + name_length)
+                .ok_or_else(truncated)?;
+            entries.insert(
+                String::from_utf8_lossy(name).replace('\\', "/"),
+                ZipEntry {
+                    offset: offset as usize,
+                    method,
+                    compressed: compressed as usize,
+                    size: size as usize,
+                },
+            );
+            at += 46 + name_length + extra_length + comment_length;
+        }
+        Ok(ZipArchive { bytes, entries })
+    }
+
+    // Contenido descomprimido de una entrada; None si no está, no se puede leer o pasa de
+    // MAX_ENTRY_SIZE
+    fn read(&self, name: &str) -> Option<Vec<u8>> {
+        let entry = self.entries.get(name)?;
+        if entry.size > MAX_ENTRY_SIZE || read_u32(&self.bytes, entry.offset)? != 0x0403_4b50 {
+            return None;
+        }
+        let name_length = read_u16(&self.bytes, entry.offset + 26)? as usize;
+        let extra_length = read_u16(&self.bytes, entry.offset + 28)? as usize;
+        let start = entry.offset + 30 + name_length + extra_length;
+        let data = self
+            .bytes
+            .get(start..start.checked_add(entry.compressed)?)?;
+        match entry.method {
+            0 => (data.len() == entry.size).then(|| data.to_vec()),
+            8 => {
+                // Una entrada que se descomprime en más de lo declarado (una bomba zip) se
+                // corta apenas se pasa, sin llegar a llenar la memoria
+                let mut out = Vec::new();
+                DeflateDecoder::new(data)
+                    .take(entry.size as u64 + 1)
+                    .read_to_end(&mut out)
+                    .ok()?;
+                (out.len() == entry.size).then_some(out)
+            }
+            _ => None,
+        }
+    }
+}
+
+fn read_u16(bytes: &[u8], at: usize) -> Option<u16> {
+    let bytes = bytes.get(at..at.checked_add(2)?)?;
+    Some(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> Option<u32> {
+    let bytes = bytes.get(at..at.checked_add(4)?)?;
+    Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageFormat, Rgba};
+    use std::io::Cursor;
+
+    // Un .zip con las entradas guardadas sin comprimir
+    fn zip(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let mut directory = Vec::new();
+        for (name, content) in files {
+            let offset = bytes.len() as u32;
+            bytes.extend(0x0403_4b50u32.to_le_bytes());
+            bytes.extend([0; 22]);
+            bytes.extend((name.len() as u16).to_le_bytes());
+            bytes.extend(0u16.to_le_bytes());
+            bytes.extend(name.as_bytes());
+            bytes.extend(*content);
+
+            directory.extend(0x0201_4b50u32.to_le_bytes());
+            directory.extend([0; 16]);
+            directory.extend((content.len() as u32).to_le_bytes());
+            directory.extend((content.len() as u32).to_le_bytes());
+            directory.extend((name.len() as u16).to_le_bytes());
+            directory.extend([0; 12]);
+            directory.extend(offset.to_le_bytes());
+            directory.extend(name.as_bytes());
+        }
+        let start = bytes.len() as u32;
+        let size = directory.len() as u32;
+        bytes.extend(directory);
+        bytes.extend(0x0605_4b50u32.to_le_bytes());
+        bytes.extend([0; 6]);
+        bytes.extend((files.len() as u16).to_le_bytes());
+        bytes.extend(size.to_le_bytes());
+        bytes.extend(start.to_le_bytes());
+        bytes.extend(0u16.to_le_bytes());
+        bytes
+    }
+
+    // PNG de `width` x `height` con cada fila del gris de su número
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let image =
+            RgbaImage::from_fn(width, height, |_, y| Rgba([y as u8, y as u8, y as u8, 255]));
+        let mut bytes = Cursor::new(Vec::new());
+        image.write_to(&mut bytes, ImageFormat::Png).unwrap();
+        bytes.into_inner()
+    }
+
+    fn meta(json: &str) -> AnimationMeta {
+        serde_json::from_str::<TextureMeta>(json)
+            .unwrap()
+            .animation
+            .unwrap()
+    }
+
+    #[test]
+    fn reads_stored_entries() {
+        let archive = ZipArchive::parse(zip(&[("a.txt", b"hola"), ("b/c.txt", b"chau")])).unwrap();
+        assert_eq!(archive.read("a.txt").as_deref(), Some(&b"hola"[..]));
+        assert_eq!(archive.read("b/c.txt").as_deref(), Some(&b"chau"[..]));
+        assert_eq!(archive.read("d.txt"), None);
+    }
+
+    #[test]
+    fn rejects_broken_archives() {
+        assert!(ZipArchive::parse(b"no es un zip".to_vec()).is_err());
+        assert!(ZipArchive::parse(Vec::new()).is_err());
+
+        // El registro final apunta a un directorio que no está
+        let mut bytes = zip(&[("a.txt", b"hola")]);
+        let end = bytes.len() - 22;
+        bytes[end + 16..end + 20].copy_from_slice(&1000u32.to_le_bytes());
+        assert!(ZipArchive::parse(bytes).is_err());
+
+        // Una entrada que dice medir más que el archivo no se lee
+        let mut bytes = zip(&[("a.txt", b"hola")]);
+        let directory = bytes.len() - 22 - (46 + 5);
+        bytes[directory + 20..directory + 24].copy_from_slice(&1000u32.to_le_bytes());
+        assert_eq!(ZipArchive::parse(bytes).unwrap().read("a.txt"), None);
+    }
+
+    #[test]
+    fn limits_the_decompressed_size() {
+        use flate2::write::DeflateEncoder;
+        use flate2::Compression;
+        use std::io::Write;
+
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::best());
+        encoder.write_all(&vec![0; 1 << 20]).unwrap();
+        let deflated = encoder.finish().unwrap();
+        // Cambia el método y el tamaño descomprimido de la única entrada del directorio
+        let bomb = |size: u32| {
+            let mut bytes = zip(&[("bomba.png", &deflated)]);
+            let directory = bytes.len() - 22 - (46 + "bomba.png".len());
+            bytes[directory + 10..directory + 12].copy_from_slice(&8u16.to_le_bytes());
+            bytes[directory + 24..directory + 28].copy_from_slice(&size.to_le_bytes());
+            ZipArchive::parse(bytes).unwrap()
+        };
+
+        assert_eq!(
+            bomb(1 << 20).read("bomba.png").map(|data| data.len()),
+            Some(1 << 20)
+        );
+        // Declara menos de lo que sale al descomprimir
+        assert_eq!(bomb(10).read("bomba.png"), None);
+        // Declara más de lo que se acepta
+        assert_eq!(bomb(MAX_ENTRY_SIZE as u32 + 1).read("bomba.png"), None);
+    }
+
+    #[test]
+    fn finds_the_pack_folder_inside_the_zip() {
+        let dirt = png(2, 2);
+        let bytes = zip(&[
+            ("Pack/pack.mcmeta", b"{}"),
+            ("Pack/assets/minecraft/textures/block/dirt.png", &dirt),
+        ]);
+        let pack = ResourcePack::from_zip("pack.zip".to_string(), bytes).unwrap();
+        assert_eq!(pack.prefix, "Pack/");
+
+        let library = pack.library().unwrap();
+        assert_eq!(library.names().collect::<Vec<_>>(), ["tierra"]);
+        let material = library.get("tierra").unwrap();
+        assert!(matches!(material.texture, Some(Texture::Image(_))));
+        assert_eq!(material.texture_path, None);
+        assert_eq!(material.texture_pack.as_deref(), Some("pack.zip"));
+    }
+
+    #[test]
+    fn apply_keeps_the_world_material_and_remembers_the_pack() {
+        let dirt = png(2, 2);
+        let bytes = zip(&[
+            ("pack.mcmeta", b"{}"),
+            ("assets/minecraft/textures/blocks/dirt.png", &dirt),
+        ]);
+        let pack = ResourcePack::from_zip("pack.zip".to_string(), bytes).unwrap();
+
+        let mut world = World::new();
+        let mut material = Material::black();
+        material.specular = 42.0;
+        material.texture_path = Some("tierra.png".to_string());
+        let block = world.add_material("tierra", material);
+        assert_eq!(pack.apply(&mut world), Ok(1));
+
+        let material = world.material(block);
+        assert_eq!(material.specular, 42.0);
+        assert_eq!(material.texture_path, None);
+        assert_eq!(material.texture_pack.as_deref(), Some("pack.zip"));
+        assert!(matches!(material.texture, Some(Texture::Image(_))));
+    }
+
+    #[test]
+    fn a_pack_without_block_textures_is_an_error() {
+        let pack =
+            ResourcePack::from_zip("pack.zip".to_string(), zip(&[("pack.mcmeta", b"{}")])).unwrap();
+        assert!(pack.library().is_err());
+        assert!(pack.apply(&mut World::new()).is_err());
+    }
+
+    #[test]
+    fn orders_the_animation_frames() {
+        let image = image::load_from_memory(&png(1, 3)).unwrap().to_rgba8();
+        let animation = animation_frames(
+            image,
+            &meta(r#"{"animation": {"frametime": 2, "frames": [2, {"index": 0, "time": 5}]}}"#),
+        )
+        .unwrap();
+        let rows: Vec<(u8, f32)> = animation
+            .frames
+            .iter()
+            .map(|(frame, duration)| (frame.level(0).get_pixel(0, 0).0[0], *duration))
+            .collect();
+        assert_eq!(rows, [(2, 2.0 * TICK), (0, 5.0 * TICK)]);
+
+        let image = image::load_from_memory(&png(1, 3)).unwrap().to_rgba8();
+        assert!(animation_frames(image, &meta(r#"{"animation": {"frames": [3]}}"#)).is_err());
+    }
+
+    #[test]
+    fn huge_frame_proportions_are_an_error() {
+        let image = image::load_from_memory(&png(16, 32)).unwrap().to_rgba8();
+        let meta = meta(r#"{"animation": {"width": 1, "height": 4294967295}}"#);
+        assert!(animation_frames(image, &meta).is_err());
+    }
+}
@@ -0,0 +1,135 @@
+// sampler.rs
+
+use nalgebra_glm::Vec3;
+use std::f32::consts::PI;
+
+use crate::blue_noise::{blue_noise, BlueNoise};
+
+// Cuánto se desplaza la máscara de ruido azul en cada pasada de la acumulación; impares
+// para recorrer las 64 posiciones de cada eje antes de repetir
+const PASS_SHIFT: (usize, usize) = (29, 41);
+// Secuencia R2: centro del pixel de cada pasada cuando hay una sola muestra por pixel
+const PASS_SEQUENCE: (f32, f32) = (0.754_877_7, 0.569_840_3);
+
+// Posición de la muestra i de n en [0, 1): una por franja, desplazada dentro de ella por
+// `jitter`, así las muestras no se amontonan aunque el desplazamiento sea aleatorio
+pub fn stratified(i: u32, n: u32, jitter: f32) -> f32 {
+    (i as f32 + jitter) / n as f32
+}
+
+// Punto i de n en el cuadrado unitario: una muestra por celda de la cuadrícula más pareja
+// en la que caben (4 da 2x2, 6 da 3x2), desplazada dentro de la celda por `jitter`
+pub fn stratified_2d(i: u32, n: u32, (jitter_x, jitter_y): (f32, f32)) -> (f32, f32) {
+    let columns = ((n as f32).sqrt().ceil() as u32).max(1);
+    let rows = n.div_ceil(columns).max(1);
+    (
+        ((i % columns) as f32 + jitter_x) / columns as f32,
+        ((i / columns) as f32 + jitter_y) / rows as f32,
+    )
+}
+
+// Dos vectores perpendiculares a `normal` y entre sí, para ubicar muestras en un disco o
+// un hemisferio alrededor de ella
+pub fn orthonormal_basis(normal: &Vec3) -> (Vec3, Vec3) {
+    let helper = if normal.y.abs() < 0.99 {
+        Vec3::new(0.0, 1.0, 0.0)
+    } else {
+        Vec3::new(1.0, 0.0, 0.0)
+    };
+    let tangent = normal.cross(&helper).normalize();
+    let bitangent = normal.cross(&tangent);
+    (tangent, bitangent)
+}
+
+// Dirección del hemisferio de `normal` con densidad proporcional al coseno con ella (un
+// punto del disco unitario proyectado hacia arriba). Para una superficie difusa cada
+// muestra pesa lo mismo: las rasantes, que casi no aportan, salen poco.
+pub fn cosine_hemisphere(normal: &Vec3, (u, v): (f32, f32)) -> Vec3 {
+    let radius = u.sqrt();
+    let angle = 2.0 * PI * v;
+    let height = (1.0 - u).max(0.0).sqrt();
+    let (tangent, bitangent) = orthonormal_basis(normal);
+    (tangent * (radius * angle.cos()) + bitangent * (radius * angle.sin()) + normal * height)
+        .normalize()
+}
+
+// Normal de microfaceta con la distribución GGX de `roughness` alrededor de `normal`: `u`
+// (de 0 a 1) elige la inclinación y `angle` el giro. Los rayos reflejados se concentran
+// donde el lóbulo especular es fuerte en vez de repartirse parejo.
+pub fn ggx_normal(normal: &Vec3, roughness: f32, u: f32, angle: f32) -> Vec3 {
+    let alpha = roughness * roughness;
+    let tan_theta = alpha * (u / (1.0 - u).max(1e-4)).sqrt();
+    let (tangent, bitangent) = orthonormal_basis(normal);
+    (normal + (tangent * angle.cos() + bitangent * angle.sin()) * tan_theta).normalize()
+}
+
+// Números de las muestras de un pixel en una pasada de la acumulación. Con varias muestras
+// por eje cada una cae en su celda de la cuadrícula y el ruido azul la mueve dentro de ella;
+// con una sola, el centro de cada pasada sigue la secuencia R2. La máscara se corre en cada
+// pasada, así los frames acumulados no repiten posiciones. El antialiasing, el sombreado
+// (sombras suaves, portales, reflejos borrosos) y la lente usan dimensiones distintas del
+// ruido para que sus errores no coincidan.
+pub struct PixelSampler {
+    noise: &'static BlueNoise,
+    // Pixel de la máscara, ya desplazado según la pasada
+    position: (usize, usize),
+    pass: u32,
+    // Muestras por eje
+    samples: u32,
+}
+
+impl PixelSampler {
+    pub fn new((x, y): (usize, usize), pass: u32, samples: u32) -> Self {
+        PixelSampler {
+            noise: blue_noise(),
+            position: (
+                x + pass as usize * PASS_SHIFT.0,
+                y + pass as usize * PASS_SHIFT.1,
+            ),
+            pass,
+            samples: samples.max(1),
+        }
+    }
+
+    // Muestras del pixel: `samples` x `samples`
+    pub fn count(&self) -> u32 {
+        self.samples * self.samples
+    }
+
+    // Desplazamiento de la muestra `index` desde el centro del pixel, de -0.5 a 0.5 en cada
+    // eje (con una muestra en la primera pasada, el centro)
+    pub fn subpixel(&self, index: u32) -> (f32, f32) {
+        let jitter = if self.samples > 1 {
+            (self.dimension(2 * index), self.dimension(2 * index + 1))
+        } else {
+            let pass = self.pass as f32;
+            (
+                (0.5 + pass * PASS_SEQUENCE.0).fract(),
+                (0.5 + pass * PASS_SEQUENCE.1).fract(),
+            )
+        };
+        let (u, v) = stratified_2d(index, self.count(), jitter);
+        (u - 0.5, v - 0.5)
+    }
+
+    // Valor en [0, 1) que gira las espirales y desplaza las cuadrículas del sombreado
+    pub fn shading(&self, index: u32) -> f32 {
+        self.dimension(2 * self.count() + index)
+    }
+
+    // Punto del cuadrado unitario que elige el lugar de la lente de la muestra `index`
+    pub fn lens(&self, index: u32) -> (f32, f32) {
+        let dimension = 3 * self.count() + 2 * index;
+        (self.dimension(dimension), self.dimension(dimension + 1))
+    }
+
+    // Valor en [0, 1) que desplaza los pasos de la niebla de la muestra `index`
+    pub fn fog(&self, index: u32) -> f32 {
+        self.dimension(5 * self.count() + index)
+    }
+
+    fn dimension(&self, dimension: u32) -> f32 {
+        let (x, y) = self.position;
+        self.noise.sample(x, y, dimension)
+    }
+}
@@ -0,0 +1,305 @@
+// scene.rs
+
+use nalgebra_glm::Vec3;
+
+use crate::assets::{load_texture, Texture};
+use crate::camera::Camera;
+use crate::clouds::CloudLayer;
+use crate::color::Color;
+use crate::cube::Cube;
+use crate::day_cycle::day_lighting;
+use crate::fog::Fog;
+use crate::light::Light;
+use crate::light_cache::LightCache;
+use crate::material_library::MaterialLibrary;
+use crate::moon::Moon;
+use crate::object::SceneObject;
+use crate::portal::Portal;
+use crate::post::PostEffect;
+use crate::skybox::Skybox;
+use crate::terrain::{generate_terrain, TerrainMaterials, TerrainSettings};
+use crate::world::World;
+
+pub struct Scene {
+    pub world: World,
+    // Cubos libres que no están alineados a la cuadrícula del mundo, agrupados en objetos animables
+    pub objects: Vec<SceneObject>,
+    pub lights: Vec<Light>,
+    // Luz del cielo que reciben todas las superficies aunque estén a la sombra; la cambia el
+    // ciclo del día y empieza apagada
+    pub ambient: Color,
+    // Luz del lado opuesto al sol para las noches; sin valor de noche solo queda el sol
+    pub moon: Option<Moon>,
+    // Segundos a los que se evaluaron las animaciones de las luces, con los que también
+    // varía la emisión de los materiales animados; sin valor brillan siempre igual
+    pub light_time: Option<f32>,
+    // Ventanas por las que entra la luz del cielo a los interiores
+    pub portals: Vec<Portal>,
+    pub skybox: Skybox,
+    // Capa de nubes delante del cielo; sin valor el cielo se ve despejado
+    pub clouds: Option<CloudLayer>,
+    // Niebla entre la cámara y la escena, con haces de luz del sol; sin valor el aire es limpio
+    pub fog: Option<Fog>,
+    // Efectos que se aplican a la imagen terminada (exposición, bloom, viñeta, tabla de
+    // color), en orden; vacío la deja como sale del trazador
+    pub post: Vec<PostEffect>,
+    pub camera: Camera,
+    // Radio de la órbita del sol durante el ciclo de día
+    pub sun_distance: f32,
+    // Luminancia máxima de cada muestra de luz indirecta (portales); 0 no limita
+    pub firefly_clamp: f32,
+    // Fracción del color del pixel por debajo de la cual los reflejos y refracciones se
+    // cortan al azar (ruleta rusa); 0 sigue solo los límites de rebotes
+    pub roulette: f32,
+    // Los rayos de cámara que no chocan con nada dejan el pixel transparente en lugar de
+    // mostrar el cielo (las reflexiones y refracciones siguen viendo el cielo)
+    pub transparent_background: bool,
+    // Las imágenes se pasan a 8 bits con ruido azul (ver `Framebuffer::dither`)
+    pub dither: bool,
+    // Sombras guardadas por cara de bloque que usa la ventana (ver `LightCache`); sin valor
+    // se trazan en cada punto
+    pub light_cache: Option<LightCache>,
+}
+
+pub const DEFAULT_SUN_DISTANCE: f32 = 10.0;
+// Un portal no puede aportar más que el cielo completo, así que 1 solo recorta muestras atípicas
+pub const DEFAULT_FIREFLY_CLAMP: f32 = 1.0;
+
+impl Scene {
+    pub fn diorama() -> Self {
+        let library = MaterialLibrary::builtin();
+        let mut world = World::new();
+        let [stone, grass, water, wood, glowstone] =
+            ["piedra", "cesped", "agua", "madera", "glowstone"]
+                .map(|name| library.block(&mut world, name).expect("material integrado"));
+
+        let water_positions = [(1, 2), (2, 2), (3, 2)];
+
+        for x in 0..5 {
+            for z in 0..5 {
+                if water_positions.contains(&(x, z)) {
+                    world.set_block((x, -1, z), water);
+                } else {
+                    // Añadir bloque de césped
+                    world.set_block((x, -1, z), grass);
+                }
+            }
+        }
+
+        world.fill((0, 0, 0), (1, 4, 1), wood);
+
+        world.set_block((0, 0, 4), glowstone);
+        world.set_block((4, 0, 0), glowstone);
+
+        for x in 1..=3 {
+            for y in 0..=2 {
+                if !(x == 2 && y == 1) {
+                    world.set_block((x, y, 4), stone);
+                }
+            }
+        }
+
+        let camera = Camera::new(
+            Vec3::new(2.5, 2.0, 10.0),
+            Vec3::new(2.5, 0.0, 2.5),
+            Vec3::new(0.0, 1.0, 0.0),
+        );
+
+        let lights = vec![Light::new(
+            Vec3::new(0.0, 10.0, 5.0),
+            Color::from_u8(255, 255, 255),
+            1.0,
+        )];
+
+        Scene {
+            world,
+            objects: Vec::new(),
+            lights,
+            ambient: Color::black(),
+            moon: None,
+            light_time: None,
+            portals: Vec::new(),
+            skybox: default_skybox(),
+            clouds: None,
+            fog: None,
+            post: Vec::new(),
+            camera,
+            sun_distance: DEFAULT_SUN_DISTANCE,
+            firefly_clamp: DEFAULT_FIREFLY_CLAMP,
+            roulette: 0.0,
+            transparent_background: false,
+            dither: false,
+            light_cache: None,
+        }
+    }
+
+    // Mundo generado con ruido en lugar del diorama hecho a mano
+    pub fn terrain(settings: &TerrainSettings) -> Self {
+        let library = MaterialLibrary::builtin();
+        let mut world = World::new();
+        let [stone, grass, water, dirt] = ["piedra", "cesped", "agua", "tierra"]
+            .map(|name| library.block(&mut world, name).expect("material integrado"));
+        let materials = TerrainMaterials {
+            stone,
+            grass,
+            water,
+            dirt,
+        };
+        generate_terrain(&mut world, settings, &materials);
+
+        let extent = settings.width.max(settings.depth) as f32;
+        let top = (settings.base_height as f32 + settings.amplitude).max(settings.sea_level as f32);
+        let camera = Camera::new(
+            Vec3::new(0.0, top + extent * 0.4, extent * 0.9),
+            Vec3::new(0.0, settings.sea_level as f32, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+        );
+
+        let lights = vec![Light::new(
+            Vec3::new(0.0, top + extent, 0.0),
+            Color::from_u8(255, 255, 255),
+            1.0,
+        )];
+
+        Scene {
+            world,
+            objects: Vec::new(),
+            lights,
+            ambient: Color::black(),
+            moon: None,
+            light_time: None,
+            portals: Vec::new(),
+            skybox: default_skybox(),
+            clouds: None,
+            fog: None,
+            post: Vec::new(),
+            camera,
+            sun_distance: (extent * 0.75).max(DEFAULT_SUN_DISTANCE),
+            firefly_clamp: DEFAULT_FIREFLY_CLAMP,
+            roulette: 0.0,
+            transparent_background: false,
+            dither: false,
+            light_cache: None,
+        }
+    }
+
+    // Cubos libres con sus animaciones evaluadas en `time` segundos; los bloques del
+    // mundo no se convierten en cubos porque el trazador los recorre directamente
+    pub fn cubes_at(&self, time: f32) -> Vec<Cube> {
+        self.objects
+            .iter()
+            .flat_map(|object| object.cubes_at(&self.world, time))
+            .collect()
+    }
+
+    pub fn is_animated(&self) -> bool {
+        self.objects.iter().any(|object| object.is_animated())
+    }
+
+    // Las luces que iluminan ahora: las de la escena y la luna, sin las que están apagadas
+    // (la luna bajo el horizonte, un grupo apagado o un destello entre pulsos)
+    pub fn active_lights(&self) -> impl Iterator<Item = &Light> {
+        let moon = self.moon.as_ref().map(|moon| &moon.light);
+        self.lights
+            .iter()
+            .chain(moon)
+            .filter(|light| light.intensity * light.intensity_scale > 0.0)
+    }
+
+    // Evalúa las animaciones de las luces (pulso, parpadeo, destellos) a los `time` segundos;
+    // las de la emisión de los materiales se evalúan con ese tiempo al sombrear
+    pub fn animate_lights(&mut self, time: f32) {
+        self.light_time = Some(time);
+        for (index, light) in self.lights.iter_mut().enumerate() {
+            light.intensity_scale = light
+                .animation
+                .map_or(1.0, |animation| animation.factor(time, index as u32));
+        }
+        if let Some(moon) = &mut self.moon {
+            moon.light.intensity_scale = 1.0;
+        }
+    }
+
+    // Pone en cada material con textura animada el cuadro de los `time` segundos
+    pub fn animate_textures(&mut self, time: f32) {
+        let frames: Vec<_> = self
+            .world
+            .materials()
+            .filter_map(|(block, _, material)| {
+                Some((block, material.texture_animation.as_ref()?.frame(time)))
+            })
+            .collect();
+        for (block, frame) in frames {
+            self.world.material_mut(block).texture = Some(Texture::Image(frame));
+        }
+    }
+
+    // Gira el cielo y mueve las nubes a los `time` segundos
+    pub fn animate_sky(&mut self, time: f32) {
+        self.skybox.animate(time);
+        if let Some(clouds) = &mut self.clouds {
+            clouds.animate(time);
+        }
+    }
+
+    // Lo que ve un rayo desde `origin` que no choca con nada: el cielo y las nubes
+    pub fn sky_color(&self, origin: &Vec3, direction: &Vec3) -> Color {
+        match &self.clouds {
+            Some(clouds) => clouds.composite(origin, direction, &self.skybox, self.lights.first()),
+            None => self.skybox.get_color_from_direction(direction),
+        }
+    }
+
+    // Reemplaza el cielo por uno calculado que sigue al sol (la primera luz); sin luces el
+    // sol queda alto, como a media mañana
+    pub fn use_dynamic_sky(&mut self) {
+        let sun_direction = self
+            .lights
+            .first()
+            .map_or(Vec3::new(0.4, 0.8, 0.3), |sun| sun.position);
+        self.skybox = Skybox::dynamic(sun_direction);
+    }
+
+    // Coloca el sol (la primera luz) según el avance del día (0.0 a 1.0), ajusta su color e
+    // intensidad y cambia la luz ambiente, el tono del cielo y la luna con la hora
+    pub fn apply_day_cycle(&mut self, day_progress: f32) {
+        let lighting = day_lighting(day_progress);
+        self.ambient = lighting.ambient;
+        self.skybox.tint = lighting.sky_tint;
+
+        if let Some(moon) = &mut self.moon {
+            let moon_direction = -lighting.sun_direction;
+            moon.light.position = moon_direction * self.sun_distance;
+            moon.light.color = lighting.moon_color;
+            moon.light.intensity = lighting.moon_intensity;
+            self.skybox.moon =
+                (moon.visible && lighting.moon_intensity > 0.0).then_some(moon_direction);
+        }
+
+        let Some(sun) = self.lights.first_mut() else {
+            return;
+        };
+        sun.position = lighting.sun_direction * self.sun_distance;
+        sun.intensity = lighting.sun_intensity;
+        sun.color = lighting.sun_color;
+
+        if let Some(sky) = &mut self.skybox.dynamic {
+            sky.sun_direction = lighting.sun_direction;
+            sky.sun_color = lighting.sun_color;
+        }
+    }
+}
+
+pub fn default_skybox() -> Skybox {
+    let face = load_texture("./src/textures/sky.jpg");
+    let mut skybox = Skybox::new(
+        face.clone(),
+        face.clone(),
+        face.clone(),
+        face.clone(),
+        face.clone(),
+        face,
+    );
+    skybox.source_path = Some("./src/textures/sky.jpg".to_string());
+    skybox
+}
@@ -0,0 +1,2099 @@
+// scene_file.rs
+//
+// Formato de escena en texto plano, una directiva por línea:
+//
+//   # comentario
+//   block_size 1m
+//   camera position 2.5 2 10 target 2.5 0 2.5 up 0 1 0 fov 60deg near 0.5 aspect 1.333
+//   camera aperture 0.1 focus 8
+//   camera near 4 far 30 clip_fade 2 roll 10deg
+//   camera target 0 0 0 position 10 10 10 projection orthographic ortho_height 12
+//   camera target 0 0 0 position 0 0 20 isometric
+//   sky ../src/textures/sky.jpg
+//   sky dynamic
+//   sky ../cielos/estudio.hdr yaw 90deg yaw_speed 1deg
+//   material piedra diffuse 90 90 90 specular 10 albedo 0.6 0.1 0.1 0 ior 1.0 texture ../src/textures/old-cobblestone-texture.png
+//   material tablero specular 5 albedo 0.9 0.1 0 0 pattern checker 230 230 230 40 40 40 0.5m
+//   material oro diffuse 255 195 85 metallic 1 roughness 0.3
+//   material hielo diffuse 200 230 255 specular 60 albedo 0.1 0.3 0.2 0.6 ior 1.31 blur 0.2
+//   material agua diffuse 50 50 200 specular 50 albedo 0.1 0.7 0.4 0.7 ior 1.33 absorption 90 35 15
+//   material pantano preset agua diffuse 60 90 40 absorption 150 60 90
+//   material vitral diffuse 40 80 230 specular 60 albedo 0.2 0.2 0.1 0.7 ior 1.5 stained
+//   light position 0 10 5 color 255 255 255 intensity 1.0 radius 0.5
+//   light position 3 2 3 color 255 170 80 intensity 0.8 flicker 120ms 0.4
+//   light position 2 2.5 2 color 255 200 150 intensity 0.6 group interior
+//   cube piedra min 1 0 4 max 2 1 5
+//   fill piedra min 0 -1 0 max 5 0 5
+//   terrain grass cesped dirt tierra stone piedra water agua seed 7 size 32 32 sea_level 4
+//   sun_distance 20
+//   moon visible
+//   firefly_clamp 1.5
+//   roulette 0.1
+//   transparent_background
+//   dither
+//   clouds height 24 thickness 4 coverage 0.5 size 16 wind 1 0.4 color 255 255 255
+//   fog density 0.05 color 215 225 235 distance 48 anisotropy 0.5
+//   post exposure 0.5
+//   post bloom threshold 0.8 strength 0.5 radius 0.02
+//   post vignette 0.4
+//   post lut calido.cube
+//   portal min 2 1 4 max 3 2 4
+//   object puerta pivot 2 0 4
+//   cube madera min 2 0 4 max 3 2 4.2 object puerta
+//   animate puerta translation catmull_rom loop key 0s 0 0 0 key 2s 0 1 0 key 4s 0 0 0
+//
+// `portal` marca una ventana (un rectángulo sin grosor en un eje) por la que entra la luz
+// del cielo a los interiores.
+//
+// Un cielo `.hdr` o `.exr` es un panorama equirectangular con radiancia mayor que 1;
+// `intensity` lo aclara u oscurece.
+//
+// `yaw` gira el cielo alrededor del eje vertical (antihorario visto desde arriba) y
+// `yaw_speed` lo sigue girando ese ángulo por segundo durante las animaciones.
+//
+// `sky dynamic` calcula el cielo con la posición del sol (la primera luz) en lugar de usar
+// una imagen: cambia de color con el ciclo de día y de noche muestra estrellas.
+//
+// `pattern <checker|noise|wood> <color> <color> <tamaño>` pinta el material con un patrón
+// calculado en cada punto en lugar de una imagen: un tablero de casillas de ese lado, ruido
+// con detalles de ese tamaño o anillos de madera alrededor del eje vertical separados por
+// esa distancia. Reemplaza a `texture`.
+//
+// `metallic` y `roughness` (de 0 a 1) pasan el material al modelo PBR: `diffuse` es el color
+// base, `ior` da el brillo de los no metálicos y `specular` y `albedo` dejan de usarse.
+// Si falta uno de los dos vale 0 (metallic) o 0.5 (roughness). Los materiales PBR son opacos.
+//
+// `blur` (de 0 a 1) hace borrosos los reflejos y refracciones de un material clásico, como
+// en el agua movida o el hielo; en los PBR lo hace la rugosidad.
+//
+// `absorption <color>` es cuánto de cada canal absorbe el interior de un material
+// transparente por cada bloque recorrido (255 lo absorbe todo): el agua profunda se
+// oscurece y el vidrio grueso se tiñe.
+//
+// `stained` hace de un material transparente un vidrio de color: la luz que pasa por él
+// llega teñida con su `diffuse` (o su textura) en lugar de quedar tapada, así el sol a
+// través de un vidrio azul deja una mancha azul en el suelo.
+//
+// `max_depth <n>` son los rebotes hasta los que se siguen los reflejos y refracciones que
+// salen del material; sin él valen los de la calidad del render, más para los transparentes.
+//
+// Los materiales de la biblioteca integrada (piedra, cesped, agua, madera, glowstone y
+// tierra) se pueden usar en `cube`, `fill` y `terrain` sin definirlos; un `material` con el
+// mismo nombre tiene prioridad. `preset <nombre>` parte de uno de ellos y las propiedades
+// que siguen lo modifican.
+//
+// `emission_map` y `specular_map` son imágenes en gris alineadas con la textura: la primera
+// multiplica `emission` (solo brillan las partes claras, como las grietas de la glowstone) y
+// la segunda apaga el brillo y los reflejos donde es oscura, como las partes secas de un
+// bloque mojado.
+//
+// Los texels de una textura con alfa menor a `alpha_cutoff` (0.5 si no se indica) se
+// recortan: los rayos y las sombras pasan por los huecos. Con 0 la textura es opaca.
+//
+// `moon` agrega una luna del lado opuesto al sol que ilumina las noches con una luz fría;
+// con `visible` además se dibuja su disco en el cielo. Sigue al ciclo del día, así que sin
+// él queda apagada.
+//
+// `firefly_clamp` limita la luminancia de cada muestra de luz indirecta para evitar
+// puntos brillantes aislados; con 0 no se limita.
+//
+// `roulette` corta al azar los reflejos y refracciones que traen menos de esa fracción del
+// color del pixel (ruleta rusa) antes de llegar al límite de rebotes, que no cambia. Con 0
+// (o sin la línea) se usan solo los límites de rebotes.
+//
+// `clouds` agrega una capa de nubes de ruido entre `height` y `height + thickness`,
+// iluminada por el sol (la primera luz). `coverage` (de 0 a 1) es cuánto del cielo tapan,
+// `size` el tamaño de cada nube y `wind` la velocidad en x y z, en bloques por segundo,
+// con la que se mueven en las animaciones.
+//
+// `fog` llena el aire de niebla: `density` es la fracción de luz que se pierde por bloque,
+// `distance` hasta dónde se recorre cada rayo y `anisotropy` (de 0 a menos de 1) cuánto se
+// concentra el brillo alrededor del sol. Donde el sol (la primera luz) llega la niebla se
+// ilumina, así que las sombras de los bloques se ven como haces de luz.
+//
+// Cada `post` agrega un efecto a la imagen terminada, en el orden en que aparecen:
+// `exposure` la aclara u oscurece en pasos de diafragma, `bloom` suma un halo a lo que pasa
+// de `threshold` de luminancia (`radius` es una fracción del alto de la imagen), `vignette`
+// (de 0 a 1) oscurece las esquinas y `lut` corrige el color con una tabla `.cube` 3D.
+//
+// `transparent_background` deja transparentes los pixeles donde se vería el cielo, para
+// componer el render sobre otra imagen.
+//
+// `dither` reparte con ruido azul el error de pasar la imagen a 8 bits, así los degradados
+// suaves del cielo no se ven en escalones.
+//
+// `group <nombre>` junta luces que se encienden y apagan juntas con una tecla en la
+// ventana, para comparar iluminaciones sin editar la escena.
+//
+// Las luces pueden animarse con `pulse <período> <profundidad>` (oscila suavemente),
+// `flicker <período> <profundidad>` (parpadeo irregular de antorcha) o
+// `strobe <período> <fracción encendida>` (destellos). La profundidad va de 0 a 1. En un
+// `material` las mismas animaciones hacen variar `emission`, con cada bloque desfasado
+// para que no titilen al unísono.
+//
+// `terrain` genera un mapa de alturas con ruido usando los cuatro materiales indicados;
+// además acepta `height`, `amplitude`, `scale`, `octaves` y `dirt_depth`.
+//
+// `vox <archivo>` agrega los bloques de un modelo de MagicaVoxel con su esquina mínima en
+// `at` (el origen si no se indica). Cada color usado es un material `<nombre>_<índice>`,
+// con `name` o el nombre del archivo; un `material` con ese nombre definido antes lo
+// reemplaza. Los metales, vidrios y emisivos de MagicaVoxel se traducen a los de acá.
+//
+// `schematic <archivo>` agrega una construcción de Minecraft (`.schem` de WorldEdit o
+// `.nbt` del bloque de estructuras) con su esquina mínima en `at`. Cada bloque usa el
+// material que se le asigne con `map <bloque> <material>`, uno definido con el id del
+// bloque (`oak_leaves`) o el integrado que le corresponde; los demás se dejan afuera.
+//
+// `resource_pack <carpeta o .zip>` texturiza los materiales integrados (`piedra`, `cesped`,
+// `agua`, `madera`, `glowstone`, `tierra`) con los de un resource pack de Minecraft, con
+// sus animaciones. Va después de los `material` con esos nombres, que conservan lo demás.
+// En un `material` integrado, `resource_pack <carpeta o .zip>` le pone solo a él la textura
+// del pack; así se guardan los materiales texturizados por uno.
+//
+// Los cubos con `object` se agrupan en un objeto con nombre que puede animarse con
+// pistas de `translation` (longitudes) o `scale` (factores alrededor de `pivot`).
+// Cada pista indica su interpolación (`step`, `linear`, `catmull_rom`) y, con `loop`,
+// se repite. Los tiempos de las claves llevan unidad (`1.5s`, `250ms`).
+//
+// Las longitudes se expresan en bloques (`2`, `2b`) o en metros (`1.5m`), usando
+// `block_size` para convertir. Los ángulos siempre llevan unidad (`60deg`, `1.05rad`).
+// Los números usan '.' como separador decimal sin importar la configuración regional.
+
+use nalgebra_glm::Vec3;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{self, Write};
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+use crate::animation::{Interpolation, Track};
+use crate::assets::{load_texture, Texture, TextureFilter};
+use crate::camera::{Camera, Projection};
+use crate::camera_path::CameraPath;
+use crate::chunk::BlockId;
+use crate::clouds::CloudLayer;
+use crate::color::Color;
+use crate::fog::Fog;
+use crate::light::{Light, LightAnimation};
+use crate::material::{Material, ShadingModel, TransparencyMode, DEFAULT_ALPHA_CUTOFF};
+use crate::material_library::MaterialLibrary;
+use crate::moon::Moon;
+use crate::object::{ObjectPart, SceneObject};
+use crate::portal::Portal;
+use crate::post::{Bloom, ColorLut, PostEffect};
+use crate::procedural::{Pattern, PatternKind};
+use crate::resource_pack::ResourcePack;
+use crate::scene::{Scene, DEFAULT_FIREFLY_CLAMP, DEFAULT_SUN_DISTANCE};
+use crate::schematic::Schematic;
+use crate::skybox::{is_hdr_path, CubemapNaming, Skybox, DEFAULT_SKY_FILTER};
+use crate::terrain::{generate_terrain, TerrainMaterials, TerrainSettings};
+use crate::vox::VoxModel;
+use crate::world::World;
+
+const DEFAULT_SKY: &str = "./src/textures/sky.jpg";
+
+#[derive(Debug, Clone)]
+pub struct SceneError {
+    pub path: PathBuf,
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.line == 0 {
+            write!(f, "{}: {}", self.path.display(), self.message)
+        } else {
+            write!(
+                f,
+                "{}:{}:{}: {}",
+                self.path.display(),
+                self.line,
+                self.column,
+                self.message
+            )
+        }
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+pub fn load_scene(path: &Path) -> Result<Scene, SceneError> {
+    let source = fs::read_to_string(path).map_err(|err| SceneError {
+        path: path.to_path_buf(),
+        line: 0,
+        column: 0,
+        message: format!("no se pudo leer el archivo: {}", err),
+    })?;
+    load_scene_source(&source, path)
+}
+
+// Como `load_scene` con el texto ya en memoria; `path` se usa en los mensajes de error y
+// las rutas de la escena son relativas a su carpeta
+pub fn load_scene_source(source: &str, path: &Path) -> Result<Scene, SceneError> {
+    let base_dir = path.parent().unwrap_or(Path::new("."));
+
+    parse_scene(source, base_dir).map_err(|err| SceneError {
+        path: path.to_path_buf(),
+        line: err.line,
+        column: err.column,
+        message: err.message,
+    })
+}
+
+// Recorrido de cámara en texto plano, con el mismo estilo que las escenas:
+//
+//   # longitudes en bloques, o en metros con block_size
+//   interpolation catmull_rom
+//   loop
+//   key 0s position 10 6 10 target 2.5 0 2.5
+//   key 4s position -10 4 10 target 2.5 1 2.5
+pub fn load_camera_path(path: &Path) -> Result<CameraPath, SceneError> {
+    let source = fs::read_to_string(path).map_err(|err| SceneError {
+        path: path.to_path_buf(),
+        line: 0,
+        column: 0,
+        message: format!("no se pudo leer el archivo: {}", err),
+    })?;
+
+    parse_camera_path(&source).map_err(|err| SceneError {
+        path: path.to_path_buf(),
+        line: err.line,
+        column: err.column,
+        message: err.message,
+    })
+}
+
+// Escribe la escena en el mismo formato que lee `load_scene`, con las rutas de
+// texturas relativas al archivo de destino
+pub fn save_scene(scene: &Scene, path: &Path) -> io::Result<()> {
+    let base_dir = path.parent().unwrap_or(Path::new("."));
+    if !base_dir.as_os_str().is_empty() {
+        fs::create_dir_all(base_dir)?;
+    }
+    fs::write(path, write_scene(scene, base_dir))
+}
+
+fn write_scene(scene: &Scene, base_dir: &Path) -> String {
+    // Escribir en un String no falla, así que se ignoran los resultados de writeln!
+    let mut out = String::new();
+    let _ = writeln!(out, "# Longitudes en bloques");
+
+    let camera = &scene.camera;
+    let _ = write!(
+        out,
+        "camera position {} target {} up {} fov {}deg",
+        vector(camera.position),
+        vector(camera.target),
+        vector(camera.up_direction),
+        camera.fov.to_degrees()
+    );
+    if camera.roll != 0.0 {
+        let _ = write!(out, " roll {}deg", camera.roll.to_degrees());
+    }
+    if camera.near > 0.0 {
+        let _ = write!(out, " near {}", camera.near);
+    }
+    if let Some(far) = camera.far {
+        let _ = write!(out, " far {}", far);
+    }
+    if camera.clip_fade > 0.0 {
+        let _ = write!(out, " clip_fade {}", camera.clip_fade);
+    }
+    if let Some(aspect) = camera.aspect_ratio {
+        let _ = write!(out, " aspect {}", aspect);
+    }
+    if camera.projection == Projection::Orthographic {
+        let _ = write!(
+            out,
+            " projection orthographic ortho_height {}",
+            camera.ortho_height
+        );
+    }
+    if camera.aperture > 0.0 {
+        let _ = write!(out, " aperture {}", camera.aperture);
+    }
+    if let Some(focus) = camera.focus_distance {
+        let _ = write!(out, " focus {}", focus);
+    }
+    let _ = writeln!(out);
+
+    if scene.sun_distance != DEFAULT_SUN_DISTANCE {
+        let _ = writeln!(out, "sun_distance {}", scene.sun_distance);
+    }
+
+    if scene.firefly_clamp != DEFAULT_FIREFLY_CLAMP {
+        let _ = writeln!(out, "firefly_clamp {}", scene.firefly_clamp);
+    }
+    if scene.roulette > 0.0 {
+        let _ = writeln!(out, "roulette {}", scene.roulette);
+    }
+
+    if let Some(clouds) = &scene.clouds {
+        let _ = writeln!(
+            out,
+            "clouds height {} thickness {} coverage {} size {} wind {} {} color {}",
+            clouds.height,
+            clouds.thickness,
+            clouds.coverage,
+            clouds.scale,
+            clouds.wind.x,
+            clouds.wind.z,
+            color(clouds.color)
+        );
+    }
+
+    if let Some(fog) = &scene.fog {
+        let _ = writeln!(
+            out,
+            "fog density {} color {} distance {} anisotropy {}",
+            fog.density,
+            color(fog.color),
+            fog.distance,
+            fog.anisotropy
+        );
+    }
+
+    for effect in &scene.post {
+        let _ = match effect {
+            PostEffect::Exposure(stops) => writeln!(out, "post exposure {}", stops),
+            PostEffect::Bloom(bloom) => writeln!(
+                out,
+                "post bloom threshold {} strength {} radius {}",
+                bloom.threshold, bloom.strength, bloom.radius
+            ),
+            PostEffect::Vignette(amount) => writeln!(out, "post vignette {}", amount),
+            PostEffect::Grade(lut) => writeln!(
+                out,
+                "post lut {}",
+                quoted(&relative_path(&lut.path, base_dir))
+            ),
+        };
+    }
+
+    if scene.transparent_background {
+        let _ = writeln!(out, "transparent_background");
+    }
+
+    if scene.dither {
+        let _ = writeln!(out, "dither");
+    }
+
+    if let Some(moon) = &scene.moon {
+        let _ = writeln!(out, "moon{}", if moon.visible { " visible" } else { "" });
+    }
+
+    if scene.skybox.dynamic.is_some() {
+        let _ = writeln!(out, "sky dynamic");
+    } else if let Some(sky) = &scene.skybox.source_path {
+        let _ = write!(out, "sky {}", quoted(&relative_path(sky, base_dir)));
+        if let Some(naming) = scene.skybox.naming {
+            let _ = write!(out, " naming {}", naming.name());
+        }
+        if scene.skybox.filter != DEFAULT_SKY_FILTER {
+            let _ = write!(out, " filter {}", scene.skybox.filter.name());
+        }
+        if let Some(environment) = scene
+            .skybox
+            .environment
+            .as_ref()
+            .filter(|environment| environment.intensity != 1.0)
+        {
+            let _ = write!(out, " intensity {}", environment.intensity);
+        }
+        if scene.skybox.yaw != 0.0 {
+            let _ = write!(out, " yaw {}deg", scene.skybox.yaw.to_degrees());
+        }
+        if scene.skybox.yaw_speed != 0.0 {
+            let _ = write!(out, " yaw_speed {}deg", scene.skybox.yaw_speed.to_degrees());
+        }
+        let _ = writeln!(out);
+    }
+
+    let _ = writeln!(out);
+    for (_, name, material) in scene.world.materials() {
+        let _ = write!(
+            out,
+            "material {} diffuse {} specular {} albedo {} {} {} {} ior {} emission {}",
+            quoted(name),
+            color(material.diffuse),
+            material.specular,
+            material.albedo[0],
+            material.albedo[1],
+            material.albedo[2],
+            material.albedo[3],
+            material.refractive_index,
+            color(material.emission)
+        );
+        if let ShadingModel::Pbr {
+            metallic,
+            roughness,
+        } = material.model
+        {
+            let _ = write!(out, " metallic {} roughness {}", metallic, roughness);
+        }
+        if material.blur > 0.0 {
+            let _ = write!(out, " blur {}", material.blur);
+        }
+        if material
+            .absorption
+            .r
+            .max(material.absorption.g)
+            .max(material.absorption.b)
+            > 0.0
+        {
+            let _ = write!(out, " absorption {}", color(material.absorption));
+        }
+        if let Some(texture) = &material.texture_path {
+            let _ = write!(
+                out,
+                " texture {}",
+                quoted(&relative_path(texture, base_dir))
+            );
+        }
+        if let Some(pack) = &material.texture_pack {
+            let _ = write!(
+                out,
+                " resource_pack {}",
+                quoted(&relative_path(pack, base_dir))
+            );
+        }
+        if let Some(Texture::Pattern(pattern)) = &material.texture {
+            let _ = write!(
+                out,
+                " pattern {} {} {} {}",
+                pattern.kind.name(),
+                color(pattern.colors[0]),
+                color(pattern.colors[1]),
+                pattern.size
+            );
+        }
+        if material.texture_filter != TextureFilter::Nearest {
+            let _ = write!(out, " texture_filter {}", material.texture_filter.name());
+        }
+        if let Some(normal_map) = &material.normal_map_path {
+            let _ = write!(
+                out,
+                " normal_map {}",
+                quoted(&relative_path(normal_map, base_dir))
+            );
+        }
+        if material.normal_map_filter != TextureFilter::Nearest {
+            let _ = write!(
+                out,
+                " normal_map_filter {}",
+                material.normal_map_filter.name()
+            );
+        }
+        if let Some(emission_map) = &material.emission_map_path {
+            let _ = write!(
+                out,
+                " emission_map {}",
+                quoted(&relative_path(emission_map, base_dir))
+            );
+        }
+        if let Some(specular_map) = &material.specular_map_path {
+            let _ = write!(
+                out,
+                " specular_map {}",
+                quoted(&relative_path(specular_map, base_dir))
+            );
+        }
+        if material.transparency != TransparencyMode::Refract {
+            let _ = write!(out, " transparency {}", material.transparency.name());
+        }
+        if material.alpha_cutoff != DEFAULT_ALPHA_CUTOFF {
+            let _ = write!(out, " alpha_cutoff {}", material.alpha_cutoff);
+        }
+        if material.stained {
+            out.push_str(" stained");
+        }
+        if let Some(max_depth) = material.max_depth {
+            let _ = write!(out, " max_depth {}", max_depth);
+        }
+        if let Some(animation) = material.emission_animation {
+            out.push_str(&animation_words(animation));
+        }
+        let _ = writeln!(out);
+    }
+
+    let _ = writeln!(out);
+    for light in &scene.lights {
+        let _ = write!(
+            out,
+            "light position {} color {} intensity {}",
+            vector(light.position),
+            color(light.color),
+            light.intensity
+        );
+        if light.radius > 0.0 {
+            let _ = write!(out, " radius {}", light.radius);
+        }
+        if let Some(animation) = light.animation {
+            out.push_str(&animation_words(animation));
+        }
+        if let Some(group) = &light.group {
+            let _ = write!(out, " group {}", quoted(group));
+        }
+        let _ = writeln!(out);
+    }
+
+    for portal in &scene.portals {
+        let _ = writeln!(
+            out,
+            "portal min {} max {}",
+            vector(portal.min),
+            vector(portal.max)
+        );
+    }
+
+    // Bloques del mundo ordenados y agrupados en tramos consecutivos a lo largo de x
+    let blocks: BTreeMap<_, _> = scene
+        .world
+        .blocks()
+        .map(|((x, y, z), block)| ((y, z, x), block))
+        .collect();
+    let mut runs: Vec<((i32, i32, i32), i32, _)> = Vec::new();
+    for (&(y, z, x), &block) in &blocks {
+        match runs.last_mut() {
+            Some(((run_x, run_y, run_z), length, run_block))
+                if *run_y == y && *run_z == z && *run_x + *length == x && *run_block == block =>
+            {
+                *length += 1
+            }
+            _ => runs.push(((x, y, z), 1, block)),
+        }
+    }
+
+    let _ = writeln!(out);
+    for ((x, y, z), length, block) in runs {
+        let _ = writeln!(
+            out,
+            "fill {} min {} {} {} max {} {} {}",
+            quoted(scene.world.material_name(block)),
+            x,
+            y,
+            z,
+            x + length,
+            y + 1,
+            z + 1
+        );
+    }
+
+    for object in &scene.objects {
+        let _ = writeln!(out);
+        let suffix = if object.name.is_empty() {
+            String::new()
+        } else {
+            let _ = writeln!(
+                out,
+                "object {} pivot {}",
+                quoted(&object.name),
+                vector(object.pivot)
+            );
+            format!(" object {}", quoted(&object.name))
+        };
+
+        for part in &object.parts {
+            let _ = writeln!(
+                out,
+                "cube {} min {} max {}{}",
+                quoted(scene.world.material_name(part.block)),
+                vector(part.min),
+                vector(part.max),
+                suffix
+            );
+        }
+
+        let tracks = [
+            ("translation", &object.translation),
+            ("scale", &object.scale),
+        ];
+        for (property, track) in tracks {
+            let Some(track) = track else {
+                continue;
+            };
+            let _ = write!(
+                out,
+                "animate {} {} {}",
+                quoted(&object.name),
+                property,
+                track.interpolation.name()
+            );
+            if track.looping {
+                let _ = write!(out, " loop");
+            }
+            for key in track.keys() {
+                let _ = write!(out, " key {}s {}", key.time, vector(key.value));
+            }
+            let _ = writeln!(out);
+        }
+    }
+
+    out
+}
+
+fn animation_words(animation: LightAnimation) -> String {
+    match animation {
+        LightAnimation::Pulse { period, depth } => format!(" pulse {}s {}", period, depth),
+        LightAnimation::Flicker { period, depth } => format!(" flicker {}s {}", period, depth),
+        LightAnimation::Strobe { period, duty } => format!(" strobe {}s {}", period, duty),
+    }
+}
+
+fn vector(value: Vec3) -> String {
+    format!("{} {} {}", value.x, value.y, value.z)
+}
+
+fn color(value: Color) -> String {
+    let channel = |c: f32| (c * 255.0).round().clamp(0.0, 255.0) as u8;
+    format!(
+        "{} {} {}",
+        channel(value.r),
+        channel(value.g),
+        channel(value.b)
+    )
+}
+
+fn quoted(text: &str) -> String {
+    if text.is_empty() || text.contains(|c: char| c.is_whitespace() || c == '#') {
+        format!("\"{}\"", text)
+    } else {
+        text.to_string()
+    }
+}
+
+// Expresa `target` relativo a `base_dir`; si no comparten ningún directorio se deja absoluto
+pub(crate) fn relative_path(target: &str, base_dir: &Path) -> String {
+    let absolute = |path: &Path| {
+        fs::canonicalize(path).unwrap_or_else(|_| {
+            std::env::current_dir()
+                .map(|dir| dir.join(path))
+                .unwrap_or_else(|_| path.to_path_buf())
+        })
+    };
+    let target_abs = absolute(Path::new(target));
+    let base_abs = absolute(base_dir);
+
+    let target_parts: Vec<Component> = target_abs.components().collect();
+    let base_parts: Vec<Component> = base_abs.components().collect();
+    let common = target_parts
+        .iter()
+        .zip(&base_parts)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let shares_directory = target_parts[..common]
+        .iter()
+        .any(|part| matches!(part, Component::Normal(_)));
+    if !shares_directory {
+        return target_abs.to_string_lossy().into_owned();
+    }
+
+    let mut relative = PathBuf::new();
+    for _ in common..base_parts.len() {
+        relative.push("..");
+    }
+    for part in &target_parts[common..] {
+        relative.push(part.as_os_str());
+    }
+    // Separadores '/' para que el archivo funcione en cualquier sistema
+    relative
+        .components()
+        .map(|part| part.as_os_str().to_string_lossy().into_owned())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+struct ParseError {
+    line: usize,
+    column: usize,
+    message: String,
+}
+
+type ParseResult<T> = Result<T, ParseError>;
+
+struct Token<'a> {
+    text: &'a str,
+    column: usize,
+}
+
+struct LineCursor<'a> {
+    line: usize,
+    end_column: usize,
+    tokens: Vec<Token<'a>>,
+    next: usize,
+}
+
+impl<'a> LineCursor<'a> {
+    fn new(line: usize, text: &'a str) -> ParseResult<Self> {
+        let mut tokens = Vec::new();
+        let mut chars = text.char_indices().peekable();
+
+        while let Some(&(start, c)) = chars.peek() {
+            if c.is_whitespace() {
+                chars.next();
+            } else if c == '#' {
+                break;
+            } else if c == '"' {
+                chars.next();
+                let content_start = start + 1;
+                let mut content_end = None;
+                for (index, c) in chars.by_ref() {
+                    if c == '"' {
+                        content_end = Some(index);
+                        break;
+                    }
+                }
+                let content_end = content_end.ok_or(ParseError {
+                    line,
+                    column: column_of(text, start),
+                    message: "comillas sin cerrar".to_string(),
+                })?;
+                tokens.push(Token {
+                    text: &text[content_start..content_end],
+                    column: column_of(text, start),
+                });
+            } else {
+                let mut end = text.len();
+                while let Some(&(index, c)) = chars.peek() {
+                    if c.is_whitespace() || c == '#' {
+                        end = index;
+                        break;
+                    }
+                    chars.next();
+                }
+                tokens.push(Token {
+                    text: &text[start..end],
+                    column: column_of(text, start),
+                });
+            }
+        }
+
+        Ok(LineCursor {
+            line,
+            end_column: text.chars().count() + 1,
+            tokens,
+            next: 0,
+        })
+    }
+
+    fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    fn error_at(&self, column: usize, message: String) -> ParseError {
+        ParseError {
+            line: self.line,
+            column,
+            message,
+        }
+    }
+
+    fn next_token(&mut self, expected: &str) -> ParseResult<&Token<'a>> {
+        let column = self.end_column;
+        let line = self.line;
+        let token = self.tokens.get(self.next).ok_or(ParseError {
+            line,
+            column,
+            message: format!("se esperaba {} pero la línea terminó", expected),
+        })?;
+        self.next += 1;
+        Ok(token)
+    }
+
+    fn optional_word(&mut self) -> Option<(&'a str, usize)> {
+        let token = self.tokens.get(self.next)?;
+        self.next += 1;
+        Some((token.text, token.column))
+    }
+
+    fn word(&mut self, expected: &str) -> ParseResult<(&'a str, usize)> {
+        let token = self.next_token(expected)?;
+        Ok((token.text, token.column))
+    }
+
+    fn number(&mut self, expected: &str) -> ParseResult<f32> {
+        let (value, unit, column) = self.number_with_unit(expected)?;
+        if !unit.is_empty() {
+            return Err(self.error_at(
+                column,
+                format!("{} no lleva unidad, se encontró '{}'", expected, unit),
+            ));
+        }
+        Ok(value)
+    }
+
+    fn integer<T: std::str::FromStr>(&mut self, expected: &str) -> ParseResult<T> {
+        let (text, column) = self.word(expected)?;
+        text.parse().map_err(|_| {
+            self.error_at(
+                column,
+                format!(
+                    "{} debe ser un número entero, se encontró '{}'",
+                    expected, text
+                ),
+            )
+        })
+    }
+
+    fn number_with_unit(&mut self, expected: &str) -> ParseResult<(f32, &'a str, usize)> {
+        let (text, column) = self.word(expected)?;
+        let (value, unit) = split_number(text).map_err(|message| self.error_at(column, message))?;
+        Ok((value, unit, column))
+    }
+
+    fn length(&mut self, units: &Units, expected: &str) -> ParseResult<f32> {
+        let (value, unit, column) = self.number_with_unit(expected)?;
+        units
+            .length_to_blocks(value, unit)
+            .map_err(|message| self.error_at(column, message))
+    }
+
+    fn angle(&mut self, expected: &str) -> ParseResult<f32> {
+        let (value, unit, column) = self.number_with_unit(expected)?;
+        match unit {
+            "deg" => Ok(value.to_radians()),
+            "rad" => Ok(value),
+            "" => Err(self.error_at(
+                column,
+                format!(
+                    "{} necesita unidad explícita: usa 'deg' o 'rad' (ej. {}deg)",
+                    expected, value
+                ),
+            )),
+            other => Err(self.error_at(
+                column,
+                format!(
+                    "unidad de ángulo desconocida '{}', usa 'deg' o 'rad'",
+                    other
+                ),
+            )),
+        }
+    }
+
+    fn time(&mut self, expected: &str) -> ParseResult<f32> {
+        let (value, unit, column) = self.number_with_unit(expected)?;
+        match unit {
+            "s" => Ok(value),
+            "ms" => Ok(value / 1000.0),
+            "" => Err(self.error_at(
+                column,
+                format!(
+                    "{} necesita unidad explícita: usa 's' o 'ms' (ej. {}s)",
+                    expected, value
+                ),
+            )),
+            other => Err(self.error_at(
+                column,
+                format!("unidad de tiempo desconocida '{}', usa 's' o 'ms'", other),
+            )),
+        }
+    }
+
+    // `pulse`, `flicker` o `strobe` (en `key`, leída en `column`) con su período y su
+    // profundidad o fracción encendida
+    fn light_animation(&mut self, key: &str, column: usize) -> ParseResult<LightAnimation> {
+        let period = self.time("el período")?;
+        if period <= 0.0 {
+            return Err(self.error_at(column, format!("el período de {} debe ser positivo", key)));
+        }
+        let amount = self.number(if key == "strobe" {
+            "la fracción encendida"
+        } else {
+            "la profundidad"
+        })?;
+        if !(0.0..=1.0).contains(&amount) {
+            return Err(self.error_at(
+                column,
+                format!("el segundo valor de {} debe estar entre 0 y 1", key),
+            ));
+        }
+        Ok(match key {
+            "pulse" => LightAnimation::Pulse {
+                period,
+                depth: amount,
+            },
+            "flicker" => LightAnimation::Flicker {
+                period,
+                depth: amount,
+            },
+            _ => LightAnimation::Strobe {
+                period,
+                duty: amount,
+            },
+        })
+    }
+
+    fn point(&mut self, units: &Units, expected: &str) -> ParseResult<Vec3> {
+        Ok(Vec3::new(
+            self.length(units, &format!("la coordenada x de {}", expected))?,
+            self.length(units, &format!("la coordenada y de {}", expected))?,
+            self.length(units, &format!("la coordenada z de {}", expected))?,
+        ))
+    }
+
+    fn direction(&mut self, expected: &str) -> ParseResult<Vec3> {
+        let column = self
+            .tokens
+            .get(self.next)
+            .map_or(self.end_column, |t| t.column);
+        let direction = Vec3::new(
+            self.number(&format!("la componente x de {}", expected))?,
+            self.number(&format!("la componente y de {}", expected))?,
+            self.number(&format!("la componente z de {}", expected))?,
+        );
+        if direction.magnitude() == 0.0 {
+            return Err(self.error_at(column, format!("{} no puede ser el vector cero", expected)));
+        }
+        Ok(direction)
+    }
+
+    fn color(&mut self, expected: &str) -> ParseResult<Color> {
+        let mut channels = [0u8; 3];
+        for (channel, name) in channels.iter_mut().zip(["rojo", "verde", "azul"]) {
+            let (text, column) = self.word(&format!("el canal {} de {}", name, expected))?;
+            *channel = text.parse().map_err(|_| {
+                self.error_at(
+                    column,
+                    format!(
+                        "canal {} inválido '{}', usa un entero entre 0 y 255",
+                        name, text
+                    ),
+                )
+            })?;
+        }
+        Ok(Color::from_u8(channels[0], channels[1], channels[2]))
+    }
+
+    fn path(&mut self, base_dir: &Path, expected: &str) -> ParseResult<String> {
+        let (text, _) = self.word(expected)?;
+        Ok(base_dir.join(text).to_string_lossy().into_owned())
+    }
+
+    fn cubemap_naming(&mut self) -> ParseResult<CubemapNaming> {
+        let (name, column) = self.word("la convención de nombres")?;
+        CubemapNaming::from_name(name).ok_or_else(|| {
+            self.error_at(
+                column,
+                format!(
+                    "convención desconocida '{}', usa 'sides', 'posx' o 'px'",
+                    name
+                ),
+            )
+        })
+    }
+
+    fn interpolation(&mut self) -> ParseResult<Interpolation> {
+        let (name, column) = self.word("la interpolación")?;
+        Interpolation::from_name(name).ok_or_else(|| {
+            self.error_at(
+                column,
+                format!(
+                    "interpolación desconocida '{}', usa 'step', 'linear' o 'catmull_rom'",
+                    name
+                ),
+            )
+        })
+    }
+
+    fn texture_filter(&mut self) -> ParseResult<TextureFilter> {
+        let (name, column) = self.word("el filtro de textura")?;
+        TextureFilter::from_name(name).ok_or_else(|| {
+            self.error_at(
+                column,
+                format!("filtro desconocido '{}', usa 'nearest' o 'bilinear'", name),
+            )
+        })
+    }
+
+    // `<checker|noise|wood> <color> <color> <tamaño>`
+    fn pattern(&mut self, units: &Units) -> ParseResult<Pattern> {
+        let (name, column) = self.word("el tipo de patrón")?;
+        let kind = PatternKind::from_name(name).ok_or_else(|| {
+            self.error_at(
+                column,
+                format!(
+                    "patrón desconocido '{}', usa 'checker', 'noise' o 'wood'",
+                    name
+                ),
+            )
+        })?;
+        let colors = [
+            self.color("el primer color del patrón")?,
+            self.color("el segundo color del patrón")?,
+        ];
+        let column = self
+            .tokens
+            .get(self.next)
+            .map_or(self.end_column, |t| t.column);
+        let size = self.length(units, "el tamaño del patrón")?;
+        if size <= 0.0 {
+            return Err(self.error_at(column, "el tamaño del patrón debe ser positivo".to_string()));
+        }
+        Ok(Pattern { kind, colors, size })
+    }
+
+    fn transparency(&mut self) -> ParseResult<TransparencyMode> {
+        let (name, column) = self.word("el modo de transparencia")?;
+        TransparencyMode::from_name(name).ok_or_else(|| {
+            self.error_at(
+                column,
+                format!(
+                    "transparencia desconocida '{}', usa 'refract' o 'blended'",
+                    name
+                ),
+            )
+        })
+    }
+
+    fn finish(&self) -> ParseResult<()> {
+        match self.tokens.get(self.next) {
+            Some(token) => Err(self.error_at(
+                token.column,
+                format!("texto inesperado '{}' al final de la línea", token.text),
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+fn column_of(text: &str, byte_index: usize) -> usize {
+    text[..byte_index].chars().count() + 1
+}
+
+// Separa "12.5m" en (12.5, "m") y "1e5m" en (100000, "m"); rechaza comas decimales, NaN
+// e infinitos
+fn split_number(text: &str) -> Result<(f32, &str), String> {
+    let mut split = text
+        .char_indices()
+        .find(|&(index, c)| {
+            !(c.is_ascii_digit() || c == '.' || ((c == '-' || c == '+') && index == 0))
+        })
+        .map_or(text.len(), |(index, _)| index);
+    // El exponente es parte del número solo si tiene dígitos; si no, la "e" es la unidad
+    if let Some(exponent) = text[split..].strip_prefix(['e', 'E']) {
+        let digits = exponent.strip_prefix(['-', '+']).unwrap_or(exponent);
+        let count = digits.bytes().take_while(u8::is_ascii_digit).count();
+        if count > 0 {
+            split = text.len() - digits.len() + count;
+        }
+    }
+    let (number, unit) = text.split_at(split);
+
+    if unit.starts_with(',') {
+        return Err(format!(
+            "número inválido '{}': usa '.' como separador decimal",
+            text
+        ));
+    }
+    if number.is_empty() || number == "-" || number == "+" {
+        return Err(format!("se esperaba un número, se encontró '{}'", text));
+    }
+
+    let value: f32 = number
+        .parse()
+        .map_err(|_| format!("número inválido '{}'", number))?;
+    if !value.is_finite() {
+        return Err(format!("número fuera de rango '{}'", number));
+    }
+    Ok((value, unit))
+}
+
+struct Units {
+    // Metros que mide un bloque
+    block_size: f32,
+}
+
+impl Units {
+    fn length_to_blocks(&self, value: f32, unit: &str) -> Result<f32, String> {
+        match unit {
+            "" | "b" | "blocks" => Ok(value),
+            "m" => Ok(value / self.block_size),
+            other => Err(format!(
+                "unidad de longitud desconocida '{}', usa 'b' (bloques) o 'm' (metros)",
+                other
+            )),
+        }
+    }
+}
+
+fn parse_scene(source: &str, base_dir: &Path) -> ParseResult<Scene> {
+    let mut units = Units { block_size: 1.0 };
+    let mut world = World::new();
+    let mut objects: Vec<SceneObject> = Vec::new();
+    let mut lights = Vec::new();
+    let mut portals = Vec::new();
+    let mut skybox = None;
+    let mut dynamic_sky = false;
+    let mut sun_distance = DEFAULT_SUN_DISTANCE;
+    let mut firefly_clamp = DEFAULT_FIREFLY_CLAMP;
+    let mut roulette = 0.0;
+    // Cada resource pack se lee una vez aunque lo usen varios materiales
+    let mut packs: HashMap<String, ResourcePack> = HashMap::new();
+    let mut transparent_background = false;
+    let mut dither = false;
+    let mut moon = None;
+    let mut clouds = None;
+    let mut fog = None;
+    let mut post = Vec::new();
+    let mut camera = Camera::new(
+        Vec3::new(2.5, 2.0, 10.0),
+        Vec3::new(2.5, 0.0, 2.5),
+        Vec3::new(0.0, 1.0, 0.0),
+    );
+
+    for (index, text) in source.lines().enumerate() {
+        let mut line = LineCursor::new(index + 1, text)?;
+        if line.is_empty() {
+            continue;
+        }
+
+        let (directive, column) = line.word("una directiva")?;
+        match directive {
+            "block_size" => {
+                let (value, unit, column) = line.number_with_unit("el tamaño de bloque")?;
+                if unit != "m" {
+                    return Err(line.error_at(
+                        column,
+                        "block_size se expresa en metros (ej. block_size 1m)".to_string(),
+                    ));
+                }
+                if value <= 0.0 {
+                    return Err(line.error_at(column, "block_size debe ser positivo".to_string()));
+                }
+                units.block_size = value;
+            }
+            "camera" => {
+                let mut isometric = false;
+                let mut ortho_height = None;
+                while let Some((key, column)) = line.optional_word() {
+                    match key {
+                        "position" => camera.position = line.point(&units, "position")?,
+                        "target" => camera.target = line.point(&units, "target")?,
+                        "up" => camera.up_direction = line.direction("up")?.normalize(),
+                        "roll" => camera.roll = line.angle("roll")?,
+                        "fov" => {
+                            let fov = line.angle("fov")?;
+                            if fov <= 0.0 || fov >= std::f32::consts::PI {
+                                return Err(line.error_at(
+                                    column,
+                                    "fov debe estar entre 0deg y 180deg".to_string(),
+                                ));
+                            }
+                            camera.fov = fov;
+                        }
+                        "near" => {
+                            let near = line.length(&units, "near")?;
+                            if near < 0.0 {
+                                return Err(
+                                    line.error_at(column, "near no puede ser negativo".to_string())
+                                );
+                            }
+                            camera.near = near;
+                        }
+                        "far" => {
+                            let far = line.length(&units, "far")?;
+                            if far <= 0.0 {
+                                return Err(
+                                    line.error_at(column, "far debe ser positivo".to_string())
+                                );
+                            }
+                            camera.far = Some(far);
+                        }
+                        "clip_fade" => {
+                            let fade = line.length(&units, "clip_fade")?;
+                            if fade < 0.0 {
+                                return Err(line.error_at(
+                                    column,
+                                    "clip_fade no puede ser negativo".to_string(),
+                                ));
+                            }
+                            camera.clip_fade = fade;
+                        }
+                        "aspect" => {
+                            let aspect = line.number("aspect")?;
+                            if aspect <= 0.0 {
+                                return Err(
+                                    line.error_at(column, "aspect debe ser positivo".to_string())
+                                );
+                            }
+                            camera.aspect_ratio = Some(aspect);
+                        }
+                        "aperture" => {
+                            let aperture = line.length(&units, "aperture")?;
+                            if aperture < 0.0 {
+                                return Err(line.error_at(
+                                    column,
+                                    "aperture no puede ser negativo".to_string(),
+                                ));
+                            }
+                            camera.aperture = aperture;
+                        }
+                        "focus" => {
+                            let focus = line.length(&units, "focus")?;
+                            if focus <= 0.0 {
+                                return Err(
+                                    line.error_at(column, "focus debe ser positivo".to_string())
+                                );
+                            }
+                            camera.focus_distance = Some(focus);
+                        }
+                        "projection" => {
+                            let (name, name_column) = line.word("la proyección")?;
+                            camera.projection = match name {
+                                "perspective" => Projection::Perspective,
+                                "orthographic" => Projection::Orthographic,
+                                other => {
+                                    return Err(line.error_at(
+                                        name_column,
+                                        format!(
+                                            "proyección desconocida '{}' (usa perspective u orthographic)",
+                                            other
+                                        ),
+                                    ))
+                                }
+                            };
+                        }
+                        "ortho_height" => {
+                            let value = line.length(&units, "ortho_height")?;
+                            if value <= 0.0 {
+                                return Err(line.error_at(
+                                    column,
+                                    "ortho_height debe ser positivo".to_string(),
+                                ));
+                            }
+                            ortho_height = Some(value);
+                        }
+                        // Se aplica al final de la línea, cuando ya se conocen posición y objetivo
+                        "isometric" => isometric = true,
+                        other => return Err(unknown_key(&line, column, "camera", other)),
+                    }
+                }
+                if camera.far.is_some_and(|far| far <= camera.near) {
+                    return Err(line.error_at(column, "far debe ser mayor que near".to_string()));
+                }
+                camera.update_view();
+                if isometric {
+                    camera.set_isometric();
+                }
+                // Una altura explícita tiene prioridad sobre la que calcula `isometric`
+                if let Some(ortho_height) = ortho_height {
+                    camera.ortho_height = ortho_height;
+                }
+            }
+            "sky" => {
+                let (text, path_column) = line.word("la imagen o carpeta del cielo")?;
+                // Se arma al final, cuando ya se conoce la posición del sol
+                dynamic_sky = text == "dynamic";
+                if dynamic_sky {
+                    skybox = None;
+                    line.finish()?;
+                    continue;
+                }
+                let path = base_dir.join(text).to_string_lossy().into_owned();
+                let mut filter = DEFAULT_SKY_FILTER;
+                let mut naming = None;
+                let mut intensity = None;
+                let mut yaw = 0.0;
+                let mut yaw_speed = 0.0;
+                while let Some((key, column)) = line.optional_word() {
+                    match key {
+                        "filter" => filter = line.texture_filter()?,
+                        "yaw" => yaw = line.angle("yaw")?,
+                        "yaw_speed" => yaw_speed = line.angle("yaw_speed")?,
+                        "naming" => naming = Some(line.cubemap_naming()?),
+                        "intensity" => {
+                            let value = line.number("intensity")?;
+                            if value < 0.0 {
+                                return Err(line.error_at(
+                                    column,
+                                    "intensity no puede ser negativa".to_string(),
+                                ));
+                            }
+                            intensity = Some((value, column));
+                        }
+                        other => return Err(unknown_key(&line, column, "sky", other)),
+                    }
+                }
+                let folder = Path::new(&path).is_dir();
+                let hdr = !folder && is_hdr_path(&path);
+                if naming.is_some() && !folder {
+                    return Err(line.error_at(
+                        path_column,
+                        "naming solo se usa cuando el cielo es una carpeta".to_string(),
+                    ));
+                }
+                if let Some((_, column)) = intensity.filter(|_| !hdr) {
+                    return Err(line.error_at(
+                        column,
+                        "intensity solo se usa con cielos .hdr o .exr".to_string(),
+                    ));
+                }
+                // Una carpeta tiene una imagen por cara, un panorama HDR cubre todo el cielo
+                // y una imagen común se usa en las seis caras
+                let mut sky = if folder {
+                    let naming = naming
+                        .or_else(|| CubemapNaming::detect(&path))
+                        .ok_or_else(|| {
+                            line.error_at(
+                                path_column,
+                                format!(
+                                    "'{}' no tiene caras de cielo llamadas right/left/..., posx/negx/... ni px/nx/...",
+                                    path
+                                ),
+                            )
+                        })?;
+                    Skybox::from_folder(&path, naming)
+                        .map_err(|message| line.error_at(path_column, message))?
+                } else if hdr {
+                    let intensity = intensity.map_or(1.0, |(value, _)| value);
+                    Skybox::from_hdr(&path, intensity)
+                        .map_err(|message| line.error_at(path_column, message))?
+                } else {
+                    let face = load_texture(&path);
+                    let mut sky = Skybox::new(
+                        face.clone(),
+                        face.clone(),
+                        face.clone(),
+                        face.clone(),
+                        face.clone(),
+                        face,
+                    );
+                    sky.source_path = Some(path);
+                    sky
+                };
+                sky.filter = filter;
+                sky.yaw = yaw;
+                sky.yaw_speed = yaw_speed;
+                skybox = Some(sky);
+            }
+            "material" => {
+                let (name, name_column) = line.word("el nombre del material")?;
+                if world.find_material(name).is_some() {
+                    return Err(line.error_at(
+                        name_column,
+                        format!("el material '{}' ya fue definido", name),
+                    ));
+                }
+                let mut material = Material::black();
+                material.diffuse = Color::from_u8(255, 255, 255);
+                while let Some((key, column)) = line.optional_word() {
+                    match key {
+                        "preset" => {
+                            let (preset, preset_column) = line.word("el nombre del preset")?;
+                            let library = MaterialLibrary::builtin();
+                            material = library.get(preset).cloned().ok_or_else(|| {
+                                let names: Vec<&str> = library.names().collect();
+                                line.error_at(
+                                    preset_column,
+                                    format!(
+                                        "preset desconocido '{}', hay: {}",
+                                        preset,
+                                        names.join(", ")
+                                    ),
+                                )
+                            })?;
+                        }
+                        "diffuse" => material.diffuse = line.color("diffuse")?,
+                        "specular" => material.specular = line.number("specular")?,
+                        "albedo" => {
+                            for value in material.albedo.iter_mut() {
+                                *value = line.number("albedo")?;
+                            }
+                        }
+                        "ior" => material.refractive_index = line.number("ior")?,
+                        "emission" => material.emission = line.color("emission")?,
+                        "pulse" | "flicker" | "strobe" => {
+                            material.emission_animation = Some(line.light_animation(key, column)?);
+                        }
+                        "absorption" => material.absorption = line.color("absorption")?,
+                        "texture" => {
+                            let path = line.path(base_dir, "texture")?;
+                            material.texture = Some(Texture::Image(load_texture(&path)));
+                            material.texture_path = Some(path);
+                            material.texture_animation = None;
+                            material.texture_pack = None;
+                        }
+                        "pattern" => {
+                            material.texture = Some(Texture::Pattern(line.pattern(&units)?));
+                            material.texture_path = None;
+                            material.texture_animation = None;
+                            material.texture_pack = None;
+                        }
+                        "resource_pack" => {
+                            let path = line.path(base_dir, "el resource pack")?;
+                            let error = |err| line.error_at(column, format!("{}: {}", path, err));
+                            if !packs.contains_key(&path) {
+                                let pack = ResourcePack::open(Path::new(&path)).map_err(error)?;
+                                packs.insert(path.clone(), pack);
+                            }
+                            let textured =
+                                packs[&path].texture(name, &mut material).map_err(error)?;
+                            if !textured {
+                                return Err(line.error_at(
+                                    column,
+                                    format!("{} no tiene textura para '{}'", path, name),
+                                ));
+                            }
+                        }
+                        "normal_map" => {
+                            let path = line.path(base_dir, "normal_map")?;
+                            material.normal_map = Some(load_texture(&path));
+                            material.normal_map_path = Some(path);
+                        }
+                        "emission_map" => {
+                            let path = line.path(base_dir, "emission_map")?;
+                            material.emission_map = Some(load_texture(&path));
+                            material.emission_map_path = Some(path);
+                        }
+                        "specular_map" => {
+                            let path = line.path(base_dir, "specular_map")?;
+                            material.specular_map = Some(load_texture(&path));
+                            material.specular_map_path = Some(path);
+                        }
+                        "texture_filter" => material.texture_filter = line.texture_filter()?,
+                        "normal_map_filter" => {
+                            material.normal_map_filter = line.texture_filter()?
+                        }
+                        "transparency" => material.transparency = line.transparency()?,
+                        "stained" => material.stained = true,
+                        "max_depth" => material.max_depth = Some(line.integer("max_depth")?),
+                        "metallic" | "roughness" | "blur" => {
+                            let value = line.number(key)?;
+                            if !(0.0..=1.0).contains(&value) {
+                                return Err(line.error_at(column, format!("{} va de 0 a 1", key)));
+                            }
+                            match key {
+                                "metallic" => material.set_metallic(value),
+                                "roughness" => material.set_roughness(value),
+                                _ => material.blur = value,
+                            }
+                        }
+                        "alpha_cutoff" => {
+                            let cutoff = line.number("alpha_cutoff")?;
+                            if !(0.0..=1.0).contains(&cutoff) {
+                                return Err(
+                                    line.error_at(column, "alpha_cutoff va de 0 a 1".to_string())
+                                );
+                            }
+                            material.alpha_cutoff = cutoff;
+                        }
+                        other => return Err(unknown_key(&line, column, "material", other)),
+                    }
+                }
+                world.add_material(name, material);
+            }
+            "light" => {
+                let mut light = Light::new(Vec3::zeros(), Color::from_u8(255, 255, 255), 1.0);
+                while let Some((key, column)) = line.optional_word() {
+                    match key {
+                        "position" => light.position = line.point(&units, "position")?,
+                        "color" => light.color = line.color("color")?,
+                        "intensity" => light.intensity = line.number("intensity")?,
+                        "radius" => {
+                            light.radius = line.length(&units, "radius")?;
+                            if light.radius < 0.0 {
+                                return Err(line.error_at(
+                                    column,
+                                    "el radio de la luz no puede ser negativo".to_string(),
+                                ));
+                            }
+                        }
+                        "pulse" | "flicker" | "strobe" => {
+                            light.animation = Some(line.light_animation(key, column)?);
+                        }
+                        "group" => {
+                            let (name, _) = line.word("el nombre del grupo")?;
+                            light.group = Some(name.to_string());
+                        }
+                        other => return Err(unknown_key(&line, column, "light", other)),
+                    }
+                }
+                lights.push(light);
+            }
+            "cube" | "fill" => {
+                let (name, name_column) = line.word("el nombre del material")?;
+                let block = find_block(&mut world, name).ok_or_else(|| {
+                    line.error_at(name_column, format!("material '{}' no definido", name))
+                })?;
+                let mut min = None;
+                let mut max = None;
+                let mut object = None;
+                while let Some((key, column)) = line.optional_word() {
+                    match key {
+                        "min" => min = Some(line.point(&units, "min")?),
+                        "max" => max = Some(line.point(&units, "max")?),
+                        "object" if directive == "cube" => {
+                            let (name, column) = line.word("el nombre del objeto")?;
+                            let index = find_object(&objects, name).ok_or_else(|| {
+                                line.error_at(column, format!("objeto '{}' no definido", name))
+                            })?;
+                            object = Some(index);
+                        }
+                        other => return Err(unknown_key(&line, column, directive, other)),
+                    }
+                }
+                let (min, max) = match (min, max) {
+                    (Some(min), Some(max)) => (min, max),
+                    _ => {
+                        return Err(line.error_at(
+                            line.end_column,
+                            format!("{} necesita 'min' y 'max'", directive),
+                        ))
+                    }
+                };
+                if min.x >= max.x || min.y >= max.y || min.z >= max.z {
+                    return Err(line.error_at(
+                        column,
+                        "cada componente de 'min' debe ser menor que la de 'max'".to_string(),
+                    ));
+                }
+
+                if directive == "cube" {
+                    // Los cubos sin objeto van a un objeto anónimo que nunca se anima
+                    let index = match object {
+                        Some(index) => index,
+                        None => find_object(&objects, "").unwrap_or_else(|| {
+                            objects.push(SceneObject::new("", Vec3::zeros()));
+                            objects.len() - 1
+                        }),
+                    };
+                    objects[index].parts.push(ObjectPart { block, min, max });
+                } else {
+                    // Rellena la región con bloques del mundo alineados a la cuadrícula
+                    world.fill(
+                        (
+                            min.x.floor() as i32,
+                            min.y.floor() as i32,
+                            min.z.floor() as i32,
+                        ),
+                        (
+                            max.x.ceil() as i32,
+                            max.y.ceil() as i32,
+                            max.z.ceil() as i32,
+                        ),
+                        block,
+                    );
+                }
+            }
+            "portal" => {
+                let mut min = None;
+                let mut max = None;
+                while let Some((key, column)) = line.optional_word() {
+                    match key {
+                        "min" => min = Some(line.point(&units, "min")?),
+                        "max" => max = Some(line.point(&units, "max")?),
+                        other => return Err(unknown_key(&line, column, "portal", other)),
+                    }
+                }
+                let (Some(min), Some(max)) = (min, max) else {
+                    return Err(
+                        line.error_at(line.end_column, "portal necesita 'min' y 'max'".to_string())
+                    );
+                };
+                let portal = Portal::new(min, max).ok_or_else(|| {
+                    line.error_at(
+                        column,
+                        "un portal debe ser plano en exactamente un eje (min y max iguales en ese eje)"
+                            .to_string(),
+                    )
+                })?;
+                portals.push(portal);
+            }
+            "sun_distance" => {
+                let value = line.length(&units, "la distancia del sol")?;
+                if value <= 0.0 {
+                    return Err(line.error_at(column, "sun_distance debe ser positivo".to_string()));
+                }
+                sun_distance = value;
+            }
+            "firefly_clamp" => {
+                let value = line.number("el límite de luminancia")?;
+                if value < 0.0 {
+                    return Err(
+                        line.error_at(column, "firefly_clamp no puede ser negativo".to_string())
+                    );
+                }
+                firefly_clamp = value;
+            }
+            "roulette" => {
+                let value = line.number("roulette")?;
+                if !(0.0..=1.0).contains(&value) {
+                    return Err(line.error_at(column, "roulette va de 0 a 1".to_string()));
+                }
+                roulette = value;
+            }
+            "transparent_background" => transparent_background = true,
+            "dither" => dither = true,
+            "moon" => {
+                let mut visible = false;
+                while let Some((key, column)) = line.optional_word() {
+                    match key {
+                        "visible" => visible = true,
+                        other => return Err(unknown_key(&line, column, "moon", other)),
+                    }
+                }
+                moon = Some(Moon::new(visible));
+            }
+            "clouds" => {
+                let mut layer = CloudLayer::default();
+                while let Some((key, column)) = line.optional_word() {
+                    match key {
+                        "height" => layer.height = line.length(&units, "height")?,
+                        "thickness" => layer.thickness = line.length(&units, "thickness")?,
+                        "coverage" => layer.coverage = line.number("coverage")?,
+                        "size" => layer.scale = line.length(&units, "size")?,
+                        "wind" => {
+                            layer.wind.x = line.length(&units, "el viento en x")?;
+                            layer.wind.z = line.length(&units, "el viento en z")?;
+                        }
+                        "color" => layer.color = line.color("color")?,
+                        other => return Err(unknown_key(&line, column, "clouds", other)),
+                    }
+                }
+                if layer.thickness <= 0.0 || layer.scale <= 0.0 {
+                    return Err(line.error_at(
+                        column,
+                        "el grosor y el tamaño de las nubes deben ser positivos".to_string(),
+                    ));
+                }
+                if !(0.0..=1.0).contains(&layer.coverage) {
+                    return Err(
+                        line.error_at(column, "coverage debe estar entre 0 y 1".to_string())
+                    );
+                }
+                clouds = Some(layer);
+            }
+            "fog" => {
+                let mut settings = Fog::default();
+                while let Some((key, column)) = line.optional_word() {
+                    match key {
+                        "density" => settings.density = line.number("density")?,
+                        "color" => settings.color = line.color("color")?,
+                        "distance" => settings.distance = line.length(&units, "distance")?,
+                        "anisotropy" => settings.anisotropy = line.number("anisotropy")?,
+                        other => return Err(unknown_key(&line, column, "fog", other)),
+                    }
+                }
+                if settings.density < 0.0 || settings.distance <= 0.0 {
+                    return Err(line.error_at(
+                        column,
+                        "la densidad de la niebla no puede ser negativa y su alcance debe ser positivo"
+                            .to_string(),
+                    ));
+                }
+                if !(0.0..1.0).contains(&settings.anisotropy) {
+                    return Err(line.error_at(
+                        column,
+                        "anisotropy debe estar entre 0 y 1 (sin incluir el 1)".to_string(),
+                    ));
+                }
+                fog = Some(settings);
+            }
+            "post" => {
+                let (kind, column) = line.word("el efecto (exposure, bloom, vignette o lut)")?;
+                let effect = match kind {
+                    "exposure" => PostEffect::Exposure(line.number("exposure")?),
+                    "bloom" => {
+                        let mut bloom = Bloom::default();
+                        while let Some((key, column)) = line.optional_word() {
+                            match key {
+                                "threshold" => bloom.threshold = line.number("threshold")?,
+                                "strength" => bloom.strength = line.number("strength")?,
+                                "radius" => bloom.radius = line.number("radius")?,
+                                other => {
+                                    return Err(unknown_key(&line, column, "post bloom", other))
+                                }
+                            }
+                        }
+                        if bloom.threshold < 0.0 || bloom.strength < 0.0 || bloom.radius <= 0.0 {
+                            return Err(line.error_at(
+                                column,
+                                "threshold y strength no pueden ser negativos y radius debe ser positivo"
+                                    .to_string(),
+                            ));
+                        }
+                        PostEffect::Bloom(bloom)
+                    }
+                    "vignette" => {
+                        let amount = line.number("vignette")?;
+                        if !(0.0..=1.0).contains(&amount) {
+                            return Err(line
+                                .error_at(column, "vignette debe estar entre 0 y 1".to_string()));
+                        }
+                        PostEffect::Vignette(amount)
+                    }
+                    "lut" => {
+                        let path = line.path(base_dir, "el archivo .cube")?;
+                        let lut = ColorLut::load(&path)
+                            .map_err(|message| line.error_at(column, message))?;
+                        PostEffect::Grade(lut)
+                    }
+                    other => {
+                        let message = format!(
+                            "efecto desconocido '{}', usa 'exposure', 'bloom', 'vignette' o 'lut'",
+                            other
+                        );
+                        return Err(line.error_at(column, message));
+                    }
+                };
+                post.push(effect);
+            }
+            "vox" => {
+                let path = line.path(base_dir, "el archivo .vox")?;
+                let mut origin = Vec3::zeros();
+                let mut name = None;
+                while let Some((key, column)) = line.optional_word() {
+                    match key {
+                        "at" => origin = line.point(&units, "at")?,
+                        "name" => name = Some(line.word("el nombre de los materiales")?.0),
+                        other => return Err(unknown_key(&line, column, "vox", other)),
+                    }
+                }
+                let model = VoxModel::load(Path::new(&path))
+                    .map_err(|err| line.error_at(column, format!("{}: {}", path, err)))?;
+                let stem = Path::new(&path)
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| "vox".to_string());
+                model.place(
+                    &mut world,
+                    (
+                        origin.x.floor() as i32,
+                        origin.y.floor() as i32,
+                        origin.z.floor() as i32,
+                    ),
+                    name.unwrap_or(&stem),
+                );
+            }
+            "schematic" => {
+                let path = line.path(base_dir, "el archivo del schematic")?;
+                let mut origin = Vec3::zeros();
+                let mut mapping = HashMap::new();
+                while let Some((key, column)) = line.optional_word() {
+                    match key {
+                        "at" => origin = line.point(&units, "at")?,
+                        "map" => {
+                            let (id, _) = line.word("el id del bloque")?;
+                            let (name, name_column) = line.word("el nombre del material")?;
+                            let block = find_block(&mut world, name).ok_or_else(|| {
+                                line.error_at(
+                                    name_column,
+                                    format!("material '{}' no definido", name),
+                                )
+                            })?;
+                            let id = id.strip_prefix("minecraft:").unwrap_or(id);
+                            mapping.insert(id.to_string(), block);
+                        }
+                        other => return Err(unknown_key(&line, column, "schematic", other)),
+                    }
+                }
+                let schematic = Schematic::load(Path::new(&path))
+                    .map_err(|err| line.error_at(column, format!("{}: {}", path, err)))?;
+                schematic.place(
+                    &mut world,
+                    (
+                        origin.x.floor() as i32,
+                        origin.y.floor() as i32,
+                        origin.z.floor() as i32,
+                    ),
+                    &mapping,
+                );
+            }
+            "resource_pack" => {
+                let path = line.path(base_dir, "el resource pack")?;
+                ResourcePack::open(Path::new(&path))
+                    .and_then(|pack| pack.apply(&mut world))
+                    .map_err(|err| line.error_at(column, format!("{}: {}", path, err)))?;
+            }
+            "terrain" => {
+                let mut settings = TerrainSettings::default();
+                let mut blocks = [None; 4];
+                while let Some((key, column)) = line.optional_word() {
+                    match key {
+                        "grass" | "dirt" | "stone" | "water" => {
+                            let (name, name_column) = line.word("el nombre del material")?;
+                            let block = find_block(&mut world, name).ok_or_else(|| {
+                                line.error_at(
+                                    name_column,
+                                    format!("material '{}' no definido", name),
+                                )
+                            })?;
+                            let slot = match key {
+                                "grass" => 0,
+                                "dirt" => 1,
+                                "stone" => 2,
+                                _ => 3,
+                            };
+                            blocks[slot] = Some(block);
+                        }
+                        "seed" => settings.seed = line.integer("seed")?,
+                        "size" => {
+                            settings.width = line.integer("el ancho del terreno")?;
+                            settings.depth = line.integer("el largo del terreno")?;
+                        }
+                        "sea_level" => settings.sea_level = line.integer("sea_level")?,
+                        "height" => settings.base_height = line.integer("height")?,
+                        "amplitude" => settings.amplitude = line.length(&units, "amplitude")?,
+                        "scale" => settings.scale = line.length(&units, "scale")?,
+                        "octaves" => settings.octaves = line.integer("octaves")?,
+                        "dirt_depth" => settings.dirt_depth = line.integer("dirt_depth")?,
+                        other => return Err(unknown_key(&line, column, "terrain", other)),
+                    }
+                }
+                let [Some(grass), Some(dirt), Some(stone), Some(water)] = blocks else {
+                    return Err(line.error_at(
+                        line.end_column,
+                        "terrain necesita los materiales 'grass', 'dirt', 'stone' y 'water'"
+                            .to_string(),
+                    ));
+                };
+                if settings.width <= 0 || settings.depth <= 0 || settings.scale <= 0.0 {
+                    return Err(line.error_at(
+                        column,
+                        "el tamaño y la escala del terreno deben ser positivos".to_string(),
+                    ));
+                }
+                generate_terrain(
+                    &mut world,
+                    &settings,
+                    &TerrainMaterials {
+                        grass,
+                        dirt,
+                        stone,
+                        water,
+                    },
+                );
+            }
+            "object" => {
+                let (name, name_column) = line.word("el nombre del objeto")?;
+                if name.is_empty() {
+                    return Err(line.error_at(
+                        name_column,
+                        "el nombre del objeto no puede estar vacío".to_string(),
+                    ));
+                }
+                if find_object(&objects, name).is_some() {
+                    return Err(
+                        line.error_at(name_column, format!("el objeto '{}' ya fue definido", name))
+                    );
+                }
+                let mut object = SceneObject::new(name, Vec3::zeros());
+                while let Some((key, column)) = line.optional_word() {
+                    match key {
+                        "pivot" => object.pivot = line.point(&units, "pivot")?,
+                        other => return Err(unknown_key(&line, column, "object", other)),
+                    }
+                }
+                objects.push(object);
+            }
+            "animate" => {
+                let (name, name_column) = line.word("el nombre del objeto")?;
+                let index = find_object(&objects, name).ok_or_else(|| {
+                    line.error_at(name_column, format!("objeto '{}' no definido", name))
+                })?;
+                let (property, property_column) = line.word("'translation' o 'scale'")?;
+                if property != "translation" && property != "scale" {
+                    return Err(line.error_at(
+                        property_column,
+                        format!(
+                            "propiedad animable desconocida '{}', usa 'translation' o 'scale'",
+                            property
+                        ),
+                    ));
+                }
+                let interpolation = line.interpolation()?;
+
+                let mut track = Track::new(interpolation, false);
+                while let Some((key, column)) = line.optional_word() {
+                    match key {
+                        "loop" => track.looping = true,
+                        "key" => {
+                            let time = line.time("el tiempo de la clave")?;
+                            let value = if property == "translation" {
+                                line.point(&units, "la traslación")?
+                            } else {
+                                Vec3::new(
+                                    line.number("la escala en x")?,
+                                    line.number("la escala en y")?,
+                                    line.number("la escala en z")?,
+                                )
+                            };
+                            track.add_key(time, value);
+                        }
+                        other => return Err(unknown_key(&line, column, "animate", other)),
+                    }
+                }
+                if track.keys().is_empty() {
+                    return Err(line.error_at(
+                        line.end_column,
+                        "animate necesita al menos una clave 'key'".to_string(),
+                    ));
+                }
+
+                let object = &mut objects[index];
+                let slot = if property == "translation" {
+                    &mut object.translation
+                } else {
+                    &mut object.scale
+                };
+                if slot.is_some() {
+                    return Err(line.error_at(
+                        property_column,
+                        format!("el objeto '{}' ya tiene una pista de '{}'", name, property),
+                    ));
+                }
+                *slot = Some(track);
+            }
+            other => {
+                return Err(line.error_at(column, format!("directiva desconocida '{}'", other)))
+            }
+        }
+
+        line.finish()?;
+    }
+
+    let skybox = skybox.unwrap_or_else(|| {
+        let face = load_texture(DEFAULT_SKY);
+        Skybox::new(
+            face.clone(),
+            face.clone(),
+            face.clone(),
+            face.clone(),
+            face.clone(),
+            face,
+        )
+    });
+
+    let mut scene = Scene {
+        world,
+        objects,
+        lights,
+        ambient: Color::black(),
+        light_time: None,
+        moon,
+        portals,
+        skybox,
+        clouds,
+        fog,
+        post,
+        camera,
+        sun_distance,
+        firefly_clamp,
+        roulette,
+        transparent_background,
+        dither,
+        light_cache: None,
+    };
+    if dynamic_sky {
+        scene.use_dynamic_sky();
+    }
+    Ok(scene)
+}
+
+fn parse_camera_path(source: &str) -> ParseResult<CameraPath> {
+    let mut units = Units { block_size: 1.0 };
+    let mut camera_path = CameraPath::new(Interpolation::CatmullRom, false);
+
+    for (index, text) in source.lines().enumerate() {
+        let mut line = LineCursor::new(index + 1, text)?;
+        if line.is_empty() {
+            continue;
+        }
+
+        let (directive, column) = line.word("una directiva")?;
+        match directive {
+            "block_size" => {
+                let (value, unit, column) = line.number_with_unit("el tamaño de bloque")?;
+                if unit != "m" || value <= 0.0 {
+                    return Err(line.error_at(
+                        column,
+                        "block_size debe ser positivo y en metros (ej. block_size 1m)".to_string(),
+                    ));
+                }
+                units.block_size = value;
+            }
+            "interpolation" => {
+                let interpolation = line.interpolation()?;
+                camera_path.position.interpolation = interpolation;
+                camera_path.target.interpolation = interpolation;
+            }
+            "loop" => {
+                camera_path.position.looping = true;
+                camera_path.target.looping = true;
+            }
+            "key" => {
+                let time = line.time("el tiempo de la clave")?;
+                let mut position = None;
+                let mut target = None;
+                while let Some((key, column)) = line.optional_word() {
+                    match key {
+                        "position" => position = Some(line.point(&units, "position")?),
+                        "target" => target = Some(line.point(&units, "target")?),
+                        other => return Err(unknown_key(&line, column, "key", other)),
+                    }
+                }
+                let (Some(position), Some(target)) = (position, target) else {
+                    return Err(line.error_at(
+                        line.end_column,
+                        "cada clave necesita 'position' y 'target'".to_string(),
+                    ));
+                };
+                camera_path.add_key(time, position, target);
+            }
+            other => {
+                return Err(line.error_at(column, format!("directiva desconocida '{}'", other)))
+            }
+        }
+
+        line.finish()?;
+    }
+
+    if camera_path.position.keys().is_empty() {
+        return Err(ParseError {
+            line: 0,
+            column: 0,
+            message: "el recorrido no tiene ninguna clave 'key'".to_string(),
+        });
+    }
+    Ok(camera_path)
+}
+
+// Material definido en la escena o, si no, uno de la biblioteca integrada
+fn find_block(world: &mut World, name: &str) -> Option<BlockId> {
+    world
+        .find_material(name)
+        .or_else(|| MaterialLibrary::builtin().block(world, name))
+}
+
+fn find_object(objects: &[SceneObject], name: &str) -> Option<usize> {
+    objects.iter().position(|object| object.name == name)
+}
+
+fn unknown_key(line: &LineCursor, column: usize, directive: &str, key: &str) -> ParseError {
+    line.error_at(
+        column,
+        format!("propiedad desconocida '{}' para '{}'", key, directive),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_numbers_from_their_units() {
+        assert_eq!(split_number("12.5m"), Ok((12.5, "m")));
+        assert_eq!(split_number("-3"), Ok((-3.0, "")));
+        assert_eq!(split_number("1e5"), Ok((100000.0, "")));
+        assert_eq!(split_number("2.5e-1m"), Ok((0.25, "m")));
+        assert_eq!(split_number("3E+2deg"), Ok((300.0, "deg")));
+        // Sin dígitos la "e" queda como unidad y la rechaza quien la lea
+        assert_eq!(split_number("1e"), Ok((1.0, "e")));
+        assert_eq!(split_number("1e-m"), Ok((1.0, "e-m")));
+        assert!(split_number("1,5").is_err());
+        assert!(split_number("1e39").is_err());
+        assert!(split_number("m").is_err());
+    }
+}
@@ -0,0 +1,655 @@
+// schematic.rs
+
+use flate2::read::GzDecoder;
+use std::collections::HashMap;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+use crate::chunk::BlockId;
+use crate::material_library::MaterialLibrary;
+use crate::world::{within_limits, BlockPos, World};
+
+// Profundidad máxima de listas y compuestos anidados; los archivos reales usan unos pocos
+const MAX_NESTING: usize = 512;
+
+// Bloques que no se colocan: el aire y los marcadores de las estructuras
+const EMPTY_BLOCKS: [&str; 4] = ["air", "cave_air", "void_air", "structure_void"];
+// Maderas de Minecraft: sus tablones, troncos, escaleras y losas son `madera`
+const WOODS: [&str; 12] = [
+    "oak", "spruce", "birch", "jungle", "acacia", "dark_oak", "mangrove", "cherry", "pale_oak",
+    "bamboo", "crimson", "warped",
+];
+// Piedras grises (y sus ladrillos, escaleras y losas), que son `piedra`
+const STONES: [&str; 28] = [
+    "stone",
+    "cobblestone",
+    "mossy_cobblestone",
+    "smooth_stone",
+    "stone_brick",
+    "stone_bricks",
+    "mossy_stone_brick",
+    "mossy_stone_bricks",
+    "cracked_stone_bricks",
+    "chiseled_stone_bricks",
+    "andesite",
+    "polished_andesite",
+    "diorite",
+    "polished_diorite",
+    "granite",
+    "polished_granite",
+    "deepslate",
+    "cobbled_deepslate",
+    "polished_deepslate",
+    "deepslate_brick",
+    "deepslate_bricks",
+    "deepslate_tile",
+    "deepslate_tiles",
+    "tuff",
+    "gravel",
+    "bedrock",
+    "infested_stone",
+    "infested_cobblestone",
+];
+
+// Una construcción de Minecraft leída de un schematic de WorldEdit (Sponge, .schem) o de
+// una estructura del juego (.nbt)
+pub struct Schematic {
+    pub size: (i32, i32, i32),
+    // Bloques que no son aire: posición desde la esquina mínima e índice en `palette`
+    pub blocks: Vec<(BlockPos, usize)>,
+    // Ids de los bloques sin el espacio de nombres ni las propiedades, como `oak_stairs`
+    pub palette: Vec<String>,
+}
+
+// Valor de un tag NBT; los textos están en UTF-8 modificado, que se lee como UTF-8. Los
+// números largos o con coma y los arreglos de enteros se saltean porque ningún formato los
+// usa para los bloques.
+#[derive(Debug, Clone)]
+enum Tag {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    ByteArray(Vec<u8>),
+    String(String),
+    List(Vec<Tag>),
+    Compound(HashMap<String, Tag>),
+    Skipped,
+}
+
+impl Tag {
+    fn get(&self, name: &str) -> Option<&Tag> {
+        match self {
+            Tag::Compound(entries) => entries.get(name),
+            _ => None,
+        }
+    }
+
+    // Los tamaños de Sponge son shorts sin signo; los demás enteros se leen como vienen
+    fn integer(&self) -> Option<i32> {
+        match *self {
+            Tag::Byte(value) => Some(value as i32),
+            Tag::Short(value) => Some(value as u16 as i32),
+            Tag::Int(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    fn list(&self) -> Option<&[Tag]> {
+        match self {
+            Tag::List(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    fn string(&self) -> Option<&str> {
+        match self {
+            Tag::String(text) => Some(text),
+            _ => None,
+        }
+    }
+}
+
+impl Schematic {
+    pub fn load(path: &Path) -> Result<Schematic, String> {
+        let bytes = fs::read(path).map_err(|err| format!("no se pudo leer el archivo: {}", err))?;
+        Schematic::parse(&bytes)
+    }
+
+    // Lee un archivo comprimido con gzip, como los guarda el juego, o sin comprimir
+    pub fn parse(bytes: &[u8]) -> Result<Schematic, String> {
+        let mut data = Vec::new();
+        let bytes = if bytes.starts_with(&[0x1f, 0x8b]) {
+            GzDecoder::new(bytes)
+                .read_to_end(&mut data)
+                .map_err(|err| format!("no se pudo descomprimir: {}", err))?;
+            &data
+        } else {
+            bytes
+        };
+
+        let mut reader = Reader::new(bytes);
+        if reader.u8()? != 10 {
+            return Err("no es un archivo NBT".to_string());
+        }
+        reader.string()?;
+        let root = reader.payload(10, 0)?;
+
+        // Sponge 3 guarda todo dentro de un compuesto `Schematic`; las versiones 1 y 2 y las
+        // estructuras lo tienen en la raíz
+        let root = root.get("Schematic").unwrap_or(&root);
+        if root.get("Palette").is_some()
+            || root.get("Blocks").and_then(|b| b.get("Palette")).is_some()
+        {
+            sponge(root)
+        } else if root.get("blocks").is_some() {
+            structure(root)
+        } else if root.get("Blocks").is_some() {
+            Err("los schematics de MCEdit con ids numéricos no están soportados".to_string())
+        } else {
+            Err("no es un schematic de WorldEdit ni una estructura de Minecraft".to_string())
+        }
+    }
+
+    // Coloca los bloques con la esquina mínima en `origin` y devuelve cuántos se pusieron.
+    // Cada id de bloque usa, en orden: su material en `mapping`, un material del mundo con
+    // el mismo nombre o el material integrado que le corresponde. Los que no tienen ninguno
+    // se dejan afuera, igual que los que quedarían más allá de MAX_COORDINATE.
+    pub fn place(
+        &self,
+        world: &mut World,
+        origin: BlockPos,
+        mapping: &HashMap<String, BlockId>,
+    ) -> usize {
+        let blocks: Vec<Option<BlockId>> = self
+            .palette
+            .iter()
+            .map(|id| {
+                mapping
+                    .get(id)
+                    .copied()
+                    .or_else(|| world.find_material(id))
+                    .or_else(|| {
+                        let name = builtin_material(id)?;
+                        MaterialLibrary::builtin().block(world, name)
+                    })
+            })
+            .collect();
+
+        let mut placed = 0;
+        for &((x, y, z), index) in &self.blocks {
+            let (Some(block), Some(x), Some(y), Some(z)) = (
+                blocks[index],
+                origin.0.checked_add(x),
+                origin.1.checked_add(y),
+                origin.2.checked_add(z),
+            ) else {
+                continue;
+            };
+            if !within_limits((x, y, z)) {
+                continue;
+            }
+            world.set_block((x, y, z), block);
+            placed += 1;
+        }
+        placed
+    }
+}
+
+// Schematic de Sponge: una paleta de estados a índices y los índices de todos los bloques
+// como varints, recorridos en x, después z y después y
+fn sponge(root: &Tag) -> Result<Schematic, String> {
+    let dimension = |name: &str| {
+        let value = root
+            .get(name)
+            .and_then(Tag::integer)
+            .ok_or_else(|| format!("falta {}", name))?;
+        if value < 0 {
+            return Err(format!("{} negativo: {}", name, value));
+        }
+        Ok(value)
+    };
+    let size = (
+        dimension("Width")?,
+        dimension("Height")?,
+        dimension("Length")?,
+    );
+    let (width, height, length) = (size.0 as usize, size.1 as usize, size.2 as usize);
+    let volume = width
+        .checked_mul(height)
+        .and_then(|area| area.checked_mul(length))
+        .ok_or_else(|| "el schematic es demasiado grande".to_string())?;
+
+    let (palette, data) = match root.get("Blocks") {
+        Some(blocks) => (blocks.get("Palette"), blocks.get("Data")),
+        None => (root.get("Palette"), root.get("BlockData")),
+    };
+    let Some(Tag::Compound(palette)) = palette else {
+        return Err("falta la paleta de bloques".to_string());
+    };
+    let Some(Tag::ByteArray(data)) = data else {
+        return Err("faltan los datos de los bloques".to_string());
+    };
+
+    let mut states = Vec::new();
+    for (state, index) in palette {
+        let index = index
+            .integer()
+            .and_then(|index| usize::try_from(index).ok())
+            .ok_or_else(|| format!("índice inválido para '{}' en la paleta", state))?;
+        // Los índices van de 0 a la cantidad de estados menos uno
+        if index >= palette.len() {
+            return Err(format!(
+                "índice {} fuera de la paleta para '{}'",
+                index, state
+            ));
+        }
+        if states.len() <= index {
+            states.resize(index + 1, None);
+        }
+        states[index] = Some(block_id(state));
+    }
+    let (palette, remap) = compact_palette(&states);
+
+    let mut blocks = Vec::new();
+    let mut bytes = data.iter();
+    for position in 0..volume {
+        let mut index = 0usize;
+        let mut shift = 0;
+        loop {
+            let byte = *bytes
+                .next()
+                .ok_or_else(|| "los datos de los bloques terminan antes de tiempo".to_string())?;
+            index |= ((byte & 0x7f) as usize) << shift;
+            if byte & 0x80 == 0 {
+                break;
+            }
+            shift += 7;
+            if shift > 28 {
+                return Err("varint demasiado largo en los datos de los bloques".to_string());
+            }
+        }
+        let Some(&target) = remap.get(index) else {
+            return Err(format!("el índice {} no está en la paleta", index));
+        };
+        if let Some(target) = target {
+            let x = position % width;
+            let z = position / width % length;
+            let y = position / (width * length);
+            blocks.push(((x as i32, y as i32, z as i32), target));
+        }
+    }
+    Ok(Schematic {
+        size,
+        blocks,
+        palette,
+    })
+}
+
+// Estructura del juego (bloque de estructuras): una lista de estados y cada bloque con su
+// posición y su estado. Si tiene varias paletas (naufragios) se usa la primera.
+fn structure(root: &Tag) -> Result<Schematic, String> {
+    let vector = |tag: Option<&Tag>| -> Option<(i32, i32, i32)> {
+        match tag?.list()? {
+            [x, y, z] => Some((x.integer()?, y.integer()?, z.integer()?)),
+            _ => None,
+        }
+    };
+    let size = vector(root.get("size")).ok_or_else(|| "falta size".to_string())?;
+
+    let palette = root
+        .get("palette")
+        .or_else(|| root.get("palettes")?.list()?.first())
+        .and_then(Tag::list)
+        .ok_or_else(|| "falta la paleta de bloques".to_string())?;
+    let states: Vec<Option<String>> = palette
+        .iter()
+        .map(|state| state.get("Name").and_then(Tag::string).map(block_id))
+        .collect();
+    let (palette, remap) = compact_palette(&states);
+
+    let mut blocks = Vec::new();
+    for block in root.get("blocks").and_then(Tag::list).unwrap_or_default() {
+        let position = vector(block.get("pos")).ok_or_else(|| "bloque sin pos".to_string())?;
+        let state = block
+            .get("state")
+            .and_then(Tag::integer)
+            .and_then(|state| usize::try_from(state).ok())
+            .ok_or_else(|| "bloque sin state".to_string())?;
+        match remap.get(state) {
+            Some(Some(target)) => blocks.push((position, *target)),
+            Some(None) => {}
+            None => return Err(format!("el estado {} no está en la paleta", state)),
+        }
+    }
+    Ok(Schematic {
+        size,
+        blocks,
+        palette,
+    })
+}
+
+// `minecraft:oak_stairs[facing=east]` pasa a `oak_stairs`
+fn block_id(state: &str) -> String {
+    let id = state.split('[').next().unwrap_or(state);
+    let id = id.rsplit(':').next().unwrap_or(id);
+    id.to_string()
+}
+
+// Ids distintos que no son aire y, para cada índice de la paleta original, su índice entre
+// ellos (None para el aire o los huecos)
+fn compact_palette(states: &[Option<String>]) -> (Vec<String>, Vec<Option<usize>>) {
+    let mut palette: Vec<String> = Vec::new();
+    let remap = states
+        .iter()
+        .map(|state| {
+            let id = state.as_ref()?;
+            if EMPTY_BLOCKS.contains(&id.as_str()) {
+                return None;
+            }
+            Some(match palette.iter().position(|known| known == id) {
+                Some(index) => index,
+                None => {
+                    palette.push(id.clone());
+                    palette.len() - 1
+                }
+            })
+        })
+        .collect();
+    (palette, remap)
+}
+
+// Material integrado para los bloques comunes. Las escaleras y las losas se ponen como
+// bloques enteros del mismo material.
+fn builtin_material(id: &str) -> Option<&'static str> {
+    let base = id
+        .strip_suffix("_stairs")
+        .or_else(|| id.strip_suffix("_slab"))
+        .unwrap_or(id);
+    let wood = WOODS.contains(&base)
+        || ["_planks", "_log", "_wood", "_stem", "_hyphae", "_block"]
+            .iter()
+            .any(|suffix| {
+                base.strip_suffix(suffix)
+                    .map(|wood| WOODS.contains(&wood.trim_start_matches("stripped_")))
+                    .unwrap_or(false)
+            });
+    match base {
+        "grass_block" => Some("cesped"),
+        "water" | "bubble_column" => Some("agua"),
+        "glowstone" | "sea_lantern" | "shroomlight" => Some("glowstone"),
+        "dirt" | "coarse_dirt" | "rooted_dirt" | "podzol" | "mycelium" | "farmland"
+        | "dirt_path" | "grass_path" | "mud" => Some("tierra"),
+        _ if wood => Some("madera"),
+        _ if STONES.contains(&base) => Some("piedra"),
+        _ => None,
+    }
+}
+
+// Lectura de los tags del formato NBT, en big endian
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Reader { bytes, position: 0 }
+    }
+
+    fn bytes(&mut self, count: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .position
+            .checked_add(count)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or_else(|| "el archivo NBT termina antes de tiempo".to_string())?;
+        let bytes = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.bytes(N)?);
+        Ok(array)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn i32(&mut self) -> Result<i32, String> {
+        Ok(i32::from_be_bytes(self.array()?))
+    }
+
+    // Largo de un arreglo o una lista; uno negativo es un archivo dañado
+    fn length(&mut self) -> Result<usize, String> {
+        let length = self.i32()?;
+        usize::try_from(length).map_err(|_| format!("largo negativo {}", length))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let length = u16::from_be_bytes(self.array()?) as usize;
+        Ok(String::from_utf8_lossy(self.bytes(length)?).into_owned())
+    }
+
+    // Contenido de un tag del tipo `kind`, dentro de `depth` listas o compuestos
+    fn payload(&mut self, kind: u8, depth: usize) -> Result<Tag, String> {
+        if depth > MAX_NESTING {
+            return Err("demasiados tags anidados".to_string());
+        }
+        Ok(match kind {
+            1 => Tag::Byte(self.u8()? as i8),
+            2 => Tag::Short(i16::from_be_bytes(self.array()?)),
+            3 => Tag::Int(self.i32()?),
+            4 | 6 => {
+                self.bytes(8)?;
+                Tag::Skipped
+            }
+            5 => {
+                self.bytes(4)?;
+                Tag::Skipped
+            }
+            7 => {
+                let length = self.length()?;
+                Tag::ByteArray(self.bytes(length)?.to_vec())
+            }
+            8 => Tag::String(self.string()?),
+            9 => {
+                let kind = self.u8()?;
+                let length = self.i32()?;
+                // Las listas vacías pueden tener tipo End y cualquier largo
+                let length = match kind {
+                    0 => 0,
+                    _ => {
+                        usize::try_from(length).map_err(|_| format!("largo negativo {}", length))?
+                    }
+                };
+                // Cada elemento ocupa al menos un byte
+                if length > self.bytes.len() - self.position {
+                    return Err("lista más larga que el archivo".to_string());
+                }
+                let mut items = Vec::with_capacity(length);
+                for _ in 0..length {
+                    items.push(self.payload(kind, depth + 1)?);
+                }
+                Tag::List(items)
+            }
+            10 => {
+                let mut entries = HashMap::new();
+                loop {
+                    let kind = self.u8()?;
+                    if kind == 0 {
+                        break;
+                    }
+                    let name = self.string()?;
+                    entries.insert(name, self.payload(kind, depth + 1)?);
+                }
+                Tag::Compound(entries)
+            }
+            11 | 12 => {
+                let width = if kind == 11 { 4 } else { 8 };
+                let length = self.length()?;
+                let bytes = length
+                    .checked_mul(width)
+                    .ok_or_else(|| "arreglo más largo que el archivo".to_string())?;
+                self.bytes(bytes)?;
+                Tag::Skipped
+            }
+            other => return Err(format!("tipo de tag desconocido {}", other)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::AIR;
+    use crate::world::MAX_COORDINATE;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    // Tag con nombre, como van dentro de un compuesto
+    fn named(kind: u8, name: &str, payload: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![kind];
+        bytes.extend((name.len() as u16).to_be_bytes());
+        bytes.extend(name.as_bytes());
+        bytes.extend(payload);
+        bytes
+    }
+
+    fn compound(entries: &[Vec<u8>]) -> Vec<u8> {
+        let mut bytes = entries.concat();
+        bytes.push(0);
+        bytes
+    }
+
+    fn list(kind: u8, items: &[Vec<u8>]) -> Vec<u8> {
+        let mut bytes = vec![kind];
+        bytes.extend((items.len() as i32).to_be_bytes());
+        bytes.extend(items.concat());
+        bytes
+    }
+
+    fn int(value: i32) -> Vec<u8> {
+        value.to_be_bytes().to_vec()
+    }
+
+    fn string(text: &str) -> Vec<u8> {
+        let mut bytes = (text.len() as u16).to_be_bytes().to_vec();
+        bytes.extend(text.as_bytes());
+        bytes
+    }
+
+    // Schematic de Sponge 2 con las dimensiones dadas como ints y los bloques de 2x1x1:
+    // piedra y aire
+    fn sponge_file(width: i32, height: i32, length: i32) -> Vec<u8> {
+        let mut data = int(2);
+        data.extend([0, 1]);
+        let palette = compound(&[
+            named(3, "minecraft:stone", &int(0)),
+            named(3, "minecraft:air", &int(1)),
+        ]);
+        named(
+            10,
+            "Schematic",
+            &compound(&[
+                named(3, "Width", &int(width)),
+                named(3, "Height", &int(height)),
+                named(3, "Length", &int(length)),
+                named(10, "Palette", &palette),
+                named(7, "BlockData", &data),
+            ]),
+        )
+    }
+
+    #[test]
+    fn reads_sponge_schematics() {
+        let schematic = Schematic::parse(&sponge_file(2, 1, 1)).unwrap();
+        assert_eq!(schematic.size, (2, 1, 1));
+        assert_eq!(schematic.palette, vec!["stone".to_string()]);
+        assert_eq!(schematic.blocks, vec![((0, 0, 0), 0)]);
+    }
+
+    #[test]
+    fn reads_gzipped_files() {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&sponge_file(2, 1, 1)).unwrap();
+        let schematic = Schematic::parse(&encoder.finish().unwrap()).unwrap();
+        assert_eq!(schematic.blocks, vec![((0, 0, 0), 0)]);
+    }
+
+    #[test]
+    fn rejects_truncated_files() {
+        let bytes = sponge_file(2, 1, 1);
+        for length in 0..bytes.len() {
+            assert!(
+                Schematic::parse(&bytes[..length]).is_err(),
+                "{} bytes",
+                length
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_negative_and_huge_dimensions() {
+        assert!(Schematic::parse(&sponge_file(-1, 1, 1)).is_err());
+        assert!(Schematic::parse(&sponge_file(2, 1, i32::MIN)).is_err());
+        assert!(Schematic::parse(&sponge_file(i32::MAX, i32::MAX, i32::MAX)).is_err());
+        // Más bloques que datos
+        assert!(Schematic::parse(&sponge_file(3, 1, 1)).is_err());
+    }
+
+    #[test]
+    fn rejects_negative_list_lengths() {
+        let mut items = vec![3];
+        items.extend(int(-5));
+        let bytes = named(10, "", &compound(&[named(9, "blocks", &items)]));
+        assert!(Schematic::parse(&bytes).is_err());
+    }
+
+    #[test]
+    fn skips_structure_blocks_placed_out_of_range() {
+        let vector = |[x, y, z]: [i32; 3]| list(3, &[int(x), int(y), int(z)]);
+        let block =
+            |pos: [i32; 3]| compound(&[named(9, "pos", &vector(pos)), named(3, "state", &int(0))]);
+        let state = compound(&[named(8, "Name", &string("minecraft:stone"))]);
+        let bytes = named(
+            10,
+            "",
+            &compound(&[
+                named(9, "size", &vector([1, 1, 1])),
+                named(9, "palette", &list(10, &[state])),
+                named(
+                    9,
+                    "blocks",
+                    &list(
+                        10,
+                        &[
+                            block([i32::MAX, 0, 0]),
+                            block([1 << 30, 0, 0]),
+                            block([0, 0, 0]),
+                        ],
+                    ),
+                ),
+            ]),
+        );
+        let schematic = Schematic::parse(&bytes).unwrap();
+        assert_eq!(schematic.blocks.len(), 3);
+
+        // Solo el del origen queda dentro del rango que recorre el trazador
+        let mut world = World::new();
+        assert_eq!(schematic.place(&mut world, (1, 0, 0), &HashMap::new()), 1);
+        assert_eq!(world.chunk_count(), 1);
+        assert_eq!(
+            schematic.place(&mut world, (MAX_COORDINATE, 0, 0), &HashMap::new()),
+            1
+        );
+        assert_ne!(world.get_block((MAX_COORDINATE, 0, 0)), AIR);
+        assert_eq!(
+            schematic.place(&mut world, (MAX_COORDINATE + 1, 0, 0), &HashMap::new()),
+            0
+        );
+    }
+}
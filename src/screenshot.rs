@@ -0,0 +1,53 @@
+// screenshot.rs
+
+use crate::framebuffer::Framebuffer;
+use image::ImageResult;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const SCREENSHOT_DIR: &str = "screenshots";
+
+pub fn save_screenshot(framebuffer: &Framebuffer, output_dir: &Path) -> ImageResult<PathBuf> {
+    fs::create_dir_all(output_dir)?;
+
+    let path = output_dir.join(format!("captura_{}.png", timestamp()));
+    framebuffer.to_image().save(&path)?;
+    Ok(path)
+}
+
+// Fecha y hora UTC en formato AAAAMMDD_HHMMSS_mmm
+fn timestamp() -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let seconds = now.as_secs();
+    let (year, month, day) = civil_from_days((seconds / 86_400) as i64);
+    let time = seconds % 86_400;
+
+    format!(
+        "{:04}{:02}{:02}_{:02}{:02}{:02}_{:03}",
+        year,
+        month,
+        day,
+        time / 3600,
+        (time / 60) % 60,
+        time % 60,
+        now.subsec_millis()
+    )
+}
+
+// Convierte días desde 1970-01-01 a (año, mes, día) del calendario gregoriano
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
@@ -0,0 +1,128 @@
+// secondary.rs
+
+use nalgebra_glm::Vec3;
+use std::ops::Range;
+
+use crate::camera::Camera;
+use crate::color::Color;
+
+// Potencia del coseno entre normales con la que pesa cada muestra: separa las caras de un
+// bloque
+const NORMAL_POWER: f32 = 32.0;
+// Diferencia de profundidad que se mezcla, como fracción de la profundidad del punto
+const DEPTH_TOLERANCE: f32 = 0.05;
+// Peso mínimo de las muestras que se parecen al punto, entre 0 y 1; con menos (un borde que
+// ninguna muestra vio) el punto traza sus propios reflejos
+const MIN_WEIGHT: f32 = 0.05;
+
+// Reflejos y refracciones de lo primero que ve el centro de una celda de la cuadrícula
+#[derive(Debug, Clone, Copy)]
+pub struct SecondarySample {
+    pub light: Color,
+    // Profundidad y normal de esa superficie, para compararla con la de cada pixel
+    pub depth: f32,
+    pub normal: Vec3,
+}
+
+// Reflejos y refracciones de lo que ven los rayos de cámara, trazados en una cuadrícula de
+// celdas de `scale` x `scale` pixeles. Cada pixel los toma de las cuatro celdas que lo
+// rodean, pesadas por la distancia (bilineal) y por cuánto se parecen su profundidad y su
+// normal a las del pixel (filtro bilateral): así el reflejo del agua no se corre sobre la
+// orilla y los bordes de lo que se ve siguen saliendo de los rayos de cada pixel.
+#[derive(Debug)]
+pub struct SecondaryBuffer {
+    scale: usize,
+    // Tamaño de la imagen completa, para proyectar los puntos
+    size: (usize, usize),
+    // Celdas trazadas en cada eje, en coordenadas de la cuadrícula de toda la imagen
+    columns: Range<usize>,
+    rows: Range<usize>,
+    samples: Vec<Option<SecondarySample>>,
+}
+
+impl SecondaryBuffer {
+    // Las celdas que cubren los pixeles `columns` x `rows` de una imagen de `size`, con una
+    // más alrededor para interpolar en los bordes; todavía sin trazar
+    pub fn new(
+        (columns, rows): (Range<usize>, Range<usize>),
+        size: (usize, usize),
+        scale: usize,
+    ) -> Self {
+        let scale = scale.max(1);
+        let cells = |pixels: Range<usize>, total: usize| {
+            if pixels.is_empty() {
+                return 0..0;
+            }
+            (pixels.start / scale).saturating_sub(1)
+                ..((pixels.end - 1) / scale + 2).min(total.div_ceil(scale))
+        };
+        SecondaryBuffer {
+            scale,
+            size,
+            columns: cells(columns, size.0),
+            rows: cells(rows, size.1),
+            samples: Vec::new(),
+        }
+    }
+
+    // Las celdas que hay que trazar, fila por fila
+    pub fn cells(&self) -> Vec<(usize, usize)> {
+        self.rows
+            .clone()
+            .flat_map(|y| self.columns.clone().map(move |x| (x, y)))
+            .collect()
+    }
+
+    // Centro de la celda en coordenadas de pixel de la imagen completa
+    pub fn center(&self, (x, y): (usize, usize)) -> (f32, f32) {
+        let scale = self.scale as f32;
+        ((x as f32 + 0.5) * scale, (y as f32 + 0.5) * scale)
+    }
+
+    // Guarda lo trazado en cada celda, en el orden de `cells`
+    pub fn fill(&mut self, samples: Vec<Option<SecondarySample>>) {
+        self.samples = samples;
+    }
+
+    // Reflejos y refracciones interpolados para el punto `point` con normal `normal`, o
+    // None si ninguna celda cercana vio una superficie parecida
+    pub fn sample(&self, camera: &Camera, point: &Vec3, normal: &Vec3) -> Option<Color> {
+        let (x, y) = camera.project(point, self.size.0 as f32, self.size.1 as f32)?;
+        let depth = camera.depth(point);
+        let scale = self.scale as f32;
+        let (u, v) = (x / scale - 0.5, y / scale - 0.5);
+        let (u0, v0) = (u.floor(), v.floor());
+        let (tu, tv) = (u - u0, v - v0);
+        let (u0, v0) = (u0 as isize, v0 as isize);
+        let neighbors = [
+            (u0, v0, (1.0 - tu) * (1.0 - tv)),
+            (u0 + 1, v0, tu * (1.0 - tv)),
+            (u0, v0 + 1, (1.0 - tu) * tv),
+            (u0 + 1, v0 + 1, tu * tv),
+        ];
+
+        let depth_range = DEPTH_TOLERANCE * depth.abs().max(1e-3);
+        let mut sum = Color::black();
+        let mut total = 0.0;
+        for (u, v, bilinear) in neighbors {
+            let Some(sample) = self.at(u, v) else {
+                continue;
+            };
+            let similar_normal = normal.dot(&sample.normal).max(0.0).powf(NORMAL_POWER);
+            let similar_depth = (-(sample.depth - depth).abs() / depth_range).exp();
+            let weight = bilinear * similar_normal * similar_depth;
+            sum = sum + sample.light * weight;
+            total += weight;
+        }
+        (total >= MIN_WEIGHT).then(|| sum * (1.0 / total))
+    }
+
+    fn at(&self, x: isize, y: isize) -> Option<&SecondarySample> {
+        let x = usize::try_from(x)
+            .ok()
+            .filter(|x| self.columns.contains(x))?;
+        let y = usize::try_from(y).ok().filter(|y| self.rows.contains(y))?;
+        let index = (y - self.rows.start) * self.columns.len() + x - self.columns.start;
+        self.samples.get(index)?.as_ref()
+    }
+}
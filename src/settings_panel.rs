@@ -0,0 +1,360 @@
+// settings_panel.rs
+
+use minifb::{Key, KeyRepeat, Window};
+
+use crate::chunk::BlockId;
+use crate::color::Color;
+use crate::day_cycle::DayNightCycle;
+use crate::fog::Fog;
+use crate::font::{draw_text, text_width, GLYPH_HEIGHT};
+use crate::framebuffer::Framebuffer;
+use crate::hud::clock_time;
+use crate::keymap::{Action, KeyMap};
+use crate::post::PostEffect;
+use crate::ray_intersect::ORIGIN_BIAS;
+use crate::render::{RenderSettings, SurfaceBias};
+use crate::scene::Scene;
+
+const TEXT_SCALE: usize = 2;
+const LINE_HEIGHT: usize = (GLYPH_HEIGHT + 3) * TEXT_SCALE;
+const PADDING: usize = 10;
+// Cuánto se oscurece la imagen detrás del panel
+const BACKDROP: f32 = 0.3;
+
+// Muestras por eje de cada pixel que se pueden pedir como máximo para la vista
+pub const MAX_PREVIEW_SAMPLES: u32 = 4;
+// Lados de las celdas en las que se pueden trazar los reflejos y refracciones de la vista
+const SECONDARY_SCALES: [usize; 3] = [1, 2, 4];
+// Distancias a las que pueden salir de la superficie los rayos de cada impacto
+const BIASES: [SurfaceBias; 4] = [
+    SurfaceBias::Adaptive(ORIGIN_BIAS),
+    SurfaceBias::Fixed(ORIGIN_BIAS),
+    SurfaceBias::Fixed(1e-3),
+    SurfaceBias::Fixed(1e-2),
+];
+
+// Valores que se pueden cambiar desde el panel, en el orden en que se muestran
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Param {
+    LightIntensity,
+    Specular,
+    Roughness,
+    Samples,
+    Reflections,
+    Roulette,
+    Bias,
+    FogDensity,
+    Exposure,
+    TimeOfDay,
+    DayLength,
+}
+
+const PARAMS: [Param; 11] = [
+    Param::LightIntensity,
+    Param::Specular,
+    Param::Roughness,
+    Param::Samples,
+    Param::Reflections,
+    Param::Roulette,
+    Param::Bias,
+    Param::FogDensity,
+    Param::Exposure,
+    Param::TimeOfDay,
+    Param::DayLength,
+];
+
+impl Param {
+    fn label(&self) -> &'static str {
+        match self {
+            Param::LightIntensity => "luces",
+            Param::Specular => "brillo del material",
+            Param::Roughness => "rugosidad del material",
+            Param::Samples => "muestras",
+            Param::Reflections => "reflejos",
+            Param::Roulette => "ruleta rusa",
+            Param::Bias => "sesgo de los rayos",
+            Param::FogDensity => "niebla",
+            Param::Exposure => "exposición",
+            Param::TimeOfDay => "hora",
+            Param::DayLength => "duración del día",
+        }
+    }
+}
+
+// Panel dibujado sobre la imagen para ajustar la escena sin recompilar: F1 lo abre, las
+// flechas arriba y abajo eligen un valor y las de los lados lo cambian (con Shift, diez
+// pasos). Los materiales que se tocan son los del bloque elegido para colocar.
+pub struct SettingsPanel {
+    pub open: bool,
+    selected: usize,
+    // Multiplicador de todas las luces, encima de su animación y del ciclo del día
+    pub light_scale: f32,
+}
+
+impl SettingsPanel {
+    pub fn new() -> Self {
+        SettingsPanel {
+            open: false,
+            selected: 0,
+            light_scale: 1.0,
+        }
+    }
+
+    // Lee las teclas del panel; devuelve si cambió algo que altera la imagen sin que se
+    // note comparando la vista (materiales, niebla)
+    pub fn update(
+        &mut self,
+        window: &Window,
+        keys: &KeyMap,
+        scene: &mut Scene,
+        block: BlockId,
+        quality: &mut RenderSettings,
+        cycle: &mut DayNightCycle,
+    ) -> bool {
+        if keys.pressed(window, Action::Settings) {
+            self.open = !self.open;
+        }
+        if !self.open {
+            return false;
+        }
+
+        let count = PARAMS.len();
+        if window.is_key_pressed(Key::Up, KeyRepeat::Yes) {
+            self.selected = (self.selected + count - 1) % count;
+        }
+        if window.is_key_pressed(Key::Down, KeyRepeat::Yes) {
+            self.selected = (self.selected + 1) % count;
+        }
+
+        let mut steps = window.is_key_pressed(Key::Right, KeyRepeat::Yes) as i32
+            - window.is_key_pressed(Key::Left, KeyRepeat::Yes) as i32;
+        if steps == 0 {
+            return false;
+        }
+        if window.is_key_down(Key::LeftShift) || window.is_key_down(Key::RightShift) {
+            steps *= 10;
+        }
+        self.adjust(PARAMS[self.selected], steps, scene, block, quality, cycle)
+    }
+
+    fn adjust(
+        &mut self,
+        param: Param,
+        steps: i32,
+        scene: &mut Scene,
+        block: BlockId,
+        quality: &mut RenderSettings,
+        cycle: &mut DayNightCycle,
+    ) -> bool {
+        let amount = steps as f32;
+        match param {
+            Param::LightIntensity => {
+                self.light_scale = (self.light_scale + 0.05 * amount).clamp(0.0, 10.0);
+                false
+            }
+            Param::Specular => {
+                let mut material = scene.world.material_mut(block);
+                material.specular = (material.specular + 5.0 * amount).clamp(0.0, 1000.0);
+                true
+            }
+            Param::Roughness => {
+                let mut material = scene.world.material_mut(block);
+                material.blur = (material.blur + 0.02 * amount).clamp(0.0, 1.0);
+                true
+            }
+            Param::Samples => {
+                let samples = quality.samples as i32 + steps;
+                quality.samples = samples.clamp(1, MAX_PREVIEW_SAMPLES as i32) as u32;
+                false
+            }
+            Param::Reflections => {
+                let current = SECONDARY_SCALES
+                    .iter()
+                    .position(|&scale| scale == quality.secondary_scale)
+                    .unwrap_or(0) as i32;
+                let index = (current + steps).clamp(0, SECONDARY_SCALES.len() as i32 - 1);
+                quality.secondary_scale = SECONDARY_SCALES[index as usize];
+                false
+            }
+            Param::Roulette => {
+                scene.roulette = (scene.roulette + 0.01 * amount).clamp(0.0, 1.0);
+                true
+            }
+            Param::Bias => {
+                let current = BIASES
+                    .iter()
+                    .position(|&bias| bias == quality.bias)
+                    .unwrap_or(0) as i32;
+                let index = (current + steps).clamp(0, BIASES.len() as i32 - 1);
+                quality.bias = BIASES[index as usize];
+                false
+            }
+            Param::FogDensity => {
+                let density = scene.fog.as_ref().map_or(0.0, |fog| fog.density);
+                let density = (density + 0.005 * amount).clamp(0.0, 1.0);
+                if density <= 0.0 {
+                    scene.fog = None;
+                } else {
+                    scene.fog.get_or_insert_with(Fog::default).density = density;
+                }
+                true
+            }
+            // Cambia el primer `post exposure` de la escena; en 0 se quita. Se aplica a la
+            // imagen ya renderizada, así que no hace falta volver a trazarla.
+            Param::Exposure => {
+                let index = scene
+                    .post
+                    .iter()
+                    .position(|effect| matches!(effect, PostEffect::Exposure(_)));
+                let stops = (exposure(scene) + 0.1 * amount).clamp(-8.0, 8.0);
+                // Redondeado a décimas para que vuelva justo a 0
+                let stops = (stops * 10.0).round() / 10.0;
+                match index {
+                    Some(index) if stops == 0.0 => {
+                        scene.post.remove(index);
+                    }
+                    Some(index) => scene.post[index] = PostEffect::Exposure(stops),
+                    None if stops != 0.0 => scene.post.insert(0, PostEffect::Exposure(stops)),
+                    None => {}
+                }
+                false
+            }
+            // De a cuarto de hora; el sol se mueve, así que la vista lo nota sola
+            Param::TimeOfDay => {
+                cycle.set_progress(cycle.progress() + amount / 96.0);
+                false
+            }
+            Param::DayLength => {
+                cycle.length = (cycle.length + 5.0 * amount).clamp(5.0, 3600.0);
+                false
+            }
+        }
+    }
+
+    // Multiplica las luces de la escena por `light_scale`; va después de animarlas
+    pub fn apply(&self, scene: &mut Scene) {
+        let moon = scene.moon.as_mut().map(|moon| &mut moon.light);
+        for light in scene.lights.iter_mut().chain(moon) {
+            light.intensity_scale *= self.light_scale;
+        }
+    }
+
+    pub fn draw(
+        &self,
+        framebuffer: &mut Framebuffer,
+        keys: &KeyMap,
+        scene: &Scene,
+        block: BlockId,
+        quality: &RenderSettings,
+        cycle: &DayNightCycle,
+    ) {
+        if !self.open {
+            return;
+        }
+
+        let help = format!(
+            "flechas eligen y cambian, shift x10, {:?} cierra",
+            keys.key(Action::Settings)
+        );
+        let material = scene.world.material(block);
+        let rows: Vec<String> = PARAMS
+            .iter()
+            .map(|param| {
+                let value = match param {
+                    Param::LightIntensity => format!("x{:.2}", self.light_scale),
+                    Param::Specular => format!("{:.0}", material.specular),
+                    Param::Roughness => format!("{:.2}", material.blur),
+                    Param::Samples => format!("{}x{}", quality.samples, quality.samples),
+                    Param::Reflections => secondary_label(quality.secondary_scale),
+                    Param::Roulette if scene.roulette <= 0.0 => "no".to_string(),
+                    Param::Roulette => format!("{:.2}", scene.roulette),
+                    Param::Bias => match quality.bias {
+                        SurfaceBias::Adaptive(_) => "adaptativo".to_string(),
+                        SurfaceBias::Fixed(bias) => format!("fijo {:.0e}", bias),
+                    },
+                    Param::FogDensity => match &scene.fog {
+                        Some(fog) => format!("{:.3}", fog.density),
+                        None => "no".to_string(),
+                    },
+                    Param::Exposure => format!("{:+.1} EV", exposure(scene)),
+                    Param::TimeOfDay => clock_time(cycle.progress()),
+                    Param::DayLength => format!("{:.0} s", cycle.length),
+                };
+                format!("{}: {}", param.label(), value)
+            })
+            .collect();
+        let title = format!("ajustes - {}", scene.world.material_name(block));
+
+        let lines = rows.len() + 4;
+        let width = rows
+            .iter()
+            .map(|row| text_width(&format!("> {}", row), TEXT_SCALE))
+            .chain([
+                text_width(&title, TEXT_SCALE),
+                text_width(&help, TEXT_SCALE),
+            ])
+            .max()
+            .unwrap_or(0)
+            + 2 * PADDING;
+        let height = lines * LINE_HEIGHT + 2 * PADDING;
+        let left = framebuffer.width.saturating_sub(width) / 2;
+        let top = framebuffer.height.saturating_sub(height) / 2;
+
+        framebuffer.set_current_color(Color::black());
+        framebuffer.fill_rect(
+            left as i32,
+            top as i32,
+            width as i32,
+            height as i32,
+            1.0 - BACKDROP,
+        );
+
+        let x = left + PADDING;
+        let mut y = top + PADDING;
+        framebuffer.set_current_color(Color::new(1.0, 0.85, 0.2));
+        draw_text(framebuffer, x, y, &title, TEXT_SCALE);
+        y += 2 * LINE_HEIGHT;
+
+        for (index, row) in rows.iter().enumerate() {
+            let (marker, color) = if index == self.selected {
+                ("> ", Color::new(1.0, 1.0, 1.0))
+            } else {
+                ("  ", Color::new(0.6, 0.6, 0.6))
+            };
+            framebuffer.set_current_color(color);
+            draw_text(framebuffer, x, y, &format!("{}{}", marker, row), TEXT_SCALE);
+            y += LINE_HEIGHT;
+        }
+
+        y += LINE_HEIGHT / 2;
+        framebuffer.set_current_color(Color::new(0.5, 0.5, 0.5));
+        draw_text(framebuffer, x, y, &help, TEXT_SCALE);
+    }
+}
+
+impl Default for SettingsPanel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Resolución de los reflejos y refracciones de la vista
+pub fn secondary_label(scale: usize) -> String {
+    if scale <= 1 {
+        "completos".to_string()
+    } else {
+        format!("celdas de {0}x{0}", scale)
+    }
+}
+
+// Pasos de diafragma del primer `post exposure` de la escena; 0 si no tiene
+fn exposure(scene: &Scene) -> f32 {
+    scene
+        .post
+        .iter()
+        .find_map(|effect| match effect {
+            PostEffect::Exposure(stops) => Some(*stops),
+            _ => None,
+        })
+        .unwrap_or(0.0)
+}
@@ -2,25 +2,182 @@
 
 use crate::color::Color;
 use nalgebra_glm::Vec3;
-use image::RgbaImage;
+use image::{Rgb32FImage, RgbaImage};
+use std::f32::consts::PI;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use crate::assets::{load_texture, sample_texture, ImageTexture, MipChain, TextureFilter};
+use crate::dynamic_sky::DynamicSky;
+use crate::moon::moon_disk;
+
+// Extensiones que se prueban para cada cara al cargar el cielo desde una carpeta
+const FACE_EXTENSIONS: [&str; 3] = ["png", "jpg", "jpeg"];
+// Imágenes con radiancia en punto flotante, que se cargan como mapa de entorno HDR
+const HDR_EXTENSIONS: [&str; 2] = ["hdr", "exr"];
+// Filtro del cielo si la escena no pide otro. Con `Nearest` los texels se ven como cuadrados
+// en los reflejos cuando la imagen tiene poca resolución.
+pub const DEFAULT_SKY_FILTER: TextureFilter = TextureFilter::Bilinear;
 
 pub struct Skybox {
-    pub right: RgbaImage,
-    pub left: RgbaImage,
-    pub top: RgbaImage,
-    pub bottom: RgbaImage,
-    pub front: RgbaImage,
-    pub back: RgbaImage,
+    pub right: ImageTexture,
+    pub left: ImageTexture,
+    pub top: ImageTexture,
+    pub bottom: ImageTexture,
+    pub front: ImageTexture,
+    pub back: ImageTexture,
+    pub filter: TextureFilter,
+    // Ruta de la imagen usada en las seis caras, si vino de un archivo, o de la carpeta de
+    // caras si `naming` tiene valor
+    pub source_path: Option<String>,
+    pub naming: Option<CubemapNaming>,
+    // Si tiene valor el cielo se calcula con la posición del sol y las caras no se usan
+    pub dynamic: Option<DynamicSky>,
+    // Panorama HDR que reemplaza a las caras
+    pub environment: Option<HdrEnvironment>,
+    // Giro del cielo alrededor del eje vertical en radianes, positivo en sentido antihorario
+    // visto desde arriba, para orientar la imagen con la escena. `yaw_speed` lo hace girar
+    // de a poco (radianes por segundo). El cielo dinámico sigue al sol y no gira.
+    pub yaw: f32,
+    pub yaw_speed: f32,
+    // Giro acumulado por `yaw_speed`; lo actualiza `Skybox::animate`
+    pub drift: f32,
+    // Multiplica las caras o el panorama para oscurecer la imagen de noche; lo cambia el
+    // ciclo del día. El cielo dinámico no lo usa.
+    pub tint: Color,
+    // Hacia la luna, si su disco se dibuja en el cielo; lo cambia el ciclo del día
+    pub moon: Option<Vec3>,
+}
+
+// Mapa de entorno en proyección equirectangular (longitud y latitud) con la radiancia en
+// punto flotante: el sol y las zonas brillantes pueden valer mucho más que 1, así que los
+// reflejos y la luz de los portales no se recortan a 8 bits. El centro de la imagen queda
+// hacia -z y la fila de arriba hacia +y.
+pub struct HdrEnvironment {
+    image: Rgb32FImage,
+    // Multiplica la radiancia, para ajustar panoramas demasiado claros u oscuros
+    pub intensity: f32,
+}
+
+impl HdrEnvironment {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let image = image::open(path)
+            .map_err(|err| format!("no se pudo cargar el cielo HDR '{}' ({})", path, err))?
+            .into_rgb32f();
+        let (width, height) = image.dimensions();
+        if width == 0 || height == 0 {
+            return Err(format!("el cielo HDR '{}' está vacío", path));
+        }
+        Ok(HdrEnvironment {
+            image,
+            intensity: 1.0,
+        })
+    }
+
+    pub fn color(&self, direction: &Vec3, filter: TextureFilter) -> Color {
+        let dir = direction.normalize();
+        let u = 0.5 + dir.x.atan2(-dir.z) / (2.0 * PI);
+        let v = dir.y.clamp(-1.0, 1.0).acos() / PI;
+
+        let (width, height) = self.image.dimensions();
+        // Horizontalmente la imagen da la vuelta completa; verticalmente se repite el borde
+        let texel = |x: i64, y: i64| {
+            let x = x.rem_euclid(width as i64) as u32;
+            let y = y.clamp(0, height as i64 - 1) as u32;
+            let [r, g, b] = self.image.get_pixel(x, y).0;
+            Color::new(r, g, b)
+        };
+        let color = match filter {
+            TextureFilter::Nearest => texel(
+                (u * width as f32).floor() as i64,
+                (v * height as f32).floor() as i64,
+            ),
+            TextureFilter::Bilinear => {
+                let x = u * width as f32 - 0.5;
+                let y = v * height as f32 - 0.5;
+                let (x0, y0) = (x.floor(), y.floor());
+                let (fx, fy) = (x - x0, y - y0);
+                let (x0, y0) = (x0 as i64, y0 as i64);
+                let top = texel(x0, y0) * (1.0 - fx) + texel(x0 + 1, y0) * fx;
+                let bottom = texel(x0, y0 + 1) * (1.0 - fx) + texel(x0 + 1, y0 + 1) * fx;
+                top * (1.0 - fy) + bottom * fy
+            }
+        };
+        color * self.intensity
+    }
+}
+
+// Si la ruta es una imagen HDR, según la extensión
+pub fn is_hdr_path(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| HDR_EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str()))
+}
+
+// Cómo se llaman los archivos de las seis caras dentro de una carpeta
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CubemapNaming {
+    // right, left, top, bottom, front, back
+    Sides,
+    // posx, negx, posy, negy, posz, negz
+    PosNeg,
+    // px, nx, py, ny, pz, nz
+    Short,
+}
+
+impl CubemapNaming {
+    pub const ALL: [CubemapNaming; 3] = [
+        CubemapNaming::Sides,
+        CubemapNaming::PosNeg,
+        CubemapNaming::Short,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CubemapNaming::Sides => "sides",
+            CubemapNaming::PosNeg => "posx",
+            CubemapNaming::Short => "px",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        CubemapNaming::ALL
+            .into_iter()
+            .find(|naming| naming.name() == name)
+    }
+
+    // Nombres sin extensión en el orden de `Skybox::new`: +x, -x, +y, -y, +z, -z
+    pub fn file_stems(&self) -> [&'static str; 6] {
+        match self {
+            CubemapNaming::Sides => ["right", "left", "top", "bottom", "front", "back"],
+            CubemapNaming::PosNeg => ["posx", "negx", "posy", "negy", "posz", "negz"],
+            CubemapNaming::Short => ["px", "nx", "py", "ny", "pz", "nz"],
+        }
+    }
+
+    // La convención de la primera cara que exista en la carpeta
+    pub fn detect(folder: &str) -> Option<Self> {
+        CubemapNaming::ALL
+            .into_iter()
+            .find(|naming| find_face(Path::new(folder), naming.file_stems()[0]).is_some())
+    }
+}
+
+fn find_face(folder: &Path, stem: &str) -> Option<PathBuf> {
+    FACE_EXTENSIONS
+        .iter()
+        .map(|extension| folder.join(format!("{}.{}", stem, extension)))
+        .find(|path| path.is_file())
 }
 
 impl Skybox {
     pub fn new(
-        right: RgbaImage,
-        left: RgbaImage,
-        top: RgbaImage,
-        bottom: RgbaImage,
-        front: RgbaImage,
-        back: RgbaImage,
+        right: ImageTexture,
+        left: ImageTexture,
+        top: ImageTexture,
+        bottom: ImageTexture,
+        front: ImageTexture,
+        back: ImageTexture,
     ) -> Self {
         Skybox {
             right,
@@ -29,77 +186,228 @@ impl Skybox {
             bottom,
             front,
             back,
+            filter: DEFAULT_SKY_FILTER,
+            source_path: None,
+            naming: None,
+            dynamic: None,
+            environment: None,
+            yaw: 0.0,
+            yaw_speed: 0.0,
+            drift: 0.0,
+            tint: Color::new(1.0, 1.0, 1.0),
+            moon: None,
         }
     }
 
+    // Avanza el giro a los `time` segundos
+    pub fn animate(&mut self, time: f32) {
+        self.drift = self.yaw_speed * time;
+    }
+
+    // Cielo que cambia con la hora del día en lugar de una imagen
+    pub fn dynamic(sun_direction: Vec3) -> Self {
+        let mut skybox = Skybox::without_faces();
+        skybox.dynamic = Some(DynamicSky::new(sun_direction));
+        skybox
+    }
+
+    // Cielo de un panorama HDR (.hdr o .exr) con la radiancia multiplicada por `intensity`
+    pub fn from_hdr(path: &str, intensity: f32) -> Result<Self, String> {
+        let mut environment = HdrEnvironment::load(path)?;
+        environment.intensity = intensity;
+        let mut skybox = Skybox::without_faces();
+        skybox.environment = Some(environment);
+        skybox.source_path = Some(path.to_string());
+        Ok(skybox)
+    }
+
+    // Caras vacías para los cielos que no las usan
+    fn without_faces() -> Self {
+        let face: ImageTexture = Arc::new(MipChain::new(RgbaImage::new(1, 1)));
+        Skybox::new(
+            face.clone(),
+            face.clone(),
+            face.clone(),
+            face.clone(),
+            face.clone(),
+            face,
+        )
+    }
+
+    // Cielo con una imagen distinta en cada cara, leídas de `folder`. Las seis tienen que
+    // existir y ser cuadradas del mismo tamaño; si no, las uniones entre caras se notan.
+    pub fn from_folder(folder: &str, naming: CubemapNaming) -> Result<Self, String> {
+        let [right, left, top, bottom, front, back] = naming.file_stems().map(|stem| {
+            find_face(Path::new(folder), stem)
+                .map(|path| (stem, load_texture(&path.to_string_lossy())))
+                .ok_or_else(|| {
+                    format!(
+                        "falta la cara '{}' del cielo en '{}' (se busca .png, .jpg o .jpeg)",
+                        stem, folder
+                    )
+                })
+        });
+        let faces = [right?, left?, top?, bottom?, front?, back?];
+
+        let (first_stem, first) = &faces[0];
+        let size = first.dimensions();
+        if size.0 != size.1 {
+            return Err(format!(
+                "la cara '{}' del cielo no es cuadrada ({}x{})",
+                first_stem, size.0, size.1
+            ));
+        }
+        for (stem, face) in &faces[1..] {
+            if face.dimensions() != size {
+                let (width, height) = face.dimensions();
+                return Err(format!(
+                    "la cara '{}' del cielo mide {}x{} y '{}' {}x{}; todas deben medir lo mismo",
+                    stem, width, height, first_stem, size.0, size.1
+                ));
+            }
+        }
+
+        let [right, left, top, bottom, front, back] = faces.map(|(_, face)| face);
+        let mut skybox = Skybox::new(right, left, top, bottom, front, back);
+        skybox.source_path = Some(folder.to_string());
+        skybox.naming = Some(naming);
+        Ok(skybox)
+    }
+
     pub fn get_color_from_direction(&self, direction: &Vec3) -> Color {
-        // Normalizar la dirección del rayo
-        let dir = direction.normalize();
+        let color = self.background(direction);
+        match &self.moon {
+            Some(moon) => moon_disk(direction, moon, color),
+            None => color,
+        }
+    }
 
-        // Obtener los valores absolutos de las componentes
-        let abs_x = dir.x.abs();
-        let abs_y = dir.y.abs();
-        let abs_z = dir.z.abs();
-
-        let is_x_positive = dir.x > 0.0;
-        let is_y_positive = dir.y > 0.0;
-        let is_z_positive = dir.z > 0.0;
-
-        let max_axis;
-        let uc;
-        let vc;
-        let face_texture;
-
-        // Determinar qué cara del cubo se está mirando
-        if is_x_positive && abs_x >= abs_y && abs_x >= abs_z {
-            // Cara derecha (Positive X)
-            max_axis = abs_x;
-            uc = -dir.z;
-            vc = dir.y;
-            face_texture = &self.right;
-        } else if !is_x_positive && abs_x >= abs_y && abs_x >= abs_z {
-            // Cara izquierda (Negative X)
-            max_axis = abs_x;
-            uc = dir.z;
-            vc = dir.y;
-            face_texture = &self.left;
-        } else if is_y_positive && abs_y >= abs_x && abs_y >= abs_z {
-            // Cara superior (Positive Y)
-            max_axis = abs_y;
-            uc = dir.x;
-            vc = -dir.z;
-            face_texture = &self.top;
-        } else if !is_y_positive && abs_y >= abs_x && abs_y >= abs_z {
-            // Cara inferior (Negative Y)
-            max_axis = abs_y;
-            uc = dir.x;
-            vc = dir.z;
-            face_texture = &self.bottom;
-        } else if is_z_positive && abs_z >= abs_x && abs_z >= abs_y {
-            // Cara frontal (Positive Z)
-            max_axis = abs_z;
-            uc = dir.x;
-            vc = dir.y;
-            face_texture = &self.front;
-        } else {
-            // Cara trasera (Negative Z)
-            max_axis = abs_z;
-            uc = -dir.x;
-            vc = dir.y;
-            face_texture = &self.back;
+    fn background(&self, direction: &Vec3) -> Color {
+        if let Some(sky) = &self.dynamic {
+            return sky.color(direction);
+        }
+        // Girar el cielo es leerlo en la dirección girada al revés
+        let direction = &self.unrotate(direction);
+        if let Some(environment) = &self.environment {
+            return environment.color(direction, self.filter) * self.tint;
         }
 
-        // Convertir coordenadas UC y VC a UV en el rango [0, 1]
-        let u = 0.5 * (uc / max_axis + 1.0);
-        let v = 0.5 * (vc / max_axis + 1.0);
+        let (face, u, v) = cube_face(direction);
+        let [r, g, b] = match self.filter {
+            TextureFilter::Nearest => self.nearest(face, u, v),
+            TextureFilter::Bilinear => self.bilinear(face, u, v),
+        };
+        Color::new(r, g, b) * self.tint
+    }
 
-        // Mapear UV a coordenadas de textura
-        let tex_x = (u * (face_texture.width() - 1) as f32) as u32;
-        let tex_y = ((1.0 - v) * (face_texture.height() - 1) as f32) as u32; // Invertir V
+    fn unrotate(&self, direction: &Vec3) -> Vec3 {
+        let angle = self.yaw + self.drift;
+        if angle == 0.0 {
+            return *direction;
+        }
+        let (sin, cos) = angle.sin_cos();
+        Vec3::new(
+            direction.x * cos - direction.z * sin,
+            direction.y,
+            direction.x * sin + direction.z * cos,
+        )
+    }
 
-        // Obtener el pixel de la textura
-        let pixel = face_texture.get_pixel(tex_x.min(face_texture.width() - 1), tex_y.min(face_texture.height() - 1));
+    // Caras en el orden de `cube_face`
+    fn face(&self, index: usize) -> &ImageTexture {
+        [
+            &self.right,
+            &self.left,
+            &self.top,
+            &self.bottom,
+            &self.front,
+            &self.back,
+        ][index]
+    }
+
+    fn nearest(&self, face: usize, u: f32, v: f32) -> [f32; 3] {
+        let [r, g, b, _] = sample_texture(self.face(face), u, v, TextureFilter::Nearest);
+        [r, g, b]
+    }
+
+    // Mezcla de los cuatro texels vecinos. En el borde de una cara los que quedan fuera se
+    // leen de la cara de al lado (el texel que apunta en esa dirección), así las uniones
+    // entre caras no muestran un corte.
+    fn bilinear(&self, face: usize, u: f32, v: f32) -> [f32; 3] {
+        let texture = self.face(face);
+        let (width, height) = texture.dimensions();
+        let x = u * width as f32 - 0.5;
+        let y = v * height as f32 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+
+        let texel = |x: i64, y: i64| -> [f32; 3] {
+            if (0..width as i64).contains(&x) && (0..height as i64).contains(&y) {
+                let pixel = texture.get_pixel(x as u32, y as u32);
+                return [0, 1, 2].map(|channel| pixel.0[channel] as f32 / 255.0);
+            }
+            let direction = face_direction(
+                face,
+                (x as f32 + 0.5) / width as f32,
+                (y as f32 + 0.5) / height as f32,
+            );
+            let (other, u, v) = cube_face(&direction);
+            self.nearest(other, u, v)
+        };
+
+        let (top_left, top_right) = (texel(x0, y0), texel(x0 + 1, y0));
+        let (bottom_left, bottom_right) = (texel(x0, y0 + 1), texel(x0 + 1, y0 + 1));
+        std::array::from_fn(|i| {
+            let top = top_left[i] * (1.0 - fx) + top_right[i] * fx;
+            let bottom = bottom_left[i] * (1.0 - fx) + bottom_right[i] * fx;
+            top * (1.0 - fy) + bottom * fy
+        })
+    }
+}
+
+// Cara del cubo que ve la dirección (0 +x, 1 -x, 2 +y, 3 -y, 4 +z, 5 -z) y coordenadas de
+// textura en ella, con v = 0 en la fila de arriba de la imagen
+fn cube_face(direction: &Vec3) -> (usize, f32, f32) {
+    let dir = direction.normalize();
+    let (abs_x, abs_y, abs_z) = (dir.x.abs(), dir.y.abs(), dir.z.abs());
+
+    // (cara, eje mayor, coordenadas horizontal y vertical en la cara de -1 a 1 sin dividir)
+    let (face, max_axis, uc, vc) = if abs_x >= abs_y && abs_x >= abs_z {
+        if dir.x > 0.0 {
+            (0, abs_x, -dir.z, dir.y)
+        } else {
+            (1, abs_x, dir.z, dir.y)
+        }
+    } else if abs_y >= abs_x && abs_y >= abs_z {
+        if dir.y > 0.0 {
+            (2, abs_y, dir.x, -dir.z)
+        } else {
+            (3, abs_y, dir.x, dir.z)
+        }
+    } else if dir.z > 0.0 {
+        (4, abs_z, dir.x, dir.y)
+    } else {
+        (5, abs_z, -dir.x, dir.y)
+    };
+
+    (
+        face,
+        0.5 * (uc / max_axis + 1.0),
+        0.5 * (1.0 - vc / max_axis),
+    )
+}
 
-        Color::new(pixel[0] as f32 / 255.0, pixel[1] as f32 / 255.0, pixel[2] as f32 / 255.0)
+// Inversa de `cube_face`: dirección (sin normalizar) del punto (u, v) de una cara. Fuera
+// del rango 0..1 apunta a las caras vecinas.
+fn face_direction(face: usize, u: f32, v: f32) -> Vec3 {
+    let (uc, vc) = (2.0 * u - 1.0, 1.0 - 2.0 * v);
+    match face {
+        0 => Vec3::new(1.0, vc, -uc),
+        1 => Vec3::new(-1.0, vc, uc),
+        2 => Vec3::new(uc, 1.0, -vc),
+        3 => Vec3::new(uc, -1.0, vc),
+        4 => Vec3::new(uc, vc, 1.0),
+        _ => Vec3::new(-uc, vc, -1.0),
     }
 }
@@ -0,0 +1,101 @@
+// stereo.rs
+
+use nalgebra_glm as glm;
+
+use crate::camera::Camera;
+use crate::cube::Cube;
+use crate::framebuffer::Framebuffer;
+use crate::post::apply_post;
+use crate::render::{render, RenderSettings};
+use crate::scene::Scene;
+
+// Distancia entre los ojos por defecto: 64 mm con bloques de 1 m
+pub const DEFAULT_EYE_SEPARATION: f32 = 0.064;
+
+// Cómo se empaquetan las dos vistas en un mismo frame para los reproductores de video VR
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StereoLayout {
+    // Ojo izquierdo a la izquierda y derecho a la derecha (el doble de ancho)
+    SideBySide,
+    // Ojo izquierdo arriba y derecho abajo (el doble de alto)
+    TopBottom,
+}
+
+#[derive(Debug, Clone)]
+pub struct StereoSettings {
+    pub layout: StereoLayout,
+    // Distancia entre las cámaras de cada ojo, en bloques
+    pub eye_separation: f32,
+    // Distancia del plano donde las dos vistas coinciden (sin paralaje); lo que está más
+    // cerca sale de la pantalla. None usa la distancia de enfoque de la cámara.
+    pub convergence: Option<f32>,
+}
+
+impl StereoSettings {
+    pub fn new(layout: StereoLayout) -> Self {
+        StereoSettings {
+            layout,
+            eye_separation: DEFAULT_EYE_SEPARATION,
+            convergence: None,
+        }
+    }
+
+    // Tamaño del frame empaquetado para vistas de width x height
+    pub fn frame_size(&self, width: usize, height: usize) -> (usize, usize) {
+        match self.layout {
+            StereoLayout::SideBySide => (width * 2, height),
+            StereoLayout::TopBottom => (width, height * 2),
+        }
+    }
+
+    // Cámara de un ojo: desplazada sobre el eje derecho de la cámara central y girada hacia
+    // el punto de convergencia (toe-in). `side` es -1 para el izquierdo y 1 para el derecho.
+    fn eye_camera(&self, center: &Camera, side: f32) -> Camera {
+        let right = glm::row(center.view_matrix(), 0).xyz();
+        let forward = -glm::row(center.view_matrix(), 2).xyz();
+        let convergence = self.convergence.unwrap_or_else(|| center.focus());
+
+        let mut eye = center.clone();
+        eye.position = center.position + right * (side * self.eye_separation * 0.5);
+        eye.target = center.position + forward * convergence;
+        eye.update_view();
+        eye
+    }
+}
+
+// Renderiza las dos vistas y las empaqueta en `framebuffer`, que debe tener el tamaño de
+// `StereoSettings::frame_size`. La cámara de la escena queda como estaba.
+pub fn render_stereo(
+    framebuffer: &mut Framebuffer,
+    scene: &mut Scene,
+    objects: &[Cube],
+    settings: &RenderSettings,
+    stereo: &StereoSettings,
+) {
+    let (eye_width, eye_height) = match stereo.layout {
+        StereoLayout::SideBySide => (framebuffer.width / 2, framebuffer.height),
+        StereoLayout::TopBottom => (framebuffer.width, framebuffer.height / 2),
+    };
+    let center = scene.camera.clone();
+    let mut view = Framebuffer::new(eye_width, eye_height);
+
+    for (index, side) in [-1.0, 1.0].into_iter().enumerate() {
+        scene.camera = stereo.eye_camera(&center, side);
+        render(&mut view, scene, objects, settings);
+        apply_post(&scene.post, &mut view);
+
+        let (offset_x, offset_y) = match stereo.layout {
+            StereoLayout::SideBySide => (index * eye_width, 0),
+            StereoLayout::TopBottom => (0, index * eye_height),
+        };
+        for y in 0..eye_height {
+            let source = y * eye_width..(y + 1) * eye_width;
+            let start = (offset_y + y) * framebuffer.width + offset_x;
+            let destination = start..start + eye_width;
+            framebuffer.buffer[destination.clone()].copy_from_slice(&view.buffer[source.clone()]);
+            framebuffer.alpha[destination].copy_from_slice(&view.alpha[source]);
+        }
+    }
+
+    scene.camera = center;
+}
@@ -0,0 +1,212 @@
+// sweep.rs
+
+use crate::color::Color;
+use crate::font::{draw_text, text_width, GLYPH_HEIGHT};
+use crate::framebuffer::Framebuffer;
+use crate::overrides::apply_override;
+use crate::post::apply_post;
+use crate::render::{render, RenderSettings};
+use crate::scene::Scene;
+
+// Ruta especial de los barridos: la hora del ciclo de día, de 0 a 1
+pub const TIME_PATH: &str = "time";
+// Separación entre celdas y alrededor de los rótulos
+const MARGIN: usize = 4;
+const LABEL_SCALE: usize = 2;
+const SHEET_BACKGROUND: Color = Color::new(0.12, 0.12, 0.12);
+const LABEL_COLOR: Color = Color::new(0.9, 0.9, 0.9);
+
+// Un parámetro que cambia a lo largo de las columnas (el primero) o de las filas (el segundo)
+#[derive(Debug, Clone)]
+pub struct SweepAxis {
+    pub path: String,
+    // Valores ya escritos como los espera `apply_override`
+    pub values: Vec<String>,
+}
+
+impl SweepAxis {
+    // `ruta=inicio:fin:pasos` reparte los valores de forma pareja entre inicio y fin (ambos
+    // con la misma unidad, ej. `camera.fov=30deg:90deg:4`); `ruta=a|b|c` usa esos valores
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let (path, values) = spec
+            .split_once('=')
+            .ok_or_else(|| format!("'{}' no tiene la forma ruta=valores", spec))?;
+        let values = if values.contains('|') {
+            values
+                .split('|')
+                .map(|value| value.trim().to_string())
+                .collect()
+        } else {
+            range(values)?
+        };
+        Ok(SweepAxis {
+            path: path.trim().to_string(),
+            values,
+        })
+    }
+
+    // Rótulo corto con la última parte de la ruta, ej. `ior=1.4`
+    fn label(&self, value: &str) -> String {
+        let name = self.path.rsplit('.').next().unwrap_or(&self.path);
+        format!("{}={}", name, value)
+    }
+
+    fn apply(&self, scene: &mut Scene, value: &str) -> Result<(), String> {
+        if self.path == TIME_PATH {
+            let time = value
+                .parse::<f32>()
+                .map_err(|_| format!("la hora '{}' no es un número", value))?;
+            scene.apply_day_cycle(time.rem_euclid(1.0));
+            Ok(())
+        } else {
+            apply_override(scene, &format!("{}={}", self.path, value))
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ContactSheetSettings {
+    // Uno o dos parámetros
+    pub axes: Vec<SweepAxis>,
+    pub cell_width: usize,
+    pub cell_height: usize,
+    pub samples: u32,
+    pub max_depth: u32,
+}
+
+// Renderiza una imagen pequeña por cada combinación de valores y las reúne en una hoja con
+// el valor de cada una escrito debajo. La escena queda con los valores de la última celda.
+pub fn render_contact_sheet(
+    scene: &mut Scene,
+    settings: &ContactSheetSettings,
+) -> Result<Framebuffer, String> {
+    let (columns_axis, rows_axis) = match settings.axes.as_slice() {
+        [columns] => (columns, None),
+        [columns, rows] => (columns, Some(rows)),
+        _ => return Err("se necesitan uno o dos parámetros para el barrido".to_string()),
+    };
+    let columns = columns_axis.values.len();
+    let rows = rows_axis.map_or(1, |axis| axis.values.len());
+
+    let label_height = GLYPH_HEIGHT * LABEL_SCALE + 2 * MARGIN;
+    let cell_step_x = settings.cell_width + MARGIN;
+    let cell_step_y = settings.cell_height + label_height;
+    let mut sheet = Framebuffer::new(MARGIN + columns * cell_step_x, MARGIN + rows * cell_step_y);
+    sheet.dither = scene.dither;
+    sheet.set_background_color(SHEET_BACKGROUND);
+    sheet.clear();
+    sheet.set_current_color(LABEL_COLOR);
+
+    let mut cell = Framebuffer::new(settings.cell_width, settings.cell_height);
+    for row in 0..rows {
+        for column in 0..columns {
+            let mut assignments = vec![(columns_axis, &columns_axis.values[column])];
+            if let Some(axis) = rows_axis {
+                assignments.push((axis, &axis.values[row]));
+            }
+            let labels: Vec<String> = assignments
+                .iter()
+                .map(|(axis, value)| axis.label(value))
+                .collect();
+
+            // La hora va primero para que los cambios a las luces no se pierdan
+            assignments.sort_by_key(|(axis, _)| axis.path != TIME_PATH);
+            for (axis, value) in assignments {
+                axis.apply(scene, value)
+                    .map_err(|err| format!("{}: {}", axis.label(value), err))?;
+            }
+
+            render(
+                &mut cell,
+                scene,
+                &scene.cubes_at(0.0),
+                &RenderSettings {
+                    max_depth: settings.max_depth,
+                    ..RenderSettings::with_samples(settings.samples)
+                },
+            );
+            apply_post(&scene.post, &mut cell);
+
+            let left = MARGIN + column * cell_step_x;
+            let top = MARGIN + row * cell_step_y;
+            for y in 0..cell.height {
+                let source = y * cell.width;
+                let target = (top + y) * sheet.width + left;
+                sheet.buffer[target..target + cell.width]
+                    .copy_from_slice(&cell.buffer[source..source + cell.width]);
+            }
+
+            // Si no entra a tamaño normal, el rótulo se escribe a la mitad
+            let label = labels.join(" ");
+            let scale = if text_width(&label, LABEL_SCALE) <= settings.cell_width {
+                LABEL_SCALE
+            } else {
+                1
+            };
+            let label_top = top + settings.cell_height + (label_height - GLYPH_HEIGHT * scale) / 2;
+            draw_text(&mut sheet, left, label_top, &label, scale);
+
+            println!("Celda {}/{}", row * columns + column + 1, rows * columns);
+        }
+    }
+
+    Ok(sheet)
+}
+
+// Valores de `inicio:fin:pasos`, con la unidad de los extremos repetida en cada uno
+fn range(spec: &str) -> Result<Vec<String>, String> {
+    let parts: Vec<&str> = spec.split(':').map(str::trim).collect();
+    let [start, end, steps] = parts.as_slice() else {
+        return Err(format!(
+            "'{}' no es un rango inicio:fin:pasos ni una lista a|b|c",
+            spec
+        ));
+    };
+    let (start, unit) = split_unit(start)?;
+    let (end, end_unit) = split_unit(end)?;
+    if unit != end_unit {
+        return Err(format!(
+            "los extremos de '{}' usan unidades distintas",
+            spec
+        ));
+    }
+    let steps: usize = steps
+        .parse()
+        .ok()
+        .filter(|&steps| steps > 0)
+        .ok_or_else(|| format!("la cantidad de pasos de '{}' debe ser positiva", spec))?;
+
+    Ok((0..steps)
+        .map(|step| {
+            let t = if steps > 1 {
+                step as f32 / (steps - 1) as f32
+            } else {
+                0.0
+            };
+            format!("{}{}", short_number(start + (end - start) * t), unit)
+        })
+        .collect())
+}
+
+// Separa `30deg` en (30, "deg")
+fn split_unit(text: &str) -> Result<(f32, &str), String> {
+    let split = text
+        .find(|c: char| c.is_ascii_alphabetic())
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let number = number
+        .parse::<f32>()
+        .map_err(|_| format!("'{}' no es un número", text))?;
+    Ok((number, unit))
+}
+
+// Hasta tres decimales, sin ceros de más
+fn short_number(value: f32) -> String {
+    let text = format!("{:.3}", value);
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    if text == "-0" {
+        "0".to_string()
+    } else {
+        text.to_string()
+    }
+}
@@ -0,0 +1,129 @@
+// temporal.rs
+
+use nalgebra_glm::Vec3;
+
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::cube::Cube;
+use crate::framebuffer::Framebuffer;
+use crate::render::primary_surfaces;
+use crate::scene::Scene;
+
+// Fracción del frame anterior que se conserva en cada pixel reproyectado
+pub const HISTORY_WEIGHT: f32 = 0.8;
+// Si el punto del frame anterior está más lejos que esta fracción de su profundidad, lo
+// que se veía ahí era otra superficie (algo que estaba tapado y quedó a la vista)
+const REJECT_DISTANCE: f32 = 0.03;
+
+// Suavizado de los frames con la cámara en movimiento, al estilo TAA: cada frame nuevo se
+// mezcla con el anterior, reproyectado al lugar donde cada punto del mundo se veía con la
+// cámara de antes. Lo que recién aparece no tiene historia y se ve tal cual, y la historia
+// se recorta a los colores vecinos del frame nuevo para no dejar estelas.
+pub struct TemporalFilter {
+    history: Vec<Color>,
+    // Punto del mundo de cada pixel de la historia; None donde se veía el cielo
+    points: Vec<Option<Vec3>>,
+    size: (usize, usize),
+    camera: Option<Camera>,
+    frames: u32,
+}
+
+impl TemporalFilter {
+    pub fn new() -> Self {
+        TemporalFilter {
+            history: Vec::new(),
+            points: Vec::new(),
+            size: (0, 0),
+            camera: None,
+            frames: 0,
+        }
+    }
+
+    // Olvida la historia; el próximo frame se ve sin mezclar
+    pub fn reset(&mut self) {
+        self.camera = None;
+        self.frames = 0;
+    }
+
+    // Pasada de muestreo del próximo frame: cada frame en movimiento mueve las muestras
+    // dentro del pixel, así la mezcla junta posiciones distintas en vez de repetirlas
+    pub fn pass(&self) -> u32 {
+        self.frames
+    }
+
+    // Mezcla `frame`, recién renderizado con la cámara de `scene`, con la historia y lo
+    // guarda como historia del siguiente
+    pub fn blend(&mut self, frame: &mut Framebuffer, scene: &Scene, objects: &[Cube]) {
+        let size = (frame.width, frame.height);
+        let points: Vec<Option<Vec3>> = primary_surfaces(scene, objects, size)
+            .iter()
+            .map(|surface| surface.map(|surface| surface.point))
+            .collect();
+
+        if let Some(previous) = self.camera.as_ref().filter(|_| self.size == size) {
+            let current = frame.buffer.clone();
+            for (index, point) in points.iter().enumerate() {
+                // El cielo no tiene ruido que suavizar y no se puede medir si se tapó
+                let Some(point) = point else {
+                    continue;
+                };
+                let Some(history) = self.history_at(previous, point) else {
+                    continue;
+                };
+                let (low, high) = neighborhood(&current, size, index);
+                let history = Color::new(
+                    history.r.clamp(low.r, high.r),
+                    history.g.clamp(low.g, high.g),
+                    history.b.clamp(low.b, high.b),
+                );
+                frame.buffer[index] =
+                    current[index] * (1.0 - HISTORY_WEIGHT) + history * HISTORY_WEIGHT;
+            }
+        }
+
+        self.history.clone_from(&frame.buffer);
+        self.points = points;
+        self.size = size;
+        self.camera = Some(scene.camera.clone());
+        self.frames += 1;
+    }
+
+    // Color que tenía `point` en el frame anterior, si se veía y era la misma superficie
+    fn history_at(&self, previous: &Camera, point: &Vec3) -> Option<Color> {
+        let (width, height) = self.size;
+        let (x, y) = previous.project(point, width as f32, height as f32)?;
+        let (x, y) = (x.round(), y.round());
+        if x < 0.0 || y < 0.0 || x >= width as f32 || y >= height as f32 {
+            return None;
+        }
+        let index = y as usize * width + x as usize;
+        let seen = self.points[index]?;
+        let tolerance = REJECT_DISTANCE * previous.depth(point).max(1.0);
+        ((seen - point).magnitude() <= tolerance).then_some(self.history[index])
+    }
+}
+
+impl Default for TemporalFilter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Mínimo y máximo de cada canal entre el pixel `index` y sus ocho vecinos
+fn neighborhood(buffer: &[Color], (width, height): (usize, usize), index: usize) -> (Color, Color) {
+    let (x, y) = (index % width, index / width);
+    let mut low = buffer[index];
+    let mut high = buffer[index];
+    for ny in y.saturating_sub(1)..(y + 2).min(height) {
+        for nx in x.saturating_sub(1)..(x + 2).min(width) {
+            let color = buffer[ny * width + nx];
+            low = Color::new(low.r.min(color.r), low.g.min(color.g), low.b.min(color.b));
+            high = Color::new(
+                high.r.max(color.r),
+                high.g.max(color.g),
+                high.b.max(color.b),
+            );
+        }
+    }
+    (low, high)
+}
@@ -0,0 +1,177 @@
+// terrain.rs
+
+use crate::chunk::BlockId;
+use crate::world::World;
+
+#[derive(Debug, Clone)]
+pub struct TerrainSettings {
+    pub seed: u64,
+    // Tamaño en bloques a lo largo de x y z; el terreno queda centrado en el origen
+    pub width: i32,
+    pub depth: i32,
+    // Los huecos por debajo de este nivel se llenan de agua
+    pub sea_level: i32,
+    pub base_height: i32,
+    // Variación máxima de altura respecto a base_height
+    pub amplitude: f32,
+    // Bloques que abarca una ondulación de la octava más grande
+    pub scale: f32,
+    pub octaves: u32,
+    // Capas de tierra debajo del césped antes de llegar a la piedra
+    pub dirt_depth: i32,
+}
+
+impl Default for TerrainSettings {
+    fn default() -> Self {
+        TerrainSettings {
+            seed: 0,
+            width: 32,
+            depth: 32,
+            sea_level: 4,
+            base_height: 4,
+            amplitude: 5.0,
+            scale: 24.0,
+            octaves: 4,
+            dirt_depth: 3,
+        }
+    }
+}
+
+pub struct TerrainMaterials {
+    pub grass: BlockId,
+    pub dirt: BlockId,
+    pub stone: BlockId,
+    pub water: BlockId,
+}
+
+// Llena el mundo con columnas de piedra, tierra y césped siguiendo un mapa de alturas
+// de ruido Perlin, y cubre con agua lo que quede bajo el nivel del mar
+pub fn generate_terrain(
+    world: &mut World,
+    settings: &TerrainSettings,
+    materials: &TerrainMaterials,
+) {
+    let noise = Perlin::new(settings.seed);
+    let min_x = -settings.width / 2;
+    let min_z = -settings.depth / 2;
+
+    for x in min_x..min_x + settings.width {
+        for z in min_z..min_z + settings.depth {
+            let height = settings.base_height as f32
+                + settings.amplitude
+                    * noise.fbm(
+                        x as f32 / settings.scale,
+                        z as f32 / settings.scale,
+                        settings.octaves,
+                    );
+            let height = (height.round() as i32).max(1);
+            let surface = height - 1;
+
+            for y in 0..height {
+                let block = if y == surface && surface >= settings.sea_level {
+                    materials.grass
+                } else if y >= surface - settings.dirt_depth {
+                    materials.dirt
+                } else {
+                    materials.stone
+                };
+                world.set_block((x, y, z), block);
+            }
+
+            for y in height..settings.sea_level {
+                world.set_block((x, y, z), materials.water);
+            }
+        }
+    }
+}
+
+// Ruido Perlin 2D con tabla de permutaciones derivada de la semilla
+struct Perlin {
+    permutation: [u8; 512],
+}
+
+impl Perlin {
+    fn new(seed: u64) -> Self {
+        let mut table: [u8; 256] = std::array::from_fn(|i| i as u8);
+
+        // Fisher-Yates con splitmix64 para no depender de un crate de números aleatorios
+        let mut state = seed;
+        for i in (1..table.len()).rev() {
+            state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+            let mut z = state;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+            z ^= z >> 31;
+            table.swap(i, (z % (i as u64 + 1)) as usize);
+        }
+
+        Perlin {
+            permutation: std::array::from_fn(|i| table[i % 256]),
+        }
+    }
+
+    fn gradient(hash: u8, x: f32, y: f32) -> f32 {
+        match hash & 7 {
+            0 => x + y,
+            1 => x - y,
+            2 => -x + y,
+            3 => -x - y,
+            4 => x,
+            5 => -x,
+            6 => y,
+            _ => -y,
+        }
+    }
+
+    // Valor aproximadamente entre -1 y 1
+    fn noise(&self, x: f32, y: f32) -> f32 {
+        let xi = x.floor();
+        let yi = y.floor();
+        let xf = x - xi;
+        let yf = y - yi;
+        let xi = (xi as i32 & 255) as usize;
+        let yi = (yi as i32 & 255) as usize;
+
+        let fade = |t: f32| t * t * t * (t * (t * 6.0 - 15.0) + 10.0);
+        let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+        let u = fade(xf);
+        let v = fade(yf);
+
+        let p = &self.permutation;
+        let aa = p[p[xi] as usize + yi];
+        let ab = p[p[xi] as usize + yi + 1];
+        let ba = p[p[xi + 1] as usize + yi];
+        let bb = p[p[xi + 1] as usize + yi + 1];
+
+        lerp(
+            lerp(
+                Self::gradient(aa, xf, yf),
+                Self::gradient(ba, xf - 1.0, yf),
+                u,
+            ),
+            lerp(
+                Self::gradient(ab, xf, yf - 1.0),
+                Self::gradient(bb, xf - 1.0, yf - 1.0),
+                u,
+            ),
+            v,
+        )
+    }
+
+    // Suma de octavas, cada una al doble de frecuencia y la mitad de amplitud
+    fn fbm(&self, x: f32, y: f32, octaves: u32) -> f32 {
+        let mut total = 0.0;
+        let mut amplitude = 1.0;
+        let mut frequency = 1.0;
+        let mut max_value = 0.0;
+
+        for _ in 0..octaves.max(1) {
+            total += self.noise(x * frequency, y * frequency) * amplitude;
+            max_value += amplitude;
+            amplitude *= 0.5;
+            frequency *= 2.0;
+        }
+
+        total / max_value
+    }
+}
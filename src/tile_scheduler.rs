@@ -0,0 +1,179 @@
+// tile_scheduler.rs
+
+use nalgebra_glm::Vec3;
+use std::time::{Duration, Instant};
+
+use crate::camera::Camera;
+use crate::cube::Cube;
+use crate::debug_view::DebugView;
+use crate::framebuffer::Framebuffer;
+use crate::render::{render_preview_tiles, RenderSettings, Tile};
+use crate::scene::Scene;
+use crate::world::BlockPos;
+
+// Lado de los bloques en que se divide la imagen
+pub const TILE_SIZE: usize = 32;
+// Bloques por hilo que se reparten en cada tanda; más de uno para que los hilos que
+// terminan antes (bloques de cielo) tomen trabajo de los lentos
+const TILES_PER_WORKER: usize = 2;
+// Pixeles que se agregan alrededor de un bloque editado, por las muestras desplazadas
+// dentro del pixel
+const DIRTY_MARGIN: usize = 2;
+
+// Reparte cada frame de la ventana en bloques que los hilos de render toman en tandas. Un
+// frame lento se completa a lo largo de varias vueltas del ciclo principal sin bloquearlo,
+// y `cancel` lo abandona para empezar otro, por ejemplo cuando se mueve la cámara.
+pub struct TileScheduler {
+    // Frame en curso; los bloques que ya se renderizaron se ven sobre el frame anterior
+    canvas: Framebuffer,
+    // Bloques que faltan, el próximo al final
+    pending: Vec<Tile>,
+}
+
+impl TileScheduler {
+    pub fn new() -> Self {
+        TileScheduler {
+            canvas: Framebuffer::new(0, 0),
+            pending: Vec::new(),
+        }
+    }
+
+    // Descarta lo que falta del frame en curso; el próximo `step` empieza uno nuevo
+    pub fn cancel(&mut self) {
+        self.pending.clear();
+    }
+
+    // Vuelve a renderizar solo `region` de una imagen de `size` sobre lo que ya tiene el
+    // lienzo, antes de lo que falte del frame en curso. Si el lienzo es de otro tamaño (la
+    // vista estaba reducida) devuelve false y hay que renderizar el frame entero.
+    pub fn invalidate(&mut self, size: (usize, usize), region: Tile) -> bool {
+        if (self.canvas.width, self.canvas.height) != size {
+            return false;
+        }
+        let mut dirty = split(region);
+        dirty.reverse();
+        self.pending.extend(dirty);
+        true
+    }
+
+    // Si hay un frame empezado y sin terminar
+    pub fn in_flight(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    // Lo renderizado hasta ahora, al tamaño reducido por `settings.scale`
+    pub fn canvas(&self) -> &Framebuffer {
+        &self.canvas
+    }
+
+    // Para retocar un frame terminado antes de mostrarlo; lo que se cambie queda debajo de
+    // los bloques del frame siguiente
+    pub fn canvas_mut(&mut self) -> &mut Framebuffer {
+        &mut self.canvas
+    }
+
+    // Renderiza tandas de bloques del frame en curso (o de uno nuevo para una imagen de
+    // `width` x `height`) hasta terminarlo o pasarse de `budget`. Devuelve si se completó.
+    pub fn step(
+        &mut self,
+        (width, height): (usize, usize),
+        scene: &Scene,
+        objects: &[Cube],
+        settings: &RenderSettings,
+        view: DebugView,
+        budget: Duration,
+    ) -> bool {
+        if self.pending.is_empty() {
+            let scale = settings.scale.max(1);
+            let size = ((width / scale).max(1), (height / scale).max(1));
+            if (self.canvas.width, self.canvas.height) != size {
+                self.canvas = Framebuffer::new(size.0, size.1);
+            }
+            self.pending = tiles(size.0, size.1);
+            self.pending.reverse();
+        }
+
+        let batch = workers() * TILES_PER_WORKER;
+        let start = Instant::now();
+        while !self.pending.is_empty() {
+            let tiles = self
+                .pending
+                .split_off(self.pending.len().saturating_sub(batch));
+            render_preview_tiles(&mut self.canvas, scene, objects, settings, view, &tiles);
+            if start.elapsed() >= budget {
+                break;
+            }
+        }
+        self.pending.is_empty()
+    }
+}
+
+impl Default for TileScheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Bloques que cubren una imagen de arriba hacia abajo; los del borde pueden ser más chicos
+pub fn tiles(width: usize, height: usize) -> Vec<Tile> {
+    split(Tile {
+        x: 0,
+        y: 0,
+        width,
+        height,
+    })
+}
+
+fn split(region: Tile) -> Vec<Tile> {
+    let (right, bottom) = (region.x + region.width, region.y + region.height);
+    (region.y..bottom)
+        .step_by(TILE_SIZE)
+        .flat_map(|y| {
+            (region.x..right).step_by(TILE_SIZE).map(move |x| Tile {
+                x,
+                y,
+                width: TILE_SIZE.min(right - x),
+                height: TILE_SIZE.min(bottom - y),
+            })
+        })
+        .collect()
+}
+
+// Pixeles de una imagen de `width` x `height` donde puede verse el bloque `pos`: el
+// rectángulo que encierra sus esquinas proyectadas, con un margen. Los rayos de cámara de
+// fuera no lo tocan, aunque sí pueden cambiar sus sombras y reflejos. None si alguna
+// esquina queda detrás de la cámara o si el bloque no se ve.
+pub fn dirty_region(
+    camera: &Camera,
+    pos: BlockPos,
+    (width, height): (usize, usize),
+) -> Option<Tile> {
+    let min = Vec3::new(pos.0 as f32, pos.1 as f32, pos.2 as f32);
+    let (mut left, mut top) = (f32::INFINITY, f32::INFINITY);
+    let (mut right, mut bottom) = (f32::NEG_INFINITY, f32::NEG_INFINITY);
+    for i in 0..8 {
+        let corner = min + Vec3::new((i & 1) as f32, ((i >> 1) & 1) as f32, ((i >> 2) & 1) as f32);
+        let (x, y) = camera.project(&corner, width as f32, height as f32)?;
+        (left, top) = (left.min(x), top.min(y));
+        (right, bottom) = (right.max(x), bottom.max(y));
+    }
+
+    let margin = DIRTY_MARGIN as f32;
+    let x0 = (left - margin).floor().max(0.0) as usize;
+    let y0 = (top - margin).floor().max(0.0) as usize;
+    let x1 = ((right + margin).ceil().max(0.0) as usize).min(width);
+    let y1 = ((bottom + margin).ceil().max(0.0) as usize).min(height);
+    (x0 < x1 && y0 < y1).then_some(Tile {
+        x: x0,
+        y: y0,
+        width: x1 - x0,
+        height: y1 - y0,
+    })
+}
+
+fn workers() -> usize {
+    #[cfg(feature = "parallel")]
+    return rayon::current_num_threads();
+    #[cfg(not(feature = "parallel"))]
+    1
+}
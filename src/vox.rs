@@ -0,0 +1,707 @@
+// vox.rs
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::chunk::BlockId;
+use crate::color::Color;
+use crate::material::Material;
+use crate::world::{within_limits, BlockPos, World};
+
+// Especular y albedo de los colores sin material propio, como los bloques integrados
+const DIFFUSE_SPECULAR: f32 = 10.0;
+const DIFFUSE_ALBEDO: [f32; 4] = [0.8, 0.1, 0.1, 0.0];
+// Vidrio: poco difuso y el resto repartido entre reflejo y transparencia
+const GLASS_SPECULAR: f32 = 50.0;
+const GLASS_ALBEDO: [f32; 3] = [0.1, 0.4, 0.1];
+const GLASS_IOR: f32 = 1.5;
+// Nodos recorridos más vóxeles colocados que acepta el grafo de la escena
+const MAX_PLACEMENTS: usize = 1 << 25;
+// Paleta por defecto de MagicaVoxel, la de los archivos sin chunk RGBA, como 0xAABBGGRR.
+// El índice 0 es el vacío.
+const DEFAULT_PALETTE: [u32; 256] = [
+    0x00000000, 0xffffffff, 0xffccffff, 0xff99ffff, 0xff66ffff, 0xff33ffff, 0xff00ffff, 0xffffccff,
+    0xffccccff, 0xff99ccff, 0xff66ccff, 0xff33ccff, 0xff00ccff, 0xffff99ff, 0xffcc99ff, 0xff9999ff,
+    0xff6699ff, 0xff3399ff, 0xff0099ff, 0xffff66ff, 0xffcc66ff, 0xff9966ff, 0xff6666ff, 0xff3366ff,
+    0xff0066ff, 0xffff33ff, 0xffcc33ff, 0xff9933ff, 0xff6633ff, 0xff3333ff, 0xff0033ff, 0xffff00ff,
+    0xffcc00ff, 0xff9900ff, 0xff6600ff, 0xff3300ff, 0xff0000ff, 0xffffffcc, 0xffccffcc, 0xff99ffcc,
+    0xff66ffcc, 0xff33ffcc, 0xff00ffcc, 0xffffcccc, 0xffcccccc, 0xff99cccc, 0xff66cccc, 0xff33cccc,
+    0xff00cccc, 0xffff99cc, 0xffcc99cc, 0xff9999cc, 0xff6699cc, 0xff3399cc, 0xff0099cc, 0xffff66cc,
+    0xffcc66cc, 0xff9966cc, 0xff6666cc, 0xff3366cc, 0xff0066cc, 0xffff33cc, 0xffcc33cc, 0xff9933cc,
+    0xff6633cc, 0xff3333cc, 0xff0033cc, 0xffff00cc, 0xffcc00cc, 0xff9900cc, 0xff6600cc, 0xff3300cc,
+    0xff0000cc, 0xffffff99, 0xffccff99, 0xff99ff99, 0xff66ff99, 0xff33ff99, 0xff00ff99, 0xffffcc99,
+    0xffcccc99, 0xff99cc99, 0xff66cc99, 0xff33cc99, 0xff00cc99, 0xffff9999, 0xffcc9999, 0xff999999,
+    0xff669999, 0xff339999, 0xff009999, 0xffff6699, 0xffcc6699, 0xff996699, 0xff666699, 0xff336699,
+    0xff006699, 0xffff3399, 0xffcc3399, 0xff993399, 0xff663399, 0xff333399, 0xff003399, 0xffff0099,
+    0xffcc0099, 0xff990099, 0xff660099, 0xff330099, 0xff000099, 0xffffff66, 0xffccff66, 0xff99ff66,
+    0xff66ff66, 0xff33ff66, 0xff00ff66, 0xffffcc66, 0xffcccc66, 0xff99cc66, 0xff66cc66, 0xff33cc66,
+    0xff00cc66, 0xffff9966, 0xffcc9966, 0xff999966, 0xff669966, 0xff339966, 0xff009966, 0xffff6666,
+    0xffcc6666, 0xff996666, 0xff666666, 0xff336666, 0xff006666, 0xffff3366, 0xffcc3366, 0xff993366,
+    0xff663366, 0xff333366, 0xff003366, 0xffff0066, 0xffcc0066, 0xff990066, 0xff660066, 0xff330066,
+    0xff000066, 0xffffff33, 0xffccff33, 0xff99ff33, 0xff66ff33, 0xff33ff33, 0xff00ff33, 0xffffcc33,
+    0xffcccc33, 0xff99cc33, 0xff66cc33, 0xff33cc33, 0xff00cc33, 0xffff9933, 0xffcc9933, 0xff999933,
+    0xff669933, 0xff339933, 0xff009933, 0xffff6633, 0xffcc6633, 0xff996633, 0xff666633, 0xff336633,
+    0xff006633, 0xffff3333, 0xffcc3333, 0xff993333, 0xff663333, 0xff333333, 0xff003333, 0xffff0033,
+    0xffcc0033, 0xff990033, 0xff660033, 0xff330033, 0xff000033, 0xffffff00, 0xffccff00, 0xff99ff00,
+    0xff66ff00, 0xff33ff00, 0xff00ff00, 0xffffcc00, 0xffcccc00, 0xff99cc00, 0xff66cc00, 0xff33cc00,
+    0xff00cc00, 0xffff9900, 0xffcc9900, 0xff999900, 0xff669900, 0xff339900, 0xff009900, 0xffff6600,
+    0xffcc6600, 0xff996600, 0xff666600, 0xff336600, 0xff006600, 0xffff3300, 0xffcc3300, 0xff993300,
+    0xff663300, 0xff333300, 0xff003300, 0xffff0000, 0xffcc0000, 0xff990000, 0xff660000, 0xff330000,
+    0xff0000ee, 0xff0000dd, 0xff0000bb, 0xff0000aa, 0xff000088, 0xff000077, 0xff000055, 0xff000044,
+    0xff000022, 0xff000011, 0xff00ee00, 0xff00dd00, 0xff00bb00, 0xff00aa00, 0xff008800, 0xff007700,
+    0xff005500, 0xff004400, 0xff002200, 0xff001100, 0xffee0000, 0xffdd0000, 0xffbb0000, 0xffaa0000,
+    0xff880000, 0xff770000, 0xff550000, 0xff440000, 0xff220000, 0xff110000, 0xffeeeeee, 0xffdddddd,
+    0xffbbbbbb, 0xffaaaaaa, 0xff888888, 0xff777777, 0xff555555, 0xff444444, 0xff222222, 0xff111111,
+];
+
+// Un modelo de MagicaVoxel (.vox): los vóxeles de todas sus figuras ya ubicados según el
+// grafo de la escena, la paleta y los materiales de cada color
+pub struct VoxModel {
+    // Posición en el mundo, con y hacia arriba, e índice de color de 1 a 255
+    pub voxels: Vec<(BlockPos, u8)>,
+    palette: [Color; 256],
+    materials: HashMap<u8, VoxMaterial>,
+}
+
+// Material de un color de la paleta (chunk MATL); los valores faltan en los archivos viejos
+#[derive(Debug, Clone, Default)]
+struct VoxMaterial {
+    kind: String,
+    roughness: Option<f32>,
+    metal: Option<f32>,
+    ior: Option<f32>,
+    transparency: Option<f32>,
+    emission: Option<f32>,
+}
+
+// Figura del archivo: tamaño y vóxeles en sus coordenadas locales (z hacia arriba)
+struct Shape {
+    size: [i32; 3],
+    voxels: Vec<([i32; 3], u8)>,
+}
+
+// Nodos del grafo de la escena de MagicaVoxel 0.99 en adelante
+enum Node {
+    Transform { child: i32, transform: Transform },
+    Group(Vec<i32>),
+    Shape(Vec<usize>),
+}
+
+// Rotación entera (una permutación de ejes con signos) y traslación
+#[derive(Debug, Clone, Copy)]
+struct Transform {
+    rotation: [[i32; 3]; 3],
+    translation: [i32; 3],
+}
+
+impl Transform {
+    const IDENTITY: Transform = Transform {
+        rotation: [[1, 0, 0], [0, 1, 0], [0, 0, 1]],
+        translation: [0, 0, 0],
+    };
+
+    // Las traslaciones vienen del archivo y pueden sacar el punto del rango de i32: en ese
+    // caso None
+    fn rotate(&self, v: [i32; 3]) -> Option<[i32; 3]> {
+        let row = |r: [i32; 3]| {
+            r[0].checked_mul(v[0])?
+                .checked_add(r[1].checked_mul(v[1])?)?
+                .checked_add(r[2].checked_mul(v[2])?)
+        };
+        Some([
+            row(self.rotation[0])?,
+            row(self.rotation[1])?,
+            row(self.rotation[2])?,
+        ])
+    }
+
+    fn apply(&self, v: [i32; 3]) -> Option<[i32; 3]> {
+        let rotated = self.rotate(v)?;
+        Some([
+            rotated[0].checked_add(self.translation[0])?,
+            rotated[1].checked_add(self.translation[1])?,
+            rotated[2].checked_add(self.translation[2])?,
+        ])
+    }
+
+    // `self` aplicada después de `inner`
+    fn then(&self, inner: &Transform) -> Option<Transform> {
+        let mut rotation = [[0; 3]; 3];
+        for (row, out) in rotation.iter_mut().enumerate() {
+            for (column, value) in out.iter_mut().enumerate() {
+                *value = (0..3)
+                    .map(|k| self.rotation[row][k] * inner.rotation[k][column])
+                    .sum();
+            }
+        }
+        Some(Transform {
+            rotation,
+            translation: self.apply(inner.translation)?,
+        })
+    }
+}
+
+impl VoxModel {
+    pub fn load(path: &Path) -> Result<VoxModel, String> {
+        let bytes = fs::read(path).map_err(|err| format!("no se pudo leer el archivo: {}", err))?;
+        VoxModel::parse(&bytes)
+    }
+
+    pub fn parse(bytes: &[u8]) -> Result<VoxModel, String> {
+        let mut reader = Reader::new(bytes);
+        if reader.bytes(4)? != b"VOX " {
+            return Err("no es un archivo .vox de MagicaVoxel".to_string());
+        }
+        reader.i32()?;
+
+        // MAIN no tiene contenido propio: todo lo demás son sus hijos, uno detrás del otro
+        let (id, content, _) = reader.chunk()?;
+        if id != b"MAIN" {
+            return Err("falta el chunk MAIN".to_string());
+        }
+        reader.bytes(content)?;
+
+        let mut shapes = Vec::new();
+        let mut size = None;
+        let mut palette = None;
+        let mut materials = HashMap::new();
+        let mut nodes = HashMap::new();
+        while !reader.is_empty() {
+            let (id, content, children) = reader.chunk()?;
+            let mut chunk = Reader::new(reader.bytes(content)?);
+            reader.bytes(children)?;
+            match id {
+                b"SIZE" => size = Some([chunk.i32()?, chunk.i32()?, chunk.i32()?]),
+                b"XYZI" => {
+                    let size = size.take().ok_or_else(|| "XYZI sin su SIZE".to_string())?;
+                    let count = chunk.u32()? as usize;
+                    let mut voxels = Vec::with_capacity(count.min(content / 4));
+                    for _ in 0..count {
+                        let voxel = chunk.bytes(4)?;
+                        let position = [voxel[0] as i32, voxel[1] as i32, voxel[2] as i32];
+                        voxels.push((position, voxel[3]));
+                    }
+                    shapes.push(Shape { size, voxels });
+                }
+                // El color i del chunk es el índice i + 1 de los vóxeles
+                b"RGBA" => {
+                    let mut colors = [Color::black(); 256];
+                    for color in colors.iter_mut().skip(1) {
+                        let rgba = chunk.bytes(4)?;
+                        *color = Color::from_u8(rgba[0], rgba[1], rgba[2]);
+                    }
+                    palette = Some(colors);
+                }
+                b"MATL" => {
+                    let index = chunk.i32()?;
+                    let properties = chunk.dict()?;
+                    let number = |key: &str| properties.get(key).and_then(|v| v.parse().ok());
+                    let material = VoxMaterial {
+                        kind: properties.get("_type").cloned().unwrap_or_default(),
+                        roughness: number("_rough"),
+                        metal: number("_metal"),
+                        // Los archivos nuevos guardan `_ior` como el índice menos 1
+                        ior: number("_ri").or_else(|| number("_ior").map(|ior: f32| ior + 1.0)),
+                        transparency: number("_trans").or_else(|| number("_alpha")),
+                        emission: number("_emit"),
+                    };
+                    if let Ok(index) = u8::try_from(index) {
+                        materials.insert(index, material);
+                    }
+                }
+                b"nTRN" => {
+                    let id = chunk.i32()?;
+                    chunk.dict()?;
+                    let child = chunk.i32()?;
+                    chunk.i32()?;
+                    chunk.i32()?;
+                    let frames = chunk.i32()?;
+                    let mut transform = Transform::IDENTITY;
+                    // Solo cuenta el primer cuadro de la animación
+                    if frames > 0 {
+                        let frame = chunk.dict()?;
+                        if let Some(rotation) = frame.get("_r") {
+                            transform.rotation = rotation_matrix(rotation)?;
+                        }
+                        if let Some(translation) = frame.get("_t") {
+                            transform.translation = translation_vector(translation)?;
+                        }
+                    }
+                    nodes.insert(id, Node::Transform { child, transform });
+                }
+                b"nGRP" => {
+                    let id = chunk.i32()?;
+                    chunk.dict()?;
+                    let count = chunk.i32()?;
+                    let children = (0..count).map(|_| chunk.i32()).collect::<Result<_, _>>()?;
+                    nodes.insert(id, Node::Group(children));
+                }
+                b"nSHP" => {
+                    let id = chunk.i32()?;
+                    chunk.dict()?;
+                    let count = chunk.i32()?;
+                    let mut models = Vec::new();
+                    for _ in 0..count {
+                        models.push(chunk.i32()? as usize);
+                        chunk.dict()?;
+                    }
+                    nodes.insert(id, Node::Shape(models));
+                }
+                // Capas, cámaras, objetos de render y demás no cambian los bloques
+                _ => {}
+            }
+        }
+        if shapes.is_empty() {
+            return Err("el archivo no tiene modelos".to_string());
+        }
+
+        // Sin grafo (archivos anteriores a 0.99) cada figura queda en sus coordenadas
+        let mut placed = Vec::new();
+        if nodes.is_empty() {
+            for shape in &shapes {
+                placed.extend(shape.voxels.iter().copied());
+            }
+        } else {
+            // Un grafo que reusa los mismos nodos en varios grupos multiplica los vóxeles en
+            // cada nivel, así que un archivo chico podría colgar la carga
+            let cost = placement_cost(&nodes, &shapes, 0, &mut HashMap::new(), 0)?;
+            if cost > MAX_PLACEMENTS {
+                return Err(format!(
+                    "la escena pasa de {} nodos y vóxeles colocados",
+                    MAX_PLACEMENTS
+                ));
+            }
+            place_node(&nodes, &shapes, 0, Transform::IDENTITY, &mut placed, 0)?;
+        }
+
+        // MagicaVoxel tiene z hacia arriba; acá es y, conservando la orientación
+        let voxels = placed
+            .into_iter()
+            .filter_map(|([x, y, z], color)| Some(((x, z, y.checked_neg()?), color)))
+            .collect();
+        Ok(VoxModel {
+            voxels,
+            palette: palette.unwrap_or_else(default_palette),
+            materials,
+        })
+    }
+
+    // Esquinas mínima y máxima (inclusiva) de los vóxeles
+    pub fn bounds(&self) -> Option<(BlockPos, BlockPos)> {
+        let mut positions = self.voxels.iter().map(|(position, _)| *position);
+        let first = positions.next()?;
+        Some(positions.fold((first, first), |(min, max), (x, y, z)| {
+            (
+                (min.0.min(x), min.1.min(y), min.2.min(z)),
+                (max.0.max(x), max.1.max(y), max.2.max(z)),
+            )
+        }))
+    }
+
+    // Material del color `index` de la paleta, según su tipo en MagicaVoxel: difuso, metal
+    // (PBR con su rugosidad), vidrio (transparente con su índice) o emisivo
+    pub fn material(&self, index: u8) -> Material {
+        let color = self.palette[index as usize];
+        let mut material = Material::new(
+            color,
+            DIFFUSE_SPECULAR,
+            DIFFUSE_ALBEDO,
+            1.0,
+            None,
+            None,
+            Color::black(),
+        );
+        let Some(vox) = self.materials.get(&index) else {
+            return material;
+        };
+        match vox.kind.as_str() {
+            "_metal" => {
+                material.set_metallic(vox.metal.unwrap_or(1.0).clamp(0.0, 1.0));
+                material.set_roughness(vox.roughness.unwrap_or(0.1).clamp(0.0, 1.0));
+            }
+            "_glass" => {
+                let transparency = vox.transparency.unwrap_or(0.5).clamp(0.0, 1.0);
+                let [diffuse, specular, reflective] = GLASS_ALBEDO;
+                material.specular = GLASS_SPECULAR;
+                material.albedo = [diffuse, specular, reflective, transparency];
+                material.refractive_index = vox.ior.unwrap_or(GLASS_IOR);
+                material.blur = vox.roughness.unwrap_or(0.0).clamp(0.0, 1.0);
+            }
+            "_emit" => material.emission = color * vox.emission.unwrap_or(1.0),
+            _ => {}
+        }
+        material
+    }
+
+    // Pone los vóxeles en el mundo con la esquina mínima del modelo en `origin`. Cada color
+    // usado es un material `<prefijo>_<índice>`; si la escena ya tiene uno con ese nombre se
+    // usa ese. Los que quedarían más allá de MAX_COORDINATE se saltean. Devuelve cuántos
+    // bloques puso.
+    pub fn place(&self, world: &mut World, origin: BlockPos, prefix: &str) -> usize {
+        let Some((min, _)) = self.bounds() else {
+            return 0;
+        };
+        let offset =
+            |origin: i32, value: i32, min: i32| origin.checked_add(value.checked_sub(min)?);
+        let mut blocks: HashMap<u8, BlockId> = HashMap::new();
+        let mut placed = 0;
+        for &((x, y, z), index) in &self.voxels {
+            let (Some(x), Some(y), Some(z)) = (
+                offset(origin.0, x, min.0),
+                offset(origin.1, y, min.1),
+                offset(origin.2, z, min.2),
+            ) else {
+                continue;
+            };
+            if !within_limits((x, y, z)) {
+                continue;
+            }
+            let block = *blocks.entry(index).or_insert_with(|| {
+                let name = format!("{}_{}", prefix, index);
+                world
+                    .find_material(&name)
+                    .unwrap_or_else(|| world.add_material(&name, self.material(index)))
+            });
+            world.set_block((x, y, z), block);
+            placed += 1;
+        }
+        placed
+    }
+}
+
+// Nodos más vóxeles que recorre `place_node` desde `id`. Cada nodo se calcula una vez
+// aunque lo usen varios grupos.
+fn placement_cost(
+    nodes: &HashMap<i32, Node>,
+    shapes: &[Shape],
+    id: i32,
+    costs: &mut HashMap<i32, usize>,
+    depth: usize,
+) -> Result<usize, String> {
+    if let Some(&cost) = costs.get(&id) {
+        return Ok(cost);
+    }
+    if depth > nodes.len() {
+        return Err("el grafo de la escena tiene un ciclo".to_string());
+    }
+    let children: &[i32] = match nodes.get(&id) {
+        Some(Node::Transform { child, .. }) => std::slice::from_ref(child),
+        Some(Node::Group(children)) => children,
+        _ => &[],
+    };
+    let mut cost: usize = 1;
+    for &child in children {
+        let child = placement_cost(nodes, shapes, child, costs, depth + 1)?;
+        cost = cost.saturating_add(child);
+    }
+    if let Some(Node::Shape(models)) = nodes.get(&id) {
+        for model in models {
+            let voxels = shapes.get(*model).map_or(0, |shape| shape.voxels.len());
+            cost = cost.saturating_add(voxels);
+        }
+    }
+    costs.insert(id, cost);
+    Ok(cost)
+}
+
+// Recorre el grafo desde `id` acumulando las transformaciones hasta las figuras
+fn place_node(
+    nodes: &HashMap<i32, Node>,
+    shapes: &[Shape],
+    id: i32,
+    transform: Transform,
+    placed: &mut Vec<([i32; 3], u8)>,
+    depth: usize,
+) -> Result<(), String> {
+    // Un grafo con ciclos no terminaría nunca
+    if depth > nodes.len() {
+        return Err("el grafo de la escena tiene un ciclo".to_string());
+    }
+    match nodes.get(&id) {
+        Some(Node::Transform {
+            child,
+            transform: local,
+        }) => match transform.then(local) {
+            Some(transform) => place_node(nodes, shapes, *child, transform, placed, depth + 1),
+            // Una traslación fuera de rango saca de la escena todo lo que cuelga del nodo
+            None => Ok(()),
+        },
+        Some(Node::Group(children)) => {
+            for &child in children {
+                place_node(nodes, shapes, child, transform, placed, depth + 1)?;
+            }
+            Ok(())
+        }
+        Some(Node::Shape(models)) => {
+            for &model in models {
+                let shape = shapes
+                    .get(model)
+                    .ok_or_else(|| format!("la figura usa el modelo {} que no existe", model))?;
+                // El centro de la figura (redondeado hacia abajo) queda en la traslación
+                let pivot = shape.size.map(|size| size / 2);
+                placed.extend(shape.voxels.iter().filter_map(|&(position, color)| {
+                    let local = [
+                        position[0] - pivot[0],
+                        position[1] - pivot[1],
+                        position[2] - pivot[2],
+                    ];
+                    Some((transform.apply(local)?, color))
+                }));
+            }
+            Ok(())
+        }
+        None => Err(format!("el grafo de la escena no tiene el nodo {}", id)),
+    }
+}
+
+// Rotación guardada en un byte: los bits 0-1 y 2-3 dicen en qué columna está el valor no
+// nulo de la primera y la segunda fila (la tercera usa la que falta) y los bits 4, 5 y 6
+// si el de cada fila es negativo
+fn rotation_matrix(text: &str) -> Result<[[i32; 3]; 3], String> {
+    let bits: u8 = text
+        .trim()
+        .parse()
+        .map_err(|_| format!("rotación inválida '{}'", text))?;
+    let first = (bits & 3) as usize;
+    let second = ((bits >> 2) & 3) as usize;
+    if first > 2 || second > 2 || first == second {
+        return Err(format!("rotación inválida '{}'", text));
+    }
+    let columns = [first, second, 3 - first - second];
+    let mut rotation = [[0; 3]; 3];
+    for (row, column) in columns.into_iter().enumerate() {
+        rotation[row][column] = if bits & (1 << (4 + row)) != 0 { -1 } else { 1 };
+    }
+    Ok(rotation)
+}
+
+fn translation_vector(text: &str) -> Result<[i32; 3], String> {
+    let values: Vec<i32> = text
+        .split_whitespace()
+        .map(|value| value.parse())
+        .collect::<Result<_, _>>()
+        .map_err(|_| format!("traslación inválida '{}'", text))?;
+    match values.as_slice() {
+        &[x, y, z] => Ok([x, y, z]),
+        _ => Err(format!("traslación inválida '{}'", text)),
+    }
+}
+
+fn default_palette() -> [Color; 256] {
+    DEFAULT_PALETTE.map(|abgr| {
+        let [r, g, b, _] = abgr.to_le_bytes();
+        Color::from_u8(r, g, b)
+    })
+}
+
+// Lectura de los enteros, textos y diccionarios del formato, en little endian
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Reader { bytes, position: 0 }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.position >= self.bytes.len()
+    }
+
+    fn bytes(&mut self, count: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .position
+            .checked_add(count)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or_else(|| "el archivo .vox termina antes de tiempo".to_string())?;
+        let bytes = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let bytes = self.bytes(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn i32(&mut self) -> Result<i32, String> {
+        Ok(self.u32()? as i32)
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let length = self.u32()? as usize;
+        Ok(String::from_utf8_lossy(self.bytes(length)?).into_owned())
+    }
+
+    fn dict(&mut self) -> Result<HashMap<String, String>, String> {
+        let count = self.u32()?;
+        let mut dict = HashMap::new();
+        for _ in 0..count {
+            let key = self.string()?;
+            dict.insert(key, self.string()?);
+        }
+        Ok(dict)
+    }
+
+    // Encabezado de un chunk: su id y los bytes de su contenido y de sus hijos
+    fn chunk(&mut self) -> Result<(&'a [u8], usize, usize), String> {
+        let id = self.bytes(4)?;
+        let content = self.u32()? as usize;
+        let children = self.u32()? as usize;
+        Ok((id, content, children))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::AIR;
+    use crate::world::MAX_COORDINATE;
+
+    fn chunk(id: &[u8; 4], content: &[u8]) -> Vec<u8> {
+        let mut bytes = id.to_vec();
+        bytes.extend((content.len() as u32).to_le_bytes());
+        bytes.extend(0u32.to_le_bytes());
+        bytes.extend(content);
+        bytes
+    }
+
+    fn file(children: &[Vec<u8>]) -> Vec<u8> {
+        let children = children.concat();
+        let mut bytes = b"VOX ".to_vec();
+        bytes.extend(150u32.to_le_bytes());
+        bytes.extend(b"MAIN");
+        bytes.extend(0u32.to_le_bytes());
+        bytes.extend((children.len() as u32).to_le_bytes());
+        bytes.extend(children);
+        bytes
+    }
+
+    fn ints(values: &[i32]) -> Vec<u8> {
+        values
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect()
+    }
+
+    fn dict(entries: &[(&str, &str)]) -> Vec<u8> {
+        let mut bytes = (entries.len() as u32).to_le_bytes().to_vec();
+        for text in entries.iter().flat_map(|(key, value)| [key, value]) {
+            bytes.extend((text.len() as u32).to_le_bytes());
+            bytes.extend(text.as_bytes());
+        }
+        bytes
+    }
+
+    // Una figura de 2x1x1 con un vóxel del color 1 y otro del 36
+    fn shape() -> [Vec<u8>; 2] {
+        let mut voxels = ints(&[2]);
+        voxels.extend([0, 0, 0, 1, 1, 0, 0, 36]);
+        [chunk(b"SIZE", &ints(&[2, 1, 1])), chunk(b"XYZI", &voxels)]
+    }
+
+    // La figura dentro de un grafo que la rota y la traslada según `frame`
+    fn transformed(frame: &[(&str, &str)]) -> Vec<u8> {
+        let mut transform = ints(&[0]);
+        transform.extend(dict(&[]));
+        transform.extend(ints(&[1, -1, 0, 1]));
+        transform.extend(dict(frame));
+        let mut shape_node = ints(&[1]);
+        shape_node.extend(dict(&[]));
+        shape_node.extend(ints(&[1, 0]));
+        shape_node.extend(dict(&[]));
+
+        let [size, voxels] = shape();
+        file(&[
+            size,
+            voxels,
+            chunk(b"nTRN", &transform),
+            chunk(b"nSHP", &shape_node),
+        ])
+    }
+
+    fn rgb(color: Color) -> [f32; 3] {
+        [color.r, color.g, color.b]
+    }
+
+    #[test]
+    fn reads_voxels_with_the_default_palette() {
+        let model = VoxModel::parse(&file(&shape())).unwrap();
+        assert_eq!(model.voxels, vec![((0, 0, 0), 1), ((1, 0, 0), 36)]);
+        assert_eq!(rgb(model.material(1).diffuse), [1.0, 1.0, 1.0]);
+        assert_eq!(rgb(model.material(36).diffuse), [1.0, 0.0, 0.0]);
+        assert_eq!(
+            rgb(model.material(255).diffuse),
+            rgb(Color::from_u8(17, 17, 17))
+        );
+    }
+
+    #[test]
+    fn rejects_truncated_files() {
+        let bytes = file(&shape());
+        for length in 0..bytes.len() {
+            assert!(
+                VoxModel::parse(&bytes[..length]).is_err(),
+                "{} bytes",
+                length
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_chunks_longer_than_the_file() {
+        let mut bytes = file(&shape());
+        // Tamaño del contenido del SIZE
+        bytes[24..28].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(VoxModel::parse(&bytes).is_err());
+        assert!(VoxModel::parse(b"PNG!\x96\0\0\0").is_err());
+    }
+
+    #[test]
+    fn skips_voxels_translated_out_of_range() {
+        // Con el eje x invertido el primer vóxel queda en x = 1 antes de trasladarlo
+        let frame = [("_r", "20"), ("_t", "2147483647 0 0")];
+        let model = VoxModel::parse(&transformed(&frame)).unwrap();
+        assert_eq!(model.voxels, vec![((i32::MAX, 0, 0), 36)]);
+
+        // La y de MagicaVoxel se invierte al pasarla a z
+        let frame = [("_t", "0 -2147483648 0")];
+        let model = VoxModel::parse(&transformed(&frame)).unwrap();
+        assert!(model.voxels.is_empty());
+    }
+
+    #[test]
+    fn skips_voxels_placed_out_of_range() {
+        let model = VoxModel::parse(&file(&shape())).unwrap();
+        let mut world = World::new();
+        assert_eq!(model.place(&mut world, (i32::MAX, 0, 0), "vox"), 0);
+        assert_eq!(world.chunk_count(), 0);
+
+        // El primero queda justo en el límite y el segundo pasa de él
+        assert_eq!(model.place(&mut world, (MAX_COORDINATE, 0, 0), "vox"), 1);
+        assert_ne!(world.get_block((MAX_COORDINATE, 0, 0)), AIR);
+        assert_eq!(world.get_block((MAX_COORDINATE + 1, 0, 0)), AIR);
+    }
+
+    #[test]
+    fn rejects_graphs_that_multiply_the_voxels() {
+        // Cada grupo usa dos veces al siguiente: 2^40 copias de la figura
+        let [size, voxels] = shape();
+        let mut chunks = vec![size, voxels];
+        let levels = 40;
+        for id in 0..levels {
+            let mut group = ints(&[id]);
+            group.extend(dict(&[]));
+            group.extend(ints(&[2, id + 1, id + 1]));
+            chunks.push(chunk(b"nGRP", &group));
+        }
+        let mut shape_node = ints(&[levels]);
+        shape_node.extend(dict(&[]));
+        shape_node.extend(ints(&[1, 0]));
+        shape_node.extend(dict(&[]));
+        chunks.push(chunk(b"nSHP", &shape_node));
+        assert!(VoxModel::parse(&file(&chunks)).is_err());
+    }
+}
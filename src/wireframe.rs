@@ -0,0 +1,218 @@
+// wireframe.rs
+
+use nalgebra_glm::Vec3;
+
+use crate::chunk::{AIR, CHUNK_SIZE};
+use crate::color::Color;
+use crate::cube::Cube;
+use crate::framebuffer::Framebuffer;
+use crate::mesh::greedy_mesh;
+use crate::scene::Scene;
+
+const BLOCK_COLOR: Color = Color::new(0.85, 0.85, 0.85);
+const OBJECT_COLOR: Color = Color::new(1.0, 0.85, 0.2);
+const CHUNK_COLOR: Color = Color::new(0.2, 0.9, 1.0);
+const FACE_COLOR: Color = Color::new(1.0, 0.45, 0.8);
+
+// Líneas que se dibujan sobre la imagen terminada para revisar cómo está armada la escena:
+// las aristas de los bloques y cubos libres, las caras visibles unidas por `greedy_mesh`,
+// los límites de los chunks que recorre el trazador (su estructura de aceleración) o los
+// bloques y los chunks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Wireframe {
+    Off,
+    Blocks,
+    Faces,
+    Chunks,
+    All,
+}
+
+impl Wireframe {
+    pub const ALL: [Wireframe; 5] = [
+        Wireframe::Off,
+        Wireframe::Blocks,
+        Wireframe::Faces,
+        Wireframe::Chunks,
+        Wireframe::All,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Wireframe::Off => "sin líneas",
+            Wireframe::Blocks => "bloques",
+            Wireframe::Faces => "caras unidas",
+            Wireframe::Chunks => "chunks",
+            Wireframe::All => "bloques y chunks",
+        }
+    }
+
+    // El siguiente en el orden de `ALL`, volviendo al primero después del último
+    pub fn next(&self) -> Self {
+        let index = Wireframe::ALL
+            .iter()
+            .position(|mode| mode == self)
+            .unwrap_or(0);
+        Wireframe::ALL[(index + 1) % Wireframe::ALL.len()]
+    }
+
+    fn shows_blocks(&self) -> bool {
+        matches!(self, Wireframe::Blocks | Wireframe::All)
+    }
+
+    fn shows_chunks(&self) -> bool {
+        matches!(self, Wireframe::Chunks | Wireframe::All)
+    }
+}
+
+// Dibuja las líneas de `mode` sobre el framebuffer. De los bloques del mundo solo se
+// dibujan los que tocan aire, los demás no se ven y llenarían la imagen de líneas.
+pub fn draw_wireframe(
+    framebuffer: &mut Framebuffer,
+    scene: &Scene,
+    objects: &[Cube],
+    mode: Wireframe,
+) {
+    if mode.shows_blocks() {
+        framebuffer.set_current_color(BLOCK_COLOR);
+        let world = &scene.world;
+        for ((x, y, z), _) in world.blocks() {
+            let exposed = [
+                (1, 0, 0),
+                (-1, 0, 0),
+                (0, 1, 0),
+                (0, -1, 0),
+                (0, 0, 1),
+                (0, 0, -1),
+            ]
+            .iter()
+            .any(|&(dx, dy, dz)| world.get_block((x + dx, y + dy, z + dz)) == AIR);
+            if exposed {
+                let min = Vec3::new(x as f32, y as f32, z as f32);
+                draw_box(framebuffer, scene, &min, &(min + Vec3::new(1.0, 1.0, 1.0)));
+            }
+        }
+
+        framebuffer.set_current_color(OBJECT_COLOR);
+        for cube in objects {
+            draw_box(framebuffer, scene, &cube.min_corner, &cube.max_corner);
+        }
+    }
+
+    if mode == Wireframe::Faces {
+        framebuffer.set_current_color(FACE_COLOR);
+        for quad in greedy_mesh(&scene.world) {
+            let corners = quad.corners();
+            for (index, corner) in corners.iter().enumerate() {
+                draw_edge(framebuffer, scene, corner, &corners[(index + 1) % 4]);
+            }
+        }
+    }
+
+    if mode.shows_chunks() {
+        framebuffer.set_current_color(CHUNK_COLOR);
+        let size = CHUNK_SIZE as f32;
+        for (x, y, z) in scene.world.chunk_positions() {
+            let min = Vec3::new(x as f32, y as f32, z as f32) * size;
+            draw_box(
+                framebuffer,
+                scene,
+                &min,
+                &(min + Vec3::new(size, size, size)),
+            );
+        }
+    }
+}
+
+// Las 12 aristas de una caja alineada a los ejes
+fn draw_box(framebuffer: &mut Framebuffer, scene: &Scene, min: &Vec3, max: &Vec3) {
+    let corner = |i: usize| {
+        Vec3::new(
+            if i & 1 == 0 { min.x } else { max.x },
+            if i & 2 == 0 { min.y } else { max.y },
+            if i & 4 == 0 { min.z } else { max.z },
+        )
+    };
+    for i in 0..8 {
+        for bit in [1, 2, 4] {
+            if i & bit == 0 {
+                draw_edge(framebuffer, scene, &corner(i), &corner(i | bit));
+            }
+        }
+    }
+}
+
+// Un segmento del mundo; la parte detrás de la cámara y la que cae fuera de la imagen
+// se recortan antes de dibujar
+fn draw_edge(framebuffer: &mut Framebuffer, scene: &Scene, start: &Vec3, end: &Vec3) {
+    let camera = &scene.camera;
+    let near = camera.near.max(1e-3) + 1e-3;
+    let (start_depth, end_depth) = (camera.depth(start), camera.depth(end));
+    if start_depth < near && end_depth < near {
+        return;
+    }
+    let cut = |from: &Vec3, to: &Vec3, from_depth: f32, to_depth: f32| {
+        from + (to - from) * ((near - from_depth) / (to_depth - from_depth))
+    };
+    let (start, end) = if start_depth < near {
+        (cut(start, end, start_depth, end_depth), *end)
+    } else if end_depth < near {
+        (*start, cut(end, start, end_depth, start_depth))
+    } else {
+        (*start, *end)
+    };
+
+    let (width, height) = (framebuffer.width as f32, framebuffer.height as f32);
+    let (Some(a), Some(b)) = (
+        camera.project(&start, width, height),
+        camera.project(&end, width, height),
+    ) else {
+        return;
+    };
+    if let Some((a, b)) = clip_to_screen(a, b, width, height) {
+        framebuffer.line(
+            a.0.round() as i32,
+            a.1.round() as i32,
+            b.0.round() as i32,
+            b.1.round() as i32,
+        );
+    }
+}
+
+// Recorta un segmento en pixeles al rectángulo de la imagen (Liang-Barsky); None si queda
+// completamente afuera
+fn clip_to_screen(
+    a: (f32, f32),
+    b: (f32, f32),
+    width: f32,
+    height: f32,
+) -> Option<((f32, f32), (f32, f32))> {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let (mut t0, mut t1) = (0.0_f32, 1.0_f32);
+    let bounds = [
+        (-dx, a.0),
+        (dx, width - 1.0 - a.0),
+        (-dy, a.1),
+        (dy, height - 1.0 - a.1),
+    ];
+    for (p, q) in bounds {
+        if p == 0.0 {
+            if q < 0.0 {
+                return None;
+            }
+            continue;
+        }
+        let t = q / p;
+        if p < 0.0 {
+            t0 = t0.max(t);
+        } else {
+            t1 = t1.min(t);
+        }
+        if t0 > t1 {
+            return None;
+        }
+    }
+    Some((
+        (a.0 + t0 * dx, a.1 + t0 * dy),
+        (a.0 + t1 * dx, a.1 + t1 * dy),
+    ))
+}
@@ -0,0 +1,658 @@
+// world.rs
+
+use nalgebra_glm::Vec3;
+use std::collections::HashMap;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, RwLock};
+
+use crate::chunk::{BlockId, Chunk, AIR, CHUNK_SIZE};
+use crate::cube::intersect_box_at_face;
+use crate::material::Material;
+use crate::mesh::{greedy_mesh, ray_cells, ChunkMesh, Quad};
+use crate::paged_world::PagedChunks;
+use crate::ray_intersect::{Intersect, RayIntersect};
+
+pub type BlockPos = (i32, i32, i32);
+
+// Coordenada más lejana (en valor absoluto) en la que los importadores ponen bloques: más
+// allá los f32 del trazador ya no distinguen un bloque de su vecino
+pub const MAX_COORDINATE: i32 = 1 << 24;
+// Chunks con sus quads armados; al llenarse se descartan todos y se vuelven a armar los que
+// se usen, así un mundo paginado enorme no los acumula
+const CACHED_MESHES: usize = 1 << 14;
+
+pub fn within_limits(pos: BlockPos) -> bool {
+    [pos.0, pos.1, pos.2]
+        .iter()
+        .all(|coordinate| coordinate.abs() <= MAX_COORDINATE)
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct BlockHit {
+    pub pos: BlockPos,
+    pub face: BlockPos,
+    pub distance: f32,
+}
+
+// Chunk en memoria o leído del archivo paginado
+pub enum ChunkRef<'a> {
+    Resident(&'a Chunk),
+    Paged(Arc<Chunk>),
+}
+
+impl Deref for ChunkRef<'_> {
+    type Target = Chunk;
+
+    fn deref(&self) -> &Chunk {
+        match self {
+            ChunkRef::Resident(chunk) => chunk,
+            ChunkRef::Paged(chunk) => chunk,
+        }
+    }
+}
+
+// Quads del último chunk que buscó un recorrido, para no volver a buscarlos en el HashMap
+type LastMesh = Option<(BlockPos, Option<Arc<ChunkMesh>>)>;
+
+// Material de la paleta prestado para modificarlo. Al soltarlo, si cambió qué caras tapa, se
+// descartan los quads ya armados.
+pub struct MaterialMut<'a> {
+    material: &'a mut Material,
+    meshes: &'a mut HashMap<BlockPos, Arc<ChunkMesh>>,
+    culling: (bool, bool),
+}
+
+impl Deref for MaterialMut<'_> {
+    type Target = Material;
+
+    fn deref(&self) -> &Material {
+        self.material
+    }
+}
+
+impl DerefMut for MaterialMut<'_> {
+    fn deref_mut(&mut self) -> &mut Material {
+        self.material
+    }
+}
+
+impl Drop for MaterialMut<'_> {
+    fn drop(&mut self) {
+        if culling(self.material) != self.culling {
+            self.meshes.clear();
+        }
+    }
+}
+
+// Lo que decide si un material tapa las caras de sus vecinos (ver `hides_face`)
+fn culling(material: &Material) -> (bool, bool) {
+    (material.is_opaque(), material.has_cut_outs())
+}
+
+pub struct World {
+    // Chunks en memoria. Si hay archivo paginado, tienen prioridad sobre los del archivo:
+    // un chunk editado se copia acá, y si queda vacío se conserva para tapar al del archivo.
+    chunks: HashMap<BlockPos, Chunk>,
+    pages: Option<PagedChunks>,
+    // La posición 0 corresponde al aire y nunca se usa para sombrear. Los cubos generados
+    // a partir del mundo comparten estos materiales.
+    palette: Vec<Arc<Material>>,
+    names: Vec<String>,
+    // Chunks mínimo y máximo (inclusivos) que alguna vez tuvieron bloques; acota el recorrido de rayos
+    bounds: Option<(BlockPos, BlockPos)>,
+    // Caras visibles de cada chunk que ya cruzó algún rayo, unidas en quads. Se arman al
+    // pedirlas y se descartan al editar el chunk o uno vecino.
+    meshes: RwLock<HashMap<BlockPos, Arc<ChunkMesh>>>,
+    // Todos los quads del mundo, probados uno por uno en lugar de recorrer los chunks: la
+    // referencia con la que parity compara el recorrido
+    exhaustive: Option<Vec<Quad>>,
+}
+
+impl World {
+    pub fn new() -> Self {
+        World {
+            chunks: HashMap::new(),
+            pages: None,
+            palette: vec![Arc::new(Material::black())],
+            names: vec!["aire".to_string()],
+            bounds: None,
+            meshes: RwLock::default(),
+            exhaustive: None,
+        }
+    }
+
+    pub fn add_material(&mut self, name: &str, material: Material) -> BlockId {
+        self.palette.push(Arc::new(material));
+        self.names.push(name.to_string());
+        (self.palette.len() - 1) as BlockId
+    }
+
+    pub fn material(&self, block: BlockId) -> &Arc<Material> {
+        &self.palette[block as usize]
+    }
+
+    // Material para modificarlo; si otro cubo comparte el mismo `Arc`, se copia primero y
+    // ese cubo conserva el anterior
+    pub fn material_mut(&mut self, block: BlockId) -> MaterialMut<'_> {
+        let material = Arc::make_mut(&mut self.palette[block as usize]);
+        MaterialMut {
+            culling: culling(material),
+            material,
+            meshes: self.meshes.get_mut().unwrap(),
+        }
+    }
+
+    pub fn material_name(&self, block: BlockId) -> &str {
+        &self.names[block as usize]
+    }
+
+    pub fn find_material(&self, name: &str) -> Option<BlockId> {
+        self.names
+            .iter()
+            .skip(1)
+            .position(|candidate| candidate == name)
+            .map(|index| (index + 1) as BlockId)
+    }
+
+    // Materiales de la paleta sin contar el aire, como (id, nombre, material)
+    pub fn materials(&self) -> impl Iterator<Item = (BlockId, &str, &Material)> + '_ {
+        self.palette
+            .iter()
+            .zip(&self.names)
+            .enumerate()
+            .skip(1)
+            .map(|(index, (material, name))| (index as BlockId, name.as_str(), &**material))
+    }
+
+    fn split(pos: BlockPos) -> (BlockPos, (usize, usize, usize)) {
+        let size = CHUNK_SIZE as i32;
+        let chunk = (
+            pos.0.div_euclid(size),
+            pos.1.div_euclid(size),
+            pos.2.div_euclid(size),
+        );
+        let local = (
+            pos.0.rem_euclid(size) as usize,
+            pos.1.rem_euclid(size) as usize,
+            pos.2.rem_euclid(size) as usize,
+        );
+        (chunk, local)
+    }
+
+    // Lee los bloques que no están en memoria de un archivo paginado
+    pub fn attach_pages(&mut self, pages: PagedChunks) {
+        if let Some((min, max)) = pages.bounds() {
+            self.include_chunk(min);
+            self.include_chunk(max);
+        }
+        self.pages = Some(pages);
+        self.meshes.get_mut().unwrap().clear();
+    }
+
+    pub fn is_paged(&self) -> bool {
+        self.pages.is_some()
+    }
+
+    fn is_paged_chunk(&self, pos: BlockPos) -> bool {
+        self.pages.as_ref().is_some_and(|pages| pages.contains(pos))
+    }
+
+    pub fn chunk(&self, pos: BlockPos) -> Option<ChunkRef<'_>> {
+        match self.chunks.get(&pos) {
+            Some(chunk) => Some(ChunkRef::Resident(chunk)),
+            None => self.pages.as_ref()?.chunk(pos).map(ChunkRef::Paged),
+        }
+    }
+
+    // Posiciones de los chunks en memoria y en el archivo paginado, incluidos los vacíos
+    pub fn chunk_positions(&self) -> impl Iterator<Item = BlockPos> + '_ {
+        let paged = self
+            .pages
+            .iter()
+            .flat_map(|pages| pages.positions())
+            .filter(|pos| !self.chunks.contains_key(pos));
+        self.chunks.keys().copied().chain(paged)
+    }
+
+    // Cantidad aproximada de chunks; cuenta dos veces los editados de un archivo paginado
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.len() + self.pages.as_ref().map_or(0, |pages| pages.len())
+    }
+
+    pub fn get_block(&self, pos: BlockPos) -> BlockId {
+        let (chunk, (x, y, z)) = Self::split(pos);
+        self.chunk(chunk).map_or(AIR, |chunk| chunk.get(x, y, z))
+    }
+
+    // Chunk en memoria para editarlo, copiándolo del archivo paginado si hace falta
+    fn resident_chunk(&mut self, pos: BlockPos) -> &mut Chunk {
+        let pages = &self.pages;
+        self.chunks.entry(pos).or_insert_with(|| {
+            pages
+                .as_ref()
+                .and_then(|pages| pages.chunk(pos))
+                .map_or_else(Chunk::new, |chunk| (*chunk).clone())
+        })
+    }
+
+    fn include_chunk(&mut self, chunk_pos: BlockPos) {
+        self.bounds = Some(match self.bounds {
+            Some((min, max)) => (
+                (
+                    min.0.min(chunk_pos.0),
+                    min.1.min(chunk_pos.1),
+                    min.2.min(chunk_pos.2),
+                ),
+                (
+                    max.0.max(chunk_pos.0),
+                    max.1.max(chunk_pos.1),
+                    max.2.max(chunk_pos.2),
+                ),
+            ),
+            None => (chunk_pos, chunk_pos),
+        });
+    }
+
+    pub fn set_block(&mut self, pos: BlockPos, block: BlockId) {
+        assert!(
+            (block as usize) < self.palette.len(),
+            "bloque {} fuera de la paleta",
+            block
+        );
+        let (chunk_pos, (x, y, z)) = Self::split(pos);
+
+        if block == AIR {
+            let paged = self.is_paged_chunk(chunk_pos);
+            if !paged && !self.chunks.contains_key(&chunk_pos) {
+                return;
+            }
+            let chunk = self.resident_chunk(chunk_pos);
+            chunk.set(x, y, z, AIR);
+            if chunk.is_empty() && !paged {
+                self.chunks.remove(&chunk_pos);
+            }
+        } else {
+            self.include_chunk(chunk_pos);
+            self.resident_chunk(chunk_pos).set(x, y, z, block);
+        }
+        self.forget_meshes(pos);
+    }
+
+    // Descarta los quads del chunk de `pos` y de los chunks de sus vecinos, cuyas caras
+    // contra el bloque pueden haber cambiado
+    fn forget_meshes(&mut self, pos: BlockPos) {
+        let meshes = self.meshes.get_mut().unwrap();
+        if meshes.is_empty() {
+            return;
+        }
+        let offsets = [
+            (0, 0, 0),
+            (-1, 0, 0),
+            (1, 0, 0),
+            (0, -1, 0),
+            (0, 1, 0),
+            (0, 0, -1),
+            (0, 0, 1),
+        ];
+        for (dx, dy, dz) in offsets {
+            let neighbor = (
+                pos.0.saturating_add(dx),
+                pos.1.saturating_add(dy),
+                pos.2.saturating_add(dz),
+            );
+            meshes.remove(&Self::split(neighbor).0);
+        }
+    }
+
+    // Quads del chunk, que se arman la primera vez que se piden; None si el chunk no existe
+    fn mesh(&self, pos: BlockPos) -> Option<Arc<ChunkMesh>> {
+        if let Some(mesh) = self.meshes.read().unwrap().get(&pos) {
+            return Some(Arc::clone(mesh));
+        }
+        if !self.chunks.contains_key(&pos) && !self.is_paged_chunk(pos) {
+            return None;
+        }
+        // Se arman sin bloquear a los otros hilos; si dos piden el mismo chunk a la vez los
+        // dos lo arman y queda el último
+        let mesh = Arc::new(ChunkMesh::build(self, pos));
+        let mut meshes = self.meshes.write().unwrap();
+        if meshes.len() >= CACHED_MESHES {
+            meshes.clear();
+        }
+        meshes.insert(pos, Arc::clone(&mesh));
+        Some(mesh)
+    }
+
+    // Con `true` los rayos prueban todos los quads del mundo uno por uno en lugar de
+    // recorrer los chunks, para comparar el recorrido con ese resultado
+    pub fn set_exhaustive(&mut self, exhaustive: bool) {
+        self.exhaustive = exhaustive.then(|| greedy_mesh(self));
+    }
+
+    pub fn fill(&mut self, min: BlockPos, max: BlockPos, block: BlockId) {
+        for x in min.0..max.0 {
+            for y in min.1..max.1 {
+                for z in min.2..max.2 {
+                    self.set_block((x, y, z), block);
+                }
+            }
+        }
+    }
+
+    // Recorre todos los bloques sólidos en coordenadas de mundo
+    pub fn blocks(&self) -> impl Iterator<Item = (BlockPos, BlockId)> + '_ {
+        let size = CHUNK_SIZE as i32;
+        self.chunks().flat_map(move |((cx, cy, cz), chunk)| {
+            // Un chunk paginado solo vive mientras se recorre, por eso se juntan sus bloques
+            chunk
+                .blocks()
+                .map(move |(x, y, z, block)| {
+                    (
+                        (
+                            cx * size + x as i32,
+                            cy * size + y as i32,
+                            cz * size + z as i32,
+                        ),
+                        block,
+                    )
+                })
+                .collect::<Vec<_>>()
+        })
+    }
+
+    // Chunks con algún bloque sólido, como (posición del chunk, chunk). Los del archivo
+    // paginado se leen de a uno al recorrerlos.
+    pub fn chunks(&self) -> impl Iterator<Item = (BlockPos, ChunkRef<'_>)> + '_ {
+        self.chunk_positions()
+            .filter_map(|pos| Some((pos, self.chunk(pos)?)))
+            .filter(|(_, chunk)| !chunk.is_empty())
+    }
+
+    // Recorrido de los chunks con DDA (Amanatides y Woo): el rayo avanza chunk por chunk
+    // dentro de los límites del mundo y en cada uno prueba solo los quads de los planos que
+    // cruza. Las caras tapadas no forman quads, así que un rayo que sale desde adentro de la
+    // geometría no choca con ellas ni se hace sombra. `cached` guarda los quads del último
+    // chunk y puede venir del recorrido de otro rayo.
+    fn traverse(
+        &self,
+        ray_origin: &Vec3,
+        ray_direction: &Vec3,
+        cached: &mut LastMesh,
+    ) -> Option<(BlockPos, Intersect<'_>)> {
+        if let Some(quads) = &self.exhaustive {
+            let nearest = |after| Self::nearest_quad(quads, ray_origin, ray_direction, after);
+            return self.first_hit(nearest, ray_origin, ray_direction);
+        }
+        let (min_chunk, max_chunk) = self.bounds?;
+        // En i64: el borde de un chunk junto a i32::MAX no entra en un i32
+        let size = CHUNK_SIZE as i64;
+        let lower = [min_chunk.0 as i64, min_chunk.1 as i64, min_chunk.2 as i64];
+        let upper = [
+            max_chunk.0 as i64 + 1,
+            max_chunk.1 as i64 + 1,
+            max_chunk.2 as i64 + 1,
+        ];
+
+        // Recorta el rayo contra la caja que contiene todos los chunks
+        let mut t_enter = 0.0f32;
+        let mut t_exit = f32::INFINITY;
+        for axis in 0..3 {
+            let origin = ray_origin[axis];
+            let direction = ray_direction[axis];
+            let (low, high) = ((lower[axis] * size) as f32, (upper[axis] * size) as f32);
+            if direction == 0.0 {
+                if origin < low || origin > high {
+                    return None;
+                }
+                continue;
+            }
+            let t0 = (low - origin) / direction;
+            let t1 = (high - origin) / direction;
+            t_enter = t_enter.max(t0.min(t1));
+            t_exit = t_exit.min(t0.max(t1));
+        }
+        if t_enter > t_exit {
+            return None;
+        }
+
+        let start = ray_origin + ray_direction * t_enter;
+        let mut chunk = [0i64; 3];
+        let mut step = [0i64; 3];
+        let mut t_max = [f32::INFINITY; 3];
+        let mut t_delta = [f32::INFINITY; 3];
+        for axis in 0..3 {
+            chunk[axis] =
+                ((start[axis] / size as f32).floor() as i64).clamp(lower[axis], upper[axis] - 1);
+            let direction = ray_direction[axis];
+            if direction > 0.0 {
+                step[axis] = 1;
+                t_max[axis] = ((chunk[axis] + 1) * size) as f32 - ray_origin[axis];
+                t_max[axis] /= direction;
+                t_delta[axis] = size as f32 / direction;
+            } else if direction < 0.0 {
+                step[axis] = -1;
+                t_max[axis] = ((chunk[axis] * size) as f32 - ray_origin[axis]) / direction;
+                t_delta[axis] = -(size as f32) / direction;
+            }
+        }
+
+        let mut t_in = t_enter;
+        loop {
+            // Dentro de los límites de los chunks cada coordenada entra en un i32
+            let pos = (chunk[0] as i32, chunk[1] as i32, chunk[2] as i32);
+            // Evita buscar los quads en el HashMap en cada paso
+            if cached.as_ref().is_none_or(|(cached, _)| *cached != pos) {
+                *cached = Some((pos, self.mesh(pos)));
+            }
+
+            let axis = if t_max[0] < t_max[1] {
+                if t_max[0] < t_max[2] {
+                    0
+                } else {
+                    2
+                }
+            } else if t_max[1] < t_max[2] {
+                1
+            } else {
+                2
+            };
+            let t_out = t_max[axis].min(t_exit);
+            let mesh = cached.as_ref().and_then(|(_, mesh)| mesh.as_ref());
+            if let Some(mesh) = mesh.filter(|mesh| !mesh.is_empty()) {
+                let hit = self.chunk_hit(mesh, pos, ray_origin, ray_direction, (t_in, t_out));
+                if hit.is_some() {
+                    return hit;
+                }
+            }
+
+            if step[axis] == 0 || t_max[axis] > t_exit {
+                return None;
+            }
+            chunk[axis] += step[axis];
+            if chunk[axis] < lower[axis] || chunk[axis] >= upper[axis] {
+                return None;
+            }
+            t_in = t_max[axis];
+            t_max[axis] += t_delta[axis];
+        }
+    }
+
+    // Primer bloque que ve el rayo entre los quads del chunk `pos`, mientras está en el chunk
+    // entre las distancias `t_in` y `t_out`. Los planos que cruza se prueban en el orden en
+    // que los cruza, juntando los de los tres ejes, y el primer quad de frente que cubre el
+    // punto del plano es el más cercano.
+    fn chunk_hit(
+        &self,
+        mesh: &ChunkMesh,
+        pos: BlockPos,
+        ray_origin: &Vec3,
+        ray_direction: &Vec3,
+        (t_in, t_out): (f32, f32),
+    ) -> Option<(BlockPos, Intersect<'_>)> {
+        let size = CHUNK_SIZE as i32;
+        let origin = [pos.0 * size, pos.1 * size, pos.2 * size];
+        let plane_distance = |axis: usize, layer: i32| {
+            ((origin[axis] + layer) as f32 - ray_origin[axis]) / ray_direction[axis]
+        };
+        // Las caras que el rayo ve de frente miran hacia el lado de donde viene
+        let side = |axis: usize| if ray_direction[axis] > 0.0 { -1 } else { 1 };
+        let next_layer = |axis: usize, layers: u32| {
+            if ray_direction[axis] > 0.0 {
+                layers.trailing_zeros() as i32
+            } else {
+                31 - layers.leading_zeros() as i32
+            }
+        };
+        // Para cada eje, los planos con quads de frente al rayo que quedan por probar, como
+        // bits, y la distancia al siguiente (infinita si no quedan)
+        let mut remaining = [0u32; 3];
+        let mut distances = [f32::INFINITY; 3];
+        for axis in 0..3 {
+            let direction = ray_direction[axis];
+            if direction == 0.0 {
+                continue;
+            }
+            let enter = ray_origin[axis] + direction * t_in - origin[axis] as f32;
+            let exit = ray_origin[axis] + direction * t_out - origin[axis] as f32;
+            // Recortados al chunk quedan entre 0 y CHUNK_SIZE, donde truncar es redondear hacia
+            // abajo; sumar uno al último cubre el redondeo hacia arriba
+            let first = enter.min(exit).clamp(0.0, size as f32) as u32;
+            let last = (enter.max(exit).clamp(0.0, size as f32) as u32 + 1).min(size as u32);
+            let crossed = (u32::MAX >> (31 - last)) & (u32::MAX << first);
+            remaining[axis] = mesh.layers(axis, side(axis)) & crossed;
+            if remaining[axis] != 0 {
+                distances[axis] = plane_distance(axis, next_layer(axis, remaining[axis]));
+            }
+        }
+
+        let nearest = |after: f32| {
+            let (mut remaining, mut distances) = (remaining, distances);
+            loop {
+                // Con la misma distancia va primero el eje más alto, como al probarlos todos
+                let axis = if distances[2] <= distances[1] {
+                    if distances[2] <= distances[0] {
+                        2
+                    } else {
+                        0
+                    }
+                } else if distances[1] <= distances[0] {
+                    1
+                } else {
+                    0
+                };
+                let distance = distances[axis];
+                if distance == f32::INFINITY {
+                    return None;
+                }
+                let layer = next_layer(axis, remaining[axis]);
+                remaining[axis] &= !(1 << layer);
+                distances[axis] = if remaining[axis] == 0 {
+                    f32::INFINITY
+                } else {
+                    plane_distance(axis, next_layer(axis, remaining[axis]))
+                };
+                if distance < 0.0 || distance <= after {
+                    continue;
+                }
+                let plane = (axis, layer as usize, side(axis));
+                let cells = ray_cells(ray_origin, ray_direction, axis, distance);
+                if let Some(quad) = mesh.quad_at(plane, cells) {
+                    return Some((distance, quad));
+                }
+            }
+        };
+        self.first_hit(nearest, ray_origin, ray_direction)
+    }
+
+    // Quad más cercano que cruza el rayo entre `quads`, más lejos que `after`. En un empate,
+    // como en una arista, gana el eje más alto igual que en el recorrido: el orden de `quads`
+    // sale de los chunks del mapa y cambia de una ejecución a otra
+    fn nearest_quad<'q>(
+        quads: &'q [Quad],
+        ray_origin: &Vec3,
+        ray_direction: &Vec3,
+        after: f32,
+    ) -> Option<(f32, &'q Quad)> {
+        quads
+            .iter()
+            .filter_map(|quad| Some((quad.ray_distance(ray_origin, ray_direction)?, quad)))
+            .filter(|(distance, _)| *distance > after)
+            .min_by(|a, b| a.0.total_cmp(&b.0).then(b.1.axis.cmp(&a.1.axis)))
+    }
+
+    // Bloque de la cara más cercana que cruza el rayo, según `nearest`, que da el quad más
+    // cercano después de una distancia. El choque se calcula con la caja del bloque entrando
+    // por la cara del quad, como el de un cubo: si el texel de la cara se atraviesa el rayo puede ver el lado interior de
+    // la cara de salida o, si tampoco, seguir a la siguiente cara.
+    fn first_hit<'q>(
+        &self,
+        nearest: impl Fn(f32) -> Option<(f32, &'q Quad)>,
+        ray_origin: &Vec3,
+        ray_direction: &Vec3,
+    ) -> Option<(BlockPos, Intersect<'_>)> {
+        let mut after = f32::NEG_INFINITY;
+        loop {
+            let (distance, quad) = nearest(after)?;
+            let pos = quad.block_at(ray_cells(ray_origin, ray_direction, quad.axis, distance));
+            let min_corner = Vec3::new(pos.0 as f32, pos.1 as f32, pos.2 as f32);
+            let intersect = intersect_box_at_face(
+                &min_corner,
+                &(min_corner + Vec3::new(1.0, 1.0, 1.0)),
+                self.material(quad.block),
+                ray_origin,
+                ray_direction,
+                (distance, quad.axis),
+            );
+            if intersect.is_intersecting {
+                return Some((pos, intersect));
+            }
+            after = distance;
+        }
+    }
+
+    // Bloque que ve el rayo y la cara por la que entra, como desplazamiento hacia el bloque vecino
+    pub fn pick(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Option<BlockHit> {
+        let (pos, intersect) = self.traverse(ray_origin, ray_direction, &mut None)?;
+
+        // La cara se obtiene del punto de impacto respecto al centro del bloque, sin depender
+        // de la normal (que el normal map puede alterar al sombrear)
+        let center = Vec3::new(pos.0 as f32, pos.1 as f32, pos.2 as f32) + Vec3::repeat(0.5);
+        let offset = intersect.point - center;
+        let axis = offset.iamax();
+        let mut face = [0; 3];
+        face[axis] = if offset[axis] > 0.0 { 1 } else { -1 };
+
+        Some(BlockHit {
+            pos,
+            face: (face[0], face[1], face[2]),
+            distance: intersect.distance,
+        })
+    }
+
+    // Intersección de cada rayo de un paquete (origen y dirección), uno detrás del otro.
+    // Son rayos vecinos que recorren los mismos chunks, así que cada uno empieza con los
+    // quads del último chunk que buscó el anterior.
+    pub fn ray_intersect_packet<'a: 'r, 'r>(
+        &'a self,
+        rays: &'r [(Vec3, Vec3)],
+    ) -> impl Iterator<Item = Intersect<'a>> + 'r {
+        let mut cached = None;
+        rays.iter().map(move |(origin, direction)| {
+            self.traverse(origin, direction, &mut cached)
+                .map_or_else(Intersect::empty, |(_, intersect)| intersect)
+        })
+    }
+}
+
+impl RayIntersect for World {
+    fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Intersect<'_> {
+        self.traverse(ray_origin, ray_direction, &mut None)
+            .map_or_else(Intersect::empty, |(_, intersect)| intersect)
+    }
+}
+
+impl Default for World {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,885 @@
+// world_file.rs
+//
+// Guardado del mundo editado en RON: paleta de materiales (con las texturas por ruta),
+// bloques de cada chunk, luces, cámara, cielo, portales y objetos. A diferencia del
+// formato .scene, los bloques se guardan chunk por chunk comprimidos en tramos, así
+// que sirve para mundos grandes como los terrenos generados. Los mundos enormes guardan
+// los bloques aparte, en un archivo .pages que se lee por partes (ver paged_world.rs).
+
+use nalgebra_glm::Vec3;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::animation::{Interpolation, Track};
+use crate::assets::{load_texture, Texture, TextureFilter};
+use crate::camera::{Camera, Projection};
+use crate::chunk::{BlockId, AIR, CHUNK_SIZE};
+use crate::clouds::CloudLayer;
+use crate::color::Color;
+use crate::fog::Fog;
+use crate::light::{Light, LightAnimation};
+use crate::material::{Material, ShadingModel, TransparencyMode, DEFAULT_ALPHA_CUTOFF};
+use crate::moon::Moon;
+use crate::object::{ObjectPart, SceneObject};
+use crate::paged_world::{write_pages, PagedChunks};
+use crate::portal::Portal;
+use crate::post::{Bloom, ColorLut, PostEffect};
+use crate::procedural::{Pattern, PatternKind};
+use crate::resource_pack::ResourcePack;
+use crate::scene::{default_skybox, Scene, DEFAULT_FIREFLY_CLAMP};
+use crate::scene_file::relative_path;
+use crate::skybox::{is_hdr_path, CubemapNaming, Skybox, DEFAULT_SKY_FILTER};
+use crate::world::World;
+
+// Se incrementa cuando cambia la estructura del archivo
+const FORMAT_VERSION: u32 = 1;
+// Con más chunks que estos los bloques se guardan en un archivo .pages
+pub const PAGED_CHUNK_THRESHOLD: usize = 4096;
+
+#[derive(Debug, Clone)]
+pub struct WorldFileError {
+    pub path: PathBuf,
+    pub message: String,
+}
+
+impl fmt::Display for WorldFileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.path.display(), self.message)
+    }
+}
+
+impl std::error::Error for WorldFileError {}
+
+#[derive(Serialize, Deserialize)]
+struct SavedWorld {
+    version: u32,
+    camera: SavedCamera,
+    sun_distance: f32,
+    // Los mundos guardados antes de existir el límite usan el valor por defecto
+    #[serde(default = "default_firefly_clamp")]
+    firefly_clamp: f32,
+    #[serde(default)]
+    roulette: f32,
+    #[serde(default)]
+    transparent_background: bool,
+    #[serde(default)]
+    dither: bool,
+    // Con valor la escena tiene luna; indica si su disco se dibuja en el cielo
+    #[serde(default)]
+    moon: Option<bool>,
+    sky: Option<String>,
+    // Sin valor el filtro por defecto del cielo (bilineal)
+    #[serde(default)]
+    sky_filter: Option<String>,
+    // Con valor `sky` es una carpeta con una imagen por cara
+    #[serde(default)]
+    sky_naming: Option<String>,
+    // El cielo se calcula con la posición del sol; entonces `sky` no se usa
+    #[serde(default)]
+    dynamic_sky: bool,
+    // Multiplica la radiancia de un cielo HDR; sin valor es 1
+    #[serde(default)]
+    sky_intensity: Option<f32>,
+    // Giro del cielo y su velocidad (grados por segundo)
+    #[serde(default)]
+    sky_yaw_degrees: f32,
+    #[serde(default)]
+    sky_yaw_speed_degrees: f32,
+    #[serde(default)]
+    clouds: Option<SavedClouds>,
+    #[serde(default)]
+    fog: Option<SavedFog>,
+    // Efectos de posproceso en el orden en que se aplican
+    #[serde(default)]
+    post: Vec<SavedPostEffect>,
+    // En el orden de la paleta: el primero es el bloque 1 (el 0 es el aire)
+    materials: Vec<SavedMaterial>,
+    lights: Vec<SavedLight>,
+    portals: Vec<SavedPortal>,
+    chunks: Vec<SavedChunk>,
+    // Archivo .pages con los bloques, relativo a este; entonces `chunks` queda vacío
+    #[serde(default)]
+    pages: Option<String>,
+    objects: Vec<SavedObject>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedCamera {
+    position: [f32; 3],
+    target: [f32; 3],
+    up: [f32; 3],
+    fov_degrees: f32,
+    #[serde(default)]
+    roll_degrees: f32,
+    #[serde(default)]
+    near: f32,
+    #[serde(default)]
+    far: Option<f32>,
+    #[serde(default)]
+    clip_fade: f32,
+    #[serde(default)]
+    aspect_ratio: Option<f32>,
+    #[serde(default)]
+    aperture: f32,
+    #[serde(default)]
+    focus_distance: Option<f32>,
+    // Altura visible si la proyección es ortográfica; None es perspectiva
+    #[serde(default)]
+    orthographic: Option<f32>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedMaterial {
+    name: String,
+    diffuse: [f32; 3],
+    specular: f32,
+    albedo: [f32; 4],
+    refractive_index: f32,
+    emission: [f32; 3],
+    // Relativas al archivo guardado
+    texture: Option<String>,
+    normal_map: Option<String>,
+    // Sin valor es el texel más cercano
+    #[serde(default)]
+    texture_filter: Option<String>,
+    #[serde(default)]
+    normal_map_filter: Option<String>,
+    // Sin valor es refracción exacta
+    #[serde(default)]
+    transparency: Option<String>,
+    // Patrón procedural en lugar de la textura
+    #[serde(default)]
+    pattern: Option<SavedPattern>,
+    // Sin valor es el recorte por defecto
+    #[serde(default)]
+    alpha_cutoff: Option<f32>,
+    // Con valor el material usa el modelo PBR
+    #[serde(default)]
+    pbr: Option<SavedPbr>,
+    // Sin valor los reflejos son de espejo
+    #[serde(default)]
+    blur: Option<f32>,
+    // Sin valor el interior no absorbe luz
+    #[serde(default)]
+    absorption: Option<[f32; 3]>,
+    // Máscaras de emisión y brillo, relativas como las texturas
+    #[serde(default)]
+    emission_map: Option<String>,
+    #[serde(default)]
+    specular_map: Option<String>,
+    // Sin valor la emisión no varía
+    #[serde(default)]
+    emission_animation: Option<SavedLightAnimation>,
+    // Vidrio de color que tiñe la luz que pasa por él
+    #[serde(default)]
+    stained: bool,
+    // Sin valor sigue los rebotes de la calidad del render
+    #[serde(default)]
+    max_depth: Option<u32>,
+    // Resource pack del que se vuelve a leer la textura, en lugar de `texture`
+    #[serde(default)]
+    resource_pack: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedPbr {
+    metallic: f32,
+    roughness: f32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedPattern {
+    kind: String,
+    colors: [[f32; 3]; 2],
+    size: f32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedLight {
+    position: [f32; 3],
+    color: [f32; 3],
+    intensity: f32,
+    radius: f32,
+    #[serde(default)]
+    animation: Option<SavedLightAnimation>,
+    #[serde(default)]
+    group: Option<String>,
+}
+
+// Tiempos en segundos
+#[derive(Serialize, Deserialize)]
+enum SavedLightAnimation {
+    Pulse { period: f32, depth: f32 },
+    Flicker { period: f32, depth: f32 },
+    Strobe { period: f32, duty: f32 },
+}
+
+// Longitudes en bloques y viento en bloques por segundo (x, z)
+#[derive(Serialize, Deserialize)]
+struct SavedClouds {
+    height: f32,
+    thickness: f32,
+    coverage: f32,
+    scale: f32,
+    wind: [f32; 2],
+    color: [f32; 3],
+}
+
+// Densidad por bloque y alcance en bloques
+#[derive(Serialize, Deserialize)]
+struct SavedFog {
+    density: f32,
+    color: [f32; 3],
+    distance: f32,
+    anisotropy: f32,
+}
+
+#[derive(Serialize, Deserialize)]
+enum SavedPostEffect {
+    // Pasos de diafragma
+    Exposure(f32),
+    Bloom {
+        threshold: f32,
+        strength: f32,
+        radius: f32,
+    },
+    Vignette(f32),
+    // Tabla `.cube`, relativa al archivo guardado
+    Lut(String),
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedPortal {
+    min: [f32; 3],
+    max: [f32; 3],
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedChunk {
+    position: (i32, i32, i32),
+    // Tramos (bloque, cantidad) recorriendo el chunk en orden y, z, x
+    runs: Vec<(BlockId, u32)>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedObject {
+    name: String,
+    pivot: [f32; 3],
+    parts: Vec<SavedPart>,
+    translation: Option<SavedTrack>,
+    scale: Option<SavedTrack>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedPart {
+    block: BlockId,
+    min: [f32; 3],
+    max: [f32; 3],
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedTrack {
+    interpolation: String,
+    looping: bool,
+    // (tiempo en segundos, valor)
+    keys: Vec<(f32, [f32; 3])>,
+}
+
+// Guarda la escena completa; las rutas de texturas quedan relativas al archivo de destino.
+// Un mundo que ya se leía de un archivo paginado, o que tiene muchos chunks, guarda los
+// bloques en `<nombre>.pages` junto al archivo.
+pub fn save_world(scene: &Scene, path: &Path) -> io::Result<()> {
+    let paged = scene.world.is_paged() || scene.world.chunk_count() > PAGED_CHUNK_THRESHOLD;
+    save(scene, path, paged)
+}
+
+// Como save_world, pero siempre con los bloques en un archivo paginado
+pub fn save_world_paged(scene: &Scene, path: &Path) -> io::Result<()> {
+    save(scene, path, true)
+}
+
+fn save(scene: &Scene, path: &Path, paged: bool) -> io::Result<()> {
+    let base_dir = path.parent().unwrap_or(Path::new("."));
+    if !base_dir.as_os_str().is_empty() {
+        fs::create_dir_all(base_dir)?;
+    }
+
+    let pages = if paged {
+        let pages_path = path.with_extension("pages");
+        write_pages(&scene.world, &pages_path)?;
+        pages_path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+    } else {
+        None
+    };
+    let saved = to_saved(scene, base_dir, pages);
+    let text = ron::ser::to_string_pretty(&saved, ron::ser::PrettyConfig::new())
+        .map_err(io::Error::other)?;
+    fs::write(path, text)
+}
+
+pub fn load_world(path: &Path) -> Result<Scene, WorldFileError> {
+    let error = |message: String| WorldFileError {
+        path: path.to_path_buf(),
+        message,
+    };
+
+    let text = fs::read_to_string(path)
+        .map_err(|err| error(format!("no se pudo leer el archivo: {}", err)))?;
+    let saved: SavedWorld =
+        ron::from_str(&text).map_err(|err| error(format!("formato inválido: {}", err)))?;
+    if saved.version != FORMAT_VERSION {
+        return Err(error(format!(
+            "versión {} no soportada (se esperaba {})",
+            saved.version, FORMAT_VERSION
+        )));
+    }
+
+    let base_dir = path.parent().unwrap_or(Path::new("."));
+    from_saved(saved, base_dir).map_err(error)
+}
+
+fn to_saved(scene: &Scene, base_dir: &Path, pages: Option<String>) -> SavedWorld {
+    let camera = &scene.camera;
+    let relative = |path: &String| relative_path(path, base_dir);
+
+    let materials = scene
+        .world
+        .materials()
+        .map(|(_, name, material)| SavedMaterial {
+            name: name.to_string(),
+            diffuse: color(material.diffuse),
+            specular: material.specular,
+            albedo: material.albedo,
+            refractive_index: material.refractive_index,
+            emission: color(material.emission),
+            emission_animation: material.emission_animation.map(save_animation),
+            texture: material.texture_path.as_ref().map(relative),
+            normal_map: material.normal_map_path.as_ref().map(relative),
+            texture_filter: filter_name(material.texture_filter),
+            normal_map_filter: filter_name(material.normal_map_filter),
+            transparency: (material.transparency != TransparencyMode::Refract)
+                .then(|| material.transparency.name().to_string()),
+            pattern: match &material.texture {
+                Some(Texture::Pattern(pattern)) => Some(SavedPattern {
+                    kind: pattern.kind.name().to_string(),
+                    colors: pattern.colors.map(color),
+                    size: pattern.size,
+                }),
+                _ => None,
+            },
+            alpha_cutoff: (material.alpha_cutoff != DEFAULT_ALPHA_CUTOFF)
+                .then_some(material.alpha_cutoff),
+            pbr: match material.model {
+                ShadingModel::Legacy => None,
+                ShadingModel::Pbr {
+                    metallic,
+                    roughness,
+                } => Some(SavedPbr {
+                    metallic,
+                    roughness,
+                }),
+            },
+            blur: (material.blur > 0.0).then_some(material.blur),
+            absorption: {
+                let absorption = material.absorption;
+                (absorption.r.max(absorption.g).max(absorption.b) > 0.0).then(|| color(absorption))
+            },
+            emission_map: material.emission_map_path.as_ref().map(relative),
+            specular_map: material.specular_map_path.as_ref().map(relative),
+            stained: material.stained,
+            max_depth: material.max_depth,
+            resource_pack: material.texture_pack.as_ref().map(relative),
+        })
+        .collect();
+
+    let lights = scene
+        .lights
+        .iter()
+        .map(|light| SavedLight {
+            position: vector(light.position),
+            color: color(light.color),
+            intensity: light.intensity,
+            radius: light.radius,
+            animation: light.animation.map(save_animation),
+            group: light.group.clone(),
+        })
+        .collect();
+
+    let portals = scene
+        .portals
+        .iter()
+        .map(|portal| SavedPortal {
+            min: vector(portal.min),
+            max: vector(portal.max),
+        })
+        .collect();
+
+    let mut chunks: Vec<SavedChunk> = scene
+        .world
+        .chunks()
+        .filter(|_| pages.is_none())
+        .map(|(position, chunk)| {
+            let mut runs: Vec<(BlockId, u32)> = Vec::new();
+            for (x, y, z) in local_positions() {
+                let block = chunk.get(x, y, z);
+                match runs.last_mut() {
+                    Some((run_block, count)) if *run_block == block => *count += 1,
+                    _ => runs.push((block, 1)),
+                }
+            }
+            SavedChunk { position, runs }
+        })
+        .collect();
+    // Orden fijo para que guardar dos veces el mismo mundo dé el mismo archivo
+    chunks.sort_by_key(|chunk| chunk.position);
+
+    let objects = scene
+        .objects
+        .iter()
+        .map(|object| SavedObject {
+            name: object.name.clone(),
+            pivot: vector(object.pivot),
+            parts: object
+                .parts
+                .iter()
+                .map(|part| SavedPart {
+                    block: part.block,
+                    min: vector(part.min),
+                    max: vector(part.max),
+                })
+                .collect(),
+            translation: object.translation.as_ref().map(saved_track),
+            scale: object.scale.as_ref().map(saved_track),
+        })
+        .collect();
+
+    SavedWorld {
+        version: FORMAT_VERSION,
+        camera: SavedCamera {
+            position: vector(camera.position),
+            target: vector(camera.target),
+            up: vector(camera.up_direction),
+            fov_degrees: camera.fov.to_degrees(),
+            roll_degrees: camera.roll.to_degrees(),
+            near: camera.near,
+            far: camera.far,
+            clip_fade: camera.clip_fade,
+            aspect_ratio: camera.aspect_ratio,
+            aperture: camera.aperture,
+            focus_distance: camera.focus_distance,
+            orthographic: (camera.projection == Projection::Orthographic)
+                .then_some(camera.ortho_height),
+        },
+        sun_distance: scene.sun_distance,
+        firefly_clamp: scene.firefly_clamp,
+        roulette: scene.roulette,
+        transparent_background: scene.transparent_background,
+        dither: scene.dither,
+        moon: scene.moon.as_ref().map(|moon| moon.visible),
+        sky: scene.skybox.source_path.as_ref().map(relative),
+        sky_filter: (scene.skybox.filter != DEFAULT_SKY_FILTER)
+            .then(|| scene.skybox.filter.name().to_string()),
+        sky_naming: scene.skybox.naming.map(|naming| naming.name().to_string()),
+        dynamic_sky: scene.skybox.dynamic.is_some(),
+        sky_intensity: scene
+            .skybox
+            .environment
+            .as_ref()
+            .map(|environment| environment.intensity)
+            .filter(|&intensity| intensity != 1.0),
+        sky_yaw_degrees: scene.skybox.yaw.to_degrees(),
+        sky_yaw_speed_degrees: scene.skybox.yaw_speed.to_degrees(),
+        clouds: scene.clouds.as_ref().map(|clouds| SavedClouds {
+            height: clouds.height,
+            thickness: clouds.thickness,
+            coverage: clouds.coverage,
+            scale: clouds.scale,
+            wind: [clouds.wind.x, clouds.wind.z],
+            color: color(clouds.color),
+        }),
+        fog: scene.fog.as_ref().map(|fog| SavedFog {
+            density: fog.density,
+            color: color(fog.color),
+            distance: fog.distance,
+            anisotropy: fog.anisotropy,
+        }),
+        post: scene
+            .post
+            .iter()
+            .map(|effect| match effect {
+                PostEffect::Exposure(stops) => SavedPostEffect::Exposure(*stops),
+                PostEffect::Bloom(bloom) => SavedPostEffect::Bloom {
+                    threshold: bloom.threshold,
+                    strength: bloom.strength,
+                    radius: bloom.radius,
+                },
+                PostEffect::Vignette(amount) => SavedPostEffect::Vignette(*amount),
+                PostEffect::Grade(lut) => SavedPostEffect::Lut(relative(&lut.path)),
+            })
+            .collect(),
+        materials,
+        lights,
+        portals,
+        chunks,
+        pages,
+        objects,
+    }
+}
+
+fn from_saved(saved: SavedWorld, base_dir: &Path) -> Result<Scene, String> {
+    let resolve = |path: &str| base_dir.join(path).to_string_lossy().into_owned();
+
+    let mut world = World::new();
+    // Cada resource pack se lee una vez aunque lo usen varios materiales
+    let mut packs: HashMap<String, ResourcePack> = HashMap::new();
+    for saved_material in saved.materials {
+        let mut material = Material::new(
+            to_color(saved_material.diffuse),
+            saved_material.specular,
+            saved_material.albedo,
+            saved_material.refractive_index,
+            None,
+            None,
+            to_color(saved_material.emission),
+        );
+        material.emission_animation = saved_material.emission_animation.map(load_animation);
+        if let Some(texture) = saved_material.texture {
+            let path = resolve(&texture);
+            material.texture = Some(Texture::Image(load_texture(&path)));
+            material.texture_path = Some(path);
+        }
+        if let Some(normal_map) = saved_material.normal_map {
+            let path = resolve(&normal_map);
+            material.normal_map = Some(load_texture(&path));
+            material.normal_map_path = Some(path);
+        }
+        if let Some(emission_map) = saved_material.emission_map {
+            let path = resolve(&emission_map);
+            material.emission_map = Some(load_texture(&path));
+            material.emission_map_path = Some(path);
+        }
+        if let Some(specular_map) = saved_material.specular_map {
+            let path = resolve(&specular_map);
+            material.specular_map = Some(load_texture(&path));
+            material.specular_map_path = Some(path);
+        }
+        if let Some(pattern) = saved_material.pattern {
+            let kind = PatternKind::from_name(&pattern.kind)
+                .ok_or_else(|| format!("patrón desconocido '{}'", pattern.kind))?;
+            material.texture = Some(Texture::Pattern(Pattern {
+                kind,
+                colors: pattern.colors.map(to_color),
+                size: pattern.size,
+            }));
+        }
+        if let Some(cutoff) = saved_material.alpha_cutoff {
+            material.alpha_cutoff = cutoff;
+        }
+        material.stained = saved_material.stained;
+        material.max_depth = saved_material.max_depth;
+        if let Some(pack) = saved_material.resource_pack {
+            let path = resolve(&pack);
+            if !packs.contains_key(&path) {
+                let pack = ResourcePack::open(Path::new(&path))
+                    .map_err(|err| format!("{}: {}", path, err))?;
+                packs.insert(path.clone(), pack);
+            }
+            let textured = packs[&path]
+                .texture(&saved_material.name, &mut material)
+                .map_err(|err| format!("{}: {}", path, err))?;
+            if !textured {
+                return Err(format!(
+                    "{} no tiene textura para '{}'",
+                    path, saved_material.name
+                ));
+            }
+        }
+        if let Some(blur) = saved_material.blur {
+            material.blur = blur;
+        }
+        if let Some(absorption) = saved_material.absorption {
+            material.absorption = to_color(absorption);
+        }
+        if let Some(pbr) = saved_material.pbr {
+            material.model = ShadingModel::Pbr {
+                metallic: pbr.metallic,
+                roughness: pbr.roughness,
+            };
+        }
+        material.texture_filter = to_filter(saved_material.texture_filter)?;
+        material.normal_map_filter = to_filter(saved_material.normal_map_filter)?;
+        if let Some(name) = saved_material.transparency {
+            material.transparency = TransparencyMode::from_name(&name)
+                .ok_or_else(|| format!("transparencia desconocida '{}'", name))?;
+        }
+        world.add_material(&saved_material.name, material);
+    }
+    let palette_size = world.materials().count() + 1;
+    let check_block = |block: BlockId| {
+        if (block as usize) < palette_size {
+            Ok(block)
+        } else {
+            Err(format!("el bloque {} no está en la paleta", block))
+        }
+    };
+
+    let size = CHUNK_SIZE as i32;
+    for chunk in saved.chunks {
+        let (cx, cy, cz) = chunk.position;
+        let mut positions = local_positions();
+        for (block, count) in chunk.runs {
+            let block = check_block(block)?;
+            for _ in 0..count {
+                let (x, y, z) = positions.next().ok_or_else(|| {
+                    format!(
+                        "el chunk {:?} tiene más bloques de los que caben",
+                        chunk.position
+                    )
+                })?;
+                if block != AIR {
+                    world.set_block(
+                        (
+                            cx * size + x as i32,
+                            cy * size + y as i32,
+                            cz * size + z as i32,
+                        ),
+                        block,
+                    );
+                }
+            }
+        }
+    }
+
+    if let Some(pages) = saved.pages {
+        let path = base_dir.join(pages);
+        let pages = PagedChunks::open(&path, palette_size)
+            .map_err(|err| format!("no se pudo abrir {}: {}", path.display(), err))?;
+        world.attach_pages(pages);
+    }
+
+    let mut objects = Vec::new();
+    for saved_object in saved.objects {
+        let mut object = SceneObject::new(&saved_object.name, to_vector(saved_object.pivot));
+        for part in saved_object.parts {
+            object.parts.push(ObjectPart {
+                block: check_block(part.block)?,
+                min: to_vector(part.min),
+                max: to_vector(part.max),
+            });
+        }
+        object.translation = saved_object.translation.map(to_track).transpose()?;
+        object.scale = saved_object.scale.map(to_track).transpose()?;
+        objects.push(object);
+    }
+
+    let mut portals = Vec::new();
+    for portal in saved.portals {
+        let portal = Portal::new(to_vector(portal.min), to_vector(portal.max))
+            .ok_or_else(|| "un portal debe ser plano en exactamente un eje".to_string())?;
+        portals.push(portal);
+    }
+
+    let lights = saved
+        .lights
+        .into_iter()
+        .map(|saved_light| {
+            let mut light = Light::new(
+                to_vector(saved_light.position),
+                to_color(saved_light.color),
+                saved_light.intensity,
+            );
+            light.radius = saved_light.radius;
+            light.animation = saved_light.animation.map(load_animation);
+            light.group = saved_light.group;
+            light
+        })
+        .collect();
+
+    let mut skybox = match (saved.sky, saved.sky_naming) {
+        (Some(sky), Some(name)) => {
+            let naming = CubemapNaming::from_name(&name)
+                .ok_or_else(|| format!("convención de caras desconocida '{}'", name))?;
+            Skybox::from_folder(&resolve(&sky), naming)?
+        }
+        (Some(sky), None) if is_hdr_path(&sky) => {
+            Skybox::from_hdr(&resolve(&sky), saved.sky_intensity.unwrap_or(1.0))?
+        }
+        (Some(sky), None) => {
+            let path = resolve(&sky);
+            let face = load_texture(&path);
+            let mut skybox = Skybox::new(
+                face.clone(),
+                face.clone(),
+                face.clone(),
+                face.clone(),
+                face.clone(),
+                face,
+            );
+            skybox.source_path = Some(path);
+            skybox
+        }
+        (None, _) => default_skybox(),
+    };
+    if saved.sky_filter.is_some() {
+        skybox.filter = to_filter(saved.sky_filter)?;
+    }
+    skybox.yaw = saved.sky_yaw_degrees.to_radians();
+    skybox.yaw_speed = saved.sky_yaw_speed_degrees.to_radians();
+
+    let mut camera = Camera::new(
+        to_vector(saved.camera.position),
+        to_vector(saved.camera.target),
+        to_vector(saved.camera.up),
+    );
+    camera.fov = saved.camera.fov_degrees.to_radians();
+    camera.roll = saved.camera.roll_degrees.to_radians();
+    camera.near = saved.camera.near;
+    camera.far = saved.camera.far;
+    camera.clip_fade = saved.camera.clip_fade;
+    camera.aspect_ratio = saved.camera.aspect_ratio;
+    camera.aperture = saved.camera.aperture;
+    camera.focus_distance = saved.camera.focus_distance;
+    if let Some(ortho_height) = saved.camera.orthographic {
+        camera.projection = Projection::Orthographic;
+        camera.ortho_height = ortho_height;
+    }
+    camera.update_view();
+
+    let mut post = Vec::new();
+    for effect in saved.post {
+        post.push(match effect {
+            SavedPostEffect::Exposure(stops) => PostEffect::Exposure(stops),
+            SavedPostEffect::Bloom {
+                threshold,
+                strength,
+                radius,
+            } => PostEffect::Bloom(Bloom {
+                threshold,
+                strength,
+                radius,
+            }),
+            SavedPostEffect::Vignette(amount) => PostEffect::Vignette(amount),
+            SavedPostEffect::Lut(path) => PostEffect::Grade(ColorLut::load(&resolve(&path))?),
+        });
+    }
+
+    let mut scene = Scene {
+        world,
+        objects,
+        lights,
+        ambient: Color::black(),
+        light_time: None,
+        moon: saved.moon.map(Moon::new),
+        portals,
+        skybox,
+        clouds: saved.clouds.map(|clouds| CloudLayer {
+            height: clouds.height,
+            thickness: clouds.thickness,
+            coverage: clouds.coverage,
+            scale: clouds.scale,
+            wind: Vec3::new(clouds.wind[0], 0.0, clouds.wind[1]),
+            color: to_color(clouds.color),
+            ..CloudLayer::default()
+        }),
+        fog: saved.fog.map(|fog| Fog {
+            density: fog.density,
+            color: to_color(fog.color),
+            distance: fog.distance,
+            anisotropy: fog.anisotropy,
+        }),
+        post,
+        camera,
+        sun_distance: saved.sun_distance,
+        firefly_clamp: saved.firefly_clamp,
+        roulette: saved.roulette,
+        transparent_background: saved.transparent_background,
+        dither: saved.dither,
+        light_cache: None,
+    };
+    if saved.dynamic_sky {
+        scene.use_dynamic_sky();
+    }
+    Ok(scene)
+}
+
+fn save_animation(animation: LightAnimation) -> SavedLightAnimation {
+    match animation {
+        LightAnimation::Pulse { period, depth } => SavedLightAnimation::Pulse { period, depth },
+        LightAnimation::Flicker { period, depth } => SavedLightAnimation::Flicker { period, depth },
+        LightAnimation::Strobe { period, duty } => SavedLightAnimation::Strobe { period, duty },
+    }
+}
+
+fn load_animation(animation: SavedLightAnimation) -> LightAnimation {
+    match animation {
+        SavedLightAnimation::Pulse { period, depth } => LightAnimation::Pulse { period, depth },
+        SavedLightAnimation::Flicker { period, depth } => LightAnimation::Flicker { period, depth },
+        SavedLightAnimation::Strobe { period, duty } => LightAnimation::Strobe { period, duty },
+    }
+}
+
+// Posiciones locales de un chunk en el orden en que se guardan los tramos
+fn local_positions() -> impl Iterator<Item = (usize, usize, usize)> {
+    (0..CHUNK_SIZE)
+        .flat_map(|y| (0..CHUNK_SIZE).flat_map(move |z| (0..CHUNK_SIZE).map(move |x| (x, y, z))))
+}
+
+fn default_firefly_clamp() -> f32 {
+    DEFAULT_FIREFLY_CLAMP
+}
+
+fn saved_track(track: &Track<Vec3>) -> SavedTrack {
+    SavedTrack {
+        interpolation: track.interpolation.name().to_string(),
+        looping: track.looping,
+        keys: track
+            .keys()
+            .iter()
+            .map(|key| (key.time, vector(key.value)))
+            .collect(),
+    }
+}
+
+fn to_track(saved: SavedTrack) -> Result<Track<Vec3>, String> {
+    let interpolation = Interpolation::from_name(&saved.interpolation)
+        .ok_or_else(|| format!("interpolación desconocida '{}'", saved.interpolation))?;
+    let mut track = Track::new(interpolation, saved.looping);
+    for (time, value) in saved.keys {
+        track.add_key(time, to_vector(value));
+    }
+    Ok(track)
+}
+
+fn filter_name(filter: TextureFilter) -> Option<String> {
+    (filter != TextureFilter::Nearest).then(|| filter.name().to_string())
+}
+
+fn to_filter(saved: Option<String>) -> Result<TextureFilter, String> {
+    match saved {
+        Some(name) => TextureFilter::from_name(&name)
+            .ok_or_else(|| format!("filtro de textura desconocido '{}'", name)),
+        None => Ok(TextureFilter::Nearest),
+    }
+}
+
+fn vector(value: Vec3) -> [f32; 3] {
+    [value.x, value.y, value.z]
+}
+
+fn to_vector(value: [f32; 3]) -> Vec3 {
+    Vec3::new(value[0], value[1], value[2])
+}
+
+fn color(value: Color) -> [f32; 3] {
+    [value.r, value.g, value.b]
+}
+
+fn to_color(value: [f32; 3]) -> Color {
+    Color::new(value[0], value[1], value[2])
+}